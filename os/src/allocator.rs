@@ -3,11 +3,13 @@
  * RISC-V 堆分配器模块
  * ============================================
  * 功能：提供内核堆内存分配
- * 实现：使用固定大小块分配器
+ * 实现：默认使用固定大小块分配器（小块 O(1)），
+ *       打开 linked_list_heap feature 时改用链表分配器（便于对比行为）
  *
  * 堆配置：
- * - 起始地址：0x8040_0000（物理内存中的某个位置）
- * - 大小：1 MB
+ * - 起始地址：紧接在内核镜像之后（链接脚本的 kernel_end，按页对齐），
+ *   从帧分配器中占用，不会与内核镜像和启动栈重叠
+ * - 大小：1 MB，之后可以用 grow_heap 在预留的 HEAP_MAX_SIZE 范围内连续增长
  * ============================================
  */
 
@@ -15,21 +17,41 @@
 // 堆配置
 // ============================================
 
-/// 堆起始地址（RISC-V 物理内存空间）
-pub const HEAP_START: usize = 0x8040_0000;
-
 /// 堆大小（1 MB）
 pub const HEAP_SIZE: usize = 1024 * 1024;
 
+/// 堆最多可以增长到的大小（16 MB，初始化时整段预留）
+pub const HEAP_MAX_SIZE: usize = 16 * 1024 * 1024;
+
+/// 堆起始地址：内核镜像之后的第一页
+pub fn heap_start() -> usize {
+    extern "C" {
+        static kernel_end: u8;
+    }
+    align_up(core::ptr::addr_of!(kernel_end) as usize, crate::memory::PAGE_SIZE)
+}
+
 // ============================================
 // 分配器实现
 // ============================================
 
+use core::sync::atomic::{AtomicUsize, Ordering};
+
 pub mod bump;
 pub mod linked_list;
 pub mod fixed_size_block;
+#[cfg(test)]
+pub mod deterministic;
+
+pub use fixed_size_block::HeapCorruption;
 
-use fixed_size_block::FixedSizeBlockAllocator;
+/// 内核堆使用的分配器
+#[cfg(not(feature = "linked_list_heap"))]
+pub type KernelAllocator = fixed_size_block::FixedSizeBlockAllocator;
+
+/// 内核堆使用的分配器
+#[cfg(feature = "linked_list_heap")]
+pub type KernelAllocator = linked_list::LinkedListAllocator;
 
 /// 互斥锁包装器
 pub struct Locked<A> {
@@ -48,10 +70,21 @@ impl<A> Locked<A> {
     }
 }
 
+/// 堆统计信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapStats {
+    /// 空闲字节数
+    pub free_bytes: usize,
+    /// 空闲链表长度（碎片化指标：越长说明空闲内存越零散）
+    pub free_list_len: usize,
+}
+
 /// 全局分配器实例
 #[global_allocator]
-static ALLOCATOR: Locked<FixedSizeBlockAllocator> =
-    Locked::new(FixedSizeBlockAllocator::new());
+static ALLOCATOR: Locked<KernelAllocator> = Locked::new(KernelAllocator::new());
+
+/// 堆的结束地址（下一次增长的位置），堆初始化之前为 0
+static HEAP_END: AtomicUsize = AtomicUsize::new(0);
 
 /// 对齐地址到指定边界
 ///
@@ -68,47 +101,111 @@ fn align_up(addr: usize, align: usize) -> usize {
 /// 初始化堆分配器
 ///
 /// # 功能
-/// - 为堆区域分配物理帧
-/// - 映射堆区域到虚拟地址空间
-/// - 初始化全局分配器
+/// - 从帧分配器占用内核镜像之后的 HEAP_MAX_SIZE 字节
+/// - 用其中开头的 HEAP_SIZE 字节初始化全局分配器，其余留给 grow_heap
 ///
 /// # 参数
-/// - `frame_allocator`: 物理帧分配器
+/// - `frame_allocator`: 物理帧分配器（还没有分配过帧）
+///
+/// # 说明
+/// 物理内存恒等映射，占用的帧直接作为堆使用；
+/// 这些帧已经分配出去或与保留区域重叠时返回错误
 pub fn init_heap(
     frame_allocator: &mut crate::memory::SimpleFrameAllocator,
 ) -> Result<(), &'static str> {
-    use crate::{serial_println, memory::PAGE_SIZE};
-
-    serial_println!("[ALLOCATOR] Initializing heap at {:#x}", HEAP_START);
-    serial_println!("[ALLOCATOR] Heap size: {} bytes", HEAP_SIZE);
-
-    // 计算需要的页数
-    let page_count = (HEAP_SIZE + PAGE_SIZE - 1) / PAGE_SIZE;
-    serial_println!("[ALLOCATOR] Allocating {} pages for heap", page_count);
-
-    // 分配物理帧
-    for _i in 0..page_count {
-        let _frame = frame_allocator
-            .allocate()
-            .ok_or("Failed to allocate frame for heap")?;
-
-        // 注释掉详细的分配输出以避免中断期间的竞态条件
-        // serial_println!(
-        //     "[ALLOCATOR] Allocated frame {} at {:#x}",
-        //     i,
-        //     frame.start_address().as_usize()
-        // );
-    }
+    use crate::serial_println;
+
+    let start = heap_start();
+    serial_println!("[ALLOCATOR] Initializing heap at {:#x}", start);
+    serial_println!("[ALLOCATOR] Heap size: {} bytes (up to {})", HEAP_SIZE, HEAP_MAX_SIZE);
+
+    frame_allocator.claim(crate::memory::PhysAddr::new(start), HEAP_MAX_SIZE)?;
 
     // 初始化分配器
     unsafe {
-        ALLOCATOR.lock().init(HEAP_START, HEAP_SIZE);
+        ALLOCATOR.lock().init(start, HEAP_SIZE);
     }
+    HEAP_END.store(start + HEAP_SIZE, Ordering::SeqCst);
 
     serial_println!("[ALLOCATOR] Heap initialized successfully");
     Ok(())
 }
 
+/// 堆的结束地址
+///
+/// # 说明
+/// grow_heap 优先从这里开始增长；堆初始化之前返回 0
+pub fn heap_end() -> usize {
+    HEAP_END.load(Ordering::SeqCst)
+}
+
+/// 增长内核堆
+///
+/// # 参数
+/// - `additional_bytes`: 增加的字节数（向上对齐到页）
+///
+/// # 返回
+/// 新增区域的起始地址（即原来的 heap_end）
+///
+/// # 说明
+/// 增长的帧在 init_heap 时已经整段预留，堆总是紧接着 heap_end 连续增长，
+/// 不需要向帧分配器申请；超出 HEAP_MAX_SIZE 时返回错误，堆保持不变
+pub fn grow_heap(additional_bytes: usize) -> Result<usize, &'static str> {
+    use crate::memory::PAGE_SIZE;
+
+    // 持有分配器锁，并发的增长不会拿到同一段区域
+    let mut allocator = ALLOCATOR.lock();
+    let end = heap_end();
+    if end == 0 {
+        return Err("Heap is not initialized");
+    }
+    let size = additional_bytes.div_ceil(PAGE_SIZE) * PAGE_SIZE;
+    if size == 0 {
+        return Err("Heap growth size is zero");
+    }
+    if size > heap_start() + HEAP_MAX_SIZE - end {
+        return Err("Heap growth exceeds the reserved heap range");
+    }
+
+    if !unsafe { allocator.extend(end, size) } {
+        return Err("Kernel allocator rejected the heap growth");
+    }
+    HEAP_END.store(end + size, Ordering::SeqCst);
+    drop(allocator);
+
+    crate::serial_println!("[ALLOCATOR] Heap grown by {} bytes at {:#x}", size, end);
+    Ok(end)
+}
+
+/// 内核堆的统计信息
+pub fn heap_stats() -> HeapStats {
+    ALLOCATOR.lock().heap_stats()
+}
+
+/// 检查内核堆的一致性（调试用）
+///
+/// # 返回
+/// - `Ok(n)`: 空闲链表中共有 n 个块，没有发现问题
+/// - `Err(c)`: 发现的第一个问题
+///
+/// # 说明
+/// 检查期间持有分配器锁，其他 hart 的分配会等待
+pub fn check_integrity() -> Result<usize, HeapCorruption> {
+    ALLOCATOR.lock().check_integrity()
+}
+
+/// 检查内核堆并打印结果（SysRq 命令）
+pub fn report_integrity() {
+    match check_integrity() {
+        Ok(free_blocks) => {
+            crate::serial_println!("[ALLOCATOR] Heap OK ({} free blocks)", free_blocks);
+        }
+        Err(corruption) => {
+            crate::serial_println!("[ALLOCATOR] Heap corruption: {}", corruption);
+        }
+    }
+}
+
 // ============================================
 // 测试
 // ============================================
@@ -134,6 +231,53 @@ mod tests {
         assert_eq!(vec.iter().sum::<u64>(), (n - 1) * n / 2);
     }
 
+    #[test_case]
+    fn test_kernel_heap_passes_integrity_check() {
+        let values: Vec<Box<u64>> = (0..16).map(Box::new).collect();
+        drop(values);
+        assert!(check_integrity().is_ok());
+    }
+
+    /// 耗尽当前的堆之后增长，新的分配落在增长出来的区域
+    #[test_case]
+    fn test_grow_heap_after_exhaustion() {
+        use core::alloc::Layout;
+
+        const CHUNK: usize = 64 * 1024;
+        const MAX_CHUNKS: usize = HEAP_MAX_SIZE / CHUNK;
+
+        let layout = Layout::from_size_align(CHUNK, 8).unwrap();
+        let mut chunks = Vec::with_capacity(MAX_CHUNKS);
+        loop {
+            let ptr = unsafe { alloc::alloc::alloc(layout) };
+            if ptr.is_null() {
+                break;
+            }
+            chunks.push(ptr);
+        }
+
+        let old_end = heap_end();
+        let start = grow_heap(4 * CHUNK).expect("heap growth failed");
+        assert_eq!(start, old_end);
+        assert_eq!(heap_end(), old_end + 4 * CHUNK);
+
+        let ptr = unsafe { alloc::alloc::alloc(layout) };
+        assert!(!ptr.is_null());
+        assert!(ptr as usize >= start && ptr as usize + CHUNK <= heap_end());
+
+        // 超出预留范围时失败，堆保持不变
+        assert!(grow_heap(HEAP_MAX_SIZE).is_err());
+        assert_eq!(heap_end(), old_end + 4 * CHUNK);
+
+        unsafe {
+            alloc::alloc::dealloc(ptr, layout);
+            for &chunk in &chunks {
+                alloc::alloc::dealloc(chunk, layout);
+            }
+        }
+        assert!(check_integrity().is_ok());
+    }
+
     #[test_case]
     fn test_many_boxes() {
         for i in 0..10000 {
@@ -0,0 +1,104 @@
+//! 确定性分配模式（仅测试构建）
+//!
+//! 从一块固定的静态内存区按顺序分配（复用 BumpAllocator），
+//! 每次 reset 后清零并从头开始，同样的分配序列总是得到同样的地址。
+//! 与全局分配器完全独立：只有显式调用这里的函数才会从该区域分配。
+
+use super::Locked;
+use super::bump::BumpAllocator;
+use alloc::alloc::{GlobalAlloc, Layout};
+use core::cell::UnsafeCell;
+
+/// 固定区域大小（64 KB）
+pub const ARENA_SIZE: usize = 64 * 1024;
+
+/// 按页对齐的固定区域
+#[repr(C, align(4096))]
+struct Arena(UnsafeCell<[u8; ARENA_SIZE]>);
+
+unsafe impl Sync for Arena {}
+
+static ARENA: Arena = Arena(UnsafeCell::new([0; ARENA_SIZE]));
+
+static ALLOCATOR: Locked<BumpAllocator> = Locked::new(BumpAllocator::new());
+
+/// 区域起始地址
+pub fn arena_start() -> usize {
+    ARENA.0.get() as usize
+}
+
+/// 清零区域并从头开始分配
+///
+/// # Safety
+/// 之前分配的对象全部失效：调用者必须保证 alloc / alloc_value 返回的
+/// 指针和引用都不再使用
+pub unsafe fn reset() {
+    let mut allocator = ALLOCATOR.lock();
+    core::ptr::write_bytes(ARENA.0.get() as *mut u8, 0, ARENA_SIZE);
+    allocator.init(arena_start(), ARENA_SIZE);
+}
+
+/// 从固定区域分配
+///
+/// # 返回
+/// 区域未初始化（没有调用过 reset）或空间不足时返回空指针
+pub fn alloc(layout: Layout) -> *mut u8 {
+    unsafe { ALLOCATOR.alloc(layout) }
+}
+
+/// 把值放入固定区域
+///
+/// # 返回
+/// 空间不足时返回 None；引用在下一次 reset 之前有效
+pub fn alloc_value<T>(value: T) -> Option<&'static mut T> {
+    let ptr = alloc(Layout::new::<T>()) as *mut T;
+    if ptr.is_null() {
+        return None;
+    }
+    unsafe {
+        ptr.write(value);
+        Some(&mut *ptr)
+    }
+}
+
+/// 地址相对区域起点的偏移（用于打印或比较）
+pub fn offset_of<T>(ptr: *const T) -> usize {
+    ptr as usize - arena_start()
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按固定顺序分配两个对象，返回它们的地址
+    fn place_two() -> (usize, usize) {
+        // 之前分配的对象只以地址的形式保留，不再解引用
+        unsafe { reset() };
+        let a = alloc_value(0x1122_3344u32).unwrap() as *const u32 as usize;
+        let b = alloc_value([7u64; 3]).unwrap() as *const [u64; 3] as usize;
+        (a, b)
+    }
+
+    #[test_case]
+    fn test_placement_is_deterministic() {
+        let (a, b) = place_two();
+        assert_eq!(offset_of(a as *const u8), 0);
+        // u32 之后按 u64 对齐
+        assert_eq!(b - a, 8);
+
+        // 中间的全局堆分配不影响固定区域
+        let noise = alloc::vec![0u8; 100];
+        assert_eq!(place_two(), (a, b));
+        drop(noise);
+
+        // reset 后区域被清零
+        unsafe { reset() };
+        let zeroed = alloc_value(0u64).unwrap() as *mut u64;
+        assert_eq!(zeroed as usize, a);
+        assert_eq!(unsafe { *(b as *const u64) }, 0);
+    }
+}
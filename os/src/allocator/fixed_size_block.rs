@@ -23,6 +23,22 @@ impl FixedSizeBlockAllocator {
     pub unsafe fn init(&mut self, heap_start: usize, heap_size: usize) {
         unsafe { self.fallback_allocator.init(heap_start as *mut u8, heap_size); }
     }
+
+    /// 把一段新的内存交给分配器（堆增长）
+    ///
+    /// # 返回
+    /// 新区域紧接在堆末尾时扩展后备分配器并返回 true；
+    /// 后备分配器只能管理一段连续内存，不相邻的区域返回 false
+    ///
+    /// # Safety
+    /// 调用者必须保证区域有效、未被使用
+    pub unsafe fn extend(&mut self, addr: usize, size: usize) -> bool {
+        if addr != self.fallback_allocator.top() as usize {
+            return false;
+        }
+        unsafe { self.fallback_allocator.extend(size); }
+        true
+    }
 }
 use alloc::alloc::Layout;
 use core::{mem, ptr::NonNull,ptr};
@@ -71,6 +87,11 @@ unsafe impl GlobalAlloc for Locked<FixedSizeBlockAllocator> {
 
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
     let mut allocator = self.lock();
+    if cfg!(feature = "alloc_debug") {
+        if let Err(e) = allocator.check_free(ptr as usize, layout) {
+            panic!("[ALLOCATOR] {}", e);
+        }
+    }
     match list_index(&layout) {
         Some(index) => {
             let new_node = ListNode {
@@ -93,4 +114,317 @@ unsafe impl GlobalAlloc for Locked<FixedSizeBlockAllocator> {
         }
     }
 }
-}
\ No newline at end of file
+}
+
+// ============================================
+// 一致性检查
+// ============================================
+
+/// 堆一致性检查发现的第一个问题
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeapCorruption {
+    /// 空闲块不在堆范围内
+    OutOfBounds { block_size: usize, addr: usize },
+    /// 空闲块没有按块大小对齐
+    Misaligned { block_size: usize, addr: usize },
+    /// 空闲链表成环（节点数超过堆能容纳的块数）
+    Cycle { block_size: usize },
+    /// 两个空闲块互相重叠
+    Overlap { first: usize, second: usize },
+    /// 后备分配器的已用 + 空闲不等于堆大小
+    Accounting { used: usize, free: usize, size: usize },
+    /// 释放的块不在堆范围内（无效释放）
+    InvalidFree { addr: usize, size: usize },
+    /// 释放的块与已经空闲的块 `free` 重叠（重复释放）
+    DoubleFree { addr: usize, free: usize },
+}
+
+impl core::fmt::Display for HeapCorruption {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            HeapCorruption::OutOfBounds { block_size, addr } => {
+                write!(f, "{}-byte free block {:#x} outside the heap", block_size, addr)
+            }
+            HeapCorruption::Misaligned { block_size, addr } => {
+                write!(f, "{}-byte free block {:#x} is misaligned", block_size, addr)
+            }
+            HeapCorruption::Cycle { block_size } => {
+                write!(f, "{}-byte free list contains a cycle", block_size)
+            }
+            HeapCorruption::Overlap { first, second } => {
+                write!(f, "free blocks {:#x} and {:#x} overlap", first, second)
+            }
+            HeapCorruption::Accounting { used, free, size } => {
+                write!(f, "fallback heap used {} + free {} != size {}", used, free, size)
+            }
+            HeapCorruption::InvalidFree { addr, size } => {
+                write!(f, "invalid free: {:#x}..{:#x} is outside the heap", addr, addr + size)
+            }
+            HeapCorruption::DoubleFree { addr, free } => {
+                write!(f, "double free: {:#x} overlaps free block {:#x}", addr, free)
+            }
+        }
+    }
+}
+
+impl FixedSizeBlockAllocator {
+    /// 遍历某个大小类的空闲链表
+    ///
+    /// # 说明
+    /// 每个节点先检查范围和对齐，合法后才读取它的 next，
+    /// 因此损坏的指针不会被解引用
+    fn walk_free_list(
+        &self,
+        index: usize,
+        mut visit: impl FnMut(usize) -> Result<(), HeapCorruption>,
+    ) -> Result<usize, HeapCorruption> {
+        let block_size = BLOCK_SIZES[index];
+        let bottom = self.fallback_allocator.bottom() as usize;
+        let top = self.fallback_allocator.top() as usize;
+        let max_nodes = (top - bottom) / block_size;
+
+        let mut addr = self.list_heads[index]
+            .as_deref()
+            .map_or(0, |node| node as *const ListNode as usize);
+        let mut count = 0;
+
+        while addr != 0 {
+            if addr < bottom || addr + block_size > top {
+                return Err(HeapCorruption::OutOfBounds { block_size, addr });
+            }
+            if !addr.is_multiple_of(block_size) {
+                return Err(HeapCorruption::Misaligned { block_size, addr });
+            }
+            count += 1;
+            if count > max_nodes {
+                return Err(HeapCorruption::Cycle { block_size });
+            }
+            visit(addr)?;
+
+            // ListNode 只有一个 Option<&mut ListNode>，布局与指针相同（None 为 0）
+            addr = unsafe { *(addr as *const usize) };
+        }
+        Ok(count)
+    }
+
+    /// 堆统计信息
+    ///
+    /// # 说明
+    /// 空闲链表长度是各大小类空闲块数之和；链表损坏时只统计到损坏处
+    pub fn heap_stats(&self) -> super::HeapStats {
+        let mut free_bytes = self.fallback_allocator.free();
+        let mut free_list_len = 0;
+        for (index, &block_size) in BLOCK_SIZES.iter().enumerate() {
+            let _ = self.walk_free_list(index, |_| {
+                free_bytes += block_size;
+                free_list_len += 1;
+                Ok(())
+            });
+        }
+        super::HeapStats { free_bytes, free_list_len }
+    }
+
+    /// 检查空闲链表和后备分配器的一致性
+    ///
+    /// # 返回
+    /// - `Ok(n)`: 各大小类空闲链表中共有 n 个块
+    /// - `Err(c)`: 发现的第一个问题
+    ///
+    /// # 说明
+    /// 空闲块之间两两比较是否重叠（O(n²)），只用于按需调试；
+    /// 检查过程不分配内存（调用者通常持有分配器的锁）
+    pub fn check_integrity(&self) -> Result<usize, HeapCorruption> {
+        let size = self.fallback_allocator.size();
+        let used = self.fallback_allocator.used();
+        let free = self.fallback_allocator.free();
+        if used + free != size {
+            return Err(HeapCorruption::Accounting { used, free, size });
+        }
+
+        // 先验证每条链表本身，之后的重叠检查可以放心遍历
+        let mut total = 0;
+        for index in 0..BLOCK_SIZES.len() {
+            total += self.walk_free_list(index, |_| Ok(()))?;
+        }
+
+        for (i, &size_a) in BLOCK_SIZES.iter().enumerate() {
+            let mut pos_a = 0;
+            self.walk_free_list(i, |a| {
+                pos_a += 1;
+                for (j, &size_b) in BLOCK_SIZES.iter().enumerate().skip(i) {
+                    let mut pos_b = 0;
+                    self.walk_free_list(j, |b| {
+                        pos_b += 1;
+                        let later = j > i || pos_b > pos_a;
+                        if later && a < b + size_b && b < a + size_a {
+                            return Err(HeapCorruption::Overlap { first: a, second: b });
+                        }
+                        Ok(())
+                    })?;
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(total)
+    }
+
+    /// 检查即将释放的块（alloc_debug 时每次释放都检查）
+    ///
+    /// # 返回
+    /// 块超出堆的范围（无效释放）或与空闲链表中的块重叠（重复释放）时返回错误
+    ///
+    /// # 说明
+    /// 出错时调用者 panic，而不是把同一个块两次放进空闲链表，
+    /// 之后分配出互相覆盖的内存。后备分配器管理的大块只检查范围；
+    /// 每次释放遍历所有空闲链表，只用于调试
+    fn check_free(&self, addr: usize, layout: Layout) -> Result<(), HeapCorruption> {
+        let size = list_index(&layout).map_or(layout.size(), |index| BLOCK_SIZES[index]);
+        let bottom = self.fallback_allocator.bottom() as usize;
+        let top = self.fallback_allocator.top() as usize;
+        if addr < bottom || addr + size > top {
+            return Err(HeapCorruption::InvalidFree { addr, size });
+        }
+
+        for (index, &block_size) in BLOCK_SIZES.iter().enumerate() {
+            self.walk_free_list(index, |free| {
+                if addr < free + block_size && free < addr + size {
+                    return Err(HeapCorruption::DoubleFree { addr, free });
+                }
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocator::HEAP_SIZE;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    /// 在 `memory` 中按页对齐新建一个 `size` 字节的固定大小块分配器
+    fn arena(memory: &mut Vec<u8>, size: usize) -> Locked<FixedSizeBlockAllocator> {
+        memory.resize(size + 4096, 0);
+        let start = (memory.as_mut_ptr() as usize + 4095) & !4095;
+        let heap = Locked::new(FixedSizeBlockAllocator::new());
+        unsafe { heap.lock().init(start, size) };
+        heap
+    }
+
+    /// 同一大小类反复分配释放，区域远小于总分配量
+    #[test_case]
+    fn test_many_boxes_reuse_blocks() {
+        let mut memory = Vec::new();
+        let heap = arena(&mut memory, 32 * 1024);
+        let layout = Layout::new::<usize>();
+        for i in 0..HEAP_SIZE {
+            let ptr = unsafe { heap.alloc(layout) } as *mut usize;
+            assert!(!ptr.is_null());
+            unsafe {
+                ptr.write(i);
+                assert_eq!(ptr.read(), i);
+                heap.dealloc(ptr as *mut u8, layout);
+            }
+        }
+        assert!(heap.lock().check_integrity().is_ok());
+    }
+
+    /// 按 Vec 的方式倍增扩容，经过各个大小类后落到后备分配器
+    #[test_case]
+    fn test_large_vec_through_all_size_classes() {
+        let mut memory = Vec::new();
+        let heap = arena(&mut memory, 32 * 1024);
+        let n = 1000;
+
+        let mut capacity = 1;
+        let mut layout = Layout::array::<u64>(capacity).unwrap();
+        let mut ptr = unsafe { heap.alloc(layout) } as *mut u64;
+        for i in 0..n {
+            if i == capacity {
+                let new_size = layout.size() * 2;
+                ptr = unsafe { heap.realloc(ptr as *mut u8, layout, new_size) } as *mut u64;
+                assert!(!ptr.is_null());
+                capacity *= 2;
+                layout = Layout::array::<u64>(capacity).unwrap();
+            }
+            unsafe { ptr.add(i).write(i as u64) };
+        }
+
+        let sum: u64 = (0..n).map(|i| unsafe { ptr.add(i).read() }).sum();
+        assert_eq!(sum, (n as u64 - 1) * n as u64 / 2);
+        unsafe { heap.dealloc(ptr as *mut u8, layout) };
+    }
+
+    #[test_case]
+    fn test_check_free_catches_double_and_invalid_free() {
+        let mut memory = Vec::new();
+        let heap = arena(&mut memory, 4096);
+
+        let layout = Layout::new::<u64>();
+        let ptr = unsafe { heap.alloc(layout) };
+        assert!(!ptr.is_null());
+        assert_eq!(heap.lock().check_free(ptr as usize, layout), Ok(()));
+
+        // 释放之后再释放一次：块已经在空闲链表中
+        unsafe { heap.dealloc(ptr, layout) };
+        assert_eq!(
+            heap.lock().check_free(ptr as usize, layout),
+            Err(HeapCorruption::DoubleFree { addr: ptr as usize, free: ptr as usize })
+        );
+
+        // 堆之外的地址
+        let outside = heap.lock().fallback_allocator.top() as usize;
+        assert_eq!(
+            heap.lock().check_free(outside, layout),
+            Err(HeapCorruption::InvalidFree { addr: outside, size: 8 })
+        );
+    }
+
+    #[test_case]
+    fn test_integrity_check_detects_corrupted_node() {
+        const HEAP: usize = 16 * 1024;
+
+        let mut memory = vec![0u8; HEAP + 4096];
+        let start = (memory.as_mut_ptr() as usize + 4095) & !4095;
+        let heap = Locked::new(FixedSizeBlockAllocator::new());
+        unsafe { heap.lock().init(start, HEAP) };
+
+        // 分配后释放，形成空闲链表
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        let blocks: [*mut u8; 3] = core::array::from_fn(|_| unsafe { heap.alloc(layout) });
+        let small = unsafe { heap.alloc(Layout::new::<u64>()) };
+        for &block in &blocks {
+            unsafe { heap.dealloc(block, layout) };
+        }
+        unsafe { heap.dealloc(small, Layout::new::<u64>()) };
+
+        // 健康的堆通过检查
+        assert_eq!(heap.lock().check_integrity(), Ok(4));
+
+        // 把链表头节点的 next 改成未对齐的地址
+        let head = blocks[2] as usize;
+        unsafe { *(head as *mut usize) = head + 8 };
+        assert_eq!(
+            heap.lock().check_integrity(),
+            Err(HeapCorruption::Misaligned { block_size: 64, addr: head + 8 })
+        );
+
+        // 让小块链表指向一个 64 字节空闲块的内部
+        unsafe { *(head as *mut usize) = blocks[1] as usize };
+        let small_index = list_index(&Layout::new::<u64>()).unwrap();
+        let inner = blocks[0] as usize + 8;
+        unsafe { *(small as *mut usize) = inner };
+        assert!(heap.lock().list_heads[small_index].is_some());
+        assert_eq!(
+            heap.lock().check_integrity(),
+            Err(HeapCorruption::Overlap { first: inner, second: blocks[0] as usize })
+        );
+    }
+}
@@ -14,15 +14,143 @@ impl ListNode {
     }
 }
 
+/// 空闲链表长度超过此值时打印碎片化警告
+pub const FRAGMENTATION_THRESHOLD: usize = 64;
+
 pub struct LinkedListAllocator {
     head: ListNode,
+    /// 交给分配器的内存的最低地址（extend 不相邻时为所有区域的包络）
+    bottom: usize,
+    /// 交给分配器的内存的最高地址（不含），未初始化时为 0
+    top: usize,
+    /// 空闲链表中的区域数（碎片化指标）
+    free_regions: usize,
+    /// 释放时是否按地址插入并与相邻空闲区域合并
+    coalesce: bool,
+    /// 当前是否处于已警告状态（长度回落到阈值一半以下后重新计）
+    warned: bool,
+    /// 累计碎片化警告次数
+    fragmentation_warnings: usize,
 }
 
 impl LinkedListAllocator {
-    /// 创建一个空的LinkedListAllocator。
+    /// 创建一个空的LinkedListAllocator（默认合并相邻空闲区域）。
     pub const fn new() -> Self {
         Self {
             head: ListNode::new(0),
+            bottom: 0,
+            top: 0,
+            free_regions: 0,
+            coalesce: true,
+            warned: false,
+            fragmentation_warnings: 0,
+        }
+    }
+
+    /// 打开或关闭空闲区域合并
+    pub fn set_coalescing(&mut self, coalesce: bool) {
+        self.coalesce = coalesce;
+    }
+
+    /// 空闲链表长度
+    pub fn free_list_len(&self) -> usize {
+        self.free_regions
+    }
+
+    /// 累计碎片化警告次数
+    pub fn fragmentation_warnings(&self) -> usize {
+        self.fragmentation_warnings
+    }
+
+    /// 堆统计信息
+    pub fn heap_stats(&self) -> super::HeapStats {
+        let mut free_bytes = 0;
+        let mut current = self.head.next.as_deref();
+        while let Some(region) = current {
+            free_bytes += region.size;
+            current = region.next.as_deref();
+        }
+        super::HeapStats {
+            free_bytes,
+            free_list_len: self.free_regions,
+        }
+    }
+
+    /// 检查空闲链表的一致性
+    ///
+    /// # 返回
+    /// - `Ok(n)`: 空闲链表中共有 n 个区域
+    /// - `Err(c)`: 发现的第一个问题（链表分配器没有大小类，Cycle 的 block_size 为 0）
+    ///
+    /// # 说明
+    /// 区域之间两两比较是否重叠（O(n²)），只用于按需调试
+    pub fn check_integrity(&self) -> Result<usize, super::HeapCorruption> {
+        use super::HeapCorruption;
+
+        let mut count = 0;
+        let mut current = self.head.next.as_deref();
+        while let Some(region) = current {
+            count += 1;
+            if count > self.free_regions {
+                return Err(HeapCorruption::Cycle { block_size: 0 });
+            }
+
+            let mut later = region.next.as_deref();
+            while let Some(other) = later {
+                if region.start_addr() < other.end_addr() && other.start_addr() < region.end_addr() {
+                    return Err(HeapCorruption::Overlap {
+                        first: region.start_addr(),
+                        second: other.start_addr(),
+                    });
+                }
+                later = other.next.as_deref();
+            }
+            current = region.next.as_deref();
+        }
+        Ok(count)
+    }
+
+    /// 检查即将加入空闲链表的区域（alloc_debug 时每次释放都检查）
+    ///
+    /// # 返回
+    /// 区域超出堆的范围（无效释放）或与已经空闲的区域重叠（重复释放、
+    /// 释放后继续使用的指针再次释放）时返回错误
+    ///
+    /// # 说明
+    /// 出错时调用者 panic，而不是让链表出现重叠的节点，
+    /// 之后分配出互相覆盖的内存。每次释放遍历整个空闲链表，只用于调试
+    fn check_free(&self, addr: usize, size: usize) -> Result<(), super::HeapCorruption> {
+        use super::HeapCorruption;
+
+        let end = addr + size;
+        if self.top != 0 && (addr < self.bottom || end > self.top) {
+            return Err(HeapCorruption::InvalidFree { addr, size });
+        }
+
+        let mut current = self.head.next.as_deref();
+        while let Some(region) = current {
+            if addr < region.end_addr() && region.start_addr() < end {
+                return Err(HeapCorruption::DoubleFree { addr, free: region.start_addr() });
+            }
+            current = region.next.as_deref();
+        }
+        Ok(())
+    }
+
+    /// 空闲链表变长时检查是否需要警告
+    fn check_fragmentation(&mut self) {
+        if self.free_regions > FRAGMENTATION_THRESHOLD {
+            if !self.warned {
+                self.warned = true;
+                self.fragmentation_warnings += 1;
+                crate::serial_println!(
+                    "[ALLOCATOR] Warning: free list has {} regions (threshold {}), heap is fragmented",
+                    self.free_regions,
+                    FRAGMENTATION_THRESHOLD
+                );
+            }
+        } else if self.free_regions <= FRAGMENTATION_THRESHOLD / 2 {
+            self.warned = false;
         }
     }
 
@@ -31,31 +159,98 @@ impl LinkedListAllocator {
     /// 这个函数是不安全的，因为调用者必须保证给定的堆边界是有效的并且堆是未使用的。
     /// 此方法只能调用一次
     pub unsafe fn init(&mut self, heap_start: usize, heap_size: usize) {
+        self.bottom = heap_start;
+        self.top = heap_start + heap_size;
         unsafe {
             self.add_free_region(heap_start, heap_size);
         }
     }
 
+    /// 把一段新的内存交给分配器（堆增长）
+    ///
+    /// # 返回
+    /// 总是成功：新区域按地址插入空闲链表，不必与已有的堆相邻，
+    /// 相邻时与末尾的空闲区域合并
+    ///
+    /// # Safety
+    /// 调用者必须保证区域有效、未被使用，且与已交给分配器的内存不重叠
+    pub unsafe fn extend(&mut self, addr: usize, size: usize) -> bool {
+        self.bottom = self.bottom.min(addr);
+        self.top = self.top.max(addr + size);
+        unsafe {
+            self.add_free_region(addr, size);
+        }
+        true
+    }
+
 
 }
 use super::align_up;
 use core::mem;
 
 impl LinkedListAllocator {
-    /// 将给定的内存区域添加到链表前端。
+    /// 将给定的内存区域加入空闲链表。
+    ///
+    /// 关闭合并时加到链表前端；打开合并时按地址顺序插入，
+    /// 并与首尾相接的前后空闲区域合并成一个节点。
     unsafe fn add_free_region(&mut self, addr: usize, size: usize) {
         // 确保给定的内存区域足以存储 ListNode
         assert_eq!(align_up(addr, mem::align_of::<ListNode>()), addr);
         assert!(size >= mem::size_of::<ListNode>());
 
-        // 创建一个新的 ListNode 并将其添加到链表前端
+        if cfg!(feature = "alloc_debug") {
+            if let Err(e) = self.check_free(addr, size) {
+                panic!("[ALLOCATOR] {}", e);
+            }
+        }
+
+        // 关闭合并时插入位置就是链表头
+        let head: *mut ListNode = &mut self.head;
+        let mut prev = head;
+        if self.coalesce {
+            loop {
+                let next = unsafe { (*prev).next.as_deref_mut().map(|n| n as *mut ListNode) };
+                match next {
+                    Some(next) if unsafe { (*next).start_addr() } < addr => prev = next,
+                    _ => break,
+                }
+            }
+        }
+
+        // 创建一个新的 ListNode 并插入到 prev 之后
         let mut node = ListNode::new(size);
-        node.next = self.head.next.take();
+        node.next = unsafe { (*prev).next.take() };
         let node_ptr = addr as *mut ListNode;
+        self.free_regions += 1;
+
         unsafe {
             node_ptr.write(node);
-            self.head.next = Some(&mut *node_ptr)
+
+            if self.coalesce {
+                // 与后一个区域相接：吸收它
+                if let Some(following) = (*node_ptr).next.take() {
+                    if (*node_ptr).end_addr() == following.start_addr() {
+                        (*node_ptr).size += following.size;
+                        (*node_ptr).next = following.next.take();
+                        self.free_regions -= 1;
+                    } else {
+                        (*node_ptr).next = Some(following);
+                    }
+                }
+
+                // 与前一个区域相接：被它吸收
+                if prev != head && (*prev).end_addr() == addr {
+                    (*prev).size += (*node_ptr).size;
+                    (*prev).next = (*node_ptr).next.take();
+                    self.free_regions -= 1;
+                    self.check_fragmentation();
+                    return;
+                }
+            }
+
+            (*prev).next = Some(&mut *node_ptr);
         }
+        self.check_fragmentation();
     }
 }
 impl LinkedListAllocator {
@@ -74,6 +269,7 @@ impl LinkedListAllocator {
                 let next = region.next.take();
                 let ret = Some((current.next.take().unwrap(), alloc_start));
                 current.next = next;
+                self.free_regions -= 1;
                 return ret;
             } else {
                 // 区域不适用 -> 继续下一个区域
@@ -155,4 +351,161 @@ impl LinkedListAllocator {
         let size = layout.size().max(mem::size_of::<ListNode>());
         (size, layout.align())
     }
-}
\ No newline at end of file
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocator::HeapCorruption;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    /// 分配 count 个块后隔一个释放一个，再释放剩下的
+    ///
+    /// # 返回
+    /// 过程中空闲链表的最大长度，以及全部释放后的长度
+    fn fragment(coalesce: bool, count: usize) -> (usize, usize, usize) {
+        const HEAP: usize = 32 * 1024;
+
+        let mut memory = vec![0u8; HEAP + 16];
+        let start = align_up(memory.as_mut_ptr() as usize, 16);
+        let heap = Locked::new(LinkedListAllocator::new());
+        unsafe { heap.lock().init(start, HEAP) };
+        heap.lock().set_coalescing(coalesce);
+
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        let blocks: Vec<*mut u8> = (0..count).map(|_| unsafe { heap.alloc(layout) }).collect();
+        assert!(blocks.iter().all(|b| !b.is_null()));
+
+        let mut max_len = heap.lock().free_list_len();
+        for pass in 0..2 {
+            for block in blocks.iter().skip(pass).step_by(2) {
+                unsafe { heap.dealloc(*block, layout) };
+                max_len = max_len.max(heap.lock().free_list_len());
+            }
+        }
+
+        let allocator = heap.lock();
+        assert_eq!(allocator.heap_stats().free_bytes, HEAP);
+        (max_len, allocator.free_list_len(), allocator.fragmentation_warnings())
+    }
+
+    #[test_case]
+    fn test_coalescing_bounds_free_list_length() {
+        const BLOCKS: usize = FRAGMENTATION_THRESHOLD;
+
+        // 合并：隔一个释放时最多 BLOCKS/2 + 1 个区域，全部释放后合并回一个
+        let (max_len, final_len, warnings) = fragment(true, BLOCKS);
+        assert!(max_len <= BLOCKS / 2 + 1);
+        assert_eq!(final_len, 1);
+        assert_eq!(warnings, 0);
+
+        // 不合并：每次释放都让链表变长，超过阈值时警告
+        let (max_len, final_len, warnings) = fragment(false, BLOCKS);
+        assert_eq!(max_len, BLOCKS + 1);
+        assert_eq!(final_len, BLOCKS + 1);
+        assert_eq!(warnings, 1);
+    }
+
+    #[test_case]
+    fn test_check_free_catches_double_and_invalid_free() {
+        const HEAP: usize = 4 * 1024;
+
+        let mut memory = vec![0u8; HEAP + 16];
+        let start = align_up(memory.as_mut_ptr() as usize, 16);
+        let heap = Locked::new(LinkedListAllocator::new());
+        unsafe { heap.lock().init(start, HEAP) };
+
+        let layout = Layout::new::<u64>();
+        let (size, _) = LinkedListAllocator::size_align(layout);
+        let ptr = unsafe { heap.alloc(layout) };
+        assert!(!ptr.is_null());
+        assert_eq!(heap.lock().check_free(ptr as usize, size), Ok(()));
+
+        // 释放之后再释放一次：与已空闲的区域重叠
+        unsafe { heap.dealloc(ptr, layout) };
+        assert_eq!(
+            heap.lock().check_free(ptr as usize, size),
+            Err(HeapCorruption::DoubleFree { addr: ptr as usize, free: start })
+        );
+
+        // 堆之外的地址
+        assert_eq!(
+            heap.lock().check_free(start + HEAP, size),
+            Err(HeapCorruption::InvalidFree { addr: start + HEAP, size })
+        );
+    }
+
+    #[test_case]
+    fn test_extend_with_non_contiguous_region() {
+        const HEAP: usize = 4 * 1024;
+
+        let mut first = vec![0u8; HEAP + 16];
+        let mut second = vec![0u8; HEAP + 16];
+        let heap = Locked::new(LinkedListAllocator::new());
+        unsafe { heap.lock().init(align_up(first.as_mut_ptr() as usize, 16), HEAP) };
+
+        // 用 1 KB 的块耗尽初始堆
+        let layout = Layout::from_size_align(1024, 8).unwrap();
+        let blocks: Vec<*mut u8> = (0..HEAP / 1024).map(|_| unsafe { heap.alloc(layout) }).collect();
+        assert!(blocks.iter().all(|b| !b.is_null()));
+        assert!(unsafe { heap.alloc(layout) }.is_null());
+
+        // 新区域在另一块内存中，与原来的堆不相邻
+        let extra = align_up(second.as_mut_ptr() as usize, 16);
+        assert!(unsafe { heap.lock().extend(extra, HEAP) });
+        let ptr = unsafe { heap.alloc(layout) };
+        assert_eq!(ptr as usize, extra);
+
+        unsafe { heap.dealloc(ptr, layout) };
+        for block in blocks {
+            unsafe { heap.dealloc(block, layout) };
+        }
+        // 两个区域各自合并，但彼此不相邻
+        assert_eq!(heap.lock().check_integrity(), Ok(2));
+        assert_eq!(heap.lock().heap_stats().free_bytes, 2 * HEAP);
+    }
+
+    #[test_case]
+    fn test_interleaved_frees_leave_room_for_large_block() {
+        const HEAP: usize = 32 * 1024;
+
+        for coalesce in [true, false] {
+            let mut memory = vec![0u8; HEAP + 16];
+            let start = align_up(memory.as_mut_ptr() as usize, 16);
+            let heap = Locked::new(LinkedListAllocator::new());
+            unsafe { heap.lock().init(start, HEAP) };
+            heap.lock().set_coalescing(coalesce);
+
+            // 大小交错的小块，先释放奇数下标，再倒序释放偶数下标
+            let layouts: Vec<Layout> = (0..128)
+                .map(|i| Layout::from_size_align(16 + (i % 5) * 24, 8).unwrap())
+                .collect();
+            let blocks: Vec<*mut u8> =
+                layouts.iter().map(|&layout| unsafe { heap.alloc(layout) }).collect();
+            assert!(blocks.iter().all(|b| !b.is_null()));
+
+            for i in (1..blocks.len()).step_by(2) {
+                unsafe { heap.dealloc(blocks[i], layouts[i]) };
+            }
+            for i in (0..blocks.len()).step_by(2).rev() {
+                unsafe { heap.dealloc(blocks[i], layouts[i]) };
+            }
+            assert_eq!(heap.lock().heap_stats().free_bytes, HEAP);
+
+            // 几乎整个堆的一次分配：只有合并回一个区域时才能成功
+            let large = Layout::from_size_align(HEAP - 64, 8).unwrap();
+            let ptr = unsafe { heap.alloc(large) };
+            assert_eq!(!ptr.is_null(), coalesce);
+            if coalesce {
+                assert_eq!(heap.lock().check_integrity(), Ok(1));
+                unsafe { heap.dealloc(ptr, large) };
+                assert_eq!(heap.lock().free_list_len(), 1);
+            }
+        }
+    }
+}
@@ -0,0 +1,227 @@
+/*
+ * ============================================
+ * 启动阶段报告（Boot Phase Reporter）
+ * ============================================
+ * 功能：把内核启动过程划分为命名阶段，统一打印并计时
+ *
+ * 启动阶段（按顺序）：
+ * - serial：串口输出可用
+ * - trap：陷阱向量与时钟中断
+ * - memory：物理内存管理
+ * - heap：内核堆
+ * - process：进程管理
+ * - fs：文件系统
+ *
+ * 每次调用 report_phase 时结束上一阶段并记录其耗时，
+ * 启动完成后调用 finish 打印各阶段耗时汇总，
+ * 便于发现耗时异常的初始化步骤。
+ *
+ * 说明：记录保存在固定大小数组中，heap 阶段之前也可使用
+ * ============================================
+ */
+
+use spin::Mutex;
+use crate::serial_println;
+
+/// 预期的启动阶段顺序
+pub const BOOT_PHASES: [&str; 6] = ["serial", "trap", "memory", "heap", "process", "fs"];
+
+/// 最多记录的阶段数
+pub const MAX_PHASES: usize = 8;
+
+/// 时钟频率：QEMU virt 机器为 10MHz，即每微秒 10 个 tick
+const TICKS_PER_US: u64 = 10;
+
+/// 单个阶段的记录
+#[derive(Debug, Clone, Copy)]
+pub struct PhaseRecord {
+    /// 阶段名称
+    pub name: &'static str,
+    /// 开始时间（time 寄存器的 tick 数）
+    pub start: u64,
+    /// 耗时（tick 数，阶段尚未结束时为 None）
+    pub duration: Option<u64>,
+}
+
+/// 启动阶段日志
+#[derive(Clone)]
+pub struct BootLog {
+    records: [Option<PhaseRecord>; MAX_PHASES],
+    len: usize,
+}
+
+impl BootLog {
+    /// 创建空日志
+    pub const fn new() -> Self {
+        BootLog {
+            records: [None; MAX_PHASES],
+            len: 0,
+        }
+    }
+
+    /// 开始新阶段，同时结束上一阶段
+    ///
+    /// # 参数
+    /// - `name`: 阶段名称
+    /// - `now`: 当前时间（tick）
+    ///
+    /// # 返回
+    /// 上一阶段的记录（如果有）
+    pub fn begin(&mut self, name: &'static str, now: u64) -> Option<PhaseRecord> {
+        let previous = self.close(now);
+
+        if self.len < MAX_PHASES {
+            self.records[self.len] = Some(PhaseRecord { name, start: now, duration: None });
+            self.len += 1;
+        }
+
+        previous
+    }
+
+    /// 结束当前阶段
+    ///
+    /// # 返回
+    /// 被结束的阶段记录（没有进行中的阶段时为 None）
+    pub fn close(&mut self, now: u64) -> Option<PhaseRecord> {
+        let last = self.len.checked_sub(1)?;
+        let record = self.records[last].as_mut()?;
+
+        if record.duration.is_none() {
+            record.duration = Some(now.saturating_sub(record.start));
+            Some(*record)
+        } else {
+            None
+        }
+    }
+
+    /// 已记录的阶段
+    pub fn records(&self) -> impl Iterator<Item = &PhaseRecord> {
+        self.records[..self.len].iter().flatten()
+    }
+
+    /// 第一个阶段的开始时间
+    fn boot_start(&self) -> Option<u64> {
+        self.records().next().map(|record| record.start)
+    }
+}
+
+impl Default for BootLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 全局启动日志
+static BOOT_LOG: Mutex<BootLog> = Mutex::new(BootLog::new());
+
+/// tick 数转换为微秒
+fn ticks_to_us(ticks: u64) -> u64 {
+    ticks / TICKS_PER_US
+}
+
+/// 报告进入新的启动阶段
+///
+/// # 参数
+/// - `name`: 阶段名称（见 BOOT_PHASES）
+///
+/// # 说明
+/// 打印带时间戳的阶段横幅，并记录上一阶段的耗时
+pub fn report_phase(name: &'static str) {
+    let now = riscv::register::time::read64();
+
+    let (boot_start, previous) = {
+        let mut log = BOOT_LOG.lock();
+        let previous = log.begin(name, now);
+        (log.boot_start().unwrap_or(now), previous)
+    };
+
+    if let Some(previous) = previous {
+        serial_println!(
+            "[BOOT] phase '{}' took {}us",
+            previous.name,
+            ticks_to_us(previous.duration.unwrap_or(0))
+        );
+    }
+
+    let elapsed_us = ticks_to_us(now - boot_start);
+    serial_println!(
+        "[BOOT {:>6}.{:03}ms] ===== {} =====",
+        elapsed_us / 1000,
+        elapsed_us % 1000,
+        name
+    );
+}
+
+/// 结束启动流程，打印各阶段耗时汇总
+pub fn finish() {
+    let now = riscv::register::time::read64();
+    let log = {
+        let mut log = BOOT_LOG.lock();
+        log.close(now);
+        log.clone()
+    };
+
+    serial_println!("[BOOT] Boot phases:");
+    for record in log.records() {
+        serial_println!(
+            "[BOOT]   {:<8} {:>8}us",
+            record.name,
+            ticks_to_us(record.duration.unwrap_or(0))
+        );
+    }
+}
+
+/// 获取已报告的阶段名称（按报告顺序）
+///
+/// # 返回
+/// (名称数组, 有效数量)
+pub fn reported_phases() -> ([&'static str; MAX_PHASES], usize) {
+    let log = BOOT_LOG.lock();
+    let mut names = [""; MAX_PHASES];
+    let mut count = 0;
+
+    for record in log.records() {
+        names[count] = record.name;
+        count += 1;
+    }
+
+    (names, count)
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_init_reports_phases_in_order() {
+        // 测试入口已调用 init()，至少报告了 serial 和 trap
+        let (names, count) = reported_phases();
+        assert!(count >= 2);
+        assert_eq!(&names[..count], &BOOT_PHASES[..count]);
+    }
+
+    #[test_case]
+    fn test_boot_log_records_durations() {
+        let mut log = BootLog::new();
+
+        for (i, name) in BOOT_PHASES.iter().enumerate() {
+            log.begin(name, (i as u64) * 100);
+        }
+        log.close(1000);
+
+        let names: alloc::vec::Vec<_> = log.records().map(|record| record.name).collect();
+        assert_eq!(names, BOOT_PHASES);
+
+        // 前面各阶段耗时 100 tick，最后一个阶段到 close 为止
+        let durations: alloc::vec::Vec<_> = log.records().map(|record| record.duration).collect();
+        assert_eq!(durations[0], Some(100));
+        assert_eq!(durations[5], Some(500));
+
+        // 重复结束不会覆盖已记录的耗时
+        assert!(log.close(2000).is_none());
+    }
+}
@@ -0,0 +1,93 @@
+/*
+ * ============================================
+ * 内核命令
+ * ============================================
+ * 功能：输出系统状态的小命令，供内置程序（user_programs）
+ *       和之后的 shell 调用
+ *
+ * 命令：
+ * - cmd_uptime：开机时间和系统负载
+ * ============================================
+ */
+
+use crate::process::loadavg;
+use alloc::format;
+use alloc::string::String;
+
+/// 每分钟的秒数
+const MINUTE: u64 = 60;
+/// 每小时的秒数
+const HOUR: u64 = 60 * MINUTE;
+/// 每天的秒数
+const DAY: u64 = 24 * HOUR;
+
+/// 格式化 uptime 的输出
+///
+/// # 参数
+/// - `seconds`: 开机以来的秒数
+/// - `loads`: 1、5、15 分钟的负载（定点数，见 loadavg）
+///
+/// # 返回
+/// 如 `up 1 day, 2:03:04, load average: 0.50, 0.20, 0.05`
+pub fn format_uptime(seconds: u64, loads: [usize; 3]) -> String {
+    let mut line = String::from("up ");
+    match seconds / DAY {
+        0 => {}
+        1 => line.push_str("1 day, "),
+        days => line.push_str(&format!("{} days, ", days)),
+    }
+    line.push_str(&format!(
+        "{}:{:02}:{:02}, load average: ",
+        seconds % DAY / HOUR,
+        seconds % HOUR / MINUTE,
+        seconds % MINUTE
+    ));
+
+    let loads = loads.map(loadavg::split_load);
+    line.push_str(&format!(
+        "{}.{:02}, {}.{:02}, {}.{:02}",
+        loads[0].0, loads[0].1, loads[1].0, loads[1].1, loads[2].0, loads[2].1
+    ));
+    line
+}
+
+/// uptime：打印开机时间和 1、5、15 分钟的系统负载
+///
+/// # 说明
+/// 开机时间按时钟中断计数换算（与 sys_sysinfo 相同）
+pub fn cmd_uptime() {
+    crate::println!(
+        "{}",
+        format_uptime(crate::trap::uptime_seconds(), loadavg::load_average())
+    );
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::loadavg::FIXED_1;
+    use crate::trap::{self, TICKS_PER_SECOND};
+
+    #[test_case]
+    fn test_uptime_reports_elapsed_ticks() {
+        const SECONDS: u64 = 2 * MINUTE + 5;
+
+        let before = trap::uptime_seconds();
+        trap::advance_ticks(SECONDS * TICKS_PER_SECOND);
+        assert_eq!(trap::uptime_seconds() - before, SECONDS);
+
+        assert_eq!(
+            format_uptime(SECONDS, [FIXED_1 / 2, FIXED_1 / 5, 0]),
+            "up 0:02:05, load average: 0.50, 0.20, 0.00"
+        );
+        assert_eq!(
+            format_uptime(DAY + 2 * HOUR + 3 * MINUTE + 4, [2 * FIXED_1, 0, 0]),
+            "up 1 day, 2:03:04, load average: 2.00, 0.00, 0.00"
+        );
+        assert!(format_uptime(3 * DAY, [0; 3]).starts_with("up 3 days, 0:00:00"));
+    }
+}
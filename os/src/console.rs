@@ -10,12 +10,15 @@
  */
 
 use core::fmt;
-use spin::Mutex;
+use crate::sync::TicketLock;
 use lazy_static::lazy_static;
 
 lazy_static! {
     /// 全局 Writer 实例
-    pub static ref WRITER: Mutex<Writer> = Mutex::new(Writer::new());
+    ///
+    /// 使用 TicketLock 按先来先得的顺序授予锁，
+    /// 高频打印的任务不会让其他写者一直等不到锁
+    pub static ref WRITER: TicketLock<Writer> = TicketLock::new(Writer::new());
 }
 
 /// 控制台写入器
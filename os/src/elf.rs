@@ -0,0 +1,242 @@
+/*
+ * ============================================
+ * ELF 可执行文件解析
+ * ============================================
+ * 功能：校验 ELF64 文件头，读出入口地址和程序头表
+ *
+ * 支持的文件：
+ * - ELF64、小端
+ * - e_machine = RISC-V（0xF3）
+ * - e_type = ET_EXEC
+ *
+ * 程序头表中的 PT_LOAD 段由 process::exec 装载到进程的地址空间
+ * ============================================
+ */
+
+use alloc::vec::Vec;
+
+/// ELF 魔数
+const ELF_MAGIC: [u8; 4] = [0x7F, b'E', b'L', b'F'];
+
+/// ELF64 文件头大小
+pub const HEADER_SIZE: usize = 64;
+
+/// e_ident[EI_CLASS]：64 位
+const ELFCLASS64: u8 = 2;
+/// e_ident[EI_DATA]：小端
+const ELFDATA2LSB: u8 = 1;
+/// e_type：可执行文件
+const ET_EXEC: u16 = 2;
+/// e_machine：RISC-V
+const EM_RISCV: u16 = 0xF3;
+
+/// 程序头表项大小（ELF64）
+const PHDR_SIZE: usize = 56;
+
+/// p_type：需要装载的段
+pub const PT_LOAD: u32 = 1;
+
+/// p_flags：可执行
+pub const PF_X: u32 = 1;
+/// p_flags：可写
+pub const PF_W: u32 = 2;
+/// p_flags：可读
+pub const PF_R: u32 = 4;
+
+/// ELF 解析错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElfError {
+    /// 数据不足一个文件头
+    TooShort,
+    /// 魔数不对，不是 ELF 文件
+    BadMagic,
+    /// 不是 ELF64 小端
+    UnsupportedClass,
+    /// 不是 RISC-V 可执行文件
+    UnsupportedMachine,
+    /// 不是可执行文件（如目标文件、共享库）
+    NotExecutable,
+    /// 程序头表超出文件范围或段大小不合法
+    BadProgramHeader,
+}
+
+/// 程序头（段）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramHeader {
+    /// 段类型（PT_*）
+    pub p_type: u32,
+    /// 权限（PF_*）
+    pub flags: u32,
+    /// 段数据在文件中的偏移
+    pub offset: usize,
+    /// 装载的虚拟地址
+    pub vaddr: usize,
+    /// 文件中的大小
+    pub filesz: usize,
+    /// 内存中的大小（超出 filesz 的部分清零，即 .bss）
+    pub memsz: usize,
+}
+
+/// 解析出的 ELF 文件头
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElfHeader {
+    /// 入口地址
+    pub entry: usize,
+    /// 程序头表偏移
+    pub phoff: usize,
+    /// 程序头表项大小
+    pub phentsize: usize,
+    /// 程序头表项数
+    pub phnum: usize,
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&data[offset..offset + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// 解析并校验 ELF 文件头
+///
+/// # 参数
+/// - `data`: 文件内容（至少包含完整的文件头）
+pub fn parse(data: &[u8]) -> Result<ElfHeader, ElfError> {
+    if data.len() < HEADER_SIZE {
+        return Err(ElfError::TooShort);
+    }
+    if data[..4] != ELF_MAGIC {
+        return Err(ElfError::BadMagic);
+    }
+    if data[4] != ELFCLASS64 || data[5] != ELFDATA2LSB {
+        return Err(ElfError::UnsupportedClass);
+    }
+    if read_u16(data, 18) != EM_RISCV {
+        return Err(ElfError::UnsupportedMachine);
+    }
+    if read_u16(data, 16) != ET_EXEC {
+        return Err(ElfError::NotExecutable);
+    }
+
+    Ok(ElfHeader {
+        entry: read_u64(data, 24) as usize,
+        phoff: read_u64(data, 32) as usize,
+        phentsize: read_u16(data, 54) as usize,
+        phnum: read_u16(data, 56) as usize,
+    })
+}
+
+/// 读取程序头表
+///
+/// # 参数
+/// - `data`: 文件内容
+/// - `header`: parse 得到的文件头
+///
+/// # 返回
+/// 所有程序头；表或段数据超出文件范围时返回 BadProgramHeader
+pub fn program_headers(data: &[u8], header: &ElfHeader) -> Result<Vec<ProgramHeader>, ElfError> {
+    if header.phnum > 0 && header.phentsize < PHDR_SIZE {
+        return Err(ElfError::BadProgramHeader);
+    }
+    let table_end = header
+        .phentsize
+        .checked_mul(header.phnum)
+        .and_then(|size| size.checked_add(header.phoff))
+        .ok_or(ElfError::BadProgramHeader)?;
+    if table_end > data.len() {
+        return Err(ElfError::BadProgramHeader);
+    }
+
+    let mut headers = Vec::with_capacity(header.phnum);
+    for i in 0..header.phnum {
+        let base = header.phoff + i * header.phentsize;
+        let phdr = ProgramHeader {
+            p_type: read_u32(data, base),
+            flags: read_u32(data, base + 4),
+            offset: read_u64(data, base + 8) as usize,
+            vaddr: read_u64(data, base + 16) as usize,
+            filesz: read_u64(data, base + 32) as usize,
+            memsz: read_u64(data, base + 40) as usize,
+        };
+
+        let data_end = phdr.offset.checked_add(phdr.filesz).ok_or(ElfError::BadProgramHeader)?;
+        if phdr.p_type == PT_LOAD && (data_end > data.len() || phdr.filesz > phdr.memsz) {
+            return Err(ElfError::BadProgramHeader);
+        }
+        headers.push(phdr);
+    }
+    Ok(headers)
+}
+
+/// 构造只有文件头的 RISC-V 可执行文件（用于测试）
+///
+/// # 参数
+/// - `entry`: 入口地址
+#[cfg(test)]
+pub(crate) fn build_executable(entry: usize) -> Vec<u8> {
+    let mut elf = alloc::vec![0u8; HEADER_SIZE];
+    elf[..4].copy_from_slice(&ELF_MAGIC);
+    elf[4] = ELFCLASS64;
+    elf[5] = ELFDATA2LSB;
+    elf[6] = 1; // EI_VERSION
+    elf[16..18].copy_from_slice(&ET_EXEC.to_le_bytes());
+    elf[18..20].copy_from_slice(&EM_RISCV.to_le_bytes());
+    elf[20..24].copy_from_slice(&1u32.to_le_bytes()); // e_version
+    elf[24..32].copy_from_slice(&(entry as u64).to_le_bytes());
+    elf[52..54].copy_from_slice(&(HEADER_SIZE as u16).to_le_bytes()); // e_ehsize
+    elf[54..56].copy_from_slice(&56u16.to_le_bytes()); // e_phentsize
+    elf
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_parse_header() {
+        let elf = build_executable(0x8040_0000);
+        let header = parse(&elf).unwrap();
+        assert_eq!(header.entry, 0x8040_0000);
+        assert_eq!((header.phoff, header.phentsize, header.phnum), (0, 56, 0));
+
+        assert_eq!(parse(&elf[..32]), Err(ElfError::TooShort));
+        assert_eq!(parse(&[0u8; HEADER_SIZE]), Err(ElfError::BadMagic));
+
+        let mut object = elf.clone();
+        object[16] = 1; // ET_REL
+        assert_eq!(parse(&object), Err(ElfError::NotExecutable));
+
+        let mut x86 = elf;
+        x86[18] = 0x3E;
+        assert_eq!(parse(&x86), Err(ElfError::UnsupportedMachine));
+    }
+
+    #[test_case]
+    fn test_program_headers_of_embedded_program() {
+        let data = crate::user_programs::find("exit7").unwrap();
+        let header = parse(data).unwrap();
+        let segments = program_headers(data, &header).unwrap();
+
+        let load: Vec<_> = segments.iter().filter(|phdr| phdr.p_type == PT_LOAD).collect();
+        assert_eq!(load.len(), 1);
+        assert_eq!(load[0].vaddr, header.entry);
+        assert_eq!(load[0].flags & (PF_R | PF_X), PF_R | PF_X);
+        assert!(load[0].filesz > 0 && load[0].filesz <= load[0].memsz);
+
+        // 程序头表超出文件范围
+        assert_eq!(program_headers(&data[..header.phoff + 8], &header), Err(ElfError::BadProgramHeader));
+    }
+}
@@ -0,0 +1,89 @@
+/*
+ * ============================================
+ * 故障注入（仅测试构建）
+ * ============================================
+ * 功能：让测试确定性地触发难以构造的错误分支
+ *
+ * 用法：
+ * - fault::fail_next(Fault::FrameAlloc, 1) 让下一次帧分配失败
+ * - 被注入的代码点调用 fault::should_fail(...)，
+ *   计数未用完时返回 true 并减一
+ * - 测试结束前调用 fault::reset() 清除未用完的注入
+ *
+ * 注入点：
+ * - FrameAlloc：SimpleFrameAllocator::allocate、进程栈分配、内核陷阱栈分配（create_process）
+ * - FsWrite：RamInode::write_at 返回 IoError
+ *
+ * 测试运行器是单线程的，注入计数是全局的
+ * ============================================
+ */
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// 可注入的故障
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// 物理帧 / 进程内存分配失败
+    FrameAlloc,
+    /// 文件系统写入返回 IoError
+    FsWrite,
+}
+
+/// 故障种类数
+const FAULT_COUNT: usize = 2;
+
+/// 每种故障剩余的注入次数
+static PENDING: [AtomicUsize; FAULT_COUNT] = [const { AtomicUsize::new(0) }; FAULT_COUNT];
+
+/// 让接下来的 `count` 次 `fault` 注入点失败
+pub fn fail_next(fault: Fault, count: usize) {
+    PENDING[fault as usize].store(count, Ordering::SeqCst);
+}
+
+/// 注入点：本次是否应当失败
+pub fn should_fail(fault: Fault) -> bool {
+    PENDING[fault as usize]
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .is_ok()
+}
+
+/// 清除所有未用完的注入
+pub fn reset() {
+    for pending in &PENDING {
+        pending.store(0, Ordering::SeqCst);
+    }
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{create_process, ProcessError};
+
+    #[test_case]
+    fn test_injected_frame_alloc_failure_makes_create_process_oom() {
+        fail_next(Fault::FrameAlloc, 1);
+        assert_eq!(
+            create_process("fault_oom", 0x1000, 0x2000, None).err(),
+            Some(ProcessError::OutOfMemory)
+        );
+
+        // 注入只生效一次
+        assert!(create_process("fault_ok", 0x1000, 0x2000, None).is_ok());
+        reset();
+    }
+
+    #[test_case]
+    fn test_injected_fs_write_failure() {
+        use crate::fs::{FileError, RamInode};
+
+        let mut inode = RamInode::new_file(usize::MAX);
+        fail_next(Fault::FsWrite, 1);
+        assert_eq!(inode.write_at(0, b"data"), Err(FileError::IoError));
+        assert_eq!(inode.write_at(0, b"data"), Ok(4));
+        reset();
+    }
+}
@@ -0,0 +1,271 @@
+/*
+ * ============================================
+ * 设备树（Flattened Device Tree）读取
+ * ============================================
+ * 功能：从 SBI 传入的设备树二进制（DTB）中读取节点属性
+ *
+ * DTB 布局（所有整数为大端序）：
+ * - 头部：magic、totalsize、结构块和字符串块的偏移与大小等
+ * - 结构块：一串 32 位对齐的 token
+ *   BEGIN_NODE(名字) / END_NODE / PROP(长度, 名字偏移, 值) / NOP / END
+ * - 字符串块：属性名
+ *
+ * 说明：
+ * - 只实现按路径查找属性，足够读取 /chosen 中的启动参数
+ * - 不分配堆内存，堆初始化之前也可以使用
+ * ============================================
+ */
+
+/// DTB 头部的 magic
+pub const FDT_MAGIC: u32 = 0xd00d_feed;
+
+/// 结构块 token
+const FDT_BEGIN_NODE: u32 = 1;
+const FDT_END_NODE: u32 = 2;
+const FDT_PROP: u32 = 3;
+const FDT_NOP: u32 = 4;
+const FDT_END: u32 = 9;
+
+/// 头部大小（本实现读取的字段）
+const HEADER_SIZE: usize = 40;
+
+/// 只读的设备树视图
+pub struct Fdt<'a> {
+    data: &'a [u8],
+    structs: &'a [u8],
+    strings: &'a [u8],
+}
+
+/// 读取大端 u32
+fn be_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// 以 NUL 结尾的字符串
+fn c_str(data: &[u8]) -> Option<&str> {
+    let len = data.iter().position(|&b| b == 0)?;
+    core::str::from_utf8(&data[..len]).ok()
+}
+
+/// 按 4 字节向上对齐
+fn align4(n: usize) -> usize {
+    (n + 3) & !3
+}
+
+impl<'a> Fdt<'a> {
+    /// 从字节切片解析设备树
+    ///
+    /// # 返回
+    /// magic 不对或各块超出 totalsize 时返回 None
+    pub fn new(data: &'a [u8]) -> Option<Self> {
+        if data.len() < HEADER_SIZE || be_u32(data, 0)? != FDT_MAGIC {
+            return None;
+        }
+        let total = be_u32(data, 4)? as usize;
+        let data = data.get(..total)?;
+
+        let off_struct = be_u32(data, 8)? as usize;
+        let off_strings = be_u32(data, 12)? as usize;
+        let size_strings = be_u32(data, 32)? as usize;
+        let size_struct = be_u32(data, 36)? as usize;
+
+        Some(Fdt {
+            data,
+            structs: data.get(off_struct..off_struct.checked_add(size_struct)?)?,
+            strings: data.get(off_strings..off_strings.checked_add(size_strings)?)?,
+        })
+    }
+
+    /// 从物理地址解析设备树
+    ///
+    /// # Safety
+    /// `addr` 必须指向有效的 DTB，且在返回值存活期间不会被修改
+    pub unsafe fn from_addr(addr: usize) -> Option<Fdt<'static>> {
+        if addr == 0 {
+            return None;
+        }
+        let header = core::slice::from_raw_parts(addr as *const u8, HEADER_SIZE);
+        if be_u32(header, 0)? != FDT_MAGIC {
+            return None;
+        }
+        let total = be_u32(header, 4)? as usize;
+        Fdt::new(core::slice::from_raw_parts(addr as *const u8, total))
+    }
+
+    /// DTB 总大小
+    pub fn total_size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// 按节点路径查找属性值
+    ///
+    /// # 参数
+    /// - `path`: 节点路径，如 "/chosen"；组件不带 @unit 时忽略节点名中的单元地址
+    /// - `name`: 属性名
+    pub fn property(&self, path: &str, name: &str) -> Option<&'a [u8]> {
+        let target_depth = path.split('/').filter(|c| !c.is_empty()).count() + 1;
+        let component = |depth: usize| path.split('/').filter(|c| !c.is_empty()).nth(depth - 2);
+
+        // depth：当前打开的节点数；matched：其中沿路径匹配的层数
+        let mut depth = 0;
+        let mut matched = 0;
+        let mut offset = 0;
+
+        loop {
+            let token = be_u32(self.structs, offset)?;
+            offset += 4;
+            match token {
+                FDT_BEGIN_NODE => {
+                    let node = c_str(self.structs.get(offset..)?)?;
+                    offset = align4(offset + node.len() + 1);
+                    depth += 1;
+                    if matched == depth - 1 {
+                        let hit = depth == 1 || component(depth).is_some_and(|want| {
+                            node == want || (!want.contains('@') && node.split('@').next() == Some(want))
+                        });
+                        if hit {
+                            matched = depth;
+                        }
+                    }
+                }
+                FDT_END_NODE => {
+                    if depth == 0 {
+                        return None;
+                    }
+                    if matched == depth {
+                        matched -= 1;
+                    }
+                    depth -= 1;
+                }
+                FDT_PROP => {
+                    let len = be_u32(self.structs, offset)? as usize;
+                    let name_off = be_u32(self.structs, offset + 4)? as usize;
+                    let value = self.structs.get(offset + 8..offset + 8 + len)?;
+                    offset = align4(offset + 8 + len);
+                    if matched == depth
+                        && depth == target_depth
+                        && c_str(self.strings.get(name_off..)?)? == name
+                    {
+                        return Some(value);
+                    }
+                }
+                FDT_NOP => {}
+                FDT_END => return None,
+                _ => return None,
+            }
+        }
+    }
+
+    /// 读取整数属性（1 或 2 个 cell）
+    pub fn property_usize(&self, path: &str, name: &str) -> Option<usize> {
+        let value = self.property(path, name)?;
+        match value.len() {
+            4 => Some(be_u32(value, 0)? as usize),
+            8 => Some(((be_u32(value, 0)? as u64) << 32 | be_u32(value, 4)? as u64) as usize),
+            _ => None,
+        }
+    }
+
+    /// 读取字符串属性
+    pub fn property_str(&self, path: &str, name: &str) -> Option<&'a str> {
+        c_str(self.property(path, name)?)
+    }
+}
+
+// ============================================
+// 测试辅助：构造最小的 DTB
+// ============================================
+
+/// 构造只有根节点和 /chosen 的 DTB（用于测试）
+///
+/// # 参数
+/// - `props`: /chosen 下的属性（名字, 值）
+#[cfg(test)]
+pub(crate) fn build_chosen_dtb(props: &[(&str, &[u8])]) -> alloc::vec::Vec<u8> {
+    use alloc::vec::Vec;
+
+    fn push_u32(buf: &mut Vec<u8>, v: u32) {
+        buf.extend_from_slice(&v.to_be_bytes());
+    }
+    fn pad(buf: &mut Vec<u8>) {
+        buf.resize(align4(buf.len()), 0);
+    }
+
+    let mut structs = Vec::new();
+    let mut strings = Vec::new();
+
+    push_u32(&mut structs, FDT_BEGIN_NODE);
+    structs.push(0);
+    pad(&mut structs);
+    push_u32(&mut structs, FDT_BEGIN_NODE);
+    structs.extend_from_slice(b"chosen\0");
+    pad(&mut structs);
+    for (name, value) in props {
+        push_u32(&mut structs, FDT_PROP);
+        push_u32(&mut structs, value.len() as u32);
+        push_u32(&mut structs, strings.len() as u32);
+        structs.extend_from_slice(value);
+        pad(&mut structs);
+        strings.extend_from_slice(name.as_bytes());
+        strings.push(0);
+    }
+    push_u32(&mut structs, FDT_END_NODE);
+    push_u32(&mut structs, FDT_END_NODE);
+    push_u32(&mut structs, FDT_END);
+
+    let off_struct = HEADER_SIZE;
+    let off_strings = off_struct + structs.len();
+    let total = off_strings + strings.len();
+
+    let mut dtb = Vec::new();
+    for field in [
+        FDT_MAGIC,
+        total as u32,
+        off_struct as u32,
+        off_strings as u32,
+        HEADER_SIZE as u32, // off_mem_rsvmap（未使用）
+        17,
+        16,
+        0,
+        strings.len() as u32,
+        structs.len() as u32,
+    ] {
+        push_u32(&mut dtb, field);
+    }
+    dtb.extend_from_slice(&structs);
+    dtb.extend_from_slice(&strings);
+    dtb
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_chosen_properties() {
+        let dtb = build_chosen_dtb(&[
+            ("linux,initrd-start", &0x8400_0000u64.to_be_bytes()),
+            ("linux,initrd-end", &0x8400_2000u32.to_be_bytes()),
+            ("bootargs", b"console=ttyS0\0"),
+        ]);
+        let fdt = Fdt::new(&dtb).unwrap();
+
+        assert_eq!(fdt.total_size(), dtb.len());
+        assert_eq!(fdt.property_usize("/chosen", "linux,initrd-start"), Some(0x8400_0000));
+        assert_eq!(fdt.property_usize("/chosen", "linux,initrd-end"), Some(0x8400_2000));
+        assert_eq!(fdt.property_str("/chosen", "bootargs"), Some("console=ttyS0"));
+        assert_eq!(fdt.property("/chosen", "missing"), None);
+        assert_eq!(fdt.property("/", "bootargs"), None);
+        assert_eq!(fdt.property("/memory", "bootargs"), None);
+
+        // magic 错误
+        let mut bad = dtb.clone();
+        bad[0] = 0;
+        assert!(Fdt::new(&bad).is_none());
+    }
+}
@@ -0,0 +1,205 @@
+//! 原始内存设备（/dev/mem，仅调试构建）和 /dev/null、/dev/zero
+//!
+//! 通过普通的文件读写访问物理内存：文件偏移即物理地址。
+//! 内核对物理内存是恒等映射的，偏移直接当作指针使用。
+//! 该设备可以读写任意内存（包括内核自身），只在调试构建
+//! （debug_assertions）中提供，发布构建中打开会得到 NotFound。
+//!
+//! /dev/null 读到的总是文件末尾（Ok(0)），/dev/zero 读出无穷的零字节；
+//! 两者都丢弃写入的数据，所有构建中都提供。
+
+use super::file::{File, FileError, FileMetadata, FileType, SeekFrom};
+use super::inode::permissions;
+use alloc::sync::Arc;
+use spin::Mutex;
+
+/// 设备路径
+pub const DEV_MEM_PATH: &str = "/dev/mem";
+pub const DEV_NULL_PATH: &str = "/dev/null";
+pub const DEV_ZERO_PATH: &str = "/dev/zero";
+
+/// /dev/null 和 /dev/zero 的权限（所有人可读写）
+const DEV_RW_ALL: u32 = permissions::S_IRUSR
+    | permissions::S_IWUSR
+    | permissions::S_IRGRP
+    | permissions::S_IWGRP
+    | permissions::S_IROTH
+    | permissions::S_IWOTH;
+
+/// 当前构建是否提供 /dev/mem
+pub const fn dev_mem_enabled() -> bool {
+    cfg!(debug_assertions)
+}
+
+/// 原始内存设备
+pub struct DevMem {
+    /// 当前读写位置（物理地址）
+    pos: usize,
+}
+
+impl DevMem {
+    pub fn new() -> Self {
+        DevMem { pos: 0 }
+    }
+}
+
+impl Default for DevMem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl File for DevMem {
+    /// 从当前地址读取 `buf.len()` 字节
+    ///
+    /// # 说明
+    /// 按字节 volatile 读取，调用者负责保证地址范围可访问
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, FileError> {
+        let end = self.pos.checked_add(buf.len()).ok_or(FileError::InvalidOperation)?;
+        for (i, byte) in buf.iter_mut().enumerate() {
+            *byte = unsafe { core::ptr::read_volatile((self.pos + i) as *const u8) };
+        }
+        self.pos = end;
+        Ok(buf.len())
+    }
+
+    /// 向当前地址写入 `buf`
+    fn write(&mut self, buf: &[u8]) -> Result<usize, FileError> {
+        let end = self.pos.checked_add(buf.len()).ok_or(FileError::InvalidOperation)?;
+        for (i, &byte) in buf.iter().enumerate() {
+            unsafe { core::ptr::write_volatile((self.pos + i) as *mut u8, byte) };
+        }
+        self.pos = end;
+        Ok(buf.len())
+    }
+
+    /// 设备没有大小，不支持从末尾定位
+    fn seek(&mut self, pos: SeekFrom) -> Result<usize, FileError> {
+        self.pos = match pos {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::Current(delta) => self
+                .pos
+                .checked_add_signed(delta)
+                .ok_or(FileError::InvalidOperation)?,
+            SeekFrom::End(_) => return Err(FileError::InvalidOperation),
+        };
+        Ok(self.pos)
+    }
+
+    fn stat(&self) -> Result<FileMetadata, FileError> {
+        Ok(FileMetadata::new(FileType::CharDevice, 0, permissions::S_IRUSR | permissions::S_IWUSR))
+    }
+}
+
+/// 空设备（/dev/null）
+pub struct DevNull;
+
+impl File for DevNull {
+    /// 总是文件末尾
+    fn read(&mut self, _buf: &mut [u8]) -> Result<usize, FileError> {
+        Ok(0)
+    }
+
+    /// 丢弃写入的数据
+    fn write(&mut self, buf: &[u8]) -> Result<usize, FileError> {
+        Ok(buf.len())
+    }
+
+    /// 设备没有内容，任何定位都停在 0
+    fn seek(&mut self, _pos: SeekFrom) -> Result<usize, FileError> {
+        Ok(0)
+    }
+
+    fn stat(&self) -> Result<FileMetadata, FileError> {
+        Ok(FileMetadata::new(FileType::CharDevice, 0, DEV_RW_ALL))
+    }
+}
+
+/// 零设备（/dev/zero）
+pub struct DevZero;
+
+impl File for DevZero {
+    /// 用零填满 `buf`，永远不会到达文件末尾
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, FileError> {
+        buf.fill(0);
+        Ok(buf.len())
+    }
+
+    /// 丢弃写入的数据
+    fn write(&mut self, buf: &[u8]) -> Result<usize, FileError> {
+        Ok(buf.len())
+    }
+
+    /// 设备没有大小，任何定位都停在 0
+    fn seek(&mut self, _pos: SeekFrom) -> Result<usize, FileError> {
+        Ok(0)
+    }
+
+    fn stat(&self) -> Result<FileMetadata, FileError> {
+        Ok(FileMetadata::new(FileType::CharDevice, 0, DEV_RW_ALL))
+    }
+}
+
+/// 按路径打开设备文件
+///
+/// # 参数
+/// - `path`: 设备路径
+/// - `debug`: 是否为调试构建（决定 /dev/mem 是否存在）
+///
+/// # 返回
+/// 不是设备路径或设备未启用时返回 NotFound
+fn open_with(path: &str, debug: bool) -> Result<Arc<Mutex<dyn File>>, FileError> {
+    match path {
+        DEV_MEM_PATH if debug => Ok(Arc::new(Mutex::new(DevMem::new()))),
+        DEV_NULL_PATH => Ok(Arc::new(Mutex::new(DevNull))),
+        DEV_ZERO_PATH => Ok(Arc::new(Mutex::new(DevZero))),
+        _ => Err(FileError::NotFound),
+    }
+}
+
+/// 按路径打开设备文件（sys_open 在查找 RamFS 之前调用）
+pub fn open_device(path: &str) -> Result<Arc<Mutex<dyn File>>, FileError> {
+    open_with(path, dev_mem_enabled())
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    #[cfg(debug_assertions)]
+    fn test_dev_mem_reads_and_writes_physical_memory() {
+        static mut SCRATCH: [u8; 8] = *b"devmem!\0";
+        let addr = core::ptr::addr_of_mut!(SCRATCH) as usize;
+
+        let dev = open_device(DEV_MEM_PATH).unwrap();
+        let mut dev = dev.lock();
+
+        // 偏移即地址：读出已知内容
+        let mut buf = [0u8; 7];
+        assert_eq!(dev.seek(SeekFrom::Start(addr)), Ok(addr));
+        assert_eq!(dev.read(&mut buf), Ok(7));
+        assert_eq!(&buf, b"devmem!");
+
+        // 写入后再读回，同时直接检查内存
+        dev.seek(SeekFrom::Start(addr)).unwrap();
+        assert_eq!(dev.write(b"patched"), Ok(7));
+        assert_eq!(unsafe { core::ptr::read_volatile(core::ptr::addr_of!(SCRATCH)) }, *b"patched\0");
+        assert_eq!(dev.seek(SeekFrom::Current(-7)), Ok(addr));
+        dev.read(&mut buf).unwrap();
+        assert_eq!(&buf, b"patched");
+
+        assert_eq!(dev.stat().unwrap().file_type, FileType::CharDevice);
+    }
+
+    #[test_case]
+    fn test_dev_mem_absent_in_non_debug_mode() {
+        assert_eq!(open_with(DEV_MEM_PATH, false).err(), Some(FileError::NotFound));
+        assert_eq!(open_device(DEV_MEM_PATH).is_ok(), dev_mem_enabled());
+        assert_eq!(open_device("/dev/tty9").err(), Some(FileError::NotFound));
+    }
+}
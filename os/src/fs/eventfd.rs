@@ -0,0 +1,210 @@
+//! 事件通知文件（eventfd）
+//!
+//! 内部是一个 u64 计数器：
+//! - write：写入 8 字节（小端 u64），加到计数器上，并唤醒等待的读者
+//! - read：计数器非零时读出 8 字节的计数值并清零；
+//!   为零时阻塞（非阻塞模式下返回 WouldBlock）
+//!
+//! 阻塞由 sys_read 完成：read 返回 WouldBlock 后通过 `register_reader`
+//! 登记当前进程，释放文件锁后再阻塞，写者唤醒后重试读取。
+
+use super::file::{File, FileError, FileMetadata, FileType, SeekFrom};
+use super::inode::permissions;
+use crate::process::{self, ProcessId};
+use alloc::vec::Vec;
+
+/// sys_eventfd 标志：非阻塞
+pub const EFD_NONBLOCK: usize = 0o4000;
+/// sys_eventfd 标志：exec 时关闭（还没有 exec，接受但忽略）
+pub const EFD_CLOEXEC: usize = 0o2000000;
+
+/// 读写的数据大小（一个 u64）
+const COUNTER_SIZE: usize = core::mem::size_of::<u64>();
+
+/// 计数器的最大值（与 Linux 一致，u64::MAX 保留）
+const COUNTER_MAX: u64 = u64::MAX - 1;
+
+/// 事件通知文件
+pub struct EventFd {
+    /// 计数器
+    counter: u64,
+    /// 非阻塞模式
+    nonblocking: bool,
+    /// 等待计数器变为非零的进程
+    waiters: Vec<ProcessId>,
+}
+
+impl EventFd {
+    /// 创建事件通知文件
+    ///
+    /// # 参数
+    /// - `initval`: 计数器初值
+    /// - `nonblocking`: 计数器为零时读取是否立即返回 WouldBlock
+    pub fn new(initval: u64, nonblocking: bool) -> Self {
+        EventFd {
+            counter: initval,
+            nonblocking,
+            waiters: Vec::new(),
+        }
+    }
+
+    /// 当前计数值
+    pub fn counter(&self) -> u64 {
+        self.counter
+    }
+}
+
+impl File for EventFd {
+    /// 读出计数值并清零
+    ///
+    /// # 说明
+    /// 缓冲区小于 8 字节返回 InvalidOperation；计数器为零返回 WouldBlock
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, FileError> {
+        if buf.len() < COUNTER_SIZE {
+            return Err(FileError::InvalidOperation);
+        }
+        if self.counter == 0 {
+            return Err(FileError::WouldBlock);
+        }
+
+        buf[..COUNTER_SIZE].copy_from_slice(&self.counter.to_le_bytes());
+        self.counter = 0;
+        Ok(COUNTER_SIZE)
+    }
+
+    /// 把写入的 u64 加到计数器上
+    ///
+    /// # 说明
+    /// 缓冲区小于 8 字节或值为 u64::MAX 时返回 InvalidOperation；
+    /// 相加会超过上限时返回 WouldBlock（计数器不变）
+    fn write(&mut self, buf: &[u8]) -> Result<usize, FileError> {
+        if buf.len() < COUNTER_SIZE {
+            return Err(FileError::InvalidOperation);
+        }
+        let mut bytes = [0u8; COUNTER_SIZE];
+        bytes.copy_from_slice(&buf[..COUNTER_SIZE]);
+        let value = u64::from_le_bytes(bytes);
+        if value == u64::MAX {
+            return Err(FileError::InvalidOperation);
+        }
+
+        self.counter = match self.counter.checked_add(value) {
+            Some(sum) if sum <= COUNTER_MAX => sum,
+            _ => return Err(FileError::WouldBlock),
+        };
+
+        if self.counter > 0 {
+            for pid in self.waiters.drain(..) {
+                process::wake_up_process(pid);
+            }
+        }
+        Ok(COUNTER_SIZE)
+    }
+
+    /// 不支持定位
+    fn seek(&mut self, _pos: SeekFrom) -> Result<usize, FileError> {
+        Err(FileError::NotSeekable)
+    }
+
+    /// 阻塞模式下登记当前进程，等待下一次写入
+    fn register_reader(&mut self) -> bool {
+        if self.nonblocking {
+            return false;
+        }
+        // 内核上下文没有进程可以阻塞
+        let Some(pid) = process::current_pid() else {
+            return false;
+        };
+        // 写者持有同一把文件锁才能唤醒，在这里标记阻塞不会错过唤醒
+        if !process::prepare_block_current_process() {
+            return false;
+        }
+        if !self.waiters.contains(&pid) {
+            self.waiters.push(pid);
+        }
+        true
+    }
+
+    fn stat(&self) -> Result<FileMetadata, FileError> {
+        Ok(FileMetadata::new(FileType::CharDevice, 0, permissions::S_IRUSR | permissions::S_IWUSR))
+    }
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_u64(file: &mut EventFd, value: u64) -> Result<usize, FileError> {
+        file.write(&value.to_le_bytes())
+    }
+
+    #[test_case]
+    fn test_eventfd_read_returns_sum_of_writes() {
+        let mut efd = EventFd::new(0, false);
+        for value in [1, 2, 3] {
+            assert_eq!(write_u64(&mut efd, value), Ok(8));
+        }
+
+        let mut buf = [0u8; 8];
+        assert_eq!(efd.read(&mut buf), Ok(8));
+        assert_eq!(u64::from_le_bytes(buf), 6);
+        assert_eq!(efd.counter(), 0);
+
+        // 读取后清零
+        assert_eq!(efd.read(&mut buf), Err(FileError::WouldBlock));
+    }
+
+    #[test_case]
+    fn test_eventfd_rejects_short_buffers_and_overflow() {
+        let mut efd = EventFd::new(COUNTER_MAX, true);
+        assert_eq!(efd.write(&[1, 0, 0]), Err(FileError::InvalidOperation));
+        assert_eq!(efd.read(&mut [0u8; 4]), Err(FileError::InvalidOperation));
+        assert_eq!(write_u64(&mut efd, u64::MAX), Err(FileError::InvalidOperation));
+        assert_eq!(write_u64(&mut efd, 1), Err(FileError::WouldBlock));
+        assert_eq!(efd.counter(), COUNTER_MAX);
+    }
+
+    #[test_case]
+    fn test_eventfd_zero_read_waits_only_in_blocking_mode() {
+        let mut nonblocking = EventFd::new(0, true);
+        assert_eq!(nonblocking.read(&mut [0u8; 8]), Err(FileError::WouldBlock));
+        assert!(!nonblocking.register_reader());
+
+        // 阻塞模式会登记等待者；内核上下文没有当前进程，不能阻塞
+        let mut blocking = EventFd::new(0, false);
+        assert_eq!(blocking.read(&mut [0u8; 8]), Err(FileError::WouldBlock));
+        assert_eq!(process::current_pid(), None);
+        assert!(!blocking.register_reader());
+    }
+
+    #[test_case]
+    fn test_wakeup_between_register_and_block_is_not_lost() {
+        use crate::process::{create_process, scheduler, ProcessState};
+
+        let reader = create_process("efd_reader", 0x1000, 0x2000, None).unwrap();
+        let pid = reader.lock().pid();
+        scheduler::add_process(reader.clone()).unwrap();
+        scheduler::lock_scheduler().run_for_test(pid);
+
+        // 登记时已经标记为阻塞
+        let mut efd = EventFd::new(0, false);
+        assert_eq!(efd.read(&mut [0u8; 8]), Err(FileError::WouldBlock));
+        assert!(efd.register_reader());
+        assert_eq!(reader.lock().state(), ProcessState::Blocked);
+
+        // sys_read 释放文件锁、还没让出 CPU 时写者到来：唤醒生效，
+        // 之后完成阻塞时不再让出 CPU，重试读取得到数据
+        assert_eq!(write_u64(&mut efd, 1), Ok(8));
+        assert_eq!(reader.lock().state(), ProcessState::Ready);
+        assert!(!scheduler::lock_scheduler().finish_block());
+        assert_eq!(reader.lock().state(), ProcessState::Running);
+        assert_eq!(scheduler::current_pid(), Some(pid));
+        assert_eq!(efd.read(&mut [0u8; 8]), Ok(8));
+
+        scheduler::lock_scheduler().remove_process(pid);
+    }
+}
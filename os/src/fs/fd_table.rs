@@ -1,8 +1,10 @@
 //! 文件描述符表
 
-use super::file::File;
+use super::file::{File, FileError, SeekFrom};
+use alloc::collections::BinaryHeap;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::cmp::Reverse;
 use spin::Mutex;
 
 pub type FileDescriptor = usize;
@@ -11,31 +13,152 @@ pub const STDIN: FileDescriptor = 0;
 pub const STDOUT: FileDescriptor = 1;
 pub const STDERR: FileDescriptor = 2;
 
-pub struct FdEntry {
+/// 描述符编号的上限（分配和 dup2 的目标都不能达到这个值）
+pub const MAX_FDS: FileDescriptor = 1024;
+
+/// 打开文件描述的状态标志：每次写入前把偏移移到文件末尾（与 Linux 的 O_APPEND 相同）
+pub const O_APPEND: u32 = 0o2000;
+
+/// 打开文件描述（open file description）
+///
+/// 每次 open 创建一个新的描述；dup 出来的描述符共享同一个描述，
+/// 也就共享其中的偏移和状态标志，分别打开的描述符互不影响。
+/// 读写前先把文件定位到描述的偏移，因此即使两个描述指向同一个文件对象，
+/// 偏移也各自独立；不支持定位的文件（终端、管道等）没有偏移
+pub struct OpenFileDescription {
     file: Arc<Mutex<dyn File>>,
+    /// 读写偏移；不支持定位的文件为 None
+    offset: Mutex<Option<usize>>,
+    /// 状态标志（O_APPEND）
     flags: u32,
 }
 
-impl FdEntry {
+impl OpenFileDescription {
     pub fn new(file: Arc<Mutex<dyn File>>) -> Self {
-        FdEntry { file, flags: 0 }
+        OpenFileDescription::with_flags(file, 0)
+    }
+
+    /// 以状态标志 `flags` 创建描述，偏移从文件当前的位置开始
+    pub fn with_flags(file: Arc<Mutex<dyn File>>, flags: u32) -> Self {
+        let offset = file.lock().seek(SeekFrom::Current(0)).ok();
+        OpenFileDescription { file, offset: Mutex::new(offset), flags }
     }
 
     pub fn file(&self) -> Arc<Mutex<dyn File>> {
         self.file.clone()
     }
+
+    /// 状态标志
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// 当前文件偏移
+    ///
+    /// # 返回
+    /// 不支持定位的文件（如终端）返回 NotSeekable
+    pub fn offset(&self) -> Result<usize, FileError> {
+        self.offset.lock().ok_or(FileError::NotSeekable)
+    }
+
+    /// 从描述的偏移处读取，并按读到的字节数推进偏移
+    ///
+    /// # 说明
+    /// `file` 是调用者已经锁住的 self.file()：读不到数据时，
+    /// 调用者还要在同一把锁下登记等待（见 sys_read）
+    pub fn read_from(&self, file: &mut dyn File, buf: &mut [u8]) -> Result<usize, FileError> {
+        let mut offset = self.offset.lock();
+        if let Some(pos) = *offset {
+            file.seek(SeekFrom::Start(pos))?;
+        }
+        let n = file.read(buf)?;
+        if let Some(pos) = offset.as_mut() {
+            *pos += n;
+        }
+        Ok(n)
+    }
+
+    /// 在描述的偏移处写入（O_APPEND 时先移到文件末尾），并推进偏移
+    pub fn write_to(&self, file: &mut dyn File, buf: &[u8]) -> Result<usize, FileError> {
+        let mut offset = self.offset.lock();
+        if let Some(pos) = offset.as_mut() {
+            *pos = if self.flags & O_APPEND != 0 {
+                file.seek(SeekFrom::End(0))?
+            } else {
+                file.seek(SeekFrom::Start(*pos))?
+            };
+        }
+        let n = file.write(buf)?;
+        if let Some(pos) = offset.as_mut() {
+            *pos += n;
+        }
+        Ok(n)
+    }
+
+    /// 从描述的偏移处读取
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize, FileError> {
+        self.read_from(&mut *self.file.lock(), buf)
+    }
+
+    /// 在描述的偏移处写入
+    pub fn write(&self, buf: &[u8]) -> Result<usize, FileError> {
+        self.write_to(&mut *self.file.lock(), buf)
+    }
+
+    /// 移动描述的偏移
+    ///
+    /// # 返回
+    /// 新的偏移；不支持定位的文件返回文件自身的错误
+    pub fn seek(&self, pos: SeekFrom) -> Result<usize, FileError> {
+        let mut offset = self.offset.lock();
+        let mut file = self.file.lock();
+        if let Some(current) = *offset {
+            file.seek(SeekFrom::Start(current))?;
+        }
+        let new_offset = file.seek(pos)?;
+        *offset = Some(new_offset);
+        Ok(new_offset)
+    }
+}
+
+/// 文件描述符表项
+pub struct FdEntry {
+    description: Arc<OpenFileDescription>,
+}
+
+impl FdEntry {
+    pub fn new(file: Arc<Mutex<dyn File>>) -> Self {
+        FdEntry::with_description(Arc::new(OpenFileDescription::new(file)))
+    }
+
+    pub fn with_description(description: Arc<OpenFileDescription>) -> Self {
+        FdEntry { description }
+    }
+
+    pub fn file(&self) -> Arc<Mutex<dyn File>> {
+        self.description.file()
+    }
+
+    pub fn description(&self) -> Arc<OpenFileDescription> {
+        self.description.clone()
+    }
 }
 
+/// 文件描述符表
+///
+/// 释放的描述符编号放入空闲堆，分配时先取出其中最小的复用（O(log n)），
+/// 空闲堆为空时才在末尾追加；表项总数不超过 MAX_FDS
 pub struct FileDescriptorTable {
     entries: Vec<Option<FdEntry>>,
-    next_fd: FileDescriptor,
+    /// 空闲的描述符编号（小顶堆，堆顶是最小的空闲编号）
+    free: BinaryHeap<Reverse<FileDescriptor>>,
 }
 
 impl FileDescriptorTable {
     pub fn new() -> Self {
         FileDescriptorTable {
             entries: Vec::new(),
-            next_fd: 3,
+            free: BinaryHeap::new(),
         }
     }
 
@@ -46,7 +169,7 @@ impl FileDescriptorTable {
     ) -> Self {
         let mut table = FileDescriptorTable {
             entries: Vec::with_capacity(16),
-            next_fd: 3,
+            free: BinaryHeap::new(),
         };
 
         table.entries.push(Some(FdEntry::new(stdin)));
@@ -56,33 +179,84 @@ impl FileDescriptorTable {
         table
     }
 
+    /// 为新打开的文件分配描述符（创建新的打开文件描述）
+    ///
+    /// # 返回
+    /// 描述符已达到 MAX_FDS 时返回 None
     pub fn alloc(&mut self, file: Arc<Mutex<dyn File>>) -> Option<FileDescriptor> {
-        let entry = FdEntry::new(file);
-
-        for (i, slot) in self.entries.iter_mut().enumerate() {
-            if slot.is_none() && i >= 3 {
-                *slot = Some(entry);
-                self.next_fd = i + 1;
-                return Some(i);
-            }
+        self.insert(FdEntry::new(file))
+    }
+
+    /// 以状态标志 `flags`（如 O_APPEND）打开文件并分配描述符
+    pub fn alloc_with_flags(
+        &mut self,
+        file: Arc<Mutex<dyn File>>,
+        flags: u32,
+    ) -> Option<FileDescriptor> {
+        let description = OpenFileDescription::with_flags(file, flags);
+        self.insert(FdEntry::with_description(Arc::new(description)))
+    }
+
+    /// 复制描述符（dup）
+    ///
+    /// # 返回
+    /// 新的描述符（>= 3 的最小空闲编号），与 `fd` 共享同一个打开文件描述（包括偏移）；
+    /// `fd` 无效时返回 None
+    pub fn dup(&mut self, fd: FileDescriptor) -> Option<FileDescriptor> {
+        let description = self.description(fd)?;
+        self.insert(FdEntry::with_description(description))
+    }
+
+    /// 让描述符 `new` 指向 `old` 的打开文件描述（dup2）
+    ///
+    /// # 返回
+    /// `old` 无效或 `new` 超出 MAX_FDS 时返回 false
+    ///
+    /// # 说明
+    /// `new` 已打开时先关闭；`new` 可以是标准流，用于重定向。
+    /// `old == new` 时不做任何修改
+    pub fn dup2(&mut self, old: FileDescriptor, new: FileDescriptor) -> bool {
+        let Some(description) = self.description(old) else {
+            return false;
+        };
+        if new >= MAX_FDS {
+            return false;
+        }
+        if old == new {
+            return true;
+        }
+
+        if new >= self.entries.len() {
+            // 跳过的编号成为空闲描述符
+            let gap = self.entries.len().max(3)..new;
+            self.free.extend(gap.map(Reverse));
+            self.entries.resize_with(new + 1, || None);
+        } else if self.entries[new].is_none() {
+            self.free.retain(|&Reverse(fd)| fd != new);
+        }
+        self.entries[new] = Some(FdEntry::with_description(description));
+        true
+    }
+
+    fn insert(&mut self, entry: FdEntry) -> Option<FileDescriptor> {
+        if let Some(Reverse(fd)) = self.free.pop() {
+            self.entries[fd] = Some(entry);
+            return Some(fd);
         }
 
         let fd = self.entries.len();
+        if fd >= MAX_FDS {
+            return None;
+        }
         self.entries.push(Some(entry));
-        self.next_fd = fd + 1;
-
         Some(fd)
     }
 
     pub fn dealloc(&mut self, fd: FileDescriptor) -> bool {
-        if fd >= 3 && fd < self.entries.len() {
-            if self.entries[fd].is_some() {
-                self.entries[fd] = None;
-                if fd < self.next_fd {
-                    self.next_fd = fd;
-                }
-                return true;
-            }
+        if fd >= 3 && fd < self.entries.len() && self.entries[fd].is_some() {
+            self.entries[fd] = None;
+            self.free.push(Reverse(fd));
+            return true;
         }
         false
     }
@@ -91,10 +265,20 @@ impl FileDescriptorTable {
         self.entries.get(fd)?.as_ref().map(|entry| entry.file())
     }
 
+    /// 描述符对应的打开文件描述
+    pub fn description(&self, fd: FileDescriptor) -> Option<Arc<OpenFileDescription>> {
+        self.entries.get(fd)?.as_ref().map(FdEntry::description)
+    }
+
     pub fn is_valid(&self, fd: FileDescriptor) -> bool {
         self.get(fd).is_some()
     }
 
+    /// 所有打开的文件（包括标准流）
+    pub fn open_files(&self) -> Vec<Arc<Mutex<dyn File>>> {
+        self.entries.iter().flatten().map(FdEntry::file).collect()
+    }
+
     pub fn count(&self) -> usize {
         self.entries.iter().filter(|e| e.is_some()).count()
     }
@@ -103,3 +287,178 @@ impl FileDescriptorTable {
         self.entries.len()
     }
 }
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::{RamFile, RamInode};
+
+    /// 内容为 "abcdef" 的独立 inode
+    fn sample_inode() -> Arc<Mutex<RamInode>> {
+        let mut inode = RamInode::new_file(usize::MAX);
+        inode.write_at(0, b"abcdef").unwrap();
+        Arc::new(Mutex::new(inode))
+    }
+
+    fn read2(table: &FileDescriptorTable, fd: FileDescriptor) -> [u8; 2] {
+        let mut buf = [0u8; 2];
+        assert_eq!(table.description(fd).unwrap().read(&mut buf), Ok(2));
+        buf
+    }
+
+    #[test_case]
+    fn test_dup_shares_offset() {
+        let stdio = || -> Arc<Mutex<dyn File>> { Arc::new(Mutex::new(RamFile::new(sample_inode()))) };
+        let mut table = FileDescriptorTable::with_stdio(stdio(), stdio(), stdio());
+        let fd = table.alloc(Arc::new(Mutex::new(RamFile::new(sample_inode())))).unwrap();
+        let dup = table.dup(fd).unwrap();
+        assert_eq!((fd, dup), (3, 4));
+        assert!(Arc::ptr_eq(&table.description(fd).unwrap(), &table.description(dup).unwrap()));
+
+        // 通过一个描述符读取会推进另一个的偏移
+        assert_eq!(&read2(&table, fd), b"ab");
+        assert_eq!(table.description(dup).unwrap().offset(), Ok(2));
+        assert_eq!(&read2(&table, dup), b"cd");
+        assert_eq!(&read2(&table, fd), b"ef");
+
+        // 关闭其中一个不影响另一个
+        assert!(table.dealloc(fd));
+        assert_eq!(table.description(dup).unwrap().offset(), Ok(6));
+        assert_eq!(table.dup(fd), None);
+    }
+
+    #[test_case]
+    fn test_dup_stdout_writes_to_same_inode() {
+        let read_all = |inode: &Arc<Mutex<RamInode>>| {
+            let mut buf = [0u8; 16];
+            let n = inode.lock().read_at(0, &mut buf).unwrap();
+            Vec::from(&buf[..n])
+        };
+        let console = Arc::new(Mutex::new(RamInode::new_file(usize::MAX)));
+        let stdio = || Arc::new(Mutex::new(RamFile::new(console.clone())));
+        let mut table = FileDescriptorTable::with_stdio(stdio(), stdio(), stdio());
+
+        // dup 出的描述符与标准输出共享偏移，写入依次追加到同一个 inode
+        let fd = table.dup(STDOUT).unwrap();
+        assert!(fd >= 3);
+        table.description(STDOUT).unwrap().write(b"ab").unwrap();
+        table.description(fd).unwrap().write(b"cd").unwrap();
+        assert_eq!(read_all(&console), b"abcd");
+
+        // dup2 把标准输出重定向到文件，原来的 dup 仍指向控制台
+        let log = Arc::new(Mutex::new(RamInode::new_file(usize::MAX)));
+        let file = table.alloc(Arc::new(Mutex::new(RamFile::new(log.clone())))).unwrap();
+        assert!(table.dup2(file, STDOUT));
+        table.description(STDOUT).unwrap().write(b"xy").unwrap();
+        table.description(fd).unwrap().write(b"ef").unwrap();
+        assert_eq!(read_all(&log), b"xy");
+        assert_eq!(read_all(&console), b"abcdef");
+
+        // 目标超出当前表长时扩展；old 无效或 new 越界时失败
+        assert!(table.dup2(file, 40));
+        assert!(Arc::ptr_eq(&table.description(40).unwrap(), &table.description(file).unwrap()));
+        assert!(table.dup2(file, file));
+        assert!(!table.dup2(39, 5));
+        assert!(!table.dup2(file, MAX_FDS));
+    }
+
+    #[test_case]
+    fn test_separate_opens_have_own_offset() {
+        let inode = sample_inode();
+        let mut table = FileDescriptorTable::new();
+        let a = table.alloc(Arc::new(Mutex::new(RamFile::new(inode.clone())))).unwrap();
+        let b = table.alloc(Arc::new(Mutex::new(RamFile::new(inode)))).unwrap();
+
+        assert_eq!(&read2(&table, a), b"ab");
+        assert_eq!(&read2(&table, a), b"cd");
+        assert_eq!(table.description(b).unwrap().offset(), Ok(0));
+        assert_eq!(&read2(&table, b), b"ab");
+    }
+
+    #[test_case]
+    fn test_descriptions_of_one_file_keep_own_offset_and_flags() {
+        let file: Arc<Mutex<dyn File>> = Arc::new(Mutex::new(RamFile::new(sample_inode())));
+        let mut table = FileDescriptorTable::new();
+        let a = table.alloc(file.clone()).unwrap();
+        let b = table.alloc(file.clone()).unwrap();
+        let append = table.alloc_with_flags(file, O_APPEND).unwrap();
+
+        // 同一个文件对象上的两个描述：偏移分别推进
+        assert_eq!(&read2(&table, a), b"ab");
+        assert_eq!(&read2(&table, b), b"ab");
+        assert_eq!(table.description(b).unwrap().seek(SeekFrom::Current(2)), Ok(4));
+        assert_eq!(&read2(&table, a), b"cd");
+
+        // O_APPEND 的描述总是写到末尾，dup 出的描述符共享标志和偏移
+        let dup = table.dup(append).unwrap();
+        assert_eq!(table.description(dup).unwrap().flags(), O_APPEND);
+        assert_eq!(table.description(append).unwrap().write(b"gh"), Ok(2));
+        assert_eq!(table.description(dup).unwrap().offset(), Ok(8));
+        assert_eq!(table.description(a).unwrap().write(b"XY"), Ok(2));
+        assert_eq!(table.description(dup).unwrap().write(b"ij"), Ok(2));
+        assert_eq!(table.description(dup).unwrap().offset(), Ok(10));
+
+        let mut buf = [0u8; 10];
+        assert_eq!(table.description(b).unwrap().seek(SeekFrom::Start(0)), Ok(0));
+        assert_eq!(table.description(b).unwrap().read(&mut buf), Ok(10));
+        assert_eq!(&buf, b"abcdXYghij");
+    }
+
+    #[test_case]
+    fn test_freed_fd_is_reused_before_appending() {
+        let file = || -> Arc<Mutex<dyn File>> { Arc::new(Mutex::new(RamFile::new(sample_inode()))) };
+        let mut table = FileDescriptorTable::with_stdio(file(), file(), file());
+        let fds: Vec<_> = (0..4).map(|_| table.alloc(file()).unwrap()).collect();
+        assert_eq!(fds, [3, 4, 5, 6]);
+
+        // 释放的低编号立即被复用，表不增长
+        assert!(table.dealloc(4));
+        assert!(!table.dealloc(4));
+        assert_eq!(table.alloc(file()), Some(4));
+        assert_eq!(table.capacity(), 7);
+        assert_eq!(table.alloc(file()), Some(7));
+
+        // dup2 跳过的编号也可以分配；被 dup2 占用的空闲编号不再分配
+        assert!(table.dup2(3, 10));
+        assert!(table.dup2(3, 8));
+        assert_eq!(table.alloc(file()), Some(9));
+        assert_eq!(table.alloc(file()), Some(11));
+    }
+
+    #[test_case]
+    fn test_dup_uses_lowest_free_fd() {
+        let file = || -> Arc<Mutex<dyn File>> { Arc::new(Mutex::new(RamFile::new(sample_inode()))) };
+        let mut table = FileDescriptorTable::with_stdio(file(), file(), file());
+        let fds: Vec<_> = (0..4).map(|_| table.alloc(file()).unwrap()).collect();
+        assert_eq!(fds, [3, 4, 5, 6]);
+
+        // 先关 3 再关 5：dup 仍从最小的空闲编号开始
+        assert!(table.dealloc(3));
+        assert!(table.dealloc(5));
+        assert_eq!(table.dup(STDOUT), Some(3));
+        assert_eq!(table.dup(STDOUT), Some(5));
+        assert_eq!(table.dup(STDOUT), Some(7));
+    }
+
+    #[test_case]
+    fn test_alloc_stops_at_max_fds() {
+        let file = || -> Arc<Mutex<dyn File>> { Arc::new(Mutex::new(RamFile::new(sample_inode()))) };
+        let mut table = FileDescriptorTable::with_stdio(file(), file(), file());
+        let fd = table.alloc(file()).unwrap();
+        assert!(table.dup2(fd, MAX_FDS - 1));
+        for gap in fd + 1..MAX_FDS - 1 {
+            assert_eq!(table.dup(fd), Some(gap));
+        }
+        assert_eq!(table.dup(fd), None);
+
+        // 表满后释放的编号仍可复用
+        assert!(table.dealloc(MAX_FDS - 1));
+        assert_eq!(table.dup(fd), Some(MAX_FDS - 1));
+        assert_eq!(table.dup(fd), None);
+        assert_eq!(table.count(), MAX_FDS);
+    }
+}
@@ -2,10 +2,19 @@
 
 use alloc::vec::Vec;
 use core::fmt;
+use super::inode::permissions;
 
 /// 文件trait - 统一的文件操作接口
 pub trait File: Send + Sync {
     /// 读取数据到缓冲区
+    ///
+    /// # 返回
+    /// - `Ok(n)`（n > 0）: 读到 n 字节
+    /// - `Ok(0)`: 已到达文件末尾（缓冲区非空时）
+    /// - `Err(e)`: 读取出错
+    ///
+    /// # 说明
+    /// 所有实现都以 `Ok(0)` 表示文件末尾，不应返回 `Err(EndOfFile)`
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, FileError>;
 
     /// 写入数据到文件
@@ -25,7 +34,6 @@ pub trait File: Send + Sync {
             match self.read(&mut chunk) {
                 Ok(0) => break,
                 Ok(n) => buffer.extend_from_slice(&chunk[..n]),
-                Err(FileError::EndOfFile) => break,
                 Err(e) => return Err(e),
             }
         }
@@ -33,11 +41,81 @@ pub trait File: Send + Sync {
         Ok(buffer)
     }
 
+    /// 读满整个缓冲区
+    ///
+    /// # 说明
+    /// `read` 可能只返回部分数据，这里循环读取直到填满 `buf`。
+    /// 数据不足（提前遇到文件末尾）时返回 `EndOfFile`，
+    /// 此时 `buf` 中已读取部分的内容未定义。
+    fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<(), FileError> {
+        while !buf.is_empty() {
+            match self.read(buf)? {
+                0 => return Err(FileError::EndOfFile),
+                n => buf = &mut buf[n..],
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 写入整个缓冲区
+    ///
+    /// # 说明
+    /// `write` 可能只写入部分数据，这里循环写入直到全部写完。
+    /// 如果 `write` 返回 `Ok(0)`（无法继续写入），返回 `IoError`。
+    fn write_all(&mut self, mut buf: &[u8]) -> Result<(), FileError> {
+        while !buf.is_empty() {
+            match self.write(buf)? {
+                0 => return Err(FileError::IoError),
+                n => buf = &buf[n..],
+            }
+        }
+
+        Ok(())
+    }
+
     /// 写入字符串
     fn write_str(&mut self, s: &str) -> Result<usize, FileError> {
         self.write(s.as_bytes())
     }
 
+    /// 把缓冲的数据写回底层存储（inode / 块设备）
+    ///
+    /// # 说明
+    /// 没有写回缓冲的文件（如 RamFile 直接写 inode）无需实现，
+    /// 默认什么也不做；由 fs::sync 对所有打开的文件调用
+    fn flush(&mut self) -> Result<(), FileError> {
+        Ok(())
+    }
+
+    /// 读操作返回 `WouldBlock` 后，登记当前进程为等待者
+    ///
+    /// # 返回
+    /// - `true`: 已登记，调用者应释放文件锁后调用 finish_block_current_process，
+    ///   被唤醒后重试读取
+    /// - `false`: 不支持等待（如非阻塞模式、没有当前进程），直接把 `WouldBlock` 返回给用户
+    ///
+    /// # 说明
+    /// 实现必须在持有唤醒者也要获取的锁（文件锁或共享缓冲区的锁）时，
+    /// 登记等待者并调用 prepare_block_current_process 把当前进程标记为阻塞：
+    /// 唤醒只对 Blocked 的进程生效，只登记不标记的话，释放锁到真正阻塞之间的唤醒会丢失。
+    /// 让出 CPU 由 sys_read 在释放文件锁之后进行，否则同一文件的写者会在锁上死等
+    fn register_reader(&mut self) -> bool {
+        false
+    }
+
+    /// 设置定时器（timerfd_settime）
+    ///
+    /// # 参数
+    /// - `value`: 距首次到期的 tick 数，0 表示停止定时器
+    /// - `interval`: 之后每次到期的间隔（tick），0 表示只到期一次
+    ///
+    /// # 返回
+    /// 之前的设置（剩余 tick 数, 间隔）；不是定时器的文件返回 InvalidOperation
+    fn set_timer(&mut self, _value: u64, _interval: u64) -> Result<(u64, u64), FileError> {
+        Err(FileError::InvalidOperation)
+    }
+
     /// 获取文件大小
     fn size(&self) -> Result<usize, FileError> {
         Err(FileError::InvalidOperation)
@@ -47,6 +125,15 @@ pub trait File: Send + Sync {
     fn stat(&self) -> Result<FileMetadata, FileError> {
         Err(FileError::InvalidOperation)
     }
+
+    /// 获取文件背后的 inode 号
+    ///
+    /// # 说明
+    /// 用于以打开的目录为起点解析路径（*at 系列系统调用），由文件系统按号找回 inode；
+    /// 不对应 inode 的文件（如终端）返回 None
+    fn ino(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// 文件操作错误
@@ -54,12 +141,24 @@ pub trait File: Send + Sync {
 pub enum FileError {
     NotFound,
     PermissionDenied,
+    /// 数据不足（用于需要确定长度的显式场景，如 read_exact）
+    ///
+    /// `File::read` 以 `Ok(0)` 表示文件末尾，不返回此错误
     EndOfFile,
     InvalidOperation,
     IoError,
     AlreadyExists,
     NotDirectory,
     IsDirectory,
+    WouldBlock,
+    /// 文件不支持定位（如终端、管道，对应 ESPIPE）
+    NotSeekable,
+    /// 文件未以该方向打开（如写标准输入，对应 EBADF）
+    BadFileDescriptor,
+    /// 管道的读端已全部关闭（对应 EPIPE）
+    BrokenPipe,
+    /// 解析路径时跟随的符号链接过多，可能有循环（对应 ELOOP）
+    TooManyLinks,
 }
 
 impl fmt::Display for FileError {
@@ -73,6 +172,11 @@ impl fmt::Display for FileError {
             FileError::AlreadyExists => write!(f, "文件已存在"),
             FileError::NotDirectory => write!(f, "不是目录"),
             FileError::IsDirectory => write!(f, "是目录"),
+            FileError::WouldBlock => write!(f, "操作将阻塞"),
+            FileError::NotSeekable => write!(f, "不支持定位"),
+            FileError::BadFileDescriptor => write!(f, "错误的文件描述符"),
+            FileError::BrokenPipe => write!(f, "管道已断开"),
+            FileError::TooManyLinks => write!(f, "符号链接层数过多"),
         }
     }
 }
@@ -96,6 +200,20 @@ pub enum FileType {
     SymbolicLink,
 }
 
+impl FileType {
+    /// 对应的 st_mode 文件类型位
+    pub fn mode_bits(&self) -> u32 {
+        match self {
+            FileType::RegularFile => permissions::S_IFREG,
+            FileType::Directory => permissions::S_IFDIR,
+            FileType::CharDevice => permissions::S_IFCHR,
+            FileType::BlockDevice => permissions::S_IFBLK,
+            FileType::Pipe => permissions::S_IFIFO,
+            FileType::SymbolicLink => permissions::S_IFLNK,
+        }
+    }
+}
+
 /// 文件元数据
 #[derive(Debug, Clone)]
 pub struct FileMetadata {
@@ -117,3 +235,91 @@ impl FileMetadata {
         }
     }
 }
+
+/// 用户态可见的文件状态（stat 系统调用的结果）
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stat {
+    /// inode 号
+    pub ino: u64,
+    /// 文件类型位 | 权限位
+    pub mode: u32,
+    /// 硬链接数
+    pub nlink: u32,
+    /// 文件大小（字节）
+    pub size: u64,
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 每次最多写入一个字节的模拟文件
+    struct OneByteWriter {
+        data: Vec<u8>,
+    }
+
+    impl File for OneByteWriter {
+        fn read(&mut self, _buf: &mut [u8]) -> Result<usize, FileError> {
+            Ok(0)
+        }
+
+        fn write(&mut self, buf: &[u8]) -> Result<usize, FileError> {
+            match buf.first() {
+                Some(&byte) => {
+                    self.data.push(byte);
+                    Ok(1)
+                }
+                None => Ok(0),
+            }
+        }
+    }
+
+    /// 每次最多返回两个字节的模拟文件
+    struct ChunkedReader {
+        data: &'static [u8],
+        pos: usize,
+    }
+
+    impl File for ChunkedReader {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, FileError> {
+            let n = buf.len().min(2).min(self.data.len() - self.pos);
+            buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+
+        fn write(&mut self, _buf: &[u8]) -> Result<usize, FileError> {
+            Err(FileError::InvalidOperation)
+        }
+    }
+
+    #[test_case]
+    fn test_read_exact_fills_buffer_from_chunks() {
+        let mut file = ChunkedReader { data: b"\x7fELF\x02\x01", pos: 0 };
+
+        let mut magic = [0u8; 4];
+        assert_eq!(file.read_exact(&mut magic), Ok(()));
+        assert_eq!(&magic, b"\x7fELF");
+
+        // 剩余 2 字节，不足以填满 4 字节缓冲区
+        let mut rest = [0u8; 4];
+        assert_eq!(file.read_exact(&mut rest), Err(FileError::EndOfFile));
+    }
+
+    #[test_case]
+    fn test_write_all_loops_on_short_writes() {
+        let mut file = OneByteWriter { data: Vec::new() };
+
+        // 单次 write 只写入一个字节
+        assert_eq!(file.write(b"xyz"), Ok(1));
+        file.data.clear();
+
+        assert_eq!(file.write_all(b"hello, world"), Ok(()));
+        assert_eq!(file.data, b"hello, world");
+    }
+}
@@ -1,7 +1,9 @@
 //! Inode抽象
 
-use super::file::{FileType, FileMetadata};
+use super::file::{FileType, FileMetadata, FileError};
+use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use spin::Mutex;
 
 /// Inode trait - 文件元数据抽象
@@ -10,6 +12,23 @@ pub trait Inode: Send + Sync {
     fn file_type(&self) -> FileType;
     fn size(&self) -> usize;
     fn mode(&self) -> u32;
+
+    /// 列出目录项名称
+    ///
+    /// # 说明
+    /// 默认实现用于非目录的 inode，返回 `NotDirectory`
+    fn list_entries(&self) -> Result<Vec<String>, FileError> {
+        Err(FileError::NotDirectory)
+    }
+
+    /// 在目录中按名称查找
+    ///
+    /// # 说明
+    /// 返回通用的 inode 句柄，调用者不需要知道具体的文件系统类型；
+    /// 默认实现返回 `NotDirectory`
+    fn lookup(&self, _name: &str) -> Result<InodeHandle, FileError> {
+        Err(FileError::NotDirectory)
+    }
 }
 
 /// 文件权限位（Unix风格）
@@ -28,6 +47,17 @@ pub mod permissions {
 
     pub const S_DEFAULT_FILE: u32 = S_IRUSR | S_IWUSR | S_IRGRP | S_IROTH;
     pub const S_DEFAULT_DIR: u32 = 0o755;
+    /// 符号链接的权限位不起作用，按惯例为 0o777
+    pub const S_DEFAULT_LINK: u32 = 0o777;
+
+    // 文件类型位（stat 的 st_mode 高位）
+    pub const S_IFMT: u32 = 0o170000;
+    pub const S_IFLNK: u32 = 0o120000;
+    pub const S_IFREG: u32 = 0o100000;
+    pub const S_IFBLK: u32 = 0o060000;
+    pub const S_IFDIR: u32 = 0o040000;
+    pub const S_IFCHR: u32 = 0o020000;
+    pub const S_IFIFO: u32 = 0o010000;
 }
 
 /// 内存中的Inode结构
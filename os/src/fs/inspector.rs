@@ -6,10 +6,12 @@
 //! - 显示文件系统树结构
 
 use crate::println;
-use super::{RAMFS, FD_TABLE, Inode};  // 添加Inode trait
+use super::{RAMFS, FD_TABLE, Inode};
 use super::file::FileType;
 use alloc::vec::Vec;
 use alloc::string::String;
+use core::fmt::Write;
+use crate::process::inspector::{verbosity, Verbosity};
 
 /// 文件/目录条目快照
 #[derive(Clone)]
@@ -36,18 +38,20 @@ pub struct FdStats {
     pub file_fds: usize,
 }
 
-/// 获取根目录下所有文件和目录
-pub fn get_root_entries() -> Vec<EntrySnapshot> {
+/// 获取目录下所有条目
+///
+/// # 说明
+/// 只通过 Inode trait 访问目录，不依赖具体的文件系统类型；
+/// 不是目录时返回空列表
+pub fn get_entries(dir: &dyn Inode) -> Vec<EntrySnapshot> {
     let mut entries = Vec::new();
-    let root = RAMFS.root();
 
-    // 获取根目录的所有条目
-    if let Ok(entry_names) = root.lock().list_entries() {
+    if let Ok(entry_names) = dir.list_entries() {
         for name in entry_names {
-            if let Ok(inode) = root.lock().lookup(&name) {
+            if let Ok(inode) = dir.lookup(&name) {
                 let inode_guard = inode.lock();
                 entries.push(EntrySnapshot {
-                    name: name.clone(),
+                    name,
                     ino: inode_guard.ino(),
                     file_type: inode_guard.file_type(),
                     size: inode_guard.size(),
@@ -59,6 +63,13 @@ pub fn get_root_entries() -> Vec<EntrySnapshot> {
     entries
 }
 
+/// 获取根目录下所有文件和目录
+pub fn get_root_entries() -> Vec<EntrySnapshot> {
+    let root = RAMFS.root();
+    let guard = root.lock();
+    get_entries(&*guard)
+}
+
 /// 获取已分配的FD列表
 pub fn get_allocated_fds() -> Vec<FdSnapshot> {
     let mut fds = Vec::new();
@@ -126,8 +137,37 @@ pub fn get_fd_stats() -> FdStats {
     }
 }
 
-/// 可视化：显示根目录文件列表
+/// 条目类型的短名称
+fn type_name(file_type: FileType) -> &'static str {
+    match file_type {
+        FileType::RegularFile => "file",
+        FileType::Directory => "dir",
+        _ => "other",
+    }
+}
+
+/// 紧凑格式的文件列表：一行表头，之后每个条目一行
+pub fn format_file_list_compact(entries: &[EntrySnapshot]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{:>5} {:<5} {:>8} NAME", "INO", "TYPE", "SIZE");
+    for entry in entries {
+        let _ = writeln!(out, "{:>5} {:<5} {:>8} {}",
+                         entry.ino, type_name(entry.file_type), entry.size, entry.name);
+    }
+    out
+}
+
+/// 紧凑格式显示根目录文件列表（每个条目一行，没有边框）
+pub fn show_file_list_compact() {
+    crate::print!("{}", format_file_list_compact(&get_root_entries()));
+}
+
+/// 可视化：显示根目录文件列表（详细程度由 process::inspector::set_verbosity 决定）
 pub fn show_file_list() {
+    if verbosity() == Verbosity::Compact {
+        return show_file_list_compact();
+    }
+
     println!("\n================================================================");
     println!("===                Root Directory File List                  ===");
     println!("================================================================");
@@ -228,7 +268,9 @@ pub fn show_filesystem_tree() {
 
             // 如果是目录，尝试列出子项
             if entry.file_type == FileType::Directory {
-                if let Ok(inode) = RAMFS.root().lock().lookup(&entry.name) {
+                let root = RAMFS.root();
+                let dir = Inode::lookup(&*root.lock(), &entry.name);
+                if let Ok(inode) = dir {
                     if let Ok(sub_entries) = inode.lock().list_entries() {
                         for (j, sub_name) in sub_entries.iter().enumerate() {
                             let is_sub_last = j == sub_entries.len() - 1;
@@ -264,3 +306,73 @@ pub fn show_filesystem_dashboard() {
 
     println!("");
 }
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::{InodeHandle, MemInode};
+    use crate::fs::file::FileError;
+    use alloc::sync::Arc;
+    use spin::Mutex;
+
+    /// 不属于 RamFS 的目录：只实现 Inode trait
+    struct FakeDir {
+        children: Vec<(String, InodeHandle)>,
+    }
+
+    impl Inode for FakeDir {
+        fn ino(&self) -> usize {
+            100
+        }
+
+        fn file_type(&self) -> FileType {
+            FileType::Directory
+        }
+
+        fn size(&self) -> usize {
+            0
+        }
+
+        fn mode(&self) -> u32 {
+            0o755
+        }
+
+        fn list_entries(&self) -> Result<Vec<String>, FileError> {
+            Ok(self.children.iter().map(|(name, _)| name.clone()).collect())
+        }
+
+        fn lookup(&self, name: &str) -> Result<InodeHandle, FileError> {
+            self.children
+                .iter()
+                .find(|(child, _)| child == name)
+                .map(|(_, inode)| inode.clone())
+                .ok_or(FileError::NotFound)
+        }
+    }
+
+    #[test_case]
+    fn test_inspector_works_through_inode_trait_object() {
+        let mut file = MemInode::new_file(101);
+        file.set_size(42);
+        let file: InodeHandle = Arc::new(Mutex::new(file));
+        let sub: InodeHandle = Arc::new(Mutex::new(MemInode::new_directory(102)));
+        let dir = FakeDir {
+            children: alloc::vec![("a.txt".into(), file), ("sub".into(), sub)],
+        };
+
+        let entries = get_entries(&dir);
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].name.as_str(), entries[0].ino, entries[0].size), ("a.txt", 101, 42));
+        assert_eq!(entries[0].file_type, FileType::RegularFile);
+        assert_eq!((entries[1].name.as_str(), entries[1].file_type), ("sub", FileType::Directory));
+
+        // 非目录 inode 使用默认实现：没有条目
+        let plain = MemInode::new_file(103);
+        assert_eq!(plain.list_entries(), Err(FileError::NotDirectory));
+        assert!(get_entries(&plain).is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 //! 文件系统管理器
 
+use super::file::FileError;
 use super::fd_table::{FileDescriptorTable, STDIN, STDOUT, STDERR};
 use super::ramfs::RamFS;
 use super::stdio::{Stdin, Stdout, Stderr};
@@ -28,3 +29,28 @@ pub fn init() {
     let _ = &*FD_TABLE;
     crate::println!("[FS] File system initialized");
 }
+
+/// 同步文件系统
+///
+/// # 返回
+/// 所有文件都刷新成功时返回 Ok；否则返回遇到的第一个错误
+///
+/// # 说明
+/// 对所有打开的文件调用 `File::flush`，把写回缓冲的数据落到 inode。
+/// RamFS 的数据全部保存在内存中，目前还没有块设备缓存需要回写。
+/// 在关机或序列化文件系统之前调用
+pub fn sync() -> Result<(), FileError> {
+    // 先复制文件列表再逐个刷新，刷新期间不持有 FD 表锁
+    let files = FD_TABLE.lock().open_files();
+
+    let mut result = Ok(());
+    for file in &files {
+        if let Err(e) = file.lock().flush() {
+            result = result.and(Err(e));
+        }
+        crate::process::maybe_yield();
+    }
+
+    crate::serial_println!("[FS] File system synced ({} open files)", files.len());
+    result
+}
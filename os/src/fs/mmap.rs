@@ -0,0 +1,208 @@
+//! 文件内存映射（mmap）
+//!
+//! 把 RamFS 文件映射到进程地址空间：
+//! - map_file 为每页分配清零的用户页，复制文件内容后建立映射（没有页缓存，
+//!   映射是文件内容的副本），并登记到映射表
+//! - 文件被 truncate 缩短时，RamInode::truncate 调用 truncate_mappings，
+//!   取消新文件末尾之后的页，最后一页中越过末尾的部分清零，
+//!   映射不会再读到被截掉的数据
+//! - 之后访问被取消的页产生页错误，页错误处理据 is_truncated 发送 SIGBUS（与 POSIX 一致）
+
+use super::inode::Inode;
+use super::ramfs::RamInode;
+use crate::memory::{
+    flush_tlb_page, unmap_page, walk_page_table, AddressSpace, PageTable, PageTableFlags,
+    PhysAddr, SimpleFrameAllocator, VirtAddr, PAGE_SIZE,
+};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+/// 一段文件映射
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileMapping {
+    /// 被映射文件的inode号
+    pub ino: usize,
+    /// 映射所在地址空间的根页表
+    pub root: PhysAddr,
+    /// 起始虚拟地址（页对齐）
+    pub start: VirtAddr,
+    /// 映射的页数
+    pub pages: usize,
+    /// 仍有文件数据的页数（truncate 后减少，之后的页已取消映射）
+    pub valid_pages: usize,
+}
+
+impl FileMapping {
+    /// 映射范围是否包含 addr
+    fn contains(&self, root: PhysAddr, addr: usize) -> bool {
+        let start = self.start.as_usize();
+        self.root == root && start <= addr && addr < start + self.pages * PAGE_SIZE
+    }
+}
+
+/// 所有文件映射
+static MAPPINGS: Mutex<Vec<FileMapping>> = Mutex::new(Vec::new());
+
+/// 把文件的前 `len` 字节映射到 `start`
+///
+/// # 参数
+/// - `space`: 目标地址空间
+/// - `start`: 起始虚拟地址（必须页对齐）
+/// - `inode`: 被映射的文件
+/// - `len`: 映射长度（向上取整到页）
+/// - `allocator`: 帧分配器
+///
+/// # 返回
+/// 成功返回映射描述；地址未对齐、长度为 0、不是普通文件或内存不足时返回错误
+pub fn map_file(
+    space: &mut AddressSpace,
+    start: VirtAddr,
+    inode: &Arc<Mutex<RamInode>>,
+    len: usize,
+    allocator: &mut SimpleFrameAllocator,
+) -> Result<FileMapping, &'static str> {
+    if len == 0 || start.page_offset() != 0 {
+        return Err("Invalid mapping range");
+    }
+
+    let pages = len.div_ceil(PAGE_SIZE);
+    let flags = PageTableFlags::Valid as usize
+        | PageTableFlags::Read as usize
+        | PageTableFlags::Write as usize
+        | PageTableFlags::User as usize;
+
+    // 持有inode锁直到登记完成，期间的 truncate 不会漏掉这段映射
+    let inode = inode.lock();
+    for i in 0..pages {
+        let vaddr = VirtAddr::new(start.as_usize() + i * PAGE_SIZE);
+        let paddr = space.map_zeroed_page(vaddr, flags, allocator)?;
+        let page = unsafe { core::slice::from_raw_parts_mut(paddr.as_usize() as *mut u8, PAGE_SIZE) };
+        inode.read_at(i * PAGE_SIZE, page).map_err(|_| "Not a regular file")?;
+    }
+
+    let mapping = FileMapping {
+        ino: inode.ino(),
+        root: space.page_table_paddr(),
+        start,
+        pages,
+        valid_pages: pages,
+    };
+    MAPPINGS.lock().push(mapping);
+    Ok(mapping)
+}
+
+/// 取消登记以 `start` 开始的映射（页面由地址空间自己释放）
+///
+/// # 返回
+/// 被移除的映射
+pub fn unmap_file(root: PhysAddr, start: VirtAddr) -> Option<FileMapping> {
+    let mut mappings = MAPPINGS.lock();
+    let index = mappings
+        .iter()
+        .position(|mapping| mapping.root == root && mapping.start == start)?;
+    Some(mappings.remove(index))
+}
+
+/// 文件缩短到 `size` 后更新它的所有映射
+///
+/// # 说明
+/// 由 RamInode::truncate 在持有inode锁时调用：
+/// - 新文件末尾所在页之后的页取消映射
+/// - 末尾所在页中越过末尾的部分清零
+pub(super) fn truncate_mappings(ino: usize, size: usize) {
+    let keep = size.div_ceil(PAGE_SIZE);
+    let tail = size % PAGE_SIZE;
+
+    for mapping in MAPPINGS.lock().iter_mut().filter(|mapping| mapping.ino == ino) {
+        let root = unsafe { &mut *(mapping.root.as_usize() as *mut PageTable) };
+        for i in keep..mapping.valid_pages {
+            let vaddr = VirtAddr::new(mapping.start.as_usize() + i * PAGE_SIZE);
+            let _ = unmap_page(root, vaddr);
+        }
+
+        if tail != 0 && keep <= mapping.valid_pages {
+            let vaddr = VirtAddr::new(mapping.start.as_usize() + (keep - 1) * PAGE_SIZE);
+            if let Some(paddr) = walk_page_table(mapping.root, vaddr) {
+                unsafe {
+                    core::ptr::write_bytes((paddr.as_usize() + tail) as *mut u8, 0, PAGE_SIZE - tail);
+                }
+                flush_tlb_page(vaddr);
+            }
+        }
+
+        mapping.valid_pages = mapping.valid_pages.min(keep);
+    }
+}
+
+/// `addr` 是否落在某个映射中因 truncate 而取消的部分
+///
+/// # 说明
+/// 页错误处理用来区分 SIGBUS（访问文件末尾之后的映射）和 SIGSEGV
+pub fn is_truncated(root: PhysAddr, addr: usize) -> bool {
+    MAPPINGS.lock().iter().any(|mapping| {
+        mapping.contains(root, addr)
+            && (addr - mapping.start.as_usize()) / PAGE_SIZE >= mapping.valid_pages
+    })
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::RAMFS;
+    use crate::memory::{lookup_pte, TestMemory};
+    use crate::trap::{classify_page_fault, FaultAccess, PageFaultKind};
+    use alloc::string::String;
+    use alloc::vec;
+
+    #[test_case]
+    fn test_truncate_unmaps_pages_past_new_end() {
+        const FRAMES: usize = 16;
+        const MAP_START: usize = 0x5000_0000;
+
+        let mut memory = TestMemory::new(FRAMES, 0xAA);
+        let allocator = &mut memory.allocator;
+        let mut space = AddressSpace::new(allocator).unwrap();
+        let root = space.page_table_paddr();
+
+        // 三页文件，每页填入不同的字节
+        let file = RAMFS.create_file(RAMFS.root(), String::from("mmap_truncate")).unwrap();
+        let data: Vec<u8> = (0..3 * PAGE_SIZE).map(|i| (i / PAGE_SIZE) as u8 + 1).collect();
+        file.lock().write_at(0, &data).unwrap();
+
+        let start = VirtAddr::new(MAP_START);
+        let mapping = map_file(&mut space, start, &file, data.len(), allocator).unwrap();
+        assert_eq!((mapping.pages, mapping.valid_pages), (3, 3));
+        let page = |i: usize| VirtAddr::new(MAP_START + i * PAGE_SIZE);
+        let read = |i: usize, offset: usize| {
+            let paddr = walk_page_table(root, page(i)).unwrap();
+            unsafe { *((paddr.as_usize() + offset) as *const u8) }
+        };
+        assert_eq!(read(2, 0), 3);
+
+        // 缩短到一页多 10 字节：第三页取消映射，第二页的尾部清零
+        file.lock().truncate(PAGE_SIZE + 10).unwrap();
+        assert_eq!(read(0, 0), 1);
+        assert_eq!(read(1, 9), 2);
+        assert_eq!(read(1, 10), 0);
+
+        // 访问被截掉的部分产生页错误，按 SIGBUS 处理
+        let removed = page(2).as_usize() + 8;
+        let kind = classify_page_fault(FaultAccess::Read, lookup_pte(root, VirtAddr::new(removed)), true);
+        assert_eq!(kind, PageFaultKind::NotMapped);
+        assert!(is_truncated(root, removed));
+        assert!(!is_truncated(root, page(1).as_usize()));
+
+        // 重新增长文件不会恢复已取消的映射
+        file.lock().write_at(0, &vec![9u8; 3 * PAGE_SIZE]).unwrap();
+        assert!(walk_page_table(root, page(2)).is_none());
+
+        assert_eq!(unmap_file(root, start), Some(FileMapping { valid_pages: 2, ..mapping }));
+        assert!(!is_truncated(root, removed));
+        RAMFS.remove(RAMFS.root(), "mmap_truncate").unwrap();
+    }
+}
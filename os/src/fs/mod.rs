@@ -7,10 +7,20 @@ pub mod stdio;
 pub mod ramfs;
 pub mod manager;
 pub mod inspector;      // 真实文件系统状态查询模块
+pub mod tar;            // initrd（ustar）解包
+pub mod devmem;         // /dev/mem（仅调试构建）、/dev/null、/dev/zero
+pub mod eventfd;        // 事件通知计数器
+pub mod timerfd;        // 定时器文件
+pub mod pipe;           // 匿名管道
+pub mod mmap;           // 文件内存映射
 
-pub use file::{File, FileError, FileType, FileMetadata, SeekFrom};
+pub use file::{File, FileError, FileType, FileMetadata, SeekFrom, Stat};
 pub use inode::{Inode, MemInode, InodeHandle, permissions};
-pub use fd_table::{FileDescriptor, FileDescriptorTable, STDIN, STDOUT, STDERR};
+pub use fd_table::{FileDescriptor, FileDescriptorTable, OpenFileDescription, STDIN, STDOUT, STDERR};
 pub use stdio::{Stdin, Stdout, Stderr};
-pub use ramfs::{RamFS, RamInode, RamFile, DirEntry};
-pub use manager::{RAMFS, FD_TABLE, init};
+pub use ramfs::{RamFS, RamInode, RamFile, RamDir, DirEntry};
+pub use manager::{RAMFS, FD_TABLE, init, sync};
+pub use devmem::{DevMem, DevNull, DevZero, open_device};
+pub use eventfd::EventFd;
+pub use timerfd::{TimerFd, TimerSpec};
+pub use pipe::{PipeReader, PipeWriter};
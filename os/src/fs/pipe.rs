@@ -0,0 +1,290 @@
+//! 匿名管道（pipe）
+//!
+//! 读端和写端共享一个环形缓冲区（容量 PIPE_CAPACITY）：
+//! - write：追加到缓冲区，最多写到填满为止（返回实际写入的字节数）；
+//!   缓冲区已满返回 WouldBlock，读端全部关闭返回 BrokenPipe
+//! - read：读出已有的数据；缓冲区为空时，写端全部关闭则返回 0（文件末尾），
+//!   否则返回 WouldBlock，由 sys_read 通过 `register_reader` 阻塞等待
+//!
+//! 读端/写端对象被最后一个引用它的描述符释放时（close、dup 的副本都关闭）
+//! 计数减一；写端全部关闭时唤醒等待的读者，使其读到文件末尾。
+
+use super::file::{File, FileError, FileMetadata, FileType, SeekFrom};
+use super::inode::permissions;
+use crate::process::{self, ProcessId};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+/// 管道缓冲区容量（字节）
+pub const PIPE_CAPACITY: usize = 4096;
+
+/// 读端和写端共享的管道状态
+struct PipeBuffer {
+    /// 缓冲的数据
+    data: VecDeque<u8>,
+    /// 容量
+    capacity: usize,
+    /// 仍打开的读端数
+    readers: usize,
+    /// 仍打开的写端数
+    writers: usize,
+    /// 等待数据（或文件末尾）的进程
+    waiters: Vec<ProcessId>,
+}
+
+impl PipeBuffer {
+    /// 唤醒所有等待的读者
+    fn wake_readers(&mut self) {
+        for pid in self.waiters.drain(..) {
+            process::wake_up_process(pid);
+        }
+    }
+}
+
+/// 管道的读端
+pub struct PipeReader {
+    pipe: Arc<Mutex<PipeBuffer>>,
+}
+
+/// 管道的写端
+pub struct PipeWriter {
+    pipe: Arc<Mutex<PipeBuffer>>,
+}
+
+/// 创建容量为 PIPE_CAPACITY 的管道
+///
+/// # 返回
+/// (读端, 写端)
+pub fn pipe() -> (PipeReader, PipeWriter) {
+    pipe_with_capacity(PIPE_CAPACITY)
+}
+
+/// 创建指定容量的管道
+pub fn pipe_with_capacity(capacity: usize) -> (PipeReader, PipeWriter) {
+    let pipe = Arc::new(Mutex::new(PipeBuffer {
+        data: VecDeque::with_capacity(capacity),
+        capacity,
+        readers: 1,
+        writers: 1,
+        waiters: Vec::new(),
+    }));
+    (PipeReader { pipe: pipe.clone() }, PipeWriter { pipe })
+}
+
+/// 管道两端共同的元数据
+fn pipe_stat(pipe: &Mutex<PipeBuffer>) -> FileMetadata {
+    FileMetadata::new(
+        FileType::Pipe,
+        pipe.lock().data.len(),
+        permissions::S_IRUSR | permissions::S_IWUSR,
+    )
+}
+
+impl File for PipeReader {
+    /// 读出缓冲区中已有的数据
+    ///
+    /// # 说明
+    /// 缓冲区为空时：写端全部关闭返回 `Ok(0)`，否则返回 WouldBlock
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, FileError> {
+        let mut pipe = self.pipe.lock();
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if pipe.data.is_empty() {
+            return if pipe.writers == 0 { Ok(0) } else { Err(FileError::WouldBlock) };
+        }
+
+        let n = buf.len().min(pipe.data.len());
+        for (dst, src) in buf.iter_mut().zip(pipe.data.drain(..n)) {
+            *dst = src;
+        }
+        Ok(n)
+    }
+
+    /// 读端不能写
+    fn write(&mut self, _buf: &[u8]) -> Result<usize, FileError> {
+        Err(FileError::BadFileDescriptor)
+    }
+
+    /// 不支持定位
+    fn seek(&mut self, _pos: SeekFrom) -> Result<usize, FileError> {
+        Err(FileError::NotSeekable)
+    }
+
+    /// 登记当前进程，等待写入或写端全部关闭
+    ///
+    /// # 说明
+    /// 写端在缓冲区锁下唤醒等待者，这里也在同一把锁下标记阻塞；
+    /// read 返回之后已经有数据写入（或写端全部关闭）时不阻塞，直接重试
+    fn register_reader(&mut self) -> bool {
+        let Some(pid) = process::current_pid() else {
+            // 内核上下文没有进程可以阻塞
+            return false;
+        };
+        let mut pipe = self.pipe.lock();
+        if !pipe.data.is_empty() || pipe.writers == 0 {
+            return true;
+        }
+        if !process::prepare_block_current_process() {
+            return false;
+        }
+        if !pipe.waiters.contains(&pid) {
+            pipe.waiters.push(pid);
+        }
+        true
+    }
+
+    fn stat(&self) -> Result<FileMetadata, FileError> {
+        Ok(pipe_stat(&self.pipe))
+    }
+}
+
+impl File for PipeWriter {
+    /// 写端不能读
+    fn read(&mut self, _buf: &mut [u8]) -> Result<usize, FileError> {
+        Err(FileError::BadFileDescriptor)
+    }
+
+    /// 追加到缓冲区
+    ///
+    /// # 返回
+    /// 实际写入的字节数（缓冲区剩余空间不足时少于 `buf.len()`）；
+    /// 缓冲区已满返回 WouldBlock，读端全部关闭返回 BrokenPipe
+    fn write(&mut self, buf: &[u8]) -> Result<usize, FileError> {
+        let mut pipe = self.pipe.lock();
+        if pipe.readers == 0 {
+            return Err(FileError::BrokenPipe);
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let n = buf.len().min(pipe.capacity - pipe.data.len());
+        if n == 0 {
+            return Err(FileError::WouldBlock);
+        }
+        pipe.data.extend(&buf[..n]);
+        pipe.wake_readers();
+        Ok(n)
+    }
+
+    /// 不支持定位
+    fn seek(&mut self, _pos: SeekFrom) -> Result<usize, FileError> {
+        Err(FileError::NotSeekable)
+    }
+
+    fn stat(&self) -> Result<FileMetadata, FileError> {
+        Ok(pipe_stat(&self.pipe))
+    }
+}
+
+impl Drop for PipeReader {
+    fn drop(&mut self) {
+        self.pipe.lock().readers -= 1;
+    }
+}
+
+impl Drop for PipeWriter {
+    /// 最后一个写端关闭：唤醒等待的读者，让它们读到文件末尾
+    fn drop(&mut self) {
+        let mut pipe = self.pipe.lock();
+        pipe.writers -= 1;
+        if pipe.writers == 0 {
+            pipe.wake_readers();
+        }
+    }
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_pipe_write_then_read() {
+        let (mut reader, mut writer) = pipe();
+        assert_eq!(writer.write(b"hello"), Ok(5));
+        assert_eq!(writer.write(b", pipe"), Ok(6));
+
+        // 按写入顺序读出，可以分多次读
+        let mut buf = [0u8; 8];
+        assert_eq!(reader.read(&mut buf), Ok(8));
+        assert_eq!(&buf, b"hello, p");
+        assert_eq!(reader.read(&mut buf), Ok(3));
+        assert_eq!(&buf[..3], b"ipe");
+
+        // 没有数据但写端还在：需要等待；写端关闭后读到文件末尾
+        assert_eq!(reader.read(&mut buf), Err(FileError::WouldBlock));
+        drop(writer);
+        assert_eq!(reader.read(&mut buf), Ok(0));
+
+        // 方向错误
+        assert_eq!(reader.write(b"x"), Err(FileError::BadFileDescriptor));
+        assert_eq!(reader.stat().unwrap().file_type, FileType::Pipe);
+    }
+
+    #[test_case]
+    fn test_pipe_write_limited_by_capacity() {
+        let (mut reader, mut writer) = pipe_with_capacity(4);
+        assert_eq!(writer.write(b"abcdef"), Ok(4));
+        assert_eq!(writer.write(b"g"), Err(FileError::WouldBlock));
+
+        // 读出一部分后腾出空间
+        let mut buf = [0u8; 2];
+        assert_eq!(reader.read(&mut buf), Ok(2));
+        assert_eq!(writer.write(b"gh"), Ok(2));
+        let mut buf = [0u8; 4];
+        assert_eq!(reader.read(&mut buf), Ok(4));
+        assert_eq!(&buf, b"cdgh");
+
+        // 读端全部关闭后写入失败
+        drop(reader);
+        assert_eq!(writer.write(b"x"), Err(FileError::BrokenPipe));
+        let mut buf = [0u8; 1];
+        assert_eq!(writer.read(&mut buf), Err(FileError::BadFileDescriptor));
+    }
+
+    #[test_case]
+    fn test_blocked_reader_woken_by_write_and_close() {
+        use crate::process::{create_process, scheduler, ProcessState};
+
+        let process = create_process("pipe_reader", 0x1000, 0x2000, None).unwrap();
+        let pid = process.lock().pid();
+        scheduler::add_process(process.clone()).unwrap();
+        scheduler::lock_scheduler().run_for_test(pid);
+        let state = || process.lock().state();
+
+        // 缓冲区为空：登记并标记阻塞，写入唤醒读者
+        let (mut reader, mut writer) = pipe();
+        let mut buf = [0u8; 4];
+        assert_eq!(reader.read(&mut buf), Err(FileError::WouldBlock));
+        assert!(reader.register_reader());
+        assert_eq!(state(), ProcessState::Blocked);
+        assert_eq!(writer.write(b"ab"), Ok(2));
+        assert_eq!(state(), ProcessState::Ready);
+        assert!(!scheduler::lock_scheduler().finish_block());
+        assert_eq!(reader.read(&mut buf), Ok(2));
+
+        // read 之后、登记之前写入的数据不会被错过：登记时发现有数据，不阻塞
+        assert_eq!(reader.read(&mut buf), Err(FileError::WouldBlock));
+        assert_eq!(writer.write(b"c"), Ok(1));
+        assert!(reader.register_reader());
+        assert_eq!(state(), ProcessState::Running);
+        assert_eq!(reader.read(&mut buf), Ok(1));
+
+        // 最后一个写端关闭也唤醒读者，读到文件末尾
+        assert!(reader.register_reader());
+        assert_eq!(state(), ProcessState::Blocked);
+        drop(writer);
+        assert_eq!(state(), ProcessState::Ready);
+        assert!(!scheduler::lock_scheduler().finish_block());
+        assert_eq!(reader.read(&mut buf), Ok(0));
+
+        scheduler::lock_scheduler().remove_process(pid);
+    }
+}
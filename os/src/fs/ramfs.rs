@@ -1,11 +1,12 @@
 //! 内存文件系统（RamFS）
 
-use super::file::{File, FileError, FileType};
-use super::inode::{Inode, MemInode, permissions};
+use super::file::{File, FileError, FileMetadata, FileType, Stat};
+use super::inode::{Inode, InodeHandle, MemInode, permissions};
 use alloc::collections::BTreeMap;
 use alloc::string::String;
-use alloc::sync::Arc;
+use alloc::sync::{Arc, Weak};
 use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
 use spin::Mutex;
 
 /// 目录项
@@ -37,6 +38,7 @@ pub struct RamInode {
     size: usize,
     created: u64,
     modified: u64,
+    /// 指向该inode的目录项数（由 add_entry / remove_entry 维护）
     nlinks: usize,
 
     // 文件数据（对于普通文件）
@@ -55,7 +57,7 @@ impl RamInode {
             size: 0,
             created: 0,
             modified: 0,
-            nlinks: 1,
+            nlinks: 0,
             data: Vec::new(),
             entries: BTreeMap::new(),
         }
@@ -69,12 +71,35 @@ impl RamInode {
             size: 0,
             created: 0,
             modified: 0,
-            nlinks: 1,
+            nlinks: 0,
             data: Vec::new(),
             entries: BTreeMap::new(),
         }
     }
 
+    /// 创建指向 `target` 的符号链接，大小为目标路径的长度
+    pub fn new_symlink(ino: usize, target: &str) -> Self {
+        RamInode {
+            ino,
+            file_type: FileType::SymbolicLink,
+            mode: permissions::S_DEFAULT_LINK,
+            size: target.len(),
+            created: 0,
+            modified: 0,
+            nlinks: 0,
+            data: Vec::from(target.as_bytes()),
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// 符号链接的目标路径（不是符号链接时返回 None）
+    pub fn link_target(&self) -> Option<String> {
+        if self.file_type != FileType::SymbolicLink {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&self.data).into_owned())
+    }
+
     pub fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize, FileError> {
         if self.file_type != FileType::RegularFile {
             return Err(FileError::IsDirectory);
@@ -95,6 +120,11 @@ impl RamInode {
             return Err(FileError::IsDirectory);
         }
 
+        #[cfg(test)]
+        if crate::fault::should_fail(crate::fault::Fault::FsWrite) {
+            return Err(FileError::IoError);
+        }
+
         let end = offset + buf.len();
         if end > self.data.len() {
             self.data.resize(end, 0);
@@ -106,17 +136,27 @@ impl RamInode {
         Ok(buf.len())
     }
 
+    /// 调整文件大小
+    ///
+    /// # 说明
+    /// 缩短时同时更新文件的内存映射：新末尾之后的页取消映射，
+    /// 之后的访问产生 SIGBUS 而不是读到被截掉的数据（见 mmap 模块）
     pub fn truncate(&mut self, size: usize) -> Result<(), FileError> {
         if self.file_type != FileType::RegularFile {
             return Err(FileError::IsDirectory);
         }
 
+        if size < self.size {
+            super::mmap::truncate_mappings(self.ino, size);
+        }
+
         self.data.resize(size, 0);
         self.size = size;
         self.modified += 1;
         Ok(())
     }
 
+    /// 添加目录项，被指向的inode链接数加一
     pub fn add_entry(&mut self, name: String, inode: Arc<Mutex<RamInode>>) -> Result<(), FileError> {
         if self.file_type != FileType::Directory {
             return Err(FileError::NotDirectory);
@@ -126,17 +166,31 @@ impl RamInode {
             return Err(FileError::AlreadyExists);
         }
 
+        inode.lock().nlinks += 1;
         self.entries.insert(name, inode);
         Ok(())
     }
 
-    pub fn remove_entry(&mut self, name: &str) -> Result<(), FileError> {
+    /// 移除目录项，被指向的inode链接数减一
+    ///
+    /// # 返回
+    /// 被移除的inode
+    pub fn remove_entry(&mut self, name: &str) -> Result<Arc<Mutex<RamInode>>, FileError> {
         if self.file_type != FileType::Directory {
             return Err(FileError::NotDirectory);
         }
 
-        self.entries.remove(name).ok_or(FileError::NotFound)?;
-        Ok(())
+        let removed = self.entries.remove(name).ok_or(FileError::NotFound)?;
+        {
+            let mut inode = removed.lock();
+            inode.nlinks = inode.nlinks.saturating_sub(1);
+        }
+        Ok(removed)
+    }
+
+    /// 指向该inode的目录项数
+    pub fn nlinks(&self) -> usize {
+        self.nlinks
     }
 
     pub fn lookup(&self, name: &str) -> Result<Arc<Mutex<RamInode>>, FileError> {
@@ -147,6 +201,16 @@ impl RamInode {
         self.entries.get(name).cloned().ok_or(FileError::NotFound)
     }
 
+    /// 生成 stat 结果
+    pub fn stat(&self) -> Stat {
+        Stat {
+            ino: self.ino as u64,
+            mode: self.file_type.mode_bits() | self.mode,
+            nlink: self.nlinks as u32,
+            size: self.size as u64,
+        }
+    }
+
     pub fn list_entries(&self) -> Result<Vec<String>, FileError> {
         if self.file_type != FileType::Directory {
             return Err(FileError::NotDirectory);
@@ -172,6 +236,14 @@ impl Inode for RamInode {
     fn mode(&self) -> u32 {
         self.mode
     }
+
+    fn list_entries(&self) -> Result<Vec<String>, FileError> {
+        RamInode::list_entries(self)
+    }
+
+    fn lookup(&self, name: &str) -> Result<InodeHandle, FileError> {
+        RamInode::lookup(self, name).map(|inode| inode as InodeHandle)
+    }
 }
 
 /// RamFS文件句柄
@@ -187,8 +259,20 @@ impl RamFile {
 }
 
 impl File for RamFile {
+    /// 从当前偏移读取
+    ///
+    /// # 返回
+    /// - 偏移位于文件末尾或之后：`Ok(0)`，偏移不变
+    /// - 读取跨越文件末尾：只返回到末尾为止的字节数
+    ///
+    /// # 说明
+    /// 读取不会让偏移超过文件大小（seek 可以，但之后的读取返回 0）
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, FileError> {
-        let n = self.inode.lock().read_at(self.offset, buf)?;
+        let inode = self.inode.lock();
+        if self.offset >= inode.size() {
+            return Ok(0);
+        }
+        let n = inode.read_at(self.offset, buf)?;
         self.offset += n;
         Ok(n)
     }
@@ -229,28 +313,72 @@ impl File for RamFile {
     fn size(&self) -> Result<usize, FileError> {
         Ok(self.inode.lock().size())
     }
+
+    fn stat(&self) -> Result<FileMetadata, FileError> {
+        let inode = self.inode.lock();
+        Ok(FileMetadata::new(inode.file_type(), inode.size(), inode.mode()))
+    }
+
+    fn ino(&self) -> Option<usize> {
+        Some(self.inode.lock().ino)
+    }
+}
+
+/// RamFS目录句柄
+///
+/// 打开的目录只能作为 *at 系列调用的起点或被 stat，不能读写
+pub struct RamDir {
+    inode: Arc<Mutex<RamInode>>,
 }
 
+impl File for RamDir {
+    fn read(&mut self, _buf: &mut [u8]) -> Result<usize, FileError> {
+        Err(FileError::IsDirectory)
+    }
+
+    fn write(&mut self, _buf: &[u8]) -> Result<usize, FileError> {
+        Err(FileError::IsDirectory)
+    }
+
+    fn stat(&self) -> Result<FileMetadata, FileError> {
+        let inode = self.inode.lock();
+        Ok(FileMetadata::new(inode.file_type(), inode.size(), inode.mode()))
+    }
+
+    fn ino(&self) -> Option<usize> {
+        Some(self.inode.lock().ino)
+    }
+}
+
+/// 解析一个路径时最多跟随的符号链接数，超过时视为循环
+pub const MAX_SYMLINK_FOLLOWS: usize = 8;
+
 /// RamFS文件系统
 pub struct RamFS {
     root: Arc<Mutex<RamInode>>,
-    next_ino: Mutex<usize>,
+    /// 下一个可用的inode号（原子递增，分配时无需加锁）
+    next_ino: AtomicUsize,
+    /// inode号索引（弱引用：文件被删除后只要仍被打开就能找到）
+    ino_index: Mutex<BTreeMap<usize, Weak<Mutex<RamInode>>>>,
 }
 
 impl RamFS {
     pub fn new() -> Self {
-        let root = Arc::new(Mutex::new(RamInode::new_directory(1)));
+        // 根目录没有父目录，自身算一个链接
+        let mut root = RamInode::new_directory(1);
+        root.nlinks = 1;
+        let root = Arc::new(Mutex::new(root));
+        let mut ino_index = BTreeMap::new();
+        ino_index.insert(1, Arc::downgrade(&root));
         RamFS {
             root,
-            next_ino: Mutex::new(2),
+            next_ino: AtomicUsize::new(2),
+            ino_index: Mutex::new(ino_index),
         }
     }
 
     fn alloc_ino(&self) -> usize {
-        let mut next = self.next_ino.lock();
-        let ino = *next;
-        *next += 1;
-        ino
+        self.next_ino.fetch_add(1, Ordering::Relaxed)
     }
 
     pub fn root(&self) -> Arc<Mutex<RamInode>> {
@@ -258,27 +386,194 @@ impl RamFS {
     }
 
     pub fn create_file(&self, parent: Arc<Mutex<RamInode>>, name: String) -> Result<Arc<Mutex<RamInode>>, FileError> {
-        let ino = self.alloc_ino();
-        let inode = Arc::new(Mutex::new(RamInode::new_file(ino)));
-        parent.lock().add_entry(name, inode.clone())?;
-        Ok(inode)
+        let mut inode = RamInode::new_file(self.alloc_ino());
+        inode.mode &= !crate::process::current_umask();
+        self.link_new(&parent, name, inode)
     }
 
     pub fn create_directory(&self, parent: Arc<Mutex<RamInode>>, name: String) -> Result<Arc<Mutex<RamInode>>, FileError> {
-        let ino = self.alloc_ino();
-        let inode = Arc::new(Mutex::new(RamInode::new_directory(ino)));
+        let mut inode = RamInode::new_directory(self.alloc_ino());
+        inode.mode &= !crate::process::current_umask();
+        self.link_new(&parent, name, inode)
+    }
+
+    /// 在 `parent` 下创建指向 `target` 的符号链接
+    ///
+    /// # 说明
+    /// 不检查目标是否存在；相对目标在解析时从链接所在的目录开始
+    pub fn create_symlink(&self, parent: Arc<Mutex<RamInode>>, name: String, target: &str) -> Result<Arc<Mutex<RamInode>>, FileError> {
+        let inode = RamInode::new_symlink(self.alloc_ino(), target);
+        self.link_new(&parent, name, inode)
+    }
+
+    /// 将新建的inode挂到父目录下
+    ///
+    /// inode在加锁前就已构造完成，父目录锁只覆盖目录项插入本身，
+    /// 并发创建时对同一目录的串行化范围尽量小
+    fn link_new(&self, parent: &Arc<Mutex<RamInode>>, name: String, inode: RamInode) -> Result<Arc<Mutex<RamInode>>, FileError> {
+        let ino = inode.ino;
+        let inode = Arc::new(Mutex::new(inode));
         parent.lock().add_entry(name, inode.clone())?;
+        self.ino_index.lock().insert(ino, Arc::downgrade(&inode));
         Ok(inode)
     }
 
+    /// 删除目录项
+    ///
+    /// # 说明
+    /// 链接数降为 0 且没有其他引用（如打开的文件）时同时移出inode号索引，数据随之释放；
+    /// 仍被打开时保留索引和数据，直到最后一个引用释放
     pub fn remove(&self, parent: Arc<Mutex<RamInode>>, name: &str) -> Result<(), FileError> {
-        parent.lock().remove_entry(name)
+        let removed = parent.lock().remove_entry(name)?;
+
+        let (ino, nlinks) = {
+            let inode = removed.lock();
+            (inode.ino, inode.nlinks)
+        };
+        if nlinks == 0 && Arc::strong_count(&removed) == 1 {
+            self.ino_index.lock().remove(&ino);
+        }
+        Ok(())
+    }
+
+    /// 删除文件的目录项（unlink）
+    ///
+    /// # 返回
+    /// - Err(NotFound): 目录项不存在
+    /// - Err(IsDirectory): 目录项是目录
+    ///
+    /// # 说明
+    /// 符号链接删除的是链接本身；其余同 remove
+    pub fn unlink(&self, parent: Arc<Mutex<RamInode>>, name: &str) -> Result<(), FileError> {
+        let inode = parent.lock().lookup(name)?;
+        if inode.lock().file_type() == FileType::Directory {
+            return Err(FileError::IsDirectory);
+        }
+        drop(inode);
+        self.remove(parent, name)
+    }
+
+    /// 按inode号查找
+    ///
+    /// # 返回
+    /// - Ok(inode): inode仍然存在（有路径引用或仍被打开）
+    /// - Err(NotFound): inode不存在或已被完全释放
+    pub fn get_by_ino(&self, ino: usize) -> Result<Arc<Mutex<RamInode>>, FileError> {
+        let mut index = self.ino_index.lock();
+
+        match index.get(&ino).map(Weak::upgrade) {
+            Some(Some(inode)) => Ok(inode),
+            Some(None) => {
+                // 最后的引用已释放，顺便清理失效条目
+                index.remove(&ino);
+                Err(FileError::NotFound)
+            }
+            None => Err(FileError::NotFound),
+        }
+    }
+
+    /// 按inode号打开文件（open_by_handle）
+    pub fn open_by_ino(&self, ino: usize) -> Result<RamFile, FileError> {
+        let inode = self.get_by_ino(ino)?;
+        self.open_file(inode)
     }
 
     pub fn lookup(&self, parent: Arc<Mutex<RamInode>>, name: &str) -> Result<Arc<Mutex<RamInode>>, FileError> {
         parent.lock().lookup(name)
     }
 
+    /// 从起始目录解析路径
+    ///
+    /// # 参数
+    /// - `start`: 相对路径的起始目录
+    /// - `path`: 路径（以 `/` 开头时从根目录开始）
+    ///
+    /// # 说明
+    /// 跳过空分量和 `.`；inode 没有父目录指针，暂不支持 `..`
+    pub fn resolve(&self, start: Arc<Mutex<RamInode>>, path: &str) -> Result<Arc<Mutex<RamInode>>, FileError> {
+        self.resolve_in(self.root(), start, path)
+    }
+
+    /// 在指定的根目录下解析路径（chroot）
+    ///
+    /// # 参数
+    /// - `root`: 绝对路径的起点
+    /// - `start`: 相对路径的起始目录
+    /// - `path`: 路径
+    ///
+    /// # 说明
+    /// 不支持 `..`，因此解析结果不会离开 `root` 所在的子树；
+    /// 路径中的符号链接（包括最后一个分量）都会被跟随，
+    /// 绝对目标从 `root` 开始，同样不会离开子树
+    pub fn resolve_in(
+        &self,
+        root: Arc<Mutex<RamInode>>,
+        start: Arc<Mutex<RamInode>>,
+        path: &str,
+    ) -> Result<Arc<Mutex<RamInode>>, FileError> {
+        self.walk(root, start, path, true, &mut 0)
+    }
+
+    /// 同 resolve_in，但最后一个分量是符号链接时返回链接本身（lstat）
+    pub fn resolve_in_nofollow(
+        &self,
+        root: Arc<Mutex<RamInode>>,
+        start: Arc<Mutex<RamInode>>,
+        path: &str,
+    ) -> Result<Arc<Mutex<RamInode>>, FileError> {
+        self.walk(root, start, path, false, &mut 0)
+    }
+
+    /// 逐个分量解析路径
+    ///
+    /// # 参数
+    /// - `follow_last`: 最后一个分量是符号链接时是否跟随
+    /// - `follows`: 已跟随的符号链接数（嵌套解析链接目标时共享）
+    fn walk(
+        &self,
+        root: Arc<Mutex<RamInode>>,
+        start: Arc<Mutex<RamInode>>,
+        path: &str,
+        follow_last: bool,
+        follows: &mut usize,
+    ) -> Result<Arc<Mutex<RamInode>>, FileError> {
+        let mut current = if path.starts_with('/') { root.clone() } else { start };
+        let components: Vec<&str> = path
+            .split('/')
+            .filter(|component| !component.is_empty() && *component != ".")
+            .collect();
+
+        for (i, &name) in components.iter().enumerate() {
+            if name == ".." {
+                return Err(FileError::InvalidOperation);
+            }
+            let next = current.lock().lookup(name)?;
+            let is_last = i + 1 == components.len();
+            let target = next.lock().link_target();
+            current = match target {
+                Some(target) if follow_last || !is_last => {
+                    *follows += 1;
+                    if *follows > MAX_SYMLINK_FOLLOWS {
+                        return Err(FileError::TooManyLinks);
+                    }
+                    // 相对目标从链接所在的目录开始解析
+                    self.walk(root.clone(), current, &target, true, follows)?
+                }
+                _ => next,
+            };
+        }
+
+        Ok(current)
+    }
+
+    /// 打开目录（用作 *at 系列调用的目录描述符）
+    pub fn open_dir(&self, inode: Arc<Mutex<RamInode>>) -> Result<RamDir, FileError> {
+        if inode.lock().file_type() != FileType::Directory {
+            return Err(FileError::NotDirectory);
+        }
+        Ok(RamDir { inode })
+    }
+
     pub fn open_file(&self, inode: Arc<Mutex<RamInode>>) -> Result<RamFile, FileError> {
         let file_type = inode.lock().file_type();
         if file_type != FileType::RegularFile {
@@ -287,3 +582,204 @@ impl RamFS {
         Ok(RamFile::new(inode))
     }
 }
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{Task, simple_executor::{yield_once, SimpleExecutor}};
+
+    #[test_case]
+    fn test_concurrent_create_distinct_inodes() {
+        const TASKS: usize = 8;
+        const FILES_PER_TASK: usize = 8;
+
+        let fs = Arc::new(RamFS::new());
+        let mut executor = SimpleExecutor::new();
+
+        for t in 0..TASKS {
+            let fs = fs.clone();
+            executor.spawn(Task::new(async move {
+                for i in 0..FILES_PER_TASK {
+                    let name = alloc::format!("f{}_{}", t, i);
+                    fs.create_file(fs.root(), name).unwrap();
+                    yield_once().await;
+                }
+            }));
+        }
+        executor.run();
+
+        let root = fs.root();
+        let names = root.lock().list_entries().unwrap();
+        assert_eq!(names.len(), TASKS * FILES_PER_TASK);
+
+        let mut inos: Vec<usize> = names
+            .iter()
+            .map(|name| root.lock().lookup(name).unwrap().lock().ino())
+            .collect();
+        inos.sort_unstable();
+        inos.dedup();
+        assert_eq!(inos.len(), TASKS * FILES_PER_TASK);
+    }
+
+    #[test_case]
+    fn test_umask_applied_on_create() {
+        use crate::syscall::{test_syscall, SyscallId};
+
+        let fs = RamFS::new();
+        let old = test_syscall(SyscallId::Umask as usize, 0o077, 0, 0);
+
+        let file = fs.create_file(fs.root(), String::from("private")).unwrap();
+        let dir = fs.create_directory(fs.root(), String::from("private_dir")).unwrap();
+
+        // 恢复原掩码，并确认 sys_umask 返回的是之前的值
+        assert_eq!(test_syscall(SyscallId::Umask as usize, old as usize, 0, 0), 0o077);
+
+        assert_eq!(file.lock().mode(), permissions::S_DEFAULT_FILE & !0o077);
+        assert_eq!(dir.lock().mode(), permissions::S_DEFAULT_DIR & !0o077);
+        assert_eq!(file.lock().mode() & 0o077, 0);
+    }
+
+    #[test_case]
+    fn test_ramfile_eof_is_ok_zero() {
+        use crate::fs::file::SeekFrom;
+
+        let fs = RamFS::new();
+        let inode = fs.create_file(fs.root(), String::from("eof")).unwrap();
+        let mut file = fs.open_file(inode).unwrap();
+        file.write(b"abc").unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+
+        let mut buf = [0u8; 8];
+        assert_eq!(file.read(&mut buf), Ok(3));
+
+        // 到达末尾后重复读取都返回 Ok(0)
+        assert_eq!(file.read(&mut buf), Ok(0));
+        assert_eq!(file.read(&mut buf), Ok(0));
+
+        // 越过末尾定位后读取同样是 Ok(0)
+        file.seek(SeekFrom::End(10)).unwrap();
+        assert_eq!(file.read(&mut buf), Ok(0));
+
+        file.seek(SeekFrom::Start(0)).unwrap();
+        assert_eq!(file.read_all().unwrap(), b"abc");
+    }
+
+    #[test_case]
+    fn test_pipe_and_devices_eof_is_ok_zero() {
+        use crate::fs::devmem::{DEV_NULL_PATH, DEV_ZERO_PATH};
+        use crate::fs::{open_device, pipe::pipe, File};
+
+        let mut buf = [0xFFu8; 8];
+
+        // 写端关闭后，剩余数据读完即为 Ok(0)，read_all 随之结束
+        let (mut reader, mut writer) = pipe();
+        writer.write(b"abc").unwrap();
+        drop(writer);
+        assert_eq!(reader.read_all().unwrap(), b"abc");
+        assert_eq!(reader.read(&mut buf), Ok(0));
+        assert_eq!(reader.read(&mut buf), Ok(0));
+
+        // /dev/null：读总是 Ok(0)
+        let null = open_device(DEV_NULL_PATH).unwrap();
+        assert_eq!(null.lock().read(&mut buf), Ok(0));
+        assert_eq!(null.lock().write(b"discarded"), Ok(9));
+        assert!(null.lock().read_all().unwrap().is_empty());
+
+        // /dev/zero：不会到达末尾，读出零字节；只有空缓冲区才返回 Ok(0)
+        let zero = open_device(DEV_ZERO_PATH).unwrap();
+        assert_eq!(zero.lock().read(&mut buf), Ok(8));
+        assert_eq!(buf, [0; 8]);
+        assert_eq!(zero.lock().read(&mut []), Ok(0));
+    }
+
+    #[test_case]
+    fn test_get_by_ino_tracks_open_references() {
+        let fs = RamFS::new();
+        let inode = fs.create_file(fs.root(), String::from("handle")).unwrap();
+        let ino = inode.lock().ino();
+        drop(inode);
+
+        assert!(fs.get_by_ino(ino).is_ok());
+
+        // 打开后删除全部路径引用：打开的文件仍使inode存活
+        let mut file = fs.open_by_ino(ino).unwrap();
+        file.write(b"still here").unwrap();
+        fs.remove(fs.root(), "handle").unwrap();
+        assert!(fs.lookup(fs.root(), "handle").is_err());
+
+        let alive = fs.get_by_ino(ino).unwrap();
+        assert_eq!(alive.lock().size(), 10);
+        drop(alive);
+
+        // 关闭最后一个引用后找不到
+        drop(file);
+        assert_eq!(fs.get_by_ino(ino).err(), Some(FileError::NotFound));
+
+        // 没有打开引用时删除，索引立即清除
+        let inode = fs.create_file(fs.root(), String::from("gone")).unwrap();
+        let ino = inode.lock().ino();
+        drop(inode);
+        fs.remove(fs.root(), "gone").unwrap();
+        assert_eq!(fs.get_by_ino(ino).err(), Some(FileError::NotFound));
+    }
+
+    #[test_case]
+    fn test_read_at_eof_returns_zero() {
+        use super::super::file::SeekFrom;
+
+        let fs = RamFS::new();
+        let inode = fs.create_file(fs.root(), String::from("eof")).unwrap();
+        let mut file = fs.open_file(inode).unwrap();
+        file.write(b"0123456789").unwrap();
+        let mut buf = [0u8; 4];
+
+        // 恰好位于末尾
+        file.seek(SeekFrom::Start(10)).unwrap();
+        assert_eq!(file.read(&mut buf), Ok(0));
+        assert_eq!(file.seek(SeekFrom::Current(0)), Ok(10));
+
+        // 跨越末尾：只读到末尾，偏移停在文件大小
+        file.seek(SeekFrom::Start(8)).unwrap();
+        assert_eq!(file.read(&mut buf), Ok(2));
+        assert_eq!(&buf[..2], b"89");
+        assert_eq!(file.seek(SeekFrom::Current(0)), Ok(10));
+        assert_eq!(file.read(&mut buf), Ok(0));
+
+        // 完全越过末尾：返回 0，偏移不变
+        file.seek(SeekFrom::Start(32)).unwrap();
+        assert_eq!(file.read(&mut buf), Ok(0));
+        assert_eq!(file.seek(SeekFrom::Current(0)), Ok(32));
+    }
+
+    #[test_case]
+    fn test_resolve_follows_symlinks() {
+        let fs = RamFS::new();
+        let etc = fs.create_directory(fs.root(), String::from("etc")).unwrap();
+        let conf = fs.create_file(etc.clone(), String::from("conf")).unwrap();
+        fs.create_symlink(fs.root(), String::from("config"), "/etc/conf").unwrap();
+        fs.create_symlink(fs.root(), String::from("cfgdir"), "etc").unwrap();
+        fs.create_symlink(etc.clone(), String::from("relative"), "conf").unwrap();
+
+        // 绝对目标、目录链接和相对目标（从链接所在目录开始）
+        for path in ["/config", "/cfgdir/conf", "/etc/relative", "/cfgdir/relative"] {
+            assert!(Arc::ptr_eq(&fs.resolve(fs.root(), path).unwrap(), &conf), "{}", path);
+        }
+
+        // 不跟随最后一个分量，但中间的链接照常跟随
+        let root = fs.root();
+        let link = fs.resolve_in_nofollow(root.clone(), root.clone(), "/cfgdir/relative").unwrap();
+        assert_eq!(link.lock().file_type(), FileType::SymbolicLink);
+        assert_eq!(link.lock().link_target().as_deref(), Some("conf"));
+        assert_eq!(link.lock().stat().size, 4);
+
+        // 循环链接
+        fs.create_symlink(fs.root(), String::from("loop_a"), "loop_b").unwrap();
+        fs.create_symlink(fs.root(), String::from("loop_b"), "loop_a").unwrap();
+        assert_eq!(fs.resolve(fs.root(), "/loop_a").err(), Some(FileError::TooManyLinks));
+        assert!(fs.resolve_in_nofollow(root.clone(), root, "/loop_a").is_ok());
+    }
+}
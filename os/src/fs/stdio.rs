@@ -1,7 +1,9 @@
 //! 标准输入输出文件
 
-use super::file::{File, FileError};
+use super::file::{File, FileError, SeekFrom};
 use crate::println;
+use crate::process;
+use crate::task::{keyboard, line};
 
 /// 标准输入
 pub struct Stdin;
@@ -13,13 +15,47 @@ impl Stdin {
 }
 
 impl File for Stdin {
-    fn read(&mut self, _buf: &mut [u8]) -> Result<usize, FileError> {
-        // 暂不支持键盘输入
-        Err(FileError::InvalidOperation)
+    /// 阻塞读取一行键盘输入（行缓冲，支持退格和回显，见 task::line）
+    ///
+    /// 没有完成的行时阻塞当前进程；在 idle/内核上下文中没有进程
+    /// 可以阻塞，返回 `WouldBlock` 而不是让内核卡死
+    ///
+    /// 先标记阻塞、登记，再检查一次输入：检查之前到达的字符由检查读到，
+    /// 之后到达的字符会唤醒已经是 Blocked 的本进程，不会丢失唤醒
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, FileError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let n = line::stdin_read(buf);
+            if n > 0 {
+                return Ok(n);
+            }
+
+            let pid = process::current_pid().ok_or(FileError::WouldBlock)?;
+            if !process::prepare_block_current_process() {
+                return Err(FileError::WouldBlock);
+            }
+            keyboard::register_blocked_reader(pid);
+
+            let n = line::stdin_read(buf);
+            if n > 0 {
+                process::cancel_block_current_process();
+                return Ok(n);
+            }
+            process::finish_block_current_process();
+        }
     }
 
+    /// 标准输入只读
     fn write(&mut self, _buf: &[u8]) -> Result<usize, FileError> {
-        Err(FileError::InvalidOperation)
+        Err(FileError::BadFileDescriptor)
+    }
+
+    /// 终端不支持定位
+    fn seek(&mut self, _pos: SeekFrom) -> Result<usize, FileError> {
+        Err(FileError::NotSeekable)
     }
 }
 
@@ -33,8 +69,9 @@ impl Stdout {
 }
 
 impl File for Stdout {
+    /// 只写
     fn read(&mut self, _buf: &mut [u8]) -> Result<usize, FileError> {
-        Err(FileError::InvalidOperation)
+        Err(FileError::BadFileDescriptor)
     }
 
     fn write(&mut self, buf: &[u8]) -> Result<usize, FileError> {
@@ -45,6 +82,11 @@ impl File for Stdout {
             Err(FileError::IoError)
         }
     }
+
+    /// 终端不支持定位
+    fn seek(&mut self, _pos: SeekFrom) -> Result<usize, FileError> {
+        Err(FileError::NotSeekable)
+    }
 }
 
 /// 标准错误
@@ -57,8 +99,9 @@ impl Stderr {
 }
 
 impl File for Stderr {
+    /// 只写
     fn read(&mut self, _buf: &mut [u8]) -> Result<usize, FileError> {
-        Err(FileError::InvalidOperation)
+        Err(FileError::BadFileDescriptor)
     }
 
     fn write(&mut self, buf: &[u8]) -> Result<usize, FileError> {
@@ -69,4 +112,46 @@ impl File for Stderr {
             Err(FileError::IoError)
         }
     }
+
+    /// 终端不支持定位
+    fn seek(&mut self, _pos: SeekFrom) -> Result<usize, FileError> {
+        Err(FileError::NotSeekable)
+    }
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_stdin_read_without_current_process() {
+        // 内核上下文没有当前进程，阻塞读应返回 WouldBlock 而不是挂起
+        assert!(process::current_pid().is_none());
+
+        let mut stdin = Stdin::new();
+        let mut buf = [0u8; 8];
+        assert_eq!(stdin.read(&mut buf), Err(FileError::WouldBlock));
+    }
+
+    #[test_case]
+    fn test_stdio_seek_and_direction_errors() {
+        let mut stdin = Stdin::new();
+        let mut stdout = Stdout::new();
+        let mut stderr = Stderr::new();
+        let mut buf = [0u8; 4];
+
+        // 定位终端：不支持定位，而不是笼统的无效操作
+        assert_eq!(stdout.seek(SeekFrom::Start(0)), Err(FileError::NotSeekable));
+        assert_eq!(stderr.seek(SeekFrom::Current(1)), Err(FileError::NotSeekable));
+        assert_eq!(stdin.seek(SeekFrom::End(0)), Err(FileError::NotSeekable));
+
+        // 反方向读写：错误的文件描述符
+        assert_eq!(stdin.write(b"x"), Err(FileError::BadFileDescriptor));
+        assert_eq!(stdout.read(&mut buf), Err(FileError::BadFileDescriptor));
+        assert_eq!(stderr.read(&mut buf), Err(FileError::BadFileDescriptor));
+    }
 }
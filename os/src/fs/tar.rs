@@ -0,0 +1,403 @@
+//! initrd 解包（ustar 格式的 tar 归档）
+//!
+//! 归档由 512 字节的块组成：每个成员一个头块，后面跟按块对齐的数据，
+//! 以全零块结束。这里只处理普通文件和目录，其他类型（链接、设备等）跳过。
+
+use super::file::{FileError, FileType};
+use super::inode::Inode;
+use super::ramfs::{RamFS, RamInode};
+use alloc::string::String;
+use alloc::sync::Arc;
+use spin::Mutex;
+
+/// tar 块大小
+pub const BLOCK_SIZE: usize = 512;
+
+/// 头块中各字段的位置
+const NAME: core::ops::Range<usize> = 0..100;
+const SIZE: core::ops::Range<usize> = 124..136;
+const CHECKSUM: core::ops::Range<usize> = 148..156;
+const TYPEFLAG: usize = 156;
+const MAGIC: core::ops::Range<usize> = 257..262;
+const PREFIX: core::ops::Range<usize> = 345..500;
+
+/// 成员类型
+const TYPE_REGULAR: u8 = b'0';
+const TYPE_REGULAR_OLD: u8 = 0;
+const TYPE_DIRECTORY: u8 = b'5';
+
+/// 解包统计
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ExtractStats {
+    /// 创建或覆盖的普通文件数
+    pub files: usize,
+    /// 创建的目录数（已存在的不计）
+    pub directories: usize,
+    /// 跳过的不支持类型的成员数
+    pub skipped: usize,
+}
+
+/// 把 tar 归档解包到 `fs` 的根目录
+///
+/// # 参数
+/// - `fs`: 目标文件系统
+/// - `archive`: 完整的归档数据
+///
+/// # 返回
+/// - `Ok(stats)`: 解包统计
+/// - `Err(InvalidOperation)`: 头块不是 ustar 格式、校验和错误或数据被截断
+/// - 其他错误：在 RamFS 中创建文件/目录失败（例如路径上有同名文件）
+///
+/// # 说明
+/// 路径中缺失的父目录会自动创建；已存在的普通文件会被覆盖。
+/// 每解包一个成员检查一次是否需要让出 CPU（见 process::maybe_yield）
+pub fn extract(fs: &RamFS, archive: &[u8]) -> Result<ExtractStats, FileError> {
+    let mut stats = ExtractStats::default();
+    let mut offset = 0;
+
+    while offset + BLOCK_SIZE <= archive.len() {
+        let header = &archive[offset..offset + BLOCK_SIZE];
+
+        // 全零块表示归档结束
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        if &header[MAGIC] != b"ustar" || !checksum_ok(header) {
+            return Err(FileError::InvalidOperation);
+        }
+
+        let size = parse_octal(&header[SIZE]).ok_or(FileError::InvalidOperation)?;
+        let data_start = offset + BLOCK_SIZE;
+        let data_end = data_start.checked_add(size).ok_or(FileError::InvalidOperation)?;
+        if data_end > archive.len() {
+            return Err(FileError::InvalidOperation);
+        }
+
+        let path = member_path(header)?;
+        match header[TYPEFLAG] {
+            TYPE_REGULAR | TYPE_REGULAR_OLD => {
+                stats.directories += extract_file(fs, &path, &archive[data_start..data_end])?;
+                stats.files += 1;
+            }
+            TYPE_DIRECTORY => {
+                stats.directories += make_dirs(fs, &path)?;
+            }
+            _ => stats.skipped += 1,
+        }
+
+        offset = data_start + size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE;
+
+        // 成员之间不持有任何 inode 锁，大归档在这里让出 CPU
+        crate::process::maybe_yield();
+    }
+
+    Ok(stats)
+}
+
+/// 解包位于固定物理地址的归档
+///
+/// # Safety
+/// `[addr, addr + len)` 必须是已映射、在解包期间不会被修改的内存
+pub unsafe fn extract_at(fs: &RamFS, addr: usize, len: usize) -> Result<ExtractStats, FileError> {
+    let archive = core::slice::from_raw_parts(addr as *const u8, len);
+    extract(fs, archive)
+}
+
+/// 校验头块（校验和字段按 8 个空格计算）
+fn checksum_ok(header: &[u8]) -> bool {
+    let expected = match parse_octal(&header[CHECKSUM]) {
+        Some(sum) => sum,
+        None => return false,
+    };
+    let sum: usize = header
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if CHECKSUM.contains(&i) { b' ' as usize } else { b as usize })
+        .sum();
+    sum == expected
+}
+
+/// 解析以 NUL 或空格结尾的八进制数字段
+fn parse_octal(field: &[u8]) -> Option<usize> {
+    let mut value: usize = 0;
+    let mut digits = 0;
+    for &b in field {
+        match b {
+            b'0'..=b'7' => {
+                value = value.checked_mul(8)?.checked_add((b - b'0') as usize)?;
+                digits += 1;
+            }
+            b' ' if digits == 0 => continue,
+            0 | b' ' => break,
+            _ => return None,
+        }
+    }
+    Some(value)
+}
+
+/// 以 NUL 结尾的字符串字段
+fn c_str(field: &[u8]) -> Result<&str, FileError> {
+    let len = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    core::str::from_utf8(&field[..len]).map_err(|_| FileError::InvalidOperation)
+}
+
+/// 成员的完整路径（prefix + "/" + name）
+fn member_path(header: &[u8]) -> Result<String, FileError> {
+    let name = c_str(&header[NAME])?;
+    let prefix = c_str(&header[PREFIX])?;
+
+    let mut path = String::from(prefix);
+    if !path.is_empty() {
+        path.push('/');
+    }
+    path.push_str(name);
+    Ok(path)
+}
+
+/// 拆分路径，忽略空组件和 "."，拒绝 ".."
+fn components(path: &str) -> Result<impl Iterator<Item = &str>, FileError> {
+    if path.split('/').any(|c| c == "..") {
+        return Err(FileError::InvalidOperation);
+    }
+    Ok(path.split('/').filter(|c| !c.is_empty() && *c != "."))
+}
+
+/// 在 `parent` 下查找目录，不存在时创建
+///
+/// # 返回
+/// (目录 inode, 是否新建)
+fn dir_child(
+    fs: &RamFS,
+    parent: &Arc<Mutex<RamInode>>,
+    name: &str,
+) -> Result<(Arc<Mutex<RamInode>>, bool), FileError> {
+    let existing = parent.lock().lookup(name);
+    match existing {
+        Ok(inode) => {
+            if inode.lock().file_type() != FileType::Directory {
+                return Err(FileError::NotDirectory);
+            }
+            Ok((inode, false))
+        }
+        Err(_) => Ok((fs.create_directory(parent.clone(), String::from(name))?, true)),
+    }
+}
+
+/// 创建路径上的所有目录（类似 mkdir -p）
+///
+/// # 返回
+/// 新建的目录数
+fn make_dirs(fs: &RamFS, path: &str) -> Result<usize, FileError> {
+    let mut current = fs.root();
+    let mut created = 0;
+    for name in components(path)? {
+        let (next, new) = dir_child(fs, &current, name)?;
+        created += new as usize;
+        current = next;
+    }
+    Ok(created)
+}
+
+/// 创建（或覆盖）普通文件并写入内容
+///
+/// # 返回
+/// 为放置该文件新建的父目录数
+fn extract_file(fs: &RamFS, path: &str, data: &[u8]) -> Result<usize, FileError> {
+    let (dir, name) = match path.trim_end_matches('/').rsplit_once('/') {
+        Some((dir, name)) => (dir, name),
+        None => ("", path),
+    };
+    if name.is_empty() || name == "." || name == ".." {
+        return Err(FileError::InvalidOperation);
+    }
+
+    let mut parent = fs.root();
+    let mut created = 0;
+    for component in components(dir)? {
+        let (next, new) = dir_child(fs, &parent, component)?;
+        created += new as usize;
+        parent = next;
+    }
+
+    let existing = parent.lock().lookup(name);
+    let inode = match existing {
+        Ok(inode) => inode,
+        Err(_) => fs.create_file(parent, String::from(name))?,
+    };
+
+    let mut inode = inode.lock();
+    inode.truncate(0)?;
+    inode.write_at(0, data)?;
+    Ok(created)
+}
+
+// ============================================
+// 测试辅助：构造 ustar 归档
+// ============================================
+
+/// 构造一个 ustar 成员（头块 + 按块对齐的数据）
+#[cfg(test)]
+fn member(path: &str, typeflag: u8, data: &[u8]) -> alloc::vec::Vec<u8> {
+    let mut header = [0u8; BLOCK_SIZE];
+    header[..path.len()].copy_from_slice(path.as_bytes());
+    header[100..107].copy_from_slice(b"0000644");
+    let size = alloc::format!("{:011o}", data.len());
+    header[SIZE.start..SIZE.start + 11].copy_from_slice(size.as_bytes());
+    header[TYPEFLAG] = typeflag;
+    header[MAGIC].copy_from_slice(b"ustar");
+    header[262..265].copy_from_slice(b"\x0000");
+
+    header[CHECKSUM].fill(b' ');
+    let sum: usize = header.iter().map(|&b| b as usize).sum();
+    let sum = alloc::format!("{:06o}\0 ", sum);
+    header[CHECKSUM].copy_from_slice(sum.as_bytes());
+
+    let mut out = header.to_vec();
+    out.extend_from_slice(data);
+    out.resize(out.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+    out
+}
+
+/// 构造包含给定普通文件的归档（含结尾的两个全零块）
+#[cfg(test)]
+pub(crate) fn build_archive(files: &[(&str, &[u8])]) -> alloc::vec::Vec<u8> {
+    let mut archive = alloc::vec::Vec::new();
+    for (path, data) in files {
+        archive.extend(member(path, TYPE_REGULAR, data));
+    }
+    archive.resize(archive.len() + 2 * BLOCK_SIZE, 0);
+    archive
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test_case]
+    fn test_extract_embedded_archive() {
+        let mut archive = Vec::new();
+        archive.extend(member("etc/", TYPE_DIRECTORY, b""));
+        archive.extend(member("etc/hostname", TYPE_REGULAR, b"errorOS\n"));
+        archive.extend(member("bin/hello", TYPE_REGULAR, &[0xAB; 600]));
+        archive.extend(member("etc/link", b'2', b""));
+        archive.extend(vec![0u8; 2 * BLOCK_SIZE]);
+
+        let fs = RamFS::new();
+        let stats = extract(&fs, &archive).unwrap();
+        assert_eq!(stats, ExtractStats { files: 2, directories: 2, skipped: 1 });
+
+        let mut names = fs.root().lock().list_entries().unwrap();
+        names.sort();
+        assert_eq!(names, ["bin", "etc"]);
+
+        let hostname = fs.resolve(fs.root(), "/etc/hostname").unwrap();
+        let mut buf = [0u8; 16];
+        let n = hostname.lock().read_at(0, &mut buf).unwrap();
+        assert_eq!(&buf[..n], b"errorOS\n");
+
+        let hello = fs.resolve(fs.root(), "/bin/hello").unwrap();
+        let mut buf = vec![0u8; 700];
+        assert_eq!(hello.lock().read_at(0, &mut buf).unwrap(), 600);
+        assert!(buf[..600].iter().all(|&b| b == 0xAB));
+
+        assert!(fs.resolve(fs.root(), "/etc/link").is_err());
+
+        // 校验和错误的归档被拒绝
+        archive[0] ^= 1;
+        assert_eq!(extract(&RamFS::new(), &archive), Err(FileError::InvalidOperation));
+    }
+
+    #[test_case]
+    fn test_extract_yields_to_higher_priority_process() {
+        use crate::process::{self, scheduler, ProcessState, PRIORITY_MAX};
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        use lazy_static::lazy_static;
+
+        lazy_static! {
+            static ref FS: RamFS = RamFS::new();
+        }
+        /// 高优先级进程运行时根目录中已有的成员数（usize::MAX 表示还没有运行）
+        static SEEN_MEMBERS: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+        fn urgent_main() -> ! {
+            let members = FS.root().lock().list_entries().unwrap().len();
+            SEEN_MEMBERS.store(members, Ordering::SeqCst);
+            process::exit_current_process(0);
+            unreachable!()
+        }
+
+        // running 正在解包（测试本身），urgent 是优先级更高的就绪进程
+        let running = process::spawn_test_process("tar_running", None);
+        let running_pid = running.lock().pid();
+        scheduler::lock_scheduler().run_for_test(running_pid);
+        let urgent = process::create_kernel_thread("tar_urgent", urgent_main).unwrap();
+        let urgent_pid = urgent.lock().pid();
+        scheduler::add_process(urgent.clone()).unwrap();
+        assert!(process::set_priority(urgent_pid, PRIORITY_MAX));
+
+        let mut archive = Vec::new();
+        for name in ["a", "b", "c"] {
+            archive.extend(member(name, TYPE_REGULAR, b"data"));
+        }
+        archive.extend(vec![0u8; 2 * BLOCK_SIZE]);
+
+        // 时间片已经用完：解包完第一个成员后切换到 urgent，它退出后继续解包
+        let switches = scheduler::context_switches();
+        crate::percpu::current().set_need_resched();
+        let stats = extract(&FS, &archive);
+
+        assert_eq!(SEEN_MEMBERS.load(Ordering::SeqCst), 1);
+        assert!(scheduler::context_switches() >= switches + 2);
+        assert_eq!(urgent.lock().state(), ProcessState::Zombie);
+        assert_eq!(scheduler::current_pid(), Some(running_pid));
+        assert_eq!(stats.map(|stats| stats.files), Ok(3));
+
+        let mut scheduler = scheduler::lock_scheduler();
+        scheduler.remove_process(urgent_pid);
+        scheduler.remove_process(running_pid);
+    }
+
+    #[test_case]
+    fn test_extract_handles_resched_between_members() {
+        use crate::process::trace::{self, SchedEvent};
+        use crate::process::{create_process, scheduler, ProcessState};
+
+        // 模拟 running 正在解包，没有其他就绪进程
+        let running = create_process("tar_running", 0x1000, 0x2000, None).unwrap();
+        let running_pid = running.lock().pid();
+        scheduler::add_process(running.clone()).unwrap();
+        scheduler::lock_scheduler().run_for_test(running_pid);
+
+        let mut archive = Vec::new();
+        for name in ["a", "b", "c"] {
+            archive.extend(member(name, TYPE_REGULAR, b"data"));
+        }
+        archive.extend(vec![0u8; 2 * BLOCK_SIZE]);
+
+        // 时间片已经用完：成员之间的安全点处理掉调度请求，
+        // 唯一可运行的进程继续解包，不发生切换
+        trace::clear();
+        trace::set_enabled(true);
+        crate::percpu::current().set_need_resched();
+        let fs = RamFS::new();
+        let stats = extract(&fs, &archive);
+        trace::set_enabled(false);
+
+        assert!(!crate::percpu::current().need_resched());
+        assert!(!trace::snapshot()
+            .into_iter()
+            .any(|record| matches!(record.event, SchedEvent::Switch { .. })));
+        assert_eq!(scheduler::current_pid(), Some(running_pid));
+        assert_eq!(running.lock().state(), ProcessState::Running);
+        assert_eq!(stats.map(|stats| stats.files), Ok(3));
+
+        scheduler::lock_scheduler().remove_process(running_pid);
+        trace::clear();
+    }
+}
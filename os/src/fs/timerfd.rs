@@ -0,0 +1,192 @@
+//! 定时器文件（timerfd）
+//!
+//! 设置后在指定的 tick 数之后变为可读，之后可以按固定间隔重复到期：
+//! - read：读出 8 字节（小端 u64），为上次读取以来的到期次数，读后清零；
+//!   还没有到期时阻塞（非阻塞模式下返回 WouldBlock）
+//! - 时间单位是时钟中断 tick（trap::uptime_ticks），不是纳秒
+//!
+//! 阻塞读取时把下一次到期时刻登记到 trap::timer 队列，到期后由时钟中断唤醒，
+//! 之后 sys_read 重试读取（见 File::register_reader）。
+
+use super::file::{File, FileError, FileMetadata, FileType, SeekFrom};
+use super::inode::permissions;
+use crate::process;
+use crate::trap::{self, timer};
+
+/// sys_timerfd_create 标志：非阻塞
+pub const TFD_NONBLOCK: usize = 0o4000;
+/// sys_timerfd_create 标志：exec 时关闭（还没有 exec，接受但忽略）
+pub const TFD_CLOEXEC: usize = 0o2000000;
+
+/// 时钟：系统时间
+pub const CLOCK_REALTIME: usize = 0;
+/// 时钟：单调时钟
+pub const CLOCK_MONOTONIC: usize = 1;
+
+/// sys_timerfd_settime 的参数（对应 itimerspec，单位为 tick）
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimerSpec {
+    /// 首次到期之后的间隔，0 表示只到期一次
+    pub interval: u64,
+    /// 距首次到期的 tick 数，0 表示停止定时器
+    pub value: u64,
+}
+
+/// 读写的数据大小（一个 u64）
+const COUNT_SIZE: usize = core::mem::size_of::<u64>();
+
+/// 定时器文件
+pub struct TimerFd {
+    /// 下一次到期的 tick（None 表示未设置）
+    deadline: Option<u64>,
+    /// 重复间隔（0 表示只到期一次）
+    interval: u64,
+    /// 非阻塞模式
+    nonblocking: bool,
+}
+
+impl TimerFd {
+    /// 创建未设置的定时器
+    pub fn new(nonblocking: bool) -> Self {
+        TimerFd {
+            deadline: None,
+            interval: 0,
+            nonblocking,
+        }
+    }
+
+    /// 截至 `now` 的到期次数，同时推进下一次到期时刻
+    fn expirations(&mut self, now: u64) -> u64 {
+        let Some(deadline) = self.deadline else {
+            return 0;
+        };
+        if now < deadline {
+            return 0;
+        }
+
+        if self.interval == 0 {
+            self.deadline = None;
+            return 1;
+        }
+        let count = 1 + (now - deadline) / self.interval;
+        self.deadline = Some(deadline + count * self.interval);
+        count
+    }
+}
+
+impl File for TimerFd {
+    /// 读出到期次数
+    ///
+    /// # 说明
+    /// 缓冲区小于 8 字节返回 InvalidOperation；还没有到期返回 WouldBlock
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, FileError> {
+        if buf.len() < COUNT_SIZE {
+            return Err(FileError::InvalidOperation);
+        }
+
+        let count = self.expirations(trap::uptime_ticks());
+        if count == 0 {
+            return Err(FileError::WouldBlock);
+        }
+        buf[..COUNT_SIZE].copy_from_slice(&count.to_le_bytes());
+        Ok(COUNT_SIZE)
+    }
+
+    /// 定时器不能写
+    fn write(&mut self, _buf: &[u8]) -> Result<usize, FileError> {
+        Err(FileError::InvalidOperation)
+    }
+
+    /// 不支持定位
+    fn seek(&mut self, _pos: SeekFrom) -> Result<usize, FileError> {
+        Err(FileError::NotSeekable)
+    }
+
+    /// 阻塞模式下登记到期时唤醒当前进程
+    ///
+    /// # 说明
+    /// 定时器未设置时永远不会到期，不登记等待（返回 WouldBlock）。
+    /// 先标记阻塞再登记：登记之后时钟中断随时可能到期唤醒
+    fn register_reader(&mut self) -> bool {
+        if self.nonblocking {
+            return false;
+        }
+        let (Some(deadline), Some(pid)) = (self.deadline, process::current_pid()) else {
+            return false;
+        };
+        if !process::prepare_block_current_process() {
+            return false;
+        }
+        if !timer::wake_at(deadline, pid) {
+            process::cancel_block_current_process();
+            return false;
+        }
+        true
+    }
+
+    fn set_timer(&mut self, value: u64, interval: u64) -> Result<(u64, u64), FileError> {
+        let now = trap::uptime_ticks();
+        let old = (
+            self.deadline.map_or(0, |deadline| deadline.saturating_sub(now)),
+            self.interval,
+        );
+
+        self.deadline = (value != 0).then(|| now + value);
+        self.interval = interval;
+        Ok(old)
+    }
+
+    fn stat(&self) -> Result<FileMetadata, FileError> {
+        Ok(FileMetadata::new(FileType::CharDevice, 0, permissions::S_IRUSR))
+    }
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_count(timer: &mut TimerFd) -> Result<u64, FileError> {
+        let mut buf = [0u8; 8];
+        timer.read(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    #[test_case]
+    fn test_timerfd_reports_one_expiration() {
+        const TICKS: u64 = 3;
+
+        let mut timer = TimerFd::new(true);
+        assert_eq!(read_count(&mut timer), Err(FileError::WouldBlock));
+
+        timer.set_timer(TICKS, 0).unwrap();
+        trap::advance_ticks(TICKS - 1);
+        assert_eq!(read_count(&mut timer), Err(FileError::WouldBlock));
+
+        trap::advance_ticks(1);
+        assert_eq!(read_count(&mut timer), Ok(1));
+
+        // 只到期一次：之后不再可读
+        trap::advance_ticks(TICKS);
+        assert_eq!(read_count(&mut timer), Err(FileError::WouldBlock));
+    }
+
+    #[test_case]
+    fn test_timerfd_interval_counts_missed_expirations() {
+        let mut timer = TimerFd::new(true);
+        assert_eq!(timer.set_timer(2, 2), Ok((0, 0)));
+
+        trap::advance_ticks(7);
+        // 到期时刻 +2、+4、+6
+        assert_eq!(read_count(&mut timer), Ok(3));
+
+        // 下一次在 +8，还剩 1 个 tick；重新设置为 0 停止定时器
+        assert_eq!(timer.set_timer(0, 0), Ok((1, 2)));
+        trap::advance_ticks(2);
+        assert_eq!(read_count(&mut timer), Err(FileError::WouldBlock));
+    }
+}
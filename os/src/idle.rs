@@ -0,0 +1,114 @@
+/*
+ * ============================================
+ * 空闲等待策略
+ * ============================================
+ * 功能：CPU 无事可做时（执行器任务队列为空、只剩 idle 进程）如何等待
+ *
+ * 策略：
+ * - Wfi（默认）：wfi 等待中断，省电，唤醒延迟取决于实现
+ * - Spin：忙等一次（spin_loop 提示），唤醒延迟最低，但一直占满 CPU
+ * - Suspend：SBI HSM hart_suspend（保持型挂起），最省电，
+ *   SBI 不支持时退回 wfi
+ *
+ * 配置：启动参数 idle=wfi|spin|suspend，或初始化时调用 set_strategy
+ * ============================================
+ */
+
+use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+/// 空闲等待策略
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleStrategy {
+    /// wfi 等待中断
+    Wfi = 0,
+    /// 忙等
+    Spin = 1,
+    /// SBI HSM 保持型挂起
+    Suspend = 2,
+}
+
+impl IdleStrategy {
+    /// 所有策略（按编码顺序）
+    pub const ALL: [IdleStrategy; 3] = [IdleStrategy::Wfi, IdleStrategy::Spin, IdleStrategy::Suspend];
+
+    /// 按启动参数中的名字解析
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "wfi" => Some(IdleStrategy::Wfi),
+            "spin" => Some(IdleStrategy::Spin),
+            "suspend" => Some(IdleStrategy::Suspend),
+            _ => None,
+        }
+    }
+
+    fn from_raw(value: u8) -> Self {
+        Self::ALL.get(value as usize).copied().unwrap_or(IdleStrategy::Wfi)
+    }
+}
+
+/// 当前的空闲等待策略
+static STRATEGY: AtomicU8 = AtomicU8::new(IdleStrategy::Wfi as u8);
+
+/// 每种策略被执行的次数（按 IdleStrategy 编码索引）
+static WAITS: [AtomicUsize; 3] = [AtomicUsize::new(0), AtomicUsize::new(0), AtomicUsize::new(0)];
+
+/// 设置空闲等待策略
+pub fn set_strategy(strategy: IdleStrategy) {
+    STRATEGY.store(strategy as u8, Ordering::Relaxed);
+}
+
+/// 当前的空闲等待策略
+pub fn strategy() -> IdleStrategy {
+    IdleStrategy::from_raw(STRATEGY.load(Ordering::Relaxed))
+}
+
+/// 从启动参数中解析 idle=<策略>
+pub fn strategy_from_bootargs(bootargs: &str) -> Option<IdleStrategy> {
+    bootargs
+        .split_whitespace()
+        .find_map(|arg| arg.strip_prefix("idle="))
+        .and_then(IdleStrategy::from_name)
+}
+
+/// 某种策略被执行的次数
+pub fn waits(strategy: IdleStrategy) -> usize {
+    WAITS[strategy as usize].load(Ordering::Relaxed)
+}
+
+/// 按当前策略等待一次
+///
+/// # 说明
+/// 调用者负责在返回后重新检查是否有工作；
+/// Spin 策略立即返回，Wfi/Suspend 在中断到来后返回
+pub fn wait() {
+    let strategy = strategy();
+    WAITS[strategy as usize].fetch_add(1, Ordering::Relaxed);
+
+    match strategy {
+        IdleStrategy::Wfi => riscv::asm::wfi(),
+        IdleStrategy::Spin => core::hint::spin_loop(),
+        IdleStrategy::Suspend => {
+            if crate::smp::hart_suspend_retentive().is_err() {
+                riscv::asm::wfi();
+            }
+        }
+    }
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_strategy_from_bootargs() {
+        assert_eq!(strategy_from_bootargs("console=ttyS0 idle=spin"), Some(IdleStrategy::Spin));
+        assert_eq!(strategy_from_bootargs("idle=suspend quiet"), Some(IdleStrategy::Suspend));
+        assert_eq!(strategy_from_bootargs("idle=poll"), None);
+        assert_eq!(strategy_from_bootargs("quiet"), None);
+    }
+}
@@ -0,0 +1,128 @@
+/*
+ * ============================================
+ * initrd 定位
+ * ============================================
+ * 功能：确定引导加载器放入内存的 initrd 镜像的位置和大小
+ *
+ * 来源（按优先级）：
+ * 1. 设备树 /chosen 的 linux,initrd-start / linux,initrd-end
+ *    （QEMU 使用 -initrd 时写入）
+ * 2. 设备树 /chosen/bootargs 中的 initrd=<起始地址>,<大小>
+ *
+ * 找到的镜像由 system_init 交给 fs::tar 解包；
+ * 没有 initrd 时使用内置的 init_filesystem_content
+ *
+ * 找到 initrd 后在帧分配器中登记为保留区域，解包前不会被分配出去
+ * ============================================
+ */
+
+use spin::Mutex;
+use crate::fdt::Fdt;
+
+/// initrd 镜像的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Initrd {
+    /// 起始物理地址
+    pub start: usize,
+    /// 大小（字节）
+    pub size: usize,
+}
+
+/// 启动时找到的 initrd
+static INITRD: Mutex<Option<Initrd>> = Mutex::new(None);
+
+/// 从设备树中查找 initrd
+///
+/// # 返回
+/// 没有 initrd 或属性不合法（结束地址不大于起始地址）时返回 None
+pub fn from_device_tree(fdt: &Fdt) -> Option<Initrd> {
+    let start = fdt.property_usize("/chosen", "linux,initrd-start");
+    let end = fdt.property_usize("/chosen", "linux,initrd-end");
+    if let (Some(start), Some(end)) = (start, end) {
+        return (end > start).then(|| Initrd { start, size: end - start });
+    }
+
+    fdt.property_str("/chosen", "bootargs").and_then(from_bootargs)
+}
+
+/// 从启动参数中解析 initrd=<起始地址>,<大小>
+///
+/// # 说明
+/// 数值可以是十进制或 0x 开头的十六进制
+pub fn from_bootargs(bootargs: &str) -> Option<Initrd> {
+    let value = bootargs
+        .split_whitespace()
+        .find_map(|arg| arg.strip_prefix("initrd="))?;
+    let (start, size) = value.split_once(',')?;
+    let initrd = Initrd {
+        start: parse_number(start)?,
+        size: parse_number(size)?,
+    };
+    (initrd.size > 0).then_some(initrd)
+}
+
+/// 解析十进制或 0x 开头的十六进制数
+fn parse_number(s: &str) -> Option<usize> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+/// 启动时调用：从 SBI 传入的设备树地址中查找 initrd 并记录
+///
+/// # 参数
+/// - `dtb`: 设备树物理地址（_start 时的 a1），为 0 表示没有
+pub fn init(dtb: usize) {
+    let initrd = unsafe { Fdt::from_addr(dtb) }.and_then(|fdt| from_device_tree(&fdt));
+    if let Some(initrd) = initrd {
+        crate::serial_println!(
+            "[INITRD] Found initrd at {:#x} ({} bytes)",
+            initrd.start,
+            initrd.size
+        );
+        let start = crate::memory::PhysAddr::new(initrd.start);
+        if let Err(e) = crate::memory::reserve_region(start, initrd.size) {
+            crate::serial_println!("[INITRD] Failed to reserve initrd: {}", e);
+        }
+    }
+    set(initrd);
+}
+
+/// 设置（或清除）记录的 initrd
+pub fn set(initrd: Option<Initrd>) {
+    *INITRD.lock() = initrd;
+}
+
+/// 启动时找到的 initrd
+pub fn get() -> Option<Initrd> {
+    *INITRD.lock()
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_initrd_from_device_tree_and_bootargs() {
+        let dtb = crate::fdt::build_chosen_dtb(&[
+            ("linux,initrd-start", &0x8800_0000u64.to_be_bytes()),
+            ("linux,initrd-end", &0x8800_1000u64.to_be_bytes()),
+        ]);
+        let fdt = Fdt::new(&dtb).unwrap();
+        assert_eq!(from_device_tree(&fdt), Some(Initrd { start: 0x8800_0000, size: 0x1000 }));
+
+        // 没有 linux,initrd-* 时退回到 bootargs
+        let dtb = crate::fdt::build_chosen_dtb(&[("bootargs", b"quiet initrd=0x88000000,4096\0")]);
+        let fdt = Fdt::new(&dtb).unwrap();
+        assert_eq!(from_device_tree(&fdt), Some(Initrd { start: 0x8800_0000, size: 4096 }));
+
+        assert_eq!(from_bootargs("console=ttyS0"), None);
+        assert_eq!(from_bootargs("initrd=0x1000"), None);
+        assert_eq!(from_bootargs("initrd=0x1000,0"), None);
+    }
+}
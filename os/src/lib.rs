@@ -12,6 +12,11 @@
  * - 内存管理（memory）
  * - 堆分配器（allocator）
  * - 异步任务（task）
+ * - 启动阶段报告（boot）
+ * - 多核启动（smp）
+ * - 每个 hart 的私有数据（percpu）
+ * - 设备树与 initrd（fdt、initrd）
+ * - 关机（system）
  * ============================================
  */
 
@@ -32,6 +37,8 @@ pub mod serial;      // 串口驱动
 pub mod console;     // 控制台输出
 pub mod interrupts;  // 中断和异常处理（旧，兼容用）
 pub mod trap;        // 陷阱处理（新，第6章）
+pub mod plic;        // 平台级中断控制器（外部中断）
+pub mod idle;        // 空闲等待策略（wfi / 自旋 / HSM 挂起）
 pub mod memory;      // 内存管理
 pub mod allocator;   // 堆分配器
 pub mod task;        // 异步任务系统
@@ -39,6 +46,18 @@ pub mod syscall;     // 系统调用
 pub mod process;     // 进程管理（第6章新增）
 pub mod fs;          // 文件系统（第7章新增）
 pub mod system_init; // 系统初始化
+pub mod boot;        // 启动阶段报告
+pub mod smp;         // 多核启动（SBI HSM）
+pub mod percpu;      // 每个 hart 的私有数据（tp）
+pub mod sync;        // 同步原语（关中断自旋锁）
+pub mod fdt;         // 设备树读取
+pub mod initrd;      // initrd 定位
+pub mod elf;         // ELF 文件解析
+pub mod user_programs; // 内嵌的用户测试程序
+pub mod commands;    // 内核命令（uptime 等）
+pub mod system;      // 关机
+#[cfg(test)]
+pub mod fault;       // 故障注入（仅测试）
 
 // ============================================
 // 外部 crate
@@ -127,10 +146,15 @@ pub fn exit_qemu(exit_code: QemuExitCode) {
 /// # 功能
 /// - 初始化中断描述符表
 /// - 启用中断
+///
+/// # 说明
+/// 报告 serial、trap 两个启动阶段，后续阶段由 kernel_main 报告
 pub fn init() {
+    boot::report_phase("serial");
     serial_println!("[INIT] Initializing RISC-V OS");
 
     // 初始化中断系统
+    boot::report_phase("trap");
     interrupts::init_idt();
 
     // 启用中断
@@ -139,6 +163,8 @@ pub fn init() {
     serial_println!("[INIT] Initialization complete");
 }
 
+pub use system::shutdown;
+
 /// 无限循环（使用 wfi 指令节能）
 ///
 /// # 说明
@@ -169,6 +195,16 @@ fn panic(info: &PanicInfo) -> ! {
 #[no_mangle]
 pub extern "C" fn _start() -> ! {
     init();
+
+    // 与 kernel_main 相同：帧分配器从内核之后开始，堆紧接在内核之后
+    extern "C" {
+        static kernel_end: u8;
+    }
+    let mut memory_manager = memory::init(core::ptr::addr_of!(kernel_end) as usize);
+    allocator::init_heap(&mut memory_manager.frame_allocator).expect("heap initialization failed");
+    memory::install_frame_allocator(memory_manager.frame_allocator);
+    trap::irq::init();
+
     test_main();
     hlt_loop();
 }
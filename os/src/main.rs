@@ -15,7 +15,8 @@ use os::task::executor::Executor;
 /// 定义在汇编中，负责：
 /// - 清零 BSS 段
 /// - 设置栈指针
-/// - 跳转到 kernel_main
+/// - 设置 tp 指向启动 hart 的控制块（a0 为 SBI 传入的 hartid）
+/// - 跳转到 kernel_main，传入设备树地址（SBI 传入的 a1）
 global_asm!(
     ".section .text.entry",
     ".globl _start",
@@ -31,7 +32,12 @@ global_asm!(
     "   addi t0, t0, 8",
     "   j 1b",
     "2:",
-    // 跳转到 kernel_main
+    // 保存设备树地址（a1），percpu_init_hart 可能破坏调用者保存的寄存器
+    "   mv s1, a1",
+    // 设置 tp（a0 = hartid，清零 BSS 时未被修改）
+    "   call percpu_init_hart",
+    // 跳转到 kernel_main(dtb)
+    "   mv a0, s1",
     "   call kernel_main",
     // 如果返回，进入死循环
     "3:",
@@ -61,8 +67,11 @@ use os::task::Task;
 /// - 初始化内核
 /// - 设置内存管理
 /// - 启动异步执行器
+///
+/// # 参数
+/// - `dtb`: SBI 传入的设备树物理地址（用于查找 initrd）
 #[no_mangle]
-pub extern "C" fn kernel_main() -> ! {
+pub extern "C" fn kernel_main(dtb: usize) -> ! {
     use os::memory;
     use os::allocator;
 
@@ -76,13 +85,17 @@ pub extern "C" fn kernel_main() -> ! {
     let kernel_end_addr = unsafe { &kernel_end as *const u8 as usize };
 
     // 初始化内存管理
+    os::boot::report_phase("memory");
     let mut memory_manager = memory::init(kernel_end_addr);
+    memory::reserve_device_tree(&mut memory_manager.frame_allocator, dtb);
 
+    os::boot::report_phase("heap");
     allocator::init_heap(&mut memory_manager.frame_allocator)
         .expect("heap initialization failed");
+    memory::install_frame_allocator(memory_manager.frame_allocator);
 
-    // 初始化文件系统（第7章新增）
-    os::fs::init();
+    // 下半部队列需要堆，且必须在中断处理函数使用之前分配
+    os::trap::irq::init();
 
     let heap_value=Box::new(41);
     println!("heap_value {:p}",heap_value);
@@ -102,14 +115,34 @@ pub extern "C" fn kernel_main() -> ! {
     // ========================================
     // 初始化进程管理系统
     // ========================================
+    os::boot::report_phase("process");
     os::process::init();
 
+    // 初始化文件系统（第7章新增）
+    os::boot::report_phase("fs");
+    os::fs::init();
+
+    // 查找引导加载器提供的 initrd（用于填充文件系统）
+    os::initrd::init(dtb);
+
+    // 启动参数中的 init=<路径>（init 程序的位置）
+    os::system_init::configure(dtb);
+
     // ========================================
     // 系统环境初始化（带可视化演示）
     // ========================================
     // 完整的系统初始化（进程 + 文件系统）
     os::system_init::initialize_system();
 
+    // 启动内核工作进程（spawn_blocking 的执行者）
+    os::task::blocking::init();
+
+    // 启动从核并停靠在 idle 循环（调度器仍只在主核运行）
+    os::smp::start_secondary_harts();
+
+    // 打印各启动阶段耗时
+    os::boot::finish();
+
     // ========================================
     // 以下是演示代码（已禁用，如需查看演示请取消注释）
     // ========================================
@@ -128,13 +161,14 @@ pub extern "C" fn kernel_main() -> ! {
     // println!("========================================\n");
     // os::fs::visualization::run_all_demos();
 
-    // 测试完成后进入等待模式（而不是异步执行器）
     println!("系统已就绪，按Ctrl+A然后X退出QEMU\n");
 
-    // 简单的死循环等待（避免wfi触发中断问题）
-    loop {
-        core::hint::spin_loop();
-    }
+    let mut executor = Executor::new();
+    executor.spawn(Task::new(keyboard::print_keypresses()));
+    executor.spawn(Task::new(os::trap::irq::bottom_half_task()));
+
+    // 执行器只有在收到关机请求（SysRq o）后才会返回，随后关闭系统
+    os::system::run_until_shutdown(&mut executor);
 }
 
 async fn async_number() -> u32 {
@@ -11,8 +11,8 @@
  * ============================================
  */
 
-use super::{PageTable, PhysAddr, VirtAddr, PageTableFlags, SimpleFrameAllocator, PAGE_SIZE};
-use super::paging::{map_page, unmap_page};
+use super::{PageTable, PhysAddr, VirtAddr, PageTableFlags, SimpleFrameAllocator, FrameInit, PAGE_SIZE};
+use super::paging::{map_page, map_zeroed_page, unmap_page};
 use alloc::vec::Vec;
 use core::ops::Range;
 
@@ -155,11 +155,18 @@ impl AddressSpace {
         // 分配并映射每个页面
         let page_count = area.page_count();
 
+        // 用户可访问的页面必须清零，避免泄漏其他进程的数据
+        let init = if area.flags & PageTableFlags::User as usize != 0 {
+            FrameInit::Zeroed
+        } else {
+            FrameInit::Uninit
+        };
+
         for i in 0..page_count {
             let vaddr = VirtAddr::new(start.as_usize() + i * PAGE_SIZE);
 
             // 分配物理帧
-            let frame = allocator.allocate().ok_or("Out of memory")?;
+            let frame = allocator.allocate_with(init).ok_or("Out of memory")?;
             let paddr = frame.start_address();
 
             // 建立映射
@@ -213,6 +220,19 @@ impl AddressSpace {
         Ok(())
     }
 
+    /// 分配一个清零的页面并映射到 `vaddr` 所在的页（缺页处理路径）
+    ///
+    /// # 返回
+    /// 新页面的物理地址
+    pub fn map_zeroed_page(
+        &mut self,
+        vaddr: VirtAddr,
+        flags: usize,
+        allocator: &mut SimpleFrameAllocator,
+    ) -> Result<PhysAddr, &'static str> {
+        unsafe { map_zeroed_page(&mut *self.page_table, vaddr, flags, allocator) }
+    }
+
     /// 取消映射内存区域
     pub fn unmap_region(&mut self, start: VirtAddr, size: usize) -> Result<(), &'static str> {
         let page_count = (size + PAGE_SIZE - 1) / PAGE_SIZE;
@@ -251,11 +271,11 @@ impl AddressSpace {
         unsafe {
             // Sv39 模式，ASID = 0
             satp::set(satp::Mode::Sv39, 0, ppn);
-
-            // 刷新整个 TLB
-            core::arch::asm!("sfence.vma");
         }
 
+        // 刷新整个 TLB
+        super::flush_tlb_all();
+
         crate::serial_println!("[ADDRESS_SPACE] Address space activated");
     }
 
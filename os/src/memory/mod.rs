@@ -26,7 +26,8 @@ pub mod address_space;
 // 重新导出页表管理函数
 pub use paging::{
     walk_page_table, walk_page_table_verbose,
-    map_page, map_page_verbose,
+    lookup_pte, lookup_pte_mut,
+    map_page, map_page_verbose, map_zeroed_page,
     unmap_page,
     translate_addr as translate_addr_current
 };
@@ -43,6 +44,47 @@ pub const PAGE_SIZE: usize = 4096;
 /// 页表项数量
 pub const PAGE_TABLE_ENTRIES: usize = 512;
 
+// ============================================
+// TLB 刷新
+// ============================================
+
+/// 测试中统计 TLB 刷新次数，用于确认页表修改后确实刷新了
+#[cfg(test)]
+static TLB_FLUSHES: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+
+/// 刷新单个虚拟页的 TLB 项（本 hart）
+///
+/// # 说明
+/// 修改（建立、删除、改权限）页表项之后必须调用，否则 TLB 中的旧翻译仍然生效；
+/// 多 hart 时还需要通知其他 hart 刷新（TLB shootdown），目前只刷新本地
+pub fn flush_tlb_page(vaddr: VirtAddr) {
+    #[cfg(test)]
+    TLB_FLUSHES.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+
+    unsafe {
+        core::arch::asm!("sfence.vma {0}, zero", in(reg) vaddr.as_usize(), options(nostack));
+    }
+}
+
+/// 刷新整个 TLB（本 hart）
+///
+/// # 说明
+/// 切换页表（写 satp）或大范围修改映射后使用
+pub fn flush_tlb_all() {
+    #[cfg(test)]
+    TLB_FLUSHES.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+
+    unsafe {
+        core::arch::asm!("sfence.vma zero, zero", options(nostack));
+    }
+}
+
+/// 到目前为止的 TLB 刷新次数
+#[cfg(test)]
+pub(crate) fn tlb_flush_count() -> usize {
+    TLB_FLUSHES.load(core::sync::atomic::Ordering::Relaxed)
+}
+
 /// RISC-V Sv39 虚拟地址
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(transparent)]
@@ -232,6 +274,15 @@ impl PageTable {
     }
 }
 
+/// 新分配物理帧的初始内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameInit {
+    /// 清零（映射到用户地址空间的帧必须使用，避免泄漏其他进程的数据）
+    Zeroed,
+    /// 不初始化（调用者会完整覆盖帧内容时使用，如堆和页表）
+    Uninit,
+}
+
 /// 简单的物理帧分配器
 ///
 /// # 说明
@@ -239,10 +290,29 @@ impl PageTable {
 /// QEMU virt 机器的物理内存布局：
 /// - 0x80000000 - 0x88000000（128MB）
 pub struct SimpleFrameAllocator {
+    first_frame: usize,
     next_frame: usize,
     end_frame: usize,
+    /// 保留区域（按页对齐的 [start, end)），分配时跳过
+    reserved: [(usize, usize); MAX_RESERVED_REGIONS],
+    reserved_count: usize,
 }
 
+/// 最多可登记的保留区域数（固定数组，堆初始化之前也能登记）
+pub const MAX_RESERVED_REGIONS: usize = 16;
+
+/// QEMU virt 机器的物理内存起始地址（OpenSBI 和内核镜像从这里开始）
+pub const MEMORY_START: usize = 0x8000_0000;
+
+/// QEMU virt 机器的 MMIO 窗口（起始地址, 大小）
+pub const MMIO_REGIONS: &[(usize, usize)] = &[
+    (0x0010_0000, 0x1000),      // 测试设备（关机）
+    (0x0200_0000, 0x1_0000),    // CLINT
+    (0x0c00_0000, 0x400_0000),  // PLIC
+    (0x1000_0000, 0x100),       // UART
+    (0x1000_1000, 0x8000),      // virtio
+];
+
 impl SimpleFrameAllocator {
     /// 创建新的帧分配器
     ///
@@ -260,31 +330,182 @@ impl SimpleFrameAllocator {
         );
 
         SimpleFrameAllocator {
+            first_frame: next_frame,
             next_frame,
             end_frame,
+            reserved: [(0, 0); MAX_RESERVED_REGIONS],
+            reserved_count: 0,
+        }
+    }
+
+    /// 登记保留区域，之后不会分配与它重叠的帧
+    ///
+    /// # 参数
+    /// - `start`: 起始物理地址（向下对齐到页）
+    /// - `size`: 大小（结束地址向上对齐到页）
+    ///
+    /// # 返回
+    /// 与已有保留区域重叠或保留区域表已满时返回错误
+    ///
+    /// # 说明
+    /// 已经分配出去的帧不受影响，应在分配开始之前登记。
+    /// 内核结束地址之下的区域（内核镜像、MMIO 窗口）分配器本来就不会分配，
+    /// 登记它们是为了让之后与它们重叠的 reserve/claim 被拒绝
+    pub fn reserve(&mut self, start: PhysAddr, size: usize) -> Result<(), &'static str> {
+        if size == 0 {
+            return Ok(());
+        }
+
+        let begin = start.as_usize() & !(PAGE_SIZE - 1);
+        let end = (start.as_usize().saturating_add(size)).div_ceil(PAGE_SIZE) * PAGE_SIZE;
+        let overlaps = self.reserved[..self.reserved_count]
+            .iter()
+            .any(|&(start, stop)| start < end && begin < stop);
+        if overlaps {
+            return Err("Reserved region overlaps an existing one");
+        }
+        if self.reserved_count == MAX_RESERVED_REGIONS {
+            return Err("Too many reserved regions");
         }
+
+        self.reserved[self.reserved_count] = (begin, end);
+        self.reserved_count += 1;
+        Ok(())
     }
 
-    /// 分配一个物理帧
+    /// 占用一段指定的物理内存（之后不会再被分配出去）
+    ///
+    /// # 参数
+    /// - `start`: 起始物理地址（必须按页对齐）
+    /// - `size`: 大小（向上对齐到页）
+    ///
+    /// # 返回
+    /// 区域中有帧已经分配出去、与保留区域重叠或超出物理内存时返回错误
+    ///
+    /// # 说明
+    /// 分配是递增的，只有还没有分配到的帧可以占用；
+    /// 成功后区域登记为保留区域（重叠检查见 reserve）
+    pub fn claim(&mut self, start: PhysAddr, size: usize) -> Result<(), &'static str> {
+        let begin = start.as_usize();
+        if !begin.is_multiple_of(PAGE_SIZE) {
+            return Err("Claimed region is not page aligned");
+        }
+        let end = begin
+            .checked_add(size.div_ceil(PAGE_SIZE) * PAGE_SIZE)
+            .ok_or("Claimed region overflows")?;
+
+        if begin < self.next_frame * PAGE_SIZE {
+            return Err("Claimed frames are already allocated");
+        }
+        if end > self.end_frame * PAGE_SIZE {
+            return Err("Claimed region is outside physical memory");
+        }
+
+        self.reserve(start, end - begin)
+    }
+
+    /// 管理的物理帧总数（包括保留区域）
+    pub fn total_frames(&self) -> usize {
+        self.end_frame - self.first_frame
+    }
+
+    /// 尚未分配出去的帧数（不包括其中的保留区域）
+    pub fn free_frames(&self) -> usize {
+        let begin = self.next_frame * PAGE_SIZE;
+        let end = self.end_frame * PAGE_SIZE;
+        let reserved: usize = self.reserved[..self.reserved_count]
+            .iter()
+            .map(|&(start, stop)| stop.min(end).saturating_sub(start.max(begin)))
+            .sum();
+        (self.end_frame - self.next_frame).saturating_sub(reserved / PAGE_SIZE)
+    }
+
+    /// 地址是否位于保留区域内
+    pub fn is_reserved(&self, addr: PhysAddr) -> bool {
+        self.reserved_end(addr.as_usize()).is_some()
+    }
+
+    /// 包含 `addr` 的保留区域的结束地址
+    fn reserved_end(&self, addr: usize) -> Option<usize> {
+        self.reserved[..self.reserved_count]
+            .iter()
+            .find(|&&(start, end)| start <= addr && addr < end)
+            .map(|&(_, end)| end)
+    }
+
+    /// 分配一个物理帧（内容未初始化）
     pub fn allocate(&mut self) -> Option<PhysFrame> {
-        if self.next_frame >= self.end_frame {
+        #[cfg(test)]
+        if crate::fault::should_fail(crate::fault::Fault::FrameAlloc) {
             return None;
         }
 
-        let frame = PhysFrame::containing_address(PhysAddr::new(
-            self.next_frame * PAGE_SIZE,
-        ));
-        self.next_frame += 1;
+        while self.next_frame < self.end_frame {
+            let addr = self.next_frame * PAGE_SIZE;
+
+            // 跳过整个保留区域
+            if let Some(end) = self.reserved_end(addr) {
+                self.next_frame = end / PAGE_SIZE;
+                continue;
+            }
+
+            self.next_frame += 1;
+            return Some(PhysFrame::containing_address(PhysAddr::new(addr)));
+        }
+
+        None
+    }
+
+    /// 按指定方式初始化并分配一个物理帧
+    ///
+    /// # 参数
+    /// - `init`: 帧的初始内容（清零或不初始化）
+    ///
+    /// # 说明
+    /// 物理内存当前恒等映射，直接通过物理地址清零
+    pub fn allocate_with(&mut self, init: FrameInit) -> Option<PhysFrame> {
+        let frame = self.allocate()?;
+
+        if init == FrameInit::Zeroed {
+            unsafe {
+                core::ptr::write_bytes(frame.start_address().as_usize() as *mut u8, 0, PAGE_SIZE);
+            }
+        }
 
         Some(frame)
     }
 
+    /// 分配一个清零的物理帧
+    pub fn allocate_zeroed(&mut self) -> Option<PhysFrame> {
+        self.allocate_with(FrameInit::Zeroed)
+    }
+
     /// 释放一个物理帧（当前实现为空，可扩展）
     pub fn deallocate(&mut self, _frame: PhysFrame) {
         // TODO: 实现帧回收
     }
 }
 
+/// 启动后供缺页处理等运行时路径使用的帧分配器
+static FRAME_ALLOCATOR: crate::sync::IrqSpinLock<Option<SimpleFrameAllocator>> =
+    crate::sync::IrqSpinLock::new(None);
+
+/// 交出帧分配器，之后通过 with_frame_allocator 使用
+///
+/// # 说明
+/// 在堆初始化之后调用；启动代码不再直接使用原来的分配器
+pub fn install_frame_allocator(allocator: SimpleFrameAllocator) {
+    *FRAME_ALLOCATOR.lock() = Some(allocator);
+}
+
+/// 使用全局帧分配器
+///
+/// # 返回
+/// 分配器尚未安装时返回 None
+pub fn with_frame_allocator<R>(f: impl FnOnce(&mut SimpleFrameAllocator) -> R) -> Option<R> {
+    FRAME_ALLOCATOR.lock().as_mut().map(f)
+}
+
 /// 内存管理器
 pub struct MemoryManager {
     pub frame_allocator: SimpleFrameAllocator,
@@ -309,7 +530,6 @@ impl MemoryManager {
 /// - `kernel_end`: 内核结束地址
 pub fn init(kernel_end: usize) -> MemoryManager {
     // QEMU virt 机器的物理内存：0x80000000 - 0x88000000（128MB）
-    const MEMORY_START: usize = 0x8000_0000;
     const MEMORY_SIZE: usize = 128 * 1024 * 1024; // 128 MB
     let memory_end = MEMORY_START + MEMORY_SIZE;
 
@@ -317,7 +537,47 @@ pub fn init(kernel_end: usize) -> MemoryManager {
     crate::serial_println!("[MEMORY] Kernel end: {:#x}", kernel_end);
     crate::serial_println!("[MEMORY] Memory range: {:#x} - {:#x}", MEMORY_START, memory_end);
 
-    MemoryManager::new(kernel_end, memory_end)
+    let mut manager = MemoryManager::new(kernel_end, memory_end);
+    reserve_platform_regions(&mut manager.frame_allocator, kernel_end);
+    manager
+}
+
+/// 把内核镜像和 MMIO 窗口登记为保留区域
+///
+/// # 说明
+/// 分配从内核结束地址开始，这些区域本来就不会作为普通帧分配；
+/// 登记之后，覆盖它们的 reserve/claim（例如错误的 DTB 地址）会因重叠被拒绝
+pub fn reserve_platform_regions(allocator: &mut SimpleFrameAllocator, kernel_end: usize) {
+    allocator
+        .reserve(PhysAddr::new(MEMORY_START), kernel_end.saturating_sub(MEMORY_START))
+        .expect("failed to reserve the kernel image");
+    for &(start, size) in MMIO_REGIONS {
+        allocator
+            .reserve(PhysAddr::new(start), size)
+            .expect("failed to reserve an MMIO window");
+    }
+}
+
+/// 保留设备树（DTB）占用的内存
+///
+/// # 参数
+/// - `dtb`: 设备树物理地址（_start 时的 a1），为 0 或不是合法 DTB 时忽略
+pub fn reserve_device_tree(allocator: &mut SimpleFrameAllocator, dtb: usize) {
+    if let Some(fdt) = unsafe { crate::fdt::Fdt::from_addr(dtb) } {
+        crate::serial_println!("[MEMORY] Reserving DTB at {:#x} ({} bytes)", dtb, fdt.total_size());
+        if let Err(e) = allocator.reserve(PhysAddr::new(dtb), fdt.total_size()) {
+            crate::serial_println!("[MEMORY] Failed to reserve DTB: {}", e);
+        }
+    }
+}
+
+/// 在全局帧分配器中登记保留区域（install_frame_allocator 之后使用）
+///
+/// # 返回
+/// 分配器尚未安装或保留区域表已满时返回错误
+pub fn reserve_region(start: PhysAddr, size: usize) -> Result<(), &'static str> {
+    with_frame_allocator(|allocator| allocator.reserve(start, size))
+        .unwrap_or(Err("Frame allocator not installed"))
 }
 
 /// 创建示例映射（用于测试）
@@ -363,6 +623,40 @@ pub fn translate_addr(vaddr: VirtAddr) -> Option<PhysAddr> {
     Some(PhysAddr::new(vaddr.as_usize()))
 }
 
+/// 用堆上的缓冲区充当物理内存的帧分配器（测试用）
+///
+/// # 说明
+/// 缓冲区与分配器一起释放，分配出的帧不能比 TestMemory 活得久
+#[cfg(test)]
+pub(crate) struct TestMemory {
+    _buffer: alloc::vec::Vec<u8>,
+    start: usize,
+    pub allocator: SimpleFrameAllocator,
+}
+
+#[cfg(test)]
+impl TestMemory {
+    /// 准备 `frames` 个按页对齐的物理帧
+    ///
+    /// # 参数
+    /// - `frames`: 帧数
+    /// - `fill`: 预先填入的字节（非零时可以检查新页面确实被清零）
+    pub(crate) fn new(frames: usize, fill: u8) -> Self {
+        let buffer = alloc::vec![fill; (frames + 1) * PAGE_SIZE];
+        let start = (buffer.as_ptr() as usize + PAGE_SIZE - 1) & !(PAGE_SIZE - 1);
+        TestMemory {
+            _buffer: buffer,
+            start,
+            allocator: SimpleFrameAllocator::new(start, start + frames * PAGE_SIZE),
+        }
+    }
+
+    /// 第一个帧的地址
+    pub(crate) fn start(&self) -> usize {
+        self.start
+    }
+}
+
 // ============================================
 // 测试
 // ============================================
@@ -376,4 +670,60 @@ mod tests {
         let addr = VirtAddr::new(0x1234_5678);
         assert_eq!(addr.page_offset(), 0x678);
     }
+
+    #[test_case]
+    fn test_reserved_frames_never_allocated() {
+        const FRAMES: usize = 8;
+
+        let mut memory = TestMemory::new(FRAMES, 0);
+        let start = memory.start();
+        let allocator = &mut memory.allocator;
+
+        // 保留第 2、3 帧（未对齐的范围按页向外扩展）和最后一帧
+        let reserved_start = start + 2 * PAGE_SIZE + 16;
+        allocator.reserve(PhysAddr::new(reserved_start), PAGE_SIZE).unwrap();
+        allocator.reserve(PhysAddr::new(start + 7 * PAGE_SIZE), 1).unwrap();
+        assert!(allocator.is_reserved(PhysAddr::new(start + 2 * PAGE_SIZE)));
+        assert!(allocator.is_reserved(PhysAddr::new(start + 3 * PAGE_SIZE + 8)));
+        assert!(!allocator.is_reserved(PhysAddr::new(start + 4 * PAGE_SIZE)));
+
+        let mut count = 0;
+        while let Some(frame) = allocator.allocate() {
+            assert!(!allocator.is_reserved(frame.start_address()));
+            count += 1;
+        }
+        assert_eq!(count, FRAMES - 3);
+
+        // 与已有保留区域重叠的区域被拒绝
+        assert!(allocator.reserve(PhysAddr::new(start + 3 * PAGE_SIZE), 2 * PAGE_SIZE).is_err());
+
+        // 保留区域表满时返回错误
+        for i in 2..MAX_RESERVED_REGIONS {
+            let region = start + (FRAMES + i) * PAGE_SIZE;
+            allocator.reserve(PhysAddr::new(region), PAGE_SIZE).unwrap();
+        }
+        let region = start + (FRAMES + MAX_RESERVED_REGIONS) * PAGE_SIZE;
+        assert!(allocator.reserve(PhysAddr::new(region), PAGE_SIZE).is_err());
+    }
+
+    #[test_case]
+    fn test_platform_regions_reject_overlapping_reservations() {
+        const FRAMES: usize = 4;
+        const UART: usize = 0x1000_0000;
+
+        let mut memory = TestMemory::new(FRAMES, 0);
+        let start = memory.start();
+        let allocator = &mut memory.allocator;
+        reserve_platform_regions(allocator, start);
+
+        // 内核镜像和 MMIO 窗口已登记，与它们重叠的区域被拒绝
+        assert!(allocator.is_reserved(PhysAddr::new(MEMORY_START)));
+        assert!(allocator.is_reserved(PhysAddr::new(UART)));
+        assert!(allocator.reserve(PhysAddr::new(UART), PAGE_SIZE).is_err());
+        assert!(allocator.reserve(PhysAddr::new(start - PAGE_SIZE), PAGE_SIZE).is_err());
+
+        // 普通帧不受影响
+        assert!(!allocator.is_reserved(PhysAddr::new(start)));
+        assert_eq!(allocator.free_frames(), FRAMES);
+    }
 }
@@ -11,7 +11,7 @@
  * ============================================
  */
 
-use super::{PhysAddr, VirtAddr, PageTable, PageTableEntry, PageTableFlags, PhysFrame, SimpleFrameAllocator, PAGE_SIZE};
+use super::{PhysAddr, VirtAddr, PageTable, PageTableEntry, PageTableFlags, PhysFrame, SimpleFrameAllocator, FrameInit, PAGE_SIZE};
 
 /// 遍历页表，将虚拟地址转换为物理地址
 ///
@@ -82,6 +82,73 @@ pub fn walk_page_table(root_paddr: PhysAddr, vaddr: VirtAddr) -> Option<PhysAddr
     Some(PhysAddr::new(pte0.phys_addr().as_usize() + offset))
 }
 
+/// 查找虚拟地址对应的叶子页表项
+///
+/// # 参数
+/// - `root_paddr`: 根页表的物理地址
+/// - `vaddr`: 要查找的虚拟地址
+///
+/// # 返回
+/// - Some(PageTableEntry): 叶子页表项（含权限位，支持大页）
+/// - None: 页面未映射
+///
+/// # 说明
+/// 与 walk_page_table 遍历方式相同，但返回页表项本身，
+/// 供页错误处理等需要检查权限位的场景使用
+pub fn lookup_pte(root_paddr: PhysAddr, vaddr: VirtAddr) -> Option<PageTableEntry> {
+    let mut table = unsafe {
+        &*(root_paddr.as_usize() as *const PageTable)
+    };
+
+    for vpn in [vaddr.vpn2(), vaddr.vpn1(), vaddr.vpn0()] {
+        let pte = *table.get_entry(vpn);
+
+        if !pte.is_valid() {
+            return None;
+        }
+
+        if pte.is_leaf() {
+            return Some(pte);
+        }
+
+        table = unsafe {
+            &*(pte.phys_addr().as_usize() as *const PageTable)
+        };
+    }
+
+    // 第 0 级仍不是叶子：页表结构无效
+    None
+}
+
+/// 查找虚拟地址对应的叶子页表项（可修改）
+///
+/// # 返回
+/// 叶子页表项的可变引用；页面未映射时返回 None
+///
+/// # Safety
+/// root_paddr 必须是有效的根页表（恒等映射下可直接访问），
+/// 调用者修改页表项后负责刷新 TLB，且不能同时持有同一项的其他引用
+pub unsafe fn lookup_pte_mut<'a>(root_paddr: PhysAddr, vaddr: VirtAddr) -> Option<&'a mut PageTableEntry> {
+    let mut table = &mut *(root_paddr.as_usize() as *mut PageTable);
+
+    for vpn in [vaddr.vpn2(), vaddr.vpn1(), vaddr.vpn0()] {
+        let pte = table.get_entry_mut(vpn);
+
+        if !pte.is_valid() {
+            return None;
+        }
+
+        if pte.is_leaf() {
+            return Some(pte);
+        }
+
+        table = &mut *(pte.phys_addr().as_usize() as *mut PageTable);
+    }
+
+    // 第 0 级仍不是叶子：页表结构无效
+    None
+}
+
 /// 可视化页表遍历（教学版本，带详细输出）
 ///
 /// # 教学特色
@@ -265,17 +332,39 @@ pub fn map_page(
     pte0.set(paddr.as_usize() >> 12, flags | PageTableFlags::Valid as usize);
 
     // 刷新 TLB
-    unsafe {
-        // RISC-V sfence.vma 指令
-        core::arch::asm!(
-            "sfence.vma {0}, zero",
-            in(reg) vaddr.as_usize(),
-        );
-    }
+    super::flush_tlb_page(vaddr);
 
     Ok(())
 }
 
+/// 为用户地址空间分配并映射一个新页面（缺页处理路径）
+///
+/// # 参数
+/// - `root_table`: 根页表
+/// - `vaddr`: 发生缺页的虚拟地址（自动按页对齐）
+/// - `flags`: 页表标志位
+/// - `allocator`: 帧分配器
+///
+/// # 返回
+/// 新页面的物理地址
+///
+/// # 说明
+/// 新页面总是清零后再映射，避免把其他进程留下的数据暴露给用户态
+pub fn map_zeroed_page(
+    root_table: &mut PageTable,
+    vaddr: VirtAddr,
+    flags: usize,
+    allocator: &mut SimpleFrameAllocator,
+) -> Result<PhysAddr, &'static str> {
+    let page_vaddr = VirtAddr::new(vaddr.as_usize() & !(PAGE_SIZE - 1));
+
+    let frame = allocator.allocate_with(FrameInit::Zeroed).ok_or("Out of memory")?;
+    let paddr = frame.start_address();
+
+    map_page(root_table, page_vaddr, paddr, flags, allocator)?;
+    Ok(paddr)
+}
+
 /// 可视化页面映射（教学版本）
 pub fn map_page_verbose(
     root_table: &mut PageTable,
@@ -348,12 +437,7 @@ pub fn unmap_page(
     *pte0 = PageTableEntry::new();
 
     // 刷新 TLB
-    unsafe {
-        core::arch::asm!(
-            "sfence.vma {0}, zero",
-            in(reg) vaddr.as_usize(),
-        );
-    }
+    super::flush_tlb_page(vaddr);
 
     Ok(paddr)
 }
@@ -369,3 +453,62 @@ pub fn translate_addr(vaddr: VirtAddr) -> Option<PhysAddr> {
 
     walk_page_table(root_paddr, vaddr)
 }
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::TestMemory;
+
+    #[test_case]
+    fn test_fault_path_maps_zeroed_page() {
+        const FRAMES: usize = 4;
+
+        // 先填满"旧数据"
+        let mut memory = TestMemory::new(FRAMES, 0xAA);
+        let allocator = &mut memory.allocator;
+
+        let root_paddr = allocator.allocate_zeroed().unwrap().start_address();
+        let root = unsafe { &mut *(root_paddr.as_usize() as *mut PageTable) };
+
+        let user_flags = PageTableFlags::Read as usize
+            | PageTableFlags::Write as usize
+            | PageTableFlags::User as usize;
+        let fault_addr = VirtAddr::new(0x1000_0123);
+        let paddr = map_zeroed_page(root, fault_addr, user_flags, allocator).unwrap();
+
+        // 映射建立在缺页地址所在的页上
+        assert_eq!(walk_page_table(root_paddr, VirtAddr::new(0x1000_0000)), Some(paddr));
+
+        // 新页面读回全零，没有残留旧数据
+        let page = unsafe { core::slice::from_raw_parts(paddr.as_usize() as *const u8, PAGE_SIZE) };
+        assert!(page.iter().all(|&byte| byte == 0));
+    }
+
+    #[test_case]
+    fn test_mapping_mutators_flush_tlb() {
+        const FRAMES: usize = 4;
+
+        let mut memory = TestMemory::new(FRAMES, 0);
+        let allocator = &mut memory.allocator;
+
+        let root_paddr = allocator.allocate_zeroed().unwrap().start_address();
+        let root = unsafe { &mut *(root_paddr.as_usize() as *mut PageTable) };
+        let flags = PageTableFlags::Read as usize | PageTableFlags::Write as usize;
+        let vaddr = VirtAddr::new(0x2000_0000);
+
+        let before = crate::memory::tlb_flush_count();
+        let paddr = map_zeroed_page(root, vaddr, flags, allocator).unwrap();
+        assert_eq!(crate::memory::tlb_flush_count(), before + 1);
+
+        assert_eq!(unmap_page(root, vaddr), Ok(paddr));
+        assert_eq!(crate::memory::tlb_flush_count(), before + 2);
+
+        // 失败的修改不需要刷新
+        assert!(unmap_page(root, vaddr).is_err());
+        assert_eq!(crate::memory::tlb_flush_count(), before + 2);
+    }
+}
@@ -0,0 +1,231 @@
+/*
+ * ============================================
+ * 每个 hart 的私有数据（Per-Hart Storage）
+ * ============================================
+ * 功能：为每个 hart 提供一块私有控制块，通过 tp 寄存器访问
+ *
+ * 约定：
+ * - 每个 hart 启动时（_start / _secondary_start）调用 percpu_init_hart，
+ *   把 tp 指向该 hart 的 HartBlock
+ * - 内核代码通过 this_cpu() / hart_id() 读取，不需要加锁
+ * - 从用户态陷入时 trap.S 会把 tp 换回内核值（KERNEL_TP），
+ *   返回用户态时恢复用户的 tp
+ *
+ * 控制块内容：
+ * - hart_id：本 hart 的编号
+ * - current_pid：本 hart 上正在运行的进程（NO_PID 表示没有；idle 进程的 PID 是 0）
+ * - need_resched：时钟中断要求重新调度，在返回用户态前处理
+ * - preempt_count：内核禁止抢占的嵌套深度，非零时推迟 need_resched
+ * ============================================
+ */
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use crate::process::ProcessId;
+use crate::smp::MAX_HARTS;
+
+/// current_pid 中表示"没有当前进程"的值（0 是 idle 进程的 PID，不能用作空值）
+const NO_PID: usize = usize::MAX;
+
+/// 每个 hart 的控制块
+#[repr(C)]
+pub struct HartBlock {
+    /// hart 编号
+    hart_id: usize,
+    /// 当前进程的 PID（NO_PID 表示没有当前进程）
+    current_pid: AtomicUsize,
+    /// 是否需要重新调度
+    need_resched: AtomicBool,
+    /// 禁止抢占的嵌套深度（0 表示允许抢占）
+    preempt_count: AtomicUsize,
+}
+
+/// 每个 CPU（hart）的私有数据
+pub type PerCpu = HartBlock;
+
+impl HartBlock {
+    const fn new(hart_id: usize) -> Self {
+        HartBlock {
+            hart_id,
+            current_pid: AtomicUsize::new(NO_PID),
+            need_resched: AtomicBool::new(false),
+            preempt_count: AtomicUsize::new(0),
+        }
+    }
+
+    /// hart 编号
+    pub fn hart_id(&self) -> usize {
+        self.hart_id
+    }
+
+    /// 本 hart 上的当前进程
+    pub fn current_pid(&self) -> Option<ProcessId> {
+        match self.current_pid.load(Ordering::Acquire) {
+            NO_PID => None,
+            pid => Some(ProcessId::from_usize(pid)),
+        }
+    }
+
+    /// 设置本 hart 上的当前进程
+    pub fn set_current_pid(&self, pid: Option<ProcessId>) {
+        self.current_pid.store(pid.map_or(NO_PID, ProcessId::as_usize), Ordering::Release);
+    }
+
+    /// 请求在下一个安全点重新调度
+    pub fn set_need_resched(&self) {
+        self.need_resched.store(true, Ordering::Release);
+    }
+
+    /// 是否有待处理的重新调度请求
+    pub fn need_resched(&self) -> bool {
+        self.need_resched.load(Ordering::Acquire)
+    }
+
+    /// 取出并清除重新调度请求
+    pub fn take_need_resched(&self) -> bool {
+        self.need_resched.swap(false, Ordering::AcqRel)
+    }
+
+    /// 禁止抢占（可嵌套）
+    pub fn preempt_disable(&self) {
+        self.preempt_count.fetch_add(1, Ordering::Acquire);
+    }
+
+    /// 恢复抢占
+    ///
+    /// # 返回
+    /// 是否回到了允许抢占的状态（嵌套深度降为 0）
+    ///
+    /// # Panics
+    /// 没有对应的 preempt_disable 时 panic
+    pub fn preempt_enable(&self) -> bool {
+        let prev = self.preempt_count.fetch_sub(1, Ordering::Release);
+        assert!(prev > 0, "preempt_enable without matching preempt_disable");
+        prev == 1
+    }
+
+    /// 当前是否允许抢占
+    pub fn preemptible(&self) -> bool {
+        self.preempt_count.load(Ordering::Acquire) == 0
+    }
+}
+
+/// 所有 hart 的控制块（按 hart 编号索引）
+static HART_BLOCKS: [HartBlock; MAX_HARTS] = {
+    let mut blocks = [const { HartBlock::new(0) }; MAX_HARTS];
+    let mut i = 0;
+    while i < MAX_HARTS {
+        blocks[i] = HartBlock::new(i);
+        i += 1;
+    }
+    blocks
+};
+
+/// 启动 hart 的 tp 值，用户态陷阱入口用它恢复内核 tp
+#[no_mangle]
+static mut KERNEL_TP: usize = 0;
+
+/// 读取 tp 寄存器
+fn read_tp() -> usize {
+    let tp: usize;
+    unsafe {
+        core::arch::asm!("mv {}, tp", out(reg) tp, options(nomem, nostack));
+    }
+    tp
+}
+
+/// 初始化当前 hart 的控制块并设置 tp
+///
+/// # 参数
+/// - `hart_id`: 当前 hart 的编号（SBI 启动时通过 a0 传入）
+///
+/// # 说明
+/// 由 _start 和 _secondary_start 在进入 Rust 代码前调用。
+/// 编号不小于 MAX_HARTS 的 hart 没有自己的控制块，不能与其他 hart 共用，
+/// 直接停靠（关中断后 wfi 循环），不再返回
+#[no_mangle]
+pub extern "C" fn percpu_init_hart(hart_id: usize) {
+    if hart_id >= MAX_HARTS {
+        park();
+    }
+
+    let block = &HART_BLOCKS[hart_id];
+    let tp = block as *const HartBlock as usize;
+
+    unsafe {
+        core::arch::asm!("mv tp, {}", in(reg) tp, options(nomem, nostack));
+
+        // 单个用户陷阱保存区只服务启动 hart（见 trap.S）
+        if core::ptr::addr_of!(KERNEL_TP).read() == 0 {
+            core::ptr::addr_of_mut!(KERNEL_TP).write(tp);
+        }
+    }
+}
+
+/// 停靠当前 hart：关闭中断后一直 wfi
+fn park() -> ! {
+    unsafe {
+        riscv::register::sstatus::clear_sie();
+    }
+    crate::hlt_loop()
+}
+
+/// 当前 hart 的控制块
+///
+/// # 说明
+/// tp 尚未设置（为 0）时退回到 hart 0 的控制块
+pub fn current() -> &'static HartBlock {
+    match read_tp() {
+        0 => &HART_BLOCKS[0],
+        tp => unsafe { &*(tp as *const HartBlock) },
+    }
+}
+
+/// 当前 hart 的私有数据（current 的别名）
+pub fn this_cpu() -> &'static PerCpu {
+    current()
+}
+
+/// 当前 hart 的编号
+pub fn hart_id() -> usize {
+    current().hart_id()
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_hart_id_through_tp() {
+        percpu_init_hart(0);
+
+        assert_eq!(read_tp(), &HART_BLOCKS[0] as *const HartBlock as usize);
+        assert_eq!(hart_id(), 0);
+        assert!(core::ptr::eq(current(), &HART_BLOCKS[0]));
+    }
+
+    #[test_case]
+    fn test_each_hart_uses_own_block() {
+        // 依次以各 hart 的身份设置 tp 并写入自己的控制块
+        for hart in 0..MAX_HARTS {
+            percpu_init_hart(hart);
+            let cpu = this_cpu();
+            assert_eq!(cpu.hart_id(), hart);
+            cpu.set_current_pid(Some(ProcessId::from_usize(100 + hart)));
+        }
+
+        // 每个 hart 读到的都是自己写入的值
+        for hart in (0..MAX_HARTS).rev() {
+            percpu_init_hart(hart);
+            let cpu = this_cpu();
+            assert_eq!(cpu.current_pid(), Some(ProcessId::from_usize(100 + hart)));
+            cpu.set_current_pid(None);
+        }
+
+        percpu_init_hart(0);
+        assert_eq!(hart_id(), 0);
+    }
+}
@@ -0,0 +1,136 @@
+/*
+ * ============================================
+ * 平台级中断控制器（PLIC）
+ * ============================================
+ * 功能：把外部设备中断路由到 hart 的 S 态
+ *
+ * QEMU virt 机器的 PLIC 基地址为 0x0c00_0000，寄存器布局：
+ * - 0x00_0000 + 4*source：中断源优先级（0 表示屏蔽）
+ * - 0x00_2000 + 0x80*context：该上下文的中断使能位图（每个源一位）
+ * - 0x20_0000 + 0x1000*context：该上下文的优先级阈值
+ * - 0x20_0004 + 0x1000*context：claim（读）/ complete（写）
+ *
+ * 上下文编号：virt 机器上每个 hart 有 M 态和 S 态两个上下文，
+ * hart h 的 S 态上下文为 2*h + 1
+ *
+ * 处理流程：claim 得到中断源编号 → 服务设备 → complete 同一个编号，
+ * complete 之前该中断源不会再次触发
+ * ============================================
+ */
+
+use core::ptr::{read_volatile, write_volatile};
+
+/// PLIC 基地址（QEMU virt）
+pub const PLIC_BASE: usize = 0x0c00_0000;
+
+/// UART0 的中断源编号（QEMU virt）
+pub const UART0_IRQ: u32 = 10;
+
+/// 优先级寄存器数组的偏移（每个源 4 字节）
+const PRIORITY_OFFSET: usize = 0x00_0000;
+
+/// 使能位图的偏移（每个上下文 0x80 字节）
+const ENABLE_OFFSET: usize = 0x00_2000;
+const ENABLE_STRIDE: usize = 0x80;
+
+/// 阈值寄存器的偏移（每个上下文 0x1000 字节）
+const THRESHOLD_OFFSET: usize = 0x20_0000;
+/// claim/complete 寄存器紧跟在阈值寄存器之后
+const CLAIM_OFFSET: usize = 0x20_0004;
+const CONTEXT_STRIDE: usize = 0x1000;
+
+/// hart 的 S 态上下文编号
+pub fn s_mode_context(hart_id: usize) -> usize {
+    2 * hart_id + 1
+}
+
+/// 中断源 source 的优先级寄存器地址
+pub fn priority_addr(source: u32) -> usize {
+    PLIC_BASE + PRIORITY_OFFSET + 4 * source as usize
+}
+
+/// 上下文 context 中包含 source 使能位的 32 位寄存器地址
+pub fn enable_addr(context: usize, source: u32) -> usize {
+    PLIC_BASE + ENABLE_OFFSET + ENABLE_STRIDE * context + 4 * (source as usize / 32)
+}
+
+/// 上下文 context 的阈值寄存器地址
+pub fn threshold_addr(context: usize) -> usize {
+    PLIC_BASE + THRESHOLD_OFFSET + CONTEXT_STRIDE * context
+}
+
+/// 上下文 context 的 claim/complete 寄存器地址
+pub fn claim_addr(context: usize) -> usize {
+    PLIC_BASE + CLAIM_OFFSET + CONTEXT_STRIDE * context
+}
+
+/// 本 hart 的 S 态上下文
+fn this_context() -> usize {
+    s_mode_context(crate::percpu::this_cpu().hart_id())
+}
+
+/// 设置中断源优先级并在本 hart 的 S 态上下文中使能
+///
+/// # 参数
+/// - `source`: 中断源编号
+/// - `priority`: 优先级（1-7，必须高于阈值才会送达）
+pub fn enable(source: u32, priority: u32) {
+    let context = this_context();
+    unsafe {
+        write_volatile(priority_addr(source) as *mut u32, priority);
+        let enable = enable_addr(context, source) as *mut u32;
+        write_volatile(enable, read_volatile(enable) | 1 << (source % 32));
+    }
+}
+
+/// 设置本 hart S 态上下文的优先级阈值（0 表示接收所有优先级大于 0 的中断）
+pub fn set_threshold(threshold: u32) {
+    unsafe {
+        write_volatile(threshold_addr(this_context()) as *mut u32, threshold);
+    }
+}
+
+/// 领取一个待处理的中断
+///
+/// # 返回
+/// 中断源编号；没有待处理的中断时返回 None
+pub fn claim() -> Option<u32> {
+    let irq = unsafe { read_volatile(claim_addr(this_context()) as *const u32) };
+    (irq != 0).then_some(irq)
+}
+
+/// 通知 PLIC 中断已处理完
+pub fn complete(irq: u32) {
+    unsafe {
+        write_volatile(claim_addr(this_context()) as *mut u32, irq);
+    }
+}
+
+/// 初始化 PLIC：使能 UART0 中断，接收所有优先级
+pub fn init() {
+    enable(UART0_IRQ, 1);
+    set_threshold(0);
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_register_addresses_for_uart_on_hart0() {
+        let context = s_mode_context(0);
+        assert_eq!(context, 1);
+        assert_eq!(priority_addr(UART0_IRQ), 0x0c00_0028);
+        assert_eq!(enable_addr(context, UART0_IRQ), 0x0c00_2080);
+        assert_eq!(threshold_addr(context), 0x0c20_1000);
+        assert_eq!(claim_addr(context), 0x0c20_1004);
+
+        // 第二个 hart 的 S 态上下文，以及位图的第二个字
+        assert_eq!(s_mode_context(1), 3);
+        assert_eq!(enable_addr(3, 33), 0x0c00_2184);
+    }
+}
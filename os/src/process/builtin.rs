@@ -0,0 +1,117 @@
+/*
+ * ============================================
+ * 内置程序
+ * ============================================
+ * 功能：按名字启动编进内核的程序（名字 → 入口函数）
+ *
+ * - 启动时用 register 登记，例如 register("hello", hello_main)
+ * - spawn 按名字创建进程：入口函数作为内核线程运行，
+ *   不需要文件系统中的 ELF，也不需要进入用户态
+ * - sys_exec 的路径是已登记的名字时，用这里启动
+ *
+ * 说明：这是 ELF 程序能真正进入用户态之前的过渡方案，
+ * 内置程序运行在内核态，可以直接调用内核函数
+ * ============================================
+ */
+
+use super::exec::ExecError;
+use super::{register_child, ProcessHandle, ProcessId};
+use crate::fs::FileError;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+/// 内置程序的入口函数（永不返回，结束时调用 exit_current_process）
+pub type BuiltinEntry = fn() -> !;
+
+/// 已登记的内置程序
+static BUILTINS: Mutex<BTreeMap<&'static str, BuiltinEntry>> = Mutex::new(BTreeMap::new());
+
+/// 登记内置程序
+///
+/// # 返回
+/// 名字已被占用时返回 false（保留原来的入口）
+pub fn register(name: &'static str, entry: BuiltinEntry) -> bool {
+    let mut builtins = BUILTINS.lock();
+    if builtins.contains_key(name) {
+        return false;
+    }
+    builtins.insert(name, entry);
+    true
+}
+
+/// 按名字查找内置程序的入口
+pub fn lookup(name: &str) -> Option<BuiltinEntry> {
+    BUILTINS.lock().get(name).copied()
+}
+
+/// 所有已登记的名字（按字母顺序）
+pub fn names() -> Vec<&'static str> {
+    BUILTINS.lock().keys().copied().collect()
+}
+
+/// 按名字创建内置程序的进程
+///
+/// # 参数
+/// - `name`: 登记时的名字
+/// - `parent_pid`: 父进程（登记为其子进程，计入它的 fork 限速）
+///
+/// # 返回
+/// 新进程句柄（尚未加入调度器）；名字没有登记时返回 `File(NotFound)`，
+/// 父进程创建子进程过快时返回 `Process(RateLimited)`
+pub fn spawn(name: &str, parent_pid: Option<ProcessId>) -> Result<ProcessHandle, ExecError> {
+    let (name, entry) = BUILTINS
+        .lock()
+        .get_key_value(name)
+        .map(|(&name, &entry)| (name, entry))
+        .ok_or(ExecError::File(FileError::NotFound))?;
+
+    let process =
+        super::create_kernel_thread_with_stack(name, entry, super::KERNEL_STACK_SIZE, parent_pid)?;
+    register_child(parent_pid, &process);
+    Ok(process)
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::scheduler;
+
+    fn hello_main() -> ! {
+        loop {
+            core::hint::spin_loop();
+        }
+    }
+
+    #[test_case]
+    fn test_spawn_registered_builtin_by_name() {
+        assert!(register("builtin_hello", hello_main));
+        assert!(!register("builtin_hello", hello_main));
+        assert!(names().contains(&"builtin_hello"));
+
+        let parent = crate::process::spawn_test_process("builtin_parent", None);
+        let parent_pid = parent.lock().pid();
+
+        let child = spawn("builtin_hello", Some(parent_pid)).unwrap();
+        let child_pid = child.lock().pid();
+        {
+            // 第一次被调度时 switch_context 的 ret 经跳板进入 a0 中的入口函数
+            let pcb = child.lock();
+            assert_eq!(pcb.name(), "builtin_hello");
+            assert_eq!(pcb.context().a0, hello_main as fn() -> ! as usize);
+            assert_eq!(pcb.parent_pid(), Some(parent_pid));
+        }
+        assert!(parent.lock().children().contains(&child_pid));
+
+        assert!(matches!(
+            spawn("builtin_missing", None),
+            Err(ExecError::File(FileError::NotFound))
+        ));
+
+        scheduler::lock_scheduler().remove_process(parent_pid);
+    }
+}
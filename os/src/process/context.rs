@@ -196,6 +196,43 @@ impl ProcessContext {
         context
     }
 
+    /// 为内核线程初始化上下文
+    ///
+    /// # 参数
+    /// - `entry_point`: 线程入口函数地址
+    /// - `kernel_stack_top`: 内核栈顶地址
+    ///
+    /// # 说明
+    /// 内核线程由 switch_context 末尾的 ret 进入：
+    /// - ra 指向首次运行的跳板（scheduler::kernel_thread_start），
+    ///   a0 是入口函数，跳板释放调度器锁、开中断后才调用入口
+    /// - sp 指向内核栈顶
+    /// - 沿用当前页表（satp）和 hart 控制块指针（tp）
+    /// - 运行在内核态（SPP=1），切换进来时中断关闭（SIE=0），与持有调度器锁的切换方一致
+    pub fn new_kernel_context(entry_point: usize, kernel_stack_top: usize) -> Self {
+        let mut context = Self::new();
+
+        context.ra = super::scheduler::kernel_thread_start as extern "C" fn(usize) -> ! as usize;
+        context.a0 = entry_point;
+        context.sp = kernel_stack_top;
+
+        let satp_value: usize;
+        let tp_value: usize;
+        let mut status_val: usize;
+        unsafe {
+            core::arch::asm!("csrr {}, satp", out(reg) satp_value);
+            core::arch::asm!("mv {}, tp", out(reg) tp_value);
+            core::arch::asm!("csrr {}, sstatus", out(reg) status_val);
+        }
+        context.satp = satp_value;
+        context.tp = tp_value;
+        sstatus_ext::set_supervisor_mode(&mut status_val);
+        sstatus_ext::disable_interrupt(&mut status_val);
+        context.sstatus = status_val;
+
+        context
+    }
+
     /// 零值初始化（用于测试）
     pub fn zero() -> Self {
         Self::new()
@@ -234,6 +271,11 @@ mod sstatus_ext {
     pub fn enable_interrupt_on_return(sstatus: &mut usize) {
         *sstatus |= 1 << SPIE_BIT;  // SPIE = 1
     }
+
+    /// 关闭当前中断
+    pub fn disable_interrupt(sstatus: &mut usize) {
+        *sstatus &= !(1 << SIE_BIT);  // SIE = 0
+    }
 }
 
 // ============================================
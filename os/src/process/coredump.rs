@@ -0,0 +1,337 @@
+/*
+ * ============================================
+ * 核心转储（core dump）
+ * ============================================
+ * 功能：用户进程因错误被终止时，把寄存器和映射的内存写入 RamFS，
+ *       供事后分析（/core.<pid>）
+ *
+ * 文件格式（所有整数为 64 位小端）：
+ * - 魔数 CORE_MAGIC（8 字节）
+ * - pid、信号编号、stval
+ * - 陷阱现场：x0-x31、sepc、sstatus
+ * - 区域个数，之后每个区域：起始地址、长度、页表标志、内容
+ *
+ * 说明：
+ * - 默认关闭，通过 set_enabled 开启
+ * - 区域取自进程地址空间的内存区域和用户栈；未映射的页填 0；
+ *   没有独立地址空间的进程（恒等映射）只记录寄存器
+ * - 目前只在陷阱处理终止进程时转储（如 SIGSEGV），
+ *   kill_process 没有陷阱现场，不转储
+ * ============================================
+ */
+
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use spin::Mutex;
+
+use super::pcb::ProcessControlBlock;
+use super::scheduler;
+use crate::fs::{FileError, RamInode, RAMFS};
+use crate::memory::{walk_page_table, MemoryAreaType, PhysAddr, VirtAddr, PAGE_SIZE};
+use crate::trap::TrapFrame;
+
+/// 核心转储文件的魔数
+pub const CORE_MAGIC: [u8; 8] = *b"ERRCORE\0";
+
+/// 是否在进程崩溃时写核心转储
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// 开启或关闭核心转储
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// 核心转储是否开启
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// 转储的一段内存
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreRegion {
+    /// 起始虚拟地址
+    pub start: usize,
+    /// 页表标志（见 PageTableFlags）
+    pub flags: usize,
+    /// 内容
+    pub data: Vec<u8>,
+}
+
+/// 核心转储的内容
+#[derive(Debug, Clone)]
+pub struct CoreDump {
+    pub pid: usize,
+    /// 终止进程的信号
+    pub signal: i32,
+    /// 出错地址（页错误时为访问的地址）
+    pub stval: usize,
+    /// 出错时的用户现场
+    pub frame: TrapFrame,
+    pub regions: Vec<CoreRegion>,
+}
+
+/// 读取 root 页表中 [start, start + len) 的内容，未映射的页填 0
+fn read_user_memory(root: PhysAddr, start: usize, len: usize) -> Vec<u8> {
+    let mut data = alloc::vec![0u8; len];
+    let mut done = 0;
+    while done < len {
+        let vaddr = start + done;
+        let chunk = (PAGE_SIZE - vaddr % PAGE_SIZE).min(len - done);
+        if let Some(paddr) = walk_page_table(root, VirtAddr::new(vaddr)) {
+            // 内核恒等映射了所有物理内存
+            let src = unsafe { core::slice::from_raw_parts(paddr.as_usize() as *const u8, chunk) };
+            data[done..done + chunk].copy_from_slice(src);
+        }
+        done += chunk;
+    }
+    data
+}
+
+impl CoreDump {
+    /// 收集进程的核心转储
+    ///
+    /// # 参数
+    /// - `pcb`: 崩溃的进程
+    /// - `signal`: 终止进程的信号
+    /// - `stval`: 出错地址
+    /// - `frame`: 出错时的用户现场
+    pub fn capture(pcb: &ProcessControlBlock, signal: i32, stval: usize, frame: &TrapFrame) -> Self {
+        let mut regions = Vec::new();
+
+        if let Some(space) = pcb.address_space() {
+            let root = space.page_table_paddr();
+            for area in space.areas() {
+                let start = area.range.start.as_usize();
+                regions.push(CoreRegion {
+                    start,
+                    flags: area.flags,
+                    data: read_user_memory(root, start, area.size()),
+                });
+            }
+
+            // 按需增长的用户栈不在内存区域中
+            let (bottom, top) = (pcb.user_stack_bottom(), pcb.user_stack_top());
+            let covered = regions
+                .iter()
+                .any(|region| region.start <= bottom && bottom < region.start + region.data.len());
+            if bottom < top && !covered {
+                regions.push(CoreRegion {
+                    start: bottom,
+                    flags: MemoryAreaType::Stack.default_flags(),
+                    data: read_user_memory(root, bottom, top - bottom),
+                });
+            }
+        }
+
+        CoreDump {
+            pid: pcb.pid().as_usize(),
+            signal,
+            stval,
+            frame: *frame,
+            regions,
+        }
+    }
+
+    /// 编码为核心转储文件的内容
+    pub fn to_bytes(&self) -> Vec<u8> {
+        fn put(out: &mut Vec<u8>, value: usize) {
+            out.extend_from_slice(&(value as u64).to_le_bytes());
+        }
+
+        let mut out = Vec::from(CORE_MAGIC);
+        put(&mut out, self.pid);
+        put(&mut out, self.signal as usize);
+        put(&mut out, self.stval);
+        for &reg in &self.frame.regs {
+            put(&mut out, reg);
+        }
+        put(&mut out, self.frame.sepc);
+        put(&mut out, self.frame.sstatus);
+        put(&mut out, self.regions.len());
+        for region in &self.regions {
+            put(&mut out, region.start);
+            put(&mut out, region.data.len());
+            put(&mut out, region.flags);
+            out.extend_from_slice(&region.data);
+        }
+        out
+    }
+
+    /// 从核心转储文件的内容解码
+    ///
+    /// # 返回
+    /// 魔数不对或内容被截断时返回 None
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        let mut reader = Reader(data.strip_prefix(&CORE_MAGIC[..])?);
+
+        let pid = reader.word()?;
+        let signal = reader.word()? as i32;
+        let stval = reader.word()?;
+        let mut frame = TrapFrame::new();
+        for reg in frame.regs.iter_mut() {
+            *reg = reader.word()?;
+        }
+        frame.sepc = reader.word()?;
+        frame.sstatus = reader.word()?;
+
+        let count = reader.word()?;
+        let mut regions = Vec::new();
+        for _ in 0..count {
+            let start = reader.word()?;
+            let len = reader.word()?;
+            let flags = reader.word()?;
+            let data = Vec::from(reader.bytes(len)?);
+            regions.push(CoreRegion { start, flags, data });
+        }
+
+        Some(CoreDump { pid, signal, stval, frame, regions })
+    }
+}
+
+/// 按顺序读取核心转储文件的内容
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Some(head)
+    }
+
+    fn word(&mut self) -> Option<usize> {
+        Some(u64::from_le_bytes(self.bytes(8)?.try_into().ok()?) as usize)
+    }
+}
+
+/// 核心转储文件的名字（位于 RamFS 根目录）
+pub fn core_file_name(pid: usize) -> String {
+    format!("core.{}", pid)
+}
+
+/// 把核心转储写入 /core.<pid>（已存在时覆盖）
+pub fn write_core(dump: &CoreDump) -> Result<Arc<Mutex<RamInode>>, FileError> {
+    let name = core_file_name(dump.pid);
+    match RAMFS.remove(RAMFS.root(), &name) {
+        Ok(()) | Err(FileError::NotFound) => {}
+        Err(err) => return Err(err),
+    }
+    let file = RAMFS.create_file(RAMFS.root(), name)?;
+    file.lock().write_at(0, &dump.to_bytes())?;
+    Ok(file)
+}
+
+/// 当前进程因错误被终止前调用：开启时写核心转储
+///
+/// # 参数
+/// - `signal`: 终止进程的信号
+/// - `stval`: 出错地址
+/// - `frame`: 出错时的用户现场
+pub fn dump_current(signal: i32, stval: usize, frame: &TrapFrame) {
+    if !enabled() {
+        return;
+    }
+    let Some(process) = scheduler::current_process() else {
+        return;
+    };
+    let dump = CoreDump::capture(&process.lock(), signal, stval, frame);
+    match write_core(&dump) {
+        Ok(_) => {
+            crate::serial_println!("[COREDUMP] PID={} dumped to /{}", dump.pid, core_file_name(dump.pid));
+        }
+        Err(err) => {
+            crate::serial_println!("[COREDUMP] PID={} dump failed: {:?}", dump.pid, err);
+        }
+    }
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::signal::SIGSEGV;
+    use crate::memory::{AddressSpace, TestMemory};
+    use alloc::vec;
+
+    #[test_case]
+    fn test_crash_writes_core_file_with_registers() {
+        const FRAMES: usize = 8;
+        const DATA: usize = 0x1000_0000;
+        const STACK_TOP: usize = 0x3000_0000;
+
+        let mut memory = TestMemory::new(FRAMES, 0);
+        let allocator = &mut memory.allocator;
+
+        // 一个数据页和一个栈页，各写入一个可识别的值
+        let process = super::super::create_process("crasher", 0x1000, STACK_TOP, None).unwrap();
+        let pid = process.lock().pid();
+        {
+            let mut space = AddressSpace::new(allocator).unwrap();
+            space.map_region(VirtAddr::new(DATA), PAGE_SIZE, MemoryAreaType::Data, allocator).unwrap();
+            let stack_page = VirtAddr::new(STACK_TOP - PAGE_SIZE);
+            let stack_flags = MemoryAreaType::Stack.default_flags();
+            space.map_zeroed_page(stack_page, stack_flags, allocator).unwrap();
+            let root = space.page_table_paddr();
+            for (vaddr, value) in [(DATA + 8, 0xdead_beef_u64), (STACK_TOP - 8, 0x5eed)] {
+                let paddr = walk_page_table(root, VirtAddr::new(vaddr)).unwrap();
+                unsafe { (paddr.as_usize() as *mut u64).write(value) };
+            }
+
+            let mut pcb = process.lock();
+            pcb.set_address_space(space);
+            pcb.set_user_stack(STACK_TOP - PAGE_SIZE, STACK_TOP);
+        }
+        scheduler::add_process(process).unwrap();
+        scheduler::lock_scheduler().run_for_test(pid);
+
+        let mut frame = TrapFrame::new();
+        frame.regs[1] = 0x1004; // ra
+        frame.regs[2] = STACK_TOP - 16; // sp
+        frame.regs[10] = 42; // a0
+        frame.sepc = 0x1010;
+        let name = core_file_name(pid.as_usize());
+
+        // 关闭时不写文件
+        dump_current(SIGSEGV, 0xbad, &frame);
+        assert!(RAMFS.resolve(RAMFS.root(), &name).is_err());
+
+        set_enabled(true);
+        dump_current(SIGSEGV, 0xbad, &frame);
+        set_enabled(false);
+
+        let file = RAMFS.resolve(RAMFS.root(), &name).unwrap();
+        let mut data = vec![0u8; 4 * PAGE_SIZE];
+        let n = file.lock().read_at(0, &mut data).unwrap();
+        let dump = CoreDump::from_bytes(&data[..n]).unwrap();
+
+        assert_eq!(dump.pid, pid.as_usize());
+        assert_eq!(dump.signal, SIGSEGV);
+        assert_eq!(dump.stval, 0xbad);
+        assert_eq!(dump.frame.regs, frame.regs);
+        assert_eq!(dump.frame.sepc, 0x1010);
+
+        // 数据区域和栈，内容与进程内存一致
+        assert_eq!(dump.regions.len(), 2);
+        let word = |region: &CoreRegion, offset: usize| {
+            u64::from_le_bytes(region.data[offset..offset + 8].try_into().unwrap())
+        };
+        assert_eq!((dump.regions[0].start, dump.regions[0].data.len()), (DATA, PAGE_SIZE));
+        assert_eq!(word(&dump.regions[0], 8), 0xdead_beef);
+        assert_eq!(dump.regions[1].start, STACK_TOP - PAGE_SIZE);
+        assert_eq!(word(&dump.regions[1], PAGE_SIZE - 8), 0x5eed);
+
+        // 截断的文件不能解码
+        assert!(CoreDump::from_bytes(&data[..n - 1]).is_none());
+
+        scheduler::lock_scheduler().remove_process(pid);
+        RAMFS.remove(RAMFS.root(), &name).unwrap();
+    }
+}
@@ -0,0 +1,309 @@
+/*
+ * ============================================
+ * 用户程序装载
+ * ============================================
+ * 功能：从 RamFS 读取 ELF 可执行文件，装载到新的地址空间并创建进程
+ *
+ * 装载过程：
+ * 1. 读取文件，校验 ELF 文件头
+ * 2. 新建地址空间（根页表）
+ * 3. 每个 PT_LOAD 段按页分配清零的物理页，复制文件中的数据，
+ *    超出 filesz 的部分保持为零（.bss）
+ * 4. 在 USER_STACK_TOP 下方映射一页初始用户栈，之后按需增长；
+ *    USER_STACK_TOP 处映射信号跳板（见 signal::map_trampoline）
+ * 5. 上下文从 ELF 入口开始，satp 指向新页表
+ *
+ * 说明：调度器进入用户态的 sret 路径尚未完成（见 scheduler::ContextSwitch::Start），
+ * 装载好的进程可以被调度器管理，但还不会真正执行用户指令
+ * ============================================
+ */
+
+use super::context::ProcessContext;
+use super::pcb::ProcessControlBlock;
+use super::stack::USER_STACK_MAX;
+use super::{create_process, register_child, ProcessError, ProcessHandle, ProcessId};
+use crate::elf::{self, ElfError, PF_W, PF_X, PT_LOAD};
+use crate::fs::{File, FileError, RAMFS};
+use crate::memory::{
+    walk_page_table, AddressSpace, PageTableFlags, PhysAddr, SimpleFrameAllocator, VirtAddr, PAGE_SIZE,
+};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// 用户栈顶（用户地址空间）
+pub const USER_STACK_TOP: usize = 0x4000_0000;
+
+/// satp 的 Sv39 模式位
+const SATP_SV39: usize = 8 << 60;
+
+/// 装载失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecError {
+    /// 读取程序文件失败（包括文件不存在）
+    File(FileError),
+    /// 不是合法的 RISC-V 可执行文件
+    BadElf(ElfError),
+    /// 分配页表或页面失败
+    OutOfMemory,
+    /// 全局帧分配器尚未安装
+    NoFrameAllocator,
+    /// 创建进程失败
+    Process(ProcessError),
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExecError::File(e) => write!(f, "{}", e),
+            ExecError::BadElf(e) => write!(f, "bad executable: {:?}", e),
+            ExecError::OutOfMemory => write!(f, "out of memory"),
+            ExecError::NoFrameAllocator => write!(f, "frame allocator not installed"),
+            ExecError::Process(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<FileError> for ExecError {
+    fn from(e: FileError) -> Self {
+        ExecError::File(e)
+    }
+}
+
+impl From<ElfError> for ExecError {
+    fn from(e: ElfError) -> Self {
+        ExecError::BadElf(e)
+    }
+}
+
+impl From<ProcessError> for ExecError {
+    fn from(e: ProcessError) -> Self {
+        ExecError::Process(e)
+    }
+}
+
+/// 读取程序文件的全部内容
+pub fn read_program(path: &str) -> Result<Vec<u8>, ExecError> {
+    let root = crate::process::current_root();
+    let inode = RAMFS.resolve_in(root.clone(), root, path)?;
+    let mut file = RAMFS.open_file(inode)?;
+    Ok(file.read_all()?)
+}
+
+/// 段权限对应的页表标志（用户可访问，总是可读）
+fn segment_flags(p_flags: u32) -> usize {
+    let mut flags = PageTableFlags::Read as usize | PageTableFlags::User as usize;
+    if p_flags & PF_W != 0 {
+        flags |= PageTableFlags::Write as usize;
+    }
+    if p_flags & PF_X != 0 {
+        flags |= PageTableFlags::Execute as usize;
+    }
+    flags
+}
+
+/// 把 ELF 的 PT_LOAD 段装载到地址空间
+///
+/// # 返回
+/// 程序入口地址
+///
+/// # 说明
+/// 两个段共用一页时复用已映射的页面（权限以先映射的段为准）
+pub fn load_segments(
+    space: &mut AddressSpace,
+    data: &[u8],
+    allocator: &mut SimpleFrameAllocator,
+) -> Result<usize, ExecError> {
+    let header = elf::parse(data)?;
+
+    for phdr in elf::program_headers(data, &header)? {
+        if phdr.p_type != PT_LOAD || phdr.memsz == 0 {
+            continue;
+        }
+
+        let seg_end = phdr.vaddr.checked_add(phdr.memsz).ok_or(ElfError::BadProgramHeader)?;
+        let file_end = phdr.vaddr + phdr.filesz;
+        let flags = segment_flags(phdr.flags);
+
+        let mut page = phdr.vaddr & !(PAGE_SIZE - 1);
+        while page < seg_end {
+            let vaddr = VirtAddr::new(page);
+            let paddr = match walk_page_table(space.page_table_paddr(), vaddr) {
+                Some(paddr) => paddr,
+                None => space
+                    .map_zeroed_page(vaddr, flags, allocator)
+                    .map_err(|_| ExecError::OutOfMemory)?,
+            };
+
+            // 本页中来自文件的部分
+            let copy_start = page.max(phdr.vaddr);
+            let copy_end = (page + PAGE_SIZE).min(file_end);
+            if copy_start < copy_end {
+                let src = &data[phdr.offset + (copy_start - phdr.vaddr)..][..copy_end - copy_start];
+                let dst = (paddr.as_usize() + (copy_start - page)) as *mut u8;
+                unsafe { core::ptr::copy_nonoverlapping(src.as_ptr(), dst, src.len()) };
+            }
+
+            page += PAGE_SIZE;
+        }
+    }
+
+    Ok(header.entry)
+}
+
+/// 程序映像的结束地址（最高 PT_LOAD 段的末尾，向上取整到页）
+///
+/// # 说明
+/// 装载后的堆从这里开始
+pub fn image_end(data: &[u8]) -> Result<usize, ExecError> {
+    let header = elf::parse(data)?;
+
+    let mut end = 0;
+    for phdr in elf::program_headers(data, &header)? {
+        if phdr.p_type != PT_LOAD || phdr.memsz == 0 {
+            continue;
+        }
+        let seg_end = phdr.vaddr.checked_add(phdr.memsz).ok_or(ElfError::BadProgramHeader)?;
+        end = end.max(seg_end);
+    }
+    end.checked_next_multiple_of(PAGE_SIZE)
+        .ok_or(ExecError::BadElf(ElfError::BadProgramHeader))
+}
+
+/// 为进程装载程序映像（替换地址空间、用户栈、堆和上下文）
+///
+/// # 返回
+/// 程序入口地址
+///
+/// # 说明
+/// 堆从映像末尾开始（初始为空），由 sys_sbrk 向上增长到用户栈的增长下界
+pub fn load_image(
+    pcb: &mut ProcessControlBlock,
+    data: &[u8],
+    allocator: &mut SimpleFrameAllocator,
+) -> Result<usize, ExecError> {
+    let mut space = AddressSpace::new(allocator).map_err(|_| ExecError::OutOfMemory)?;
+    let entry = load_segments(&mut space, data, allocator)?;
+    let heap_start = image_end(data)?;
+
+    let stack_bottom = USER_STACK_TOP - PAGE_SIZE;
+    let stack_flags = PageTableFlags::Read as usize
+        | PageTableFlags::Write as usize
+        | PageTableFlags::User as usize;
+    space
+        .map_zeroed_page(VirtAddr::new(stack_bottom), stack_flags, allocator)
+        .map_err(|_| ExecError::OutOfMemory)?;
+    super::signal::map_trampoline(&mut space, allocator).map_err(|_| ExecError::OutOfMemory)?;
+
+    let satp = SATP_SV39 | (space.page_table_paddr().as_usize() >> 12);
+    pcb.set_address_space(space);
+    pcb.set_user_stack(stack_bottom, USER_STACK_TOP);
+    pcb.set_user_stack_limit(USER_STACK_TOP - USER_STACK_MAX);
+    pcb.set_heap(heap_start);
+    *pcb.context_mut() = ProcessContext::new_user_context(entry, USER_STACK_TOP, satp);
+
+    Ok(entry)
+}
+
+/// 从文件创建并装载一个用户进程
+///
+/// # 参数
+/// - `name`: 进程名称
+/// - `path`: 程序路径
+/// - `parent_pid`: 父进程（登记为其子进程）
+/// - `allocator`: 帧分配器
+///
+/// # 返回
+/// 新进程句柄（尚未加入调度器）
+pub fn spawn_with(
+    name: &'static str,
+    path: &str,
+    parent_pid: Option<ProcessId>,
+    allocator: &mut SimpleFrameAllocator,
+) -> Result<ProcessHandle, ExecError> {
+    let data = read_program(path)?;
+    let header = elf::parse(&data)?;
+
+    let process = create_process(name, header.entry, USER_STACK_TOP, parent_pid)?;
+    load_image(&mut process.lock(), &data, allocator)?;
+
+    register_child(parent_pid, &process);
+    Ok(process)
+}
+
+/// 从文件创建并装载一个用户进程（使用全局帧分配器）
+pub fn spawn(
+    name: &'static str,
+    path: &str,
+    parent_pid: Option<ProcessId>,
+) -> Result<ProcessHandle, ExecError> {
+    crate::memory::with_frame_allocator(|allocator| spawn_with(name, path, parent_pid, allocator))
+        .unwrap_or(Err(ExecError::NoFrameAllocator))
+}
+
+/// 用户地址 `vaddr` 对应的物理地址
+pub fn translate(pcb: &ProcessControlBlock, vaddr: usize) -> Option<PhysAddr> {
+    let space = pcb.address_space()?;
+    let page = walk_page_table(space.page_table_paddr(), VirtAddr::new(vaddr & !(PAGE_SIZE - 1)))?;
+    Some(PhysAddr::new(page.as_usize() + (vaddr & (PAGE_SIZE - 1))))
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{exit_process, reap_child, scheduler, spawn_test_process, WaitStatus};
+    use crate::memory::TestMemory;
+    use alloc::vec;
+
+    #[test_case]
+    fn test_exit7_program_is_reaped_with_code_7() {
+        const FRAMES: usize = 16;
+
+        let mut memory = TestMemory::new(FRAMES, 0);
+        let allocator = &mut memory.allocator;
+
+        crate::user_programs::install().unwrap();
+
+        let parent = spawn_test_process("exec_parent", None);
+        let parent_pid = parent.lock().pid();
+
+        let child = spawn_with("exit7", "/bin/exit7", Some(parent_pid), allocator).unwrap();
+        let child_pid = child.lock().pid();
+        scheduler::add_process(child.clone()).unwrap();
+
+        // 上下文从 ELF 入口开始，代码已装载到入口地址
+        let data = crate::user_programs::find("exit7").unwrap();
+        let entry = elf::parse(data).unwrap().entry;
+        {
+            let pcb = child.lock();
+            assert_eq!(pcb.context().sepc, entry);
+            assert_eq!(pcb.context().sp, USER_STACK_TOP);
+            let code = translate(&pcb, entry).unwrap().as_usize() as *const u32;
+            // li a0, 7
+            assert_eq!(unsafe { code.read() }, 0x0070_0513);
+            assert!(translate(&pcb, USER_STACK_TOP - 8).is_some());
+
+            // 堆紧接在映像之后，不会与栈或信号跳板重叠
+            assert_eq!(pcb.heap_bottom(), image_end(data).unwrap());
+            assert!(pcb.heap_bottom() > entry);
+            assert!(pcb.heap_bottom() <= pcb.user_stack_limit());
+        }
+        assert_eq!(parent.lock().children(), &vec![child_pid]);
+
+        // 子进程还在运行：没有可回收的
+        assert_eq!(reap_child(parent_pid, None), Ok(None));
+
+        // 调度器还不能进入用户态，这里代替 CPU 执行程序的 sys_exit(7)
+        exit_process(&child, 7);
+
+        assert_eq!(reap_child(parent_pid, Some(child_pid.as_usize())), Ok(Some((child_pid, WaitStatus::exited(7)))));
+        assert!(scheduler::get_process(child_pid).is_none());
+        assert!(parent.lock().children().is_empty());
+        assert_eq!(reap_child(parent_pid, None), Err(ProcessError::NoChild));
+
+        scheduler::lock_scheduler().remove_process(parent_pid);
+    }
+}
@@ -4,12 +4,46 @@
 //! - 列出所有进程及其状态
 //! - 查看进程详细信息
 //! - 统计系统资源使用情况
+//!
+//! 输出有两种详细程度：默认的完整表格，以及便于嵌入 ps 等工具的紧凑格式
 
 use crate::println;
-use super::scheduler::SCHEDULER;
+use super::scheduler::{cached_current_pid, context_switches};
+use super::table::{ProcessTable, PROCESS_TABLE};
+use super::ProcessId;
+use super::pcb::ProcessHandle;
+use crate::trap::without_interrupts;
 use super::pcb::ProcessState;
 use alloc::vec::Vec;
 use alloc::string::String;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// 检查器输出的详细程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verbosity {
+    /// 带边框的完整表格
+    Full,
+    /// 每个条目一行，没有边框
+    Compact,
+}
+
+/// 全局是否使用紧凑格式（进程和文件系统检查器共用）
+static COMPACT: AtomicBool = AtomicBool::new(false);
+
+/// 设置检查器的默认详细程度
+pub fn set_verbosity(verbosity: Verbosity) {
+    COMPACT.store(verbosity == Verbosity::Compact, Ordering::Relaxed);
+}
+
+/// 当前检查器的默认详细程度
+pub fn verbosity() -> Verbosity {
+    if COMPACT.load(Ordering::Relaxed) {
+        Verbosity::Compact
+    } else {
+        Verbosity::Full
+    }
+}
 
 /// 进程快照 - 某一时刻的进程状态
 #[derive(Clone)]
@@ -18,6 +52,10 @@ pub struct ProcessSnapshot {
     pub name: String,
     pub state: ProcessState,
     pub parent_pid: Option<usize>,
+    /// 运行期间经历的时钟中断数
+    pub cpu_ticks: u64,
+    /// 被切换上 CPU 的次数
+    pub switch_count: u64,
 }
 
 /// 系统统计信息
@@ -27,25 +65,48 @@ pub struct SystemStats {
     pub ready_processes: usize,
     pub blocked_processes: usize,
     pub zombie_processes: usize,
+    pub context_switches: u64,
 }
 
 /// 获取所有进程的快照
 pub fn get_all_processes() -> Vec<ProcessSnapshot> {
-    let scheduler = SCHEDULER.lock();
-    let mut snapshots = Vec::new();
-
-    // 遍历调度器中的所有进程
-    for (pid, process_handle) in scheduler.processes() {
-        let pcb = process_handle.lock();
-        snapshots.push(ProcessSnapshot {
-            pid: (*pid).as_usize(),  // 转换ProcessId到usize
+    snapshot_table(&PROCESS_TABLE)
+}
+
+/// 获取指定进程表中所有进程的快照
+///
+/// # 说明
+/// - 先在读锁下复制句柄（只增加引用计数），随即释放读锁，
+///   不会阻塞并发的创建/退出，也不获取调度器锁
+/// - 之后逐个短暂锁住 PCB 复制需要的字段；关中断是因为时钟中断也会锁 PCB
+/// - 列表以取句柄那一刻的进程表为准：期间退出的进程仍会出现（句柄使其存活），
+///   期间创建的进程不会出现；结果按 PID 排序且不重复
+pub fn snapshot_table(table: &ProcessTable) -> Vec<ProcessSnapshot> {
+    let handles: Vec<(ProcessId, ProcessHandle)> = table
+        .read()
+        .iter()
+        .map(|(pid, handle)| (*pid, handle.clone()))
+        .collect();
+
+    handles
+        .into_iter()
+        .map(|(pid, handle)| snapshot_process(pid, &handle))
+        .collect()
+}
+
+/// 短暂锁住 PCB，复制快照需要的字段
+fn snapshot_process(pid: ProcessId, handle: &ProcessHandle) -> ProcessSnapshot {
+    without_interrupts(|| {
+        let pcb = handle.lock();
+        ProcessSnapshot {
+            pid: pid.as_usize(),  // 转换ProcessId到usize
             name: pcb.name().into(),
             state: pcb.state(),
             parent_pid: pcb.parent_pid().map(|p| p.as_usize()),  // 转换Option<ProcessId>
-        });
-    }
-
-    snapshots
+            cpu_ticks: pcb.cpu_ticks(),
+            switch_count: pcb.switch_count(),
+        }
+    })
 }
 
 /// 获取系统统计信息
@@ -72,41 +133,75 @@ pub fn get_system_stats() -> SystemStats {
         ready_processes: ready,
         blocked_processes: blocked,
         zombie_processes: zombie,
+        context_switches: context_switches(),
     }
 }
 
 /// 获取当前正在运行的进程信息
 pub fn get_current_process() -> Option<ProcessSnapshot> {
-    let scheduler = SCHEDULER.lock();
-
-    if let Some(current_pid) = scheduler.current_pid() {
-        if let Some(process_handle) = scheduler.get_process(current_pid) {
-            let pcb = process_handle.lock();
-            return Some(ProcessSnapshot {
-                pid: current_pid.as_usize(),  // 转换ProcessId
-                name: pcb.name().into(),
-                state: pcb.state(),
-                parent_pid: pcb.parent_pid().map(|p| p.as_usize()),  // 转换Option<ProcessId>
-            });
-        }
+    let current_pid = cached_current_pid()?;
+    let handle = PROCESS_TABLE.get(current_pid)?;
+    Some(snapshot_process(current_pid, &handle))
+}
+
+/// 进程状态的短名称
+fn state_name(state: ProcessState) -> &'static str {
+    match state {
+        ProcessState::Running => "Running",
+        ProcessState::Ready => "Ready",
+        ProcessState::Blocked => "Blocked",
+        ProcessState::Zombie => "Zombie",
     }
+}
 
-    None
+/// 紧凑格式的进程列表：一行表头，之后每个进程一行
+pub fn format_process_list_compact(processes: &[ProcessSnapshot]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{:>5} {:<16} {:<8} {:>5} {:>8} {:>6}",
+                     "PID", "NAME", "STATE", "PPID", "CPU", "SWITCH");
+    for proc in processes {
+        let ppid = match proc.parent_pid {
+            Some(ppid) => alloc::format!("{}", ppid),
+            None => "-".into(),
+        };
+        let _ = writeln!(out, "{:>5} {:<16} {:<8} {:>5} {:>8} {:>6}",
+                         proc.pid, proc.name, state_name(proc.state), ppid,
+                         proc.cpu_ticks, proc.switch_count);
+    }
+    out
 }
 
-/// 可视化：显示所有进程列表
+/// 紧凑格式显示所有进程（每个进程一行，没有边框）
+pub fn show_process_list_compact() {
+    crate::print!("{}", format_process_list_compact(&get_all_processes()));
+}
+
+/// 按指定的详细程度显示所有进程
+pub fn show_process_list_with(verbosity: Verbosity) {
+    match verbosity {
+        Verbosity::Full => show_process_list_full(),
+        Verbosity::Compact => show_process_list_compact(),
+    }
+}
+
+/// 可视化：显示所有进程列表（详细程度由 set_verbosity 决定）
 pub fn show_process_list() {
-    println!("\n================================================================");
-    println!("===                  System Process List                     ===");
-    println!("================================================================");
+    show_process_list_with(verbosity());
+}
+
+/// 完整表格格式的进程列表
+fn show_process_list_full() {
+    println!("\n===================================================================================");
+    println!("===                             System Process List                             ===");
+    println!("===================================================================================");
 
     let processes = get_all_processes();
 
     if processes.is_empty() {
-        println!("===  (No processes in system)                                ===");
+        println!("===  (No processes in system)                                                   ===");
     } else {
-        println!("===  PID  |  Name              |  State      |  Parent PID  ===");
-        println!("================================================================");
+        println!("===  PID  |  Name             |  State       |  PPID    |       CPU  |  Switch  ===");
+        println!("===================================================================================");
 
         for proc in processes {
             let state_str = match proc.state {
@@ -121,12 +216,12 @@ pub fn show_process_list() {
                 None => "  -   ".into(),
             };
 
-            println!("===  {:3}  |  {:16} |  {}  |  {:8}       ===",
-                     proc.pid, proc.name, state_str, parent_str);
+            println!("===  {:3}  |  {:16} |  {}  |  {}  |  {:8}  |  {:6}  ===",
+                     proc.pid, proc.name, state_str, parent_str, proc.cpu_ticks, proc.switch_count);
         }
     }
 
-    println!("================================================================");
+    println!("===================================================================================");
 }
 
 /// 可视化：显示系统统计信息
@@ -142,6 +237,7 @@ pub fn show_system_stats() {
     println!("===  Ready:             {:3}                                 ===", stats.ready_processes);
     println!("===  Blocked:           {:3}                                 ===", stats.blocked_processes);
     println!("===  Zombie:            {:3}                                 ===", stats.zombie_processes);
+    println!("===  Context Switches:  {:<10}                          ===", stats.context_switches);
     println!("================================================================");
 }
 
@@ -164,6 +260,36 @@ pub fn show_current_process() {
     println!("================================================================");
 }
 
+/// 可视化：显示调度事件追踪记录
+pub fn show_scheduler_trace() {
+    use super::trace::{self, SchedEvent};
+
+    println!("\n================================================================");
+    println!("===                  Scheduler Trace                         ===");
+    println!("================================================================");
+
+    let records = trace::snapshot();
+
+    if records.is_empty() {
+        let hint = if trace::is_enabled() { "" } else { " (tracing disabled)" };
+        println!("===  (No events recorded){}", hint);
+    }
+
+    for record in records {
+        let event = match record.event {
+            SchedEvent::Tick(pid) => alloc::format!("tick      current={:?}", pid.map(|p| p.as_usize())),
+            SchedEvent::Switch { from, to } => {
+                alloc::format!("switch    {:?} -> {}", from.map(|p| p.as_usize()), to)
+            }
+            SchedEvent::Block(pid) => alloc::format!("block     pid={}", pid),
+            SchedEvent::Wake(pid) => alloc::format!("wake      pid={}", pid),
+        };
+        println!("===  [{:>12}] {}", record.timestamp, event);
+    }
+
+    println!("================================================================");
+}
+
 /// 可视化：完整的系统状态仪表盘
 pub fn show_system_dashboard() {
     println!("\n");
@@ -179,3 +305,105 @@ pub fn show_system_dashboard() {
 
     println!("");
 }
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_compact_process_list_one_line_per_process() {
+        let processes: Vec<ProcessSnapshot> = (1..=3)
+            .map(|pid| ProcessSnapshot {
+                pid,
+                name: alloc::format!("proc{}", pid),
+                state: ProcessState::Ready,
+                parent_pid: if pid == 1 { None } else { Some(1) },
+                cpu_ticks: 10 * pid as u64,
+                switch_count: pid as u64,
+            })
+            .collect();
+
+        let out = format_process_list_compact(&processes);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), processes.len() + 1);
+        assert!(lines[0].contains("PID"));
+        assert!(lines[2].contains("proc2") && lines[2].contains("Ready"));
+        assert!(lines[0].contains("CPU") && lines[0].contains("SWITCH"));
+        assert!(lines[3].ends_with("      30      3"));
+        assert!(!out.contains("==="));
+
+        set_verbosity(Verbosity::Compact);
+        assert_eq!(verbosity(), Verbosity::Compact);
+        set_verbosity(Verbosity::Full);
+    }
+
+    #[test_case]
+    fn test_snapshot_during_scheduling_is_complete() {
+        use crate::process::create_process;
+        use crate::process::scheduler::Scheduler;
+        use crate::task::{Task, simple_executor::{yield_once, SimpleExecutor}};
+        use alloc::sync::Arc;
+        use spin::Mutex;
+
+        const PROCESSES: usize = 4;
+        const ROUNDS: usize = 8;
+
+        let table = Arc::new(ProcessTable::new());
+        let scheduler = Arc::new(Mutex::new(Scheduler::with_table(table.clone())));
+        let mut pids = Vec::new();
+        for _ in 0..PROCESSES {
+            let process = create_process("snap", 0x1000, 0x2000, None).unwrap();
+            pids.push(process.lock().pid().as_usize());
+            scheduler.lock().add_process(process).unwrap();
+        }
+
+        let mut executor = SimpleExecutor::new();
+
+        // 调度方：不断改变进程状态，并创建/移除临时进程
+        let sched = scheduler.clone();
+        executor.spawn(Task::new(async move {
+            for round in 0..ROUNDS {
+                let transient = create_process("transient", 0x1000, 0x2000, None).unwrap();
+                let transient_pid = transient.lock().pid();
+                {
+                    let mut sched = sched.lock();
+                    sched.add_process(transient).unwrap();
+                    for (i, pid) in sched.process_table().read().keys().enumerate() {
+                        let state = if (i + round) % 2 == 0 {
+                            ProcessState::Blocked
+                        } else {
+                            ProcessState::Ready
+                        };
+                        sched.get_process(*pid).unwrap().lock().set_state(state);
+                    }
+                }
+                yield_once().await;
+                sched.lock().remove_process(transient_pid);
+                yield_once().await;
+            }
+        }));
+
+        // 观察方：每轮都拿到完整、有序、不重复的列表
+        let observed = Arc::new(Mutex::new(0));
+        let count = observed.clone();
+        executor.spawn(Task::new(async move {
+            for _ in 0..2 * ROUNDS {
+                let snapshot = snapshot_table(&table);
+                for pid in &pids {
+                    assert_eq!(snapshot.iter().filter(|p| p.pid == *pid).count(), 1);
+                }
+                assert!(snapshot.windows(2).all(|w| w[0].pid < w[1].pid));
+                assert!(snapshot.len() == PROCESSES || snapshot.len() == PROCESSES + 1);
+                *count.lock() += 1;
+                yield_once().await;
+            }
+        }));
+        executor.run();
+
+        assert_eq!(*observed.lock(), 2 * ROUNDS);
+    }
+}
@@ -0,0 +1,102 @@
+/*
+ * ============================================
+ * 系统负载（load average）
+ * ============================================
+ * 功能：按 Linux 的方式统计 1、5、15 分钟的平均可运行进程数
+ *
+ * - 每 LOAD_FREQ 个 tick（5 秒）采样一次可运行进程数
+ *   （就绪队列中的进程加上正在运行的进程）
+ * - 每个平均值是指数衰减的移动平均：
+ *   load = load * e + active * (1 - e)，e = exp(-5s / 1min) 等
+ * - 用 FSHIFT 位小数的定点数计算，中断中不使用浮点
+ * ============================================
+ */
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// 定点数的小数位数
+pub const FSHIFT: u32 = 11;
+/// 定点数的 1.0
+pub const FIXED_1: usize = 1 << FSHIFT;
+
+/// 采样间隔（tick），5 秒
+pub const LOAD_FREQ: u64 = 5 * crate::trap::TICKS_PER_SECOND;
+
+/// 1/exp(5s/1min)、1/exp(5s/5min)、1/exp(5s/15min) 的定点值
+const EXP: [usize; 3] = [1884, 2014, 2037];
+
+/// 1、5、15 分钟的负载（定点数）
+static LOADS: [AtomicUsize; 3] = [const { AtomicUsize::new(0) }; 3];
+
+/// 用一次采样更新一个平均值
+///
+/// # 参数
+/// - `load`: 当前平均值（定点数）
+/// - `exp`: 衰减系数（定点数）
+/// - `active`: 可运行进程数（定点数）
+pub const fn calc_load(load: usize, exp: usize, active: usize) -> usize {
+    let mut new = load * exp + active * (FIXED_1 - exp);
+    // 负载上升时向上取整，避免长期停在略低于 active 的值
+    if active >= load {
+        new += FIXED_1 - 1;
+    }
+    new / FIXED_1
+}
+
+/// 时钟中断中调用：每 LOAD_FREQ 个 tick 采样一次
+///
+/// # 参数
+/// - `now`: 当前 tick
+/// - `active`: 取得可运行进程数（只在采样时调用）
+pub fn tick(now: u64, active: impl FnOnce() -> usize) {
+    if !now.is_multiple_of(LOAD_FREQ) {
+        return;
+    }
+    sample(active());
+}
+
+/// 记录一次采样
+pub fn sample(active: usize) {
+    let active = active * FIXED_1;
+    for (load, &exp) in LOADS.iter().zip(EXP.iter()) {
+        load.store(calc_load(load.load(Ordering::Relaxed), exp, active), Ordering::Relaxed);
+    }
+}
+
+/// 1、5、15 分钟的负载（定点数，FIXED_1 表示 1.0）
+pub fn load_average() -> [usize; 3] {
+    [0, 1, 2].map(|i| LOADS[i].load(Ordering::Relaxed))
+}
+
+/// 定点数的整数部分和两位小数（四舍五入到 0.01）
+pub const fn split_load(load: usize) -> (usize, usize) {
+    let hundredths = (load * 100 + FIXED_1 / 2) >> FSHIFT;
+    (hundredths / 100, hundredths % 100)
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_calc_load_converges_to_active_count() {
+        // 一直有 2 个可运行进程：1 分钟平均值 10 分钟后为 2.00
+        let mut load = 0;
+        for _ in 0..120 {
+            load = calc_load(load, EXP[0], 2 * FIXED_1);
+        }
+        assert_eq!(split_load(load), (2, 0));
+
+        // 之后没有可运行进程：逐渐衰减到 0
+        for _ in 0..120 {
+            load = calc_load(load, EXP[0], 0);
+        }
+        assert_eq!(split_load(load), (0, 0));
+
+        assert_eq!(split_load(FIXED_1 + FIXED_1 / 4), (1, 25));
+    }
+}
@@ -30,6 +30,17 @@ pub mod context;
 pub mod pcb;
 pub mod scheduler;
 pub mod inspector;      // 真实系统状态查询模块
+pub mod trace;          // 调度事件追踪
+pub mod table;          // 进程表（读写锁）
+pub mod stack;          // 用户栈按需增长
+pub mod exec;           // 用户程序装载
+pub mod wait_status;    // waitpid 的状态编码
+pub mod signal;         // 信号
+pub mod builtin;        // 按名字启动的内置程序
+pub mod loadavg;        // 系统负载
+pub mod coredump;       // 崩溃时的核心转储
+#[cfg(feature = "ptrace")]
+pub mod ptrace;         // 单步调试
 
 // ============================================
 // 重新导出核心类型
@@ -42,10 +53,18 @@ pub use pcb::{
     ProcessState,
     ProcessHandle,
     create_process_handle,
+    DEFAULT_UMASK,
+    NICE_MIN,
+    NICE_MAX,
+    PRIORITY_MAX,
 };
-pub use scheduler::SCHEDULER;
+pub use scheduler::{SCHEDULER, ProcessLimitError, maybe_yield};
+pub use wait_status::{WaitStatus, wifexited, wexitstatus, wifsignaled, wtermsig};
+pub use signal::{SignalState, send_signal, SIGCHLD, SIGKILL, SIGTERM};
 
 use crate::serial_println;
+use crate::trap::frame::KernelStack;
+use core::sync::atomic::{AtomicU32, Ordering};
 
 // ============================================
 // 初始化
@@ -55,7 +74,7 @@ use crate::serial_println;
 ///
 /// # 说明
 /// - 初始化PID分配器
-/// - 初始化调度器
+/// - 初始化调度器（创建 idle 进程）
 /// - 准备创建init进程
 pub fn init() {
     serial_println!("[PROCESS] Initializing process management system");
@@ -70,6 +89,103 @@ pub fn init() {
 // 进程创建
 // ============================================
 
+/// 进程创建错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessError {
+    /// 内存不足（栈等资源分配失败）
+    OutOfMemory,
+    /// 进程数已达上限
+    LimitExceeded(ProcessLimitError),
+    /// 入口地址无效（为空或未按指令对齐）
+    InvalidEntry(usize),
+    /// 没有符合条件的子进程（waitpid）
+    NoChild,
+    /// 父进程创建子进程过快（fork 限速）
+    RateLimited,
+}
+
+impl core::fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            ProcessError::OutOfMemory => write!(f, "out of memory"),
+            ProcessError::LimitExceeded(e) => write!(f, "{}", e),
+            ProcessError::InvalidEntry(entry) => write!(f, "invalid entry point {:#x}", entry),
+            ProcessError::NoChild => write!(f, "no such child process"),
+            ProcessError::RateLimited => write!(f, "child creation rate limit exceeded"),
+        }
+    }
+}
+
+impl From<ProcessLimitError> for ProcessError {
+    fn from(e: ProcessLimitError) -> Self {
+        ProcessError::LimitExceeded(e)
+    }
+}
+
+/// 检查父进程的 fork 限速（只检查，成功创建后由 register_child 计数）
+fn check_fork_rate(parent_pid: ProcessId) -> Result<(), ProcessError> {
+    let now = riscv::register::time::read64();
+    match scheduler::get_process(parent_pid) {
+        Some(parent) if !parent.lock().may_create_child(now) => Err(ProcessError::RateLimited),
+        _ => Ok(()),
+    }
+}
+
+/// 登记创建成功的子进程
+///
+/// # 说明
+/// 加入父进程的子进程列表，并计入父进程的 fork 限速；
+/// 创建失败的进程不经过这里，不占用限额
+pub(crate) fn register_child(parent_pid: Option<ProcessId>, child: &ProcessHandle) {
+    let Some(parent) = parent_pid.and_then(scheduler::get_process) else {
+        return;
+    };
+    let child_pid = child.lock().pid();
+    let now = riscv::register::time::read64();
+
+    let mut parent = parent.lock();
+    parent.add_child(child_pid);
+    parent.record_child_creation(now);
+}
+
+/// 指令对齐要求（RVC 压缩指令为 2 字节）
+const INSTRUCTION_ALIGN: usize = 2;
+
+/// 用户栈初始大小（64KB），之后按需增长到 stack::USER_STACK_MAX
+const USER_STACK_SIZE: usize = 0x10000;
+
+/// 检查入口地址是否合法
+fn validate_entry(entry_point: usize) -> Result<(), ProcessError> {
+    if entry_point == 0 || !entry_point.is_multiple_of(INSTRUCTION_ALIGN) {
+        return Err(ProcessError::InvalidEntry(entry_point));
+    }
+    Ok(())
+}
+
+/// 从内核堆分配一块栈
+///
+/// # 返回
+/// 栈顶地址（16 字节对齐），分配失败时返回 `ProcessError::OutOfMemory`
+///
+/// # 说明
+/// 使用 try_reserve 而不是 vec!，堆耗尽时返回错误而不是 panic；
+/// 栈在线程生命周期内一直有效，因此直接泄漏
+fn alloc_stack(size: usize) -> Result<usize, ProcessError> {
+    #[cfg(test)]
+    if crate::fault::should_fail(crate::fault::Fault::FrameAlloc) {
+        return Err(ProcessError::OutOfMemory);
+    }
+
+    let mut stack: alloc::vec::Vec<u8> = alloc::vec::Vec::new();
+    stack
+        .try_reserve_exact(size)
+        .map_err(|_| ProcessError::OutOfMemory)?;
+    stack.resize(size, 0);
+
+    let stack = stack.leak();
+    Ok((stack.as_ptr() as usize + size) & !0xF)
+}
+
 /// 创建新进程
 ///
 /// # 参数
@@ -79,9 +195,14 @@ pub fn init() {
 /// - `parent_pid`: 父进程PID（None表示init进程）
 ///
 /// # 返回
-/// 新创建的进程句柄
+/// 新创建的进程句柄（尚未加入调度器）；失败时返回 `ProcessError`：
+/// - `InvalidEntry`: 入口地址为空或未对齐
+/// - `LimitExceeded`: 有父进程且进程数已达上限（init 进程不受限制）
+/// - `RateLimited`: 父进程当前窗口内创建的子进程过多
+/// - `OutOfMemory`: 内核陷阱栈分配失败
 ///
 /// # 说明
+/// 0. 检查入口地址和进程数上限，分配内核陷阱栈
 /// 1. 分配PID
 /// 2. 创建PCB
 /// 3. 初始化上下文
@@ -92,7 +213,15 @@ pub fn create_process(
     entry_point: usize,
     user_stack_top: usize,
     parent_pid: Option<ProcessId>,
-) -> ProcessHandle {
+) -> Result<ProcessHandle, ProcessError> {
+    validate_entry(entry_point)?;
+
+    // 提前检查上限，避免为注定加不进调度器的进程分配PID
+    if let Some(parent_pid) = parent_pid {
+        scheduler::check_capacity()?;
+        check_fork_rate(parent_pid)?;
+    }
+
     // 注释掉调试输出，避免刷屏
     // serial_println!(
     //     "[PROCESS] Creating process: {} (entry={:#x}, stack={:#x})",
@@ -101,15 +230,33 @@ pub fn create_process(
     //     user_stack_top
     // );
 
+    // 先分配内核陷阱栈，失败时不会消耗PID
+    let kernel_stack = KernelStack::try_new().ok_or(ProcessError::OutOfMemory)?;
+
     // 创建PCB
     let process = create_process_handle(name, parent_pid);
 
+    // 继承父进程的文件创建掩码和根目录
+    let parent_fs = parent_pid.and_then(scheduler::get_process).map(|parent| {
+        let parent = parent.lock();
+        (parent.umask(), parent.root_dir())
+    });
+
     // 初始化上下文
     {
         let mut pcb = process.lock();
+        pcb.set_kernel_stack(kernel_stack);
+
+        if let Some((mask, root)) = parent_fs {
+            pcb.set_umask(mask);
+            if let Some(root) = root {
+                pcb.set_root_dir(root);
+            }
+        }
 
         // 设置用户栈
-        pcb.set_user_stack(user_stack_top - 0x10000, user_stack_top);
+        pcb.set_user_stack(user_stack_top.saturating_sub(USER_STACK_SIZE), user_stack_top);
+        pcb.set_user_stack_limit(user_stack_top.saturating_sub(stack::USER_STACK_MAX));
 
         // 创建用户态上下文
         // 注意：当前使用恒等映射（identity mapping），即虚拟地址=物理地址
@@ -125,49 +272,278 @@ pub fn create_process(
 
     // serial_println!("[PROCESS] Process created: PID={}", process.lock().pid());
 
-    process
+    Ok(process)
+}
+
+/// 内核线程栈大小（16KB）
+const KERNEL_STACK_SIZE: usize = 16 * 1024;
+
+/// 创建内核线程
+///
+/// # 参数
+/// - `name`: 线程名称
+/// - `entry`: 线程入口函数（永不返回）
+///
+/// # 返回
+/// 新创建的进程句柄（尚未加入调度器）；内核栈分配失败时返回 `ProcessError::OutOfMemory`
+///
+/// # 说明
+/// 内核线程与用户进程共用 PCB 和调度器，但运行在内核态，
+/// 使用从内核堆分配的独立栈
+pub fn create_kernel_thread(
+    name: &'static str,
+    entry: fn() -> !,
+) -> Result<ProcessHandle, ProcessError> {
+    create_kernel_thread_with_stack(name, entry, KERNEL_STACK_SIZE, None)
+}
+
+/// idle 进程的主体：有就绪进程时让出 CPU，否则按配置的策略等待（见 idle 模块）
+///
+/// # 说明
+/// 第一次运行时经 scheduler::kernel_thread_start 进入，调度器锁已经释放、中断已经打开，
+/// 时钟中断才能在有就绪进程时设置 need_resched，让这里的 maybe_yield 切走
+fn idle_main() -> ! {
+    loop {
+        if !maybe_yield() {
+            crate::idle::wait();
+        }
+    }
+}
+
+/// 创建 idle 进程（PID 0 的内核线程）
+///
+/// # 说明
+/// 由调度器初始化时创建并登记（见 Scheduler::set_idle），
+/// 不进入就绪队列，只在没有其他就绪进程时运行
+pub fn create_idle_thread() -> Result<ProcessHandle, ProcessError> {
+    let stack_top = alloc_stack(KERNEL_STACK_SIZE)?;
+
+    let entry: fn() -> ! = idle_main;
+    let pcb = ProcessControlBlock::with_pid(ProcessId::IDLE, "idle", None);
+    let process = alloc::sync::Arc::new(spin::Mutex::new(pcb));
+    *process.lock().context_mut() =
+        ProcessContext::new_kernel_context(entry as usize, stack_top);
+
+    Ok(process)
+}
+
+/// 创建指定栈大小的内核线程
+///
+/// # 说明
+/// `parent_pid` 只记录在 PCB 中，由调用者用 register_child 登记到父进程
+fn create_kernel_thread_with_stack(
+    name: &'static str,
+    entry: fn() -> !,
+    stack_size: usize,
+    parent_pid: Option<ProcessId>,
+) -> Result<ProcessHandle, ProcessError> {
+    if let Some(parent_pid) = parent_pid {
+        check_fork_rate(parent_pid)?;
+    }
+
+    // 先分配栈，失败时不会消耗PID
+    let stack_top = alloc_stack(stack_size)?;
+
+    let process = create_process_handle(name, parent_pid);
+    *process.lock().context_mut() =
+        ProcessContext::new_kernel_context(entry as usize, stack_top);
+
+    Ok(process)
 }
 
 // ============================================
 // 进程控制
 // ============================================
 
+/// 让进程以 `exit_code` 退出
+///
+/// # 说明
+/// 1. 保存退出码，状态变为 Zombie（等待父进程回收）
+/// 2. 向父进程发送 SIGCHLD，同时唤醒可能在 waitpid 中等待的父进程
+pub fn exit_process(process: &ProcessHandle, exit_code: i32) {
+    let parent = {
+        let mut pcb = process.lock();
+        serial_println!("[PROCESS] Process PID={} exiting with code {}", pcb.pid(), exit_code);
+        pcb.set_exit_code(exit_code);
+        pcb.parent_pid()
+    };
+    notify_parent(parent);
+}
+
+/// 用信号 `signal` 终止进程
+///
+/// # 说明
+/// 与 exit_process 相同，但父进程 waitpid 得到的状态表示被信号终止
+pub fn kill_process(process: &ProcessHandle, signal: i32) {
+    let parent = {
+        let mut pcb = process.lock();
+        serial_println!("[PROCESS] Process PID={} killed by signal {}", pcb.pid(), signal);
+        pcb.set_killed(signal);
+        pcb.parent_pid()
+    };
+    notify_parent(parent);
+}
+
+/// 进程结束后通知父进程（SIGCHLD，阻塞时唤醒）
+fn notify_parent(parent: Option<ProcessId>) {
+    // TODO: 回收资源（页表、内存等）
+
+    if let Some(parent) = parent {
+        send_signal(parent, SIGCHLD);
+    }
+}
+
 /// 退出当前进程
 ///
 /// # 参数
 /// - `exit_code`: 退出码
 ///
 /// # 说明
-/// 1. 设置进程状态为 Zombie
-/// 2. 保存退出码
-/// 3. 通知父进程
-/// 4. 触发调度
+/// 见 exit_process；之后触发调度，不再返回到该进程
 pub fn exit_current_process(exit_code: i32) {
     let current = scheduler::current_process();
 
     if let Some(process) = current {
-        let pid = process.lock().pid();
-        serial_println!("[PROCESS] Process PID={} exiting with code {}", pid, exit_code);
+        exit_process(&process, exit_code);
 
-        // 设置退出码和状态
-        process.lock().set_exit_code(exit_code);
+        // 触发调度
+        scheduler::schedule();
+    }
+}
 
-        // TODO: 通知父进程
-        // TODO: 回收资源（页表、内存等）
+/// 用信号 `signal` 终止当前进程
+///
+/// # 说明
+/// 见 kill_process；之后触发调度，不再返回到该进程
+pub fn kill_current_process(signal: i32) {
+    if let Some(process) = scheduler::current_process() {
+        kill_process(&process, signal);
+        scheduler::schedule();
+    }
+}
 
-        // 触发调度
-        scheduler::SCHEDULER.lock().schedule();
+/// 回收一个已退出的子进程（waitpid 的核心）
+///
+/// # 参数
+/// - `parent_pid`: 父进程
+/// - `target`: 只回收该 PID 的子进程；None 表示任意子进程
+///
+/// # 返回
+/// - `Ok(Some((pid, status)))`: 已回收，子进程从进程表中移除
+/// - `Ok(None)`: 有符合条件的子进程，但都还没有退出
+/// - `Err(NoChild)`: 没有符合条件的子进程
+pub fn reap_child(
+    parent_pid: ProcessId,
+    target: Option<usize>,
+) -> Result<Option<(ProcessId, WaitStatus)>, ProcessError> {
+    let parent = scheduler::get_process(parent_pid).ok_or(ProcessError::NoChild)?;
+    let children: alloc::vec::Vec<ProcessId> = parent
+        .lock()
+        .children()
+        .iter()
+        .copied()
+        .filter(|pid| target.is_none_or(|target| pid.as_usize() == target))
+        .collect();
+    if children.is_empty() {
+        return Err(ProcessError::NoChild);
+    }
+
+    let mut running = 0;
+    for pid in children {
+        let status = match scheduler::get_process(pid) {
+            Some(child) => child.lock().wait_status(),
+            // 已不在进程表中（被别处回收），从子进程列表中清除
+            None => {
+                parent.lock().remove_child(pid);
+                continue;
+            }
+        };
+
+        match status {
+            Some(status) => {
+                scheduler::lock_scheduler().remove_process(pid);
+                parent.lock().remove_child(pid);
+                return Ok(Some((pid, status)));
+            }
+            None => running += 1,
+        }
+    }
+
+    if running == 0 {
+        return Err(ProcessError::NoChild);
     }
+    Ok(None)
 }
 
 /// 阻塞当前进程
-pub fn block_current_process() {
-    scheduler::SCHEDULER.lock().block_current();
+///
+/// # 返回
+/// - `true`: 当前进程已阻塞，被唤醒后才会返回
+/// - `false`: 处于 idle/内核上下文，没有进程可以阻塞
+///
+/// # 说明
+/// 内核代码（而非用户进程）走到阻塞路径时没有当前进程，
+/// 阻塞会让内核永远等下去。调用者应在返回 `false` 时
+/// 向上报告"将阻塞"错误，而不是继续等待。
+pub fn block_current_process() -> bool {
+    scheduler::block_current()
+}
+
+/// 开始阻塞当前进程：只标记为 Blocked，不让出 CPU
+///
+/// # 返回
+/// 与 block_current_process 相同：没有进程可以阻塞时返回 `false`
+///
+/// # 说明
+/// 等待某个条件时，在持有唤醒者也要获取的锁时调用：
+/// 1. 持锁检查条件，不满足时 prepare_block_current_process 并登记为等待者
+/// 2. 释放锁后 finish_block_current_process，仍未被唤醒才让出 CPU
+///
+/// 条件在两步之间已经满足时用 cancel_block_current_process 放弃阻塞
+pub fn prepare_block_current_process() -> bool {
+    scheduler::prepare_block()
+}
+
+/// 完成 prepare_block_current_process 开始的阻塞
+///
+/// # 返回
+/// 是否让出了 CPU（期间已被唤醒时不让出，直接返回 `false`）
+pub fn finish_block_current_process() -> bool {
+    scheduler::finish_block()
+}
+
+/// 放弃 prepare_block_current_process 开始的阻塞
+pub fn cancel_block_current_process() {
+    scheduler::cancel_block();
 }
 
 /// 唤醒进程
 pub fn wake_up_process(pid: ProcessId) {
-    scheduler::SCHEDULER.lock().wake_up(pid);
+    scheduler::wake_up(pid);
+}
+
+/// 当前进程主动让出 CPU（没有其他就绪进程时继续运行）
+///
+/// # 返回
+/// 是否切换到了其他进程
+pub fn yield_now() -> bool {
+    scheduler::yield_current()
+}
+
+/// 让当前进程睡眠 ticks 个 tick
+///
+/// # 返回
+/// 与 block_current_process 相同：没有当前进程可睡眠时返回 `false`
+pub fn sleep_current_process(ticks: u64) -> bool {
+    scheduler::sleep_current(ticks)
+}
+
+/// 设置进程的调度优先级（0 到 PRIORITY_MAX，数值越大优先级越高）
+///
+/// # 返回
+/// 进程不在调度器中时返回 false
+pub fn set_priority(pid: ProcessId, priority: usize) -> bool {
+    scheduler::set_nice(pid, pcb::priority_to_nice(priority)).is_some()
 }
 
 // ============================================
@@ -184,6 +560,41 @@ pub fn current_process() -> Option<ProcessHandle> {
     scheduler::current_process()
 }
 
+/// 内核上下文（没有当前进程时）使用的文件创建掩码
+static KERNEL_UMASK: AtomicU32 = AtomicU32::new(DEFAULT_UMASK);
+
+/// 获取当前文件创建掩码
+///
+/// # 说明
+/// 有当前进程时返回该进程的掩码，否则返回内核上下文的掩码
+pub fn current_umask() -> u32 {
+    match current_process() {
+        Some(process) => process.lock().umask(),
+        None => KERNEL_UMASK.load(Ordering::Relaxed),
+    }
+}
+
+/// 设置当前文件创建掩码
+///
+/// # 返回
+/// 之前的掩码
+pub fn set_current_umask(mask: u32) -> u32 {
+    match current_process() {
+        Some(process) => process.lock().set_umask(mask),
+        None => KERNEL_UMASK.swap(mask & 0o777, Ordering::Relaxed),
+    }
+}
+
+/// 当前进程的根目录
+///
+/// # 说明
+/// 没有当前进程或没有 chroot 时返回文件系统的根
+pub fn current_root() -> alloc::sync::Arc<spin::Mutex<crate::fs::RamInode>> {
+    current_process()
+        .and_then(|process| process.lock().root_dir())
+        .unwrap_or_else(|| crate::fs::RAMFS.root())
+}
+
 // ============================================
 // 调试
 // ============================================
@@ -193,6 +604,20 @@ pub fn print_process_info() {
     scheduler::print_status();
 }
 
+/// 创建一个测试用的进程并加入调度器
+///
+/// # 参数
+/// - `name`: 进程名
+/// - `parent`: 父进程；不为空时同时登记到父进程的子进程列表
+#[cfg(test)]
+pub(crate) fn spawn_test_process(name: &'static str, parent: Option<&ProcessHandle>) -> ProcessHandle {
+    let parent_pid = parent.map(|parent| parent.lock().pid());
+    let process = create_process(name, 0x1000, 0x2000, parent_pid).unwrap();
+    register_child(parent_pid, &process);
+    scheduler::add_process(process.clone()).unwrap();
+    process
+}
+
 // ============================================
 // 测试
 // ============================================
@@ -205,7 +630,7 @@ mod tests {
     fn test_process_creation() {
         init();
 
-        let process = create_process("test", 0x1000, 0x2000, None);
+        let process = create_process("test", 0x1000, 0x2000, None).unwrap();
         let pid = process.lock().pid();
 
         assert!(pid.as_usize() > 0);
@@ -215,7 +640,7 @@ mod tests {
     fn test_process_state_transition() {
         init();
 
-        let process = create_process("test", 0x1000, 0x2000, None);
+        let process = create_process("test", 0x1000, 0x2000, None).unwrap();
 
         {
             let mut pcb = process.lock();
@@ -225,4 +650,107 @@ mod tests {
             assert_eq!(pcb.state(), ProcessState::Running);
         }
     }
+
+    #[test_case]
+    fn test_create_process_rejects_invalid_entry() {
+        assert_eq!(
+            create_process("bad", 0, 0x2000, None).err(),
+            Some(ProcessError::InvalidEntry(0))
+        );
+        assert_eq!(
+            create_process("bad", 0x1001, 0x2000, None).err(),
+            Some(ProcessError::InvalidEntry(0x1001))
+        );
+    }
+
+    #[test_case]
+    fn test_stack_allocation_failure_returns_oom() {
+        fn never_runs() -> ! {
+            unreachable!()
+        }
+
+        // 远超堆容量的栈必然分配失败，应返回错误而不是 panic
+        assert_eq!(
+            create_kernel_thread_with_stack("huge", never_runs, usize::MAX / 2, None).err(),
+            Some(ProcessError::OutOfMemory)
+        );
+    }
+
+    #[test_case]
+    fn test_child_creation_rate_limit_counts_only_successes() {
+        use crate::fault::{self, Fault};
+        use alloc::vec::Vec;
+
+        let parent = spawn_test_process("rate_parent", None);
+        let parent_pid = parent.lock().pid();
+
+        // 失败的创建不占用限额
+        assert_eq!(
+            create_process("rate_bad", 0, 0x2000, Some(parent_pid)).err(),
+            Some(ProcessError::InvalidEntry(0))
+        );
+        fault::fail_next(Fault::FrameAlloc, 1);
+        assert_eq!(
+            create_process("rate_oom", 0x1000, 0x2000, Some(parent_pid)).err(),
+            Some(ProcessError::OutOfMemory)
+        );
+        fault::reset();
+
+        let children: Vec<ProcessId> = (0..pcb::FORK_RATE_LIMIT)
+            .map(|_| spawn_test_process("rate_child", Some(&parent)).lock().pid())
+            .collect();
+        assert_eq!(parent.lock().children().len(), pcb::FORK_RATE_LIMIT);
+
+        // 同一窗口内再创建被拒绝，不分配PID、不登记
+        assert_eq!(
+            create_process("rate_extra", 0x1000, 0x2000, Some(parent_pid)).err(),
+            Some(ProcessError::RateLimited)
+        );
+        assert_eq!(parent.lock().children().len(), pcb::FORK_RATE_LIMIT);
+
+        let mut sched = scheduler::lock_scheduler();
+        for pid in children {
+            sched.remove_process(pid);
+        }
+        sched.remove_process(parent_pid);
+    }
+
+    #[test_case]
+    fn test_idle_dispatched_then_switches_to_ready_process() {
+        use trace::SchedEvent;
+
+        // 重新登记一个还没有运行过的 idle 进程
+        init();
+        let idle_pid = scheduler::lock_scheduler().idle_pid().unwrap();
+
+        // 测试本身作为当前进程运行
+        let runner = spawn_test_process("idle_runner", None);
+        let runner_pid = runner.lock().pid();
+        scheduler::lock_scheduler().run_for_test(runner_pid);
+
+        // 睡眠时没有其他就绪进程，真正切换到 idle；
+        // 时钟中断唤醒 runner 后 idle 让出 CPU，切回这里
+        trace::clear();
+        trace::set_enabled(true);
+        assert!(sleep_current_process(1));
+        trace::set_enabled(false);
+
+        let switches: alloc::vec::Vec<_> = trace::snapshot()
+            .into_iter()
+            .filter(|record| matches!(record.event, SchedEvent::Switch { .. }))
+            .map(|record| record.event)
+            .collect();
+        assert_eq!(
+            switches,
+            [
+                SchedEvent::Switch { from: Some(runner_pid), to: idle_pid },
+                SchedEvent::Switch { from: Some(idle_pid), to: runner_pid },
+            ]
+        );
+        assert_eq!(scheduler::current_pid(), Some(runner_pid));
+        assert_eq!(runner.lock().state(), ProcessState::Running);
+
+        scheduler::lock_scheduler().remove_process(runner_pid);
+        trace::clear();
+    }
 }
@@ -20,7 +20,12 @@ use spin::Mutex;
 
 use super::pid::ProcessId;
 use super::context::ProcessContext;
+use super::wait_status::WaitStatus;
+use super::signal::SignalState;
 use crate::memory::AddressSpace;
+use crate::fs::RamInode;
+use crate::trap::TrapFrame;
+use crate::trap::frame::KernelStack;
 
 // ============================================
 // 进程状态
@@ -89,6 +94,12 @@ pub struct ProcessControlBlock {
     /// 在上下文切换时保存/恢复
     context: ProcessContext,
 
+    /// 被切换出去时的用户现场（USER_TRAP_FRAME 的副本），切回时恢复
+    trap_frame: TrapFrame,
+
+    /// 处理本进程用户态陷阱的内核栈（第一次被调度时分配）
+    kernel_stack: Option<KernelStack>,
+
     // ============================================
     // 内存信息
     // ============================================
@@ -109,6 +120,9 @@ pub struct ProcessControlBlock {
     /// 用户栈顶地址
     user_stack_top: usize,
 
+    /// 用户栈可以增长到的最低地址（保留范围的下界）
+    user_stack_limit: usize,
+
     // ============================================
     // 调度信息
     // ============================================
@@ -116,8 +130,20 @@ pub struct ProcessControlBlock {
     /// 剩余时间片（时钟中断计数）
     time_slice: usize,
 
-    /// 优先级（数值越大优先级越高，暂时未使用）
-    priority: usize,
+    /// nice 值（NICE_MIN 到 NICE_MAX，数值越小优先级越高）
+    nice: i32,
+
+    /// 是否为特权进程（可以提高优先级）
+    privileged: bool,
+
+    /// 禁止抢占后经过的时钟中断数（None 表示允许抢占）
+    no_preempt_ticks: Option<usize>,
+
+    /// 运行期间经历的时钟中断数（CPU 时间）
+    cpu_ticks: u64,
+
+    /// 被切换上 CPU 的次数
+    switch_count: u64,
 
     // ============================================
     // 进程关系
@@ -128,6 +154,63 @@ pub struct ProcessControlBlock {
 
     /// 退出码（Some表示已退出）
     exit_code: Option<i32>,
+
+    /// 终止进程的信号（Some表示被信号杀死）
+    term_signal: Option<i32>,
+
+    /// 待处理信号和处理函数
+    signals: SignalState,
+
+    /// 当前限速窗口的起始时间（time 寄存器 tick）
+    fork_window_start: u64,
+
+    /// 当前限速窗口内已创建的子进程数
+    forks_in_window: usize,
+
+    // ============================================
+    // 文件系统信息
+    // ============================================
+
+    /// 文件创建掩码（新建文件/目录的权限位会清除其中的位）
+    umask: u32,
+
+    /// 根目录（chroot 设置）；None 表示文件系统的根
+    root_dir: Option<Arc<Mutex<RamInode>>>,
+}
+
+/// 默认文件创建掩码（去掉组和其他用户的写权限）
+pub const DEFAULT_UMASK: u32 = 0o022;
+
+/// fork 限速窗口长度（tick，10MHz 时钟下为 1 秒）
+pub const FORK_RATE_WINDOW: u64 = 10_000_000;
+
+/// 每个限速窗口内允许创建的子进程数
+pub const FORK_RATE_LIMIT: usize = 16;
+
+/// 禁止抢占最多持续的时钟中断数，超过后强制恢复抢占
+pub const MAX_NO_PREEMPT_TICKS: usize = 10;
+
+/// 最小 nice 值（最高优先级）
+pub const NICE_MIN: i32 = -20;
+
+/// 最大 nice 值（最低优先级）
+pub const NICE_MAX: i32 = 19;
+
+/// 默认 nice 值
+pub const DEFAULT_NICE: i32 = 0;
+
+/// 最高调度优先级（对应 NICE_MIN）
+pub const PRIORITY_MAX: usize = (NICE_MAX - NICE_MIN) as usize;
+
+/// nice 值对应的调度优先级（数值越大优先级越高）
+pub const fn nice_to_priority(nice: i32) -> usize {
+    (NICE_MAX - nice) as usize
+}
+
+/// 调度优先级对应的 nice 值（超出范围时截断）
+pub const fn priority_to_nice(priority: usize) -> i32 {
+    let priority = if priority > PRIORITY_MAX { PRIORITY_MAX } else { priority };
+    NICE_MAX - priority as i32
 }
 
 impl ProcessControlBlock {
@@ -139,22 +222,43 @@ impl ProcessControlBlock {
     ///
     /// # 返回
     /// 新创建的 PCB，状态为 Ready
+    ///
+    /// # 说明
+    /// 没有父进程的进程（init、内核直接创建的进程）是特权进程
     pub fn new(name: &'static str, parent_pid: Option<ProcessId>) -> Self {
+        Self::with_pid(ProcessId::new(), name, parent_pid)
+    }
+
+    /// 使用指定的PID创建进程控制块（如 idle 进程的 PID 0）
+    pub fn with_pid(pid: ProcessId, name: &'static str, parent_pid: Option<ProcessId>) -> Self {
         ProcessControlBlock {
-            pid: ProcessId::new(),
+            pid,
             parent_pid,
             state: ProcessState::Ready,
             name,
             context: ProcessContext::new(),
+            trap_frame: TrapFrame::new(),
+            kernel_stack: None,
             address_space: None,
             heap_bottom: 0,
             heap_top: 0,
             user_stack_bottom: 0,
             user_stack_top: 0,
+            user_stack_limit: 0,
             time_slice: 5,  // 默认时间片：5个时钟周期
-            priority: 1,     // 默认优先级
+            nice: DEFAULT_NICE,
+            privileged: parent_pid.is_none(),
+            no_preempt_ticks: None,
+            cpu_ticks: 0,
+            switch_count: 0,
             children: Vec::new(),
             exit_code: None,
+            term_signal: None,
+            signals: SignalState::new(),
+            fork_window_start: 0,
+            forks_in_window: 0,
+            umask: DEFAULT_UMASK,
+            root_dir: None,
         }
     }
 
@@ -182,6 +286,27 @@ impl ProcessControlBlock {
         self.exit_code
     }
 
+    pub fn term_signal(&self) -> Option<i32> {
+        self.term_signal
+    }
+
+    pub fn signals(&self) -> &SignalState {
+        &self.signals
+    }
+
+    pub fn signals_mut(&mut self) -> &mut SignalState {
+        &mut self.signals
+    }
+
+    /// 进程结束方式（还没有结束时为 None）
+    pub fn wait_status(&self) -> Option<WaitStatus> {
+        match (self.term_signal, self.exit_code) {
+            (Some(signal), _) => Some(WaitStatus::signaled(signal)),
+            (None, Some(code)) => Some(WaitStatus::exited(code)),
+            (None, None) => None,
+        }
+    }
+
     pub fn context(&self) -> &ProcessContext {
         &self.context
     }
@@ -190,14 +315,81 @@ impl ProcessControlBlock {
         &mut self.context
     }
 
+    pub fn trap_frame(&self) -> &TrapFrame {
+        &self.trap_frame
+    }
+
+    pub fn trap_frame_mut(&mut self) -> &mut TrapFrame {
+        &mut self.trap_frame
+    }
+
+    /// 内核陷阱栈的栈顶，还没有分配时先分配
+    pub fn kernel_stack_top(&mut self) -> usize {
+        self.kernel_stack.get_or_insert_with(KernelStack::new).top()
+    }
+
+    /// 设置预先分配的内核陷阱栈（create_process 在分配失败时可以返回错误）
+    pub fn set_kernel_stack(&mut self, stack: KernelStack) {
+        self.kernel_stack = Some(stack);
+    }
+
     pub fn address_space(&self) -> Option<&AddressSpace> {
         self.address_space.as_ref()
     }
 
+    pub fn address_space_mut(&mut self) -> Option<&mut AddressSpace> {
+        self.address_space.as_mut()
+    }
+
+    /// 用户栈当前已映射部分的底部
+    pub fn user_stack_bottom(&self) -> usize {
+        self.user_stack_bottom
+    }
+
+    pub fn user_stack_top(&self) -> usize {
+        self.user_stack_top
+    }
+
+    pub fn heap_bottom(&self) -> usize {
+        self.heap_bottom
+    }
+
+    /// 当前的堆顶（program break）
+    pub fn heap_top(&self) -> usize {
+        self.heap_top
+    }
+
+    /// 用户栈可以增长到的最低地址
+    pub fn user_stack_limit(&self) -> usize {
+        self.user_stack_limit
+    }
+
     pub fn children(&self) -> &Vec<ProcessId> {
         &self.children
     }
 
+    pub fn umask(&self) -> u32 {
+        self.umask
+    }
+
+    /// chroot 设置的根目录（None 表示文件系统的根）
+    pub fn root_dir(&self) -> Option<Arc<Mutex<RamInode>>> {
+        self.root_dir.clone()
+    }
+
+    pub fn nice(&self) -> i32 {
+        self.nice
+    }
+
+    /// 调度优先级（0 到 PRIORITY_MAX，数值越大优先级越高）
+    pub fn priority(&self) -> usize {
+        nice_to_priority(self.nice)
+    }
+
+    pub fn is_privileged(&self) -> bool {
+        self.privileged
+    }
+
     // ============================================
     // Setter 方法
     // ============================================
@@ -215,16 +407,89 @@ impl ProcessControlBlock {
         self.user_stack_top = top;
     }
 
+    /// 设置用户栈的增长下界
+    pub fn set_user_stack_limit(&mut self, limit: usize) {
+        self.user_stack_limit = limit;
+    }
+
+    /// 设置堆底（初始为空堆），由 exec::load_image 设在程序映像之后
     pub fn set_heap(&mut self, bottom: usize) {
         self.heap_bottom = bottom;
         self.heap_top = bottom;
     }
 
+    /// 移动堆顶（sbrk）
+    ///
+    /// # 返回
+    /// 不修改并返回 false 的情况：
+    /// - 进程没有堆（没有装载程序映像，堆底为 0）
+    /// - top 低于堆底
+    /// - top 越过用户栈的增长下界（堆不能伸进为栈保留的范围）
+    ///
+    /// # 说明
+    /// 目前只记录边界，不映射新页
+    pub fn set_heap_top(&mut self, top: usize) -> bool {
+        if top == self.heap_top {
+            return true;
+        }
+        if self.heap_bottom == 0 || top < self.heap_bottom || top > self.user_stack_limit {
+            return false;
+        }
+        self.heap_top = top;
+        true
+    }
+
+    /// 设置文件创建掩码
+    ///
+    /// # 返回
+    /// 之前的掩码
+    pub fn set_umask(&mut self, mask: u32) -> u32 {
+        core::mem::replace(&mut self.umask, mask & 0o777)
+    }
+
+    /// 设置根目录（chroot），之后的绝对路径从这里开始解析
+    ///
+    /// # 说明
+    /// 不检查权限，由调用者（sys_chroot）负责
+    pub fn set_root_dir(&mut self, root: Arc<Mutex<RamInode>>) {
+        self.root_dir = Some(root);
+    }
+
+    /// 设置 nice 值（超出范围的值被截断到 NICE_MIN..=NICE_MAX）
+    ///
+    /// # 返回
+    /// 之前的 nice 值
+    ///
+    /// # 说明
+    /// 不检查权限，由调用者（sys_nice / sys_setpriority）负责
+    pub fn set_nice(&mut self, nice: i32) -> i32 {
+        core::mem::replace(&mut self.nice, nice.clamp(NICE_MIN, NICE_MAX))
+    }
+
+    /// 设置调度优先级（超出 PRIORITY_MAX 时截断）
+    ///
+    /// # 说明
+    /// 优先级与 nice 值是同一个量的两种表示，见 priority_to_nice；
+    /// 进程在就绪队列中时应使用 process::set_priority，使其按新优先级重新入队
+    pub fn set_priority(&mut self, priority: usize) {
+        self.set_nice(priority_to_nice(priority));
+    }
+
+    pub fn set_privileged(&mut self, privileged: bool) {
+        self.privileged = privileged;
+    }
+
     pub fn set_exit_code(&mut self, code: i32) {
         self.exit_code = Some(code);
         self.state = ProcessState::Zombie;
     }
 
+    /// 记录进程被信号 `signal` 终止
+    pub fn set_killed(&mut self, signal: i32) {
+        self.term_signal = Some(signal);
+        self.state = ProcessState::Zombie;
+    }
+
     // ============================================
     // 进程关系管理
     // ============================================
@@ -234,6 +499,40 @@ impl ProcessControlBlock {
         self.children.push(child_pid);
     }
 
+    /// 当前窗口内是否还能创建子进程（fork 限速，只检查不计数）
+    ///
+    /// # 参数
+    /// - `now`: 当前时间（tick）
+    pub fn may_create_child(&self, now: u64) -> bool {
+        now.saturating_sub(self.fork_window_start) >= FORK_RATE_WINDOW
+            || self.forks_in_window < FORK_RATE_LIMIT
+    }
+
+    /// 记录一次子进程创建（fork 限速）
+    ///
+    /// # 参数
+    /// - `now`: 当前时间（tick）
+    ///
+    /// # 返回
+    /// - `true`: 未超过限速，已计入当前窗口
+    /// - `false`: 当前窗口内创建过多，应拒绝（EAGAIN）
+    ///
+    /// # 说明
+    /// 固定窗口计数：距窗口起点超过 FORK_RATE_WINDOW 时开启新窗口
+    pub fn record_child_creation(&mut self, now: u64) -> bool {
+        if now.saturating_sub(self.fork_window_start) >= FORK_RATE_WINDOW {
+            self.fork_window_start = now;
+            self.forks_in_window = 0;
+        }
+
+        if self.forks_in_window >= FORK_RATE_LIMIT {
+            return false;
+        }
+
+        self.forks_in_window += 1;
+        true
+    }
+
     /// 移除子进程
     pub fn remove_child(&mut self, child_pid: ProcessId) {
         self.children.retain(|&pid| pid != child_pid);
@@ -243,6 +542,26 @@ impl ProcessControlBlock {
     // 调度相关
     // ============================================
 
+    /// 运行期间经历的时钟中断数
+    pub fn cpu_ticks(&self) -> u64 {
+        self.cpu_ticks
+    }
+
+    /// 被切换上 CPU 的次数
+    pub fn switch_count(&self) -> u64 {
+        self.switch_count
+    }
+
+    /// 记一个时钟中断的 CPU 时间（由 Scheduler::tick 对运行中的进程调用）
+    pub fn account_tick(&mut self) {
+        self.cpu_ticks += 1;
+    }
+
+    /// 记一次上下文切换（由调度器在切换到该进程时调用）
+    pub fn account_switch(&mut self) {
+        self.switch_count += 1;
+    }
+
     /// 重置时间片
     pub fn reset_time_slice(&mut self) {
         self.time_slice = 5;
@@ -253,13 +572,51 @@ impl ProcessControlBlock {
     /// # 返回
     /// - `true`: 时间片用完，需要调度
     /// - `false`: 还有剩余时间片
+    ///
+    /// # 说明
+    /// 禁止抢占期间即使时间片用完也返回 `false`；
+    /// 禁止抢占持续 MAX_NO_PREEMPT_TICKS 个时钟中断后自动恢复抢占
     pub fn tick(&mut self) -> bool {
         if self.time_slice > 0 {
             self.time_slice -= 1;
         }
+
+        if let Some(held) = self.no_preempt_ticks {
+            let held = held + 1;
+            if held < MAX_NO_PREEMPT_TICKS {
+                self.no_preempt_ticks = Some(held);
+                return false;
+            }
+            // 超过上限，强制恢复抢占
+            self.no_preempt_ticks = None;
+        }
+
         self.time_slice == 0
     }
 
+    /// 禁止抢占（时钟中断不再强制调度，直到 enable_preempt 或达到上限）
+    ///
+    /// # 说明
+    /// 已经禁止抢占时再次调用不会重置计数，避免无限延长
+    pub fn disable_preempt(&mut self) {
+        if self.no_preempt_ticks.is_none() {
+            self.no_preempt_ticks = Some(0);
+        }
+    }
+
+    /// 恢复抢占
+    ///
+    /// # 返回
+    /// 调用前是否处于禁止抢占状态
+    pub fn enable_preempt(&mut self) -> bool {
+        self.no_preempt_ticks.take().is_some()
+    }
+
+    /// 是否禁止抢占
+    pub fn preempt_disabled(&self) -> bool {
+        self.no_preempt_ticks.is_some()
+    }
+
     // ============================================
     // 状态检查
     // ============================================
@@ -302,8 +659,12 @@ impl core::fmt::Debug for ProcessControlBlock {
             .field("state", &self.state)
             .field("parent_pid", &self.parent_pid)
             .field("time_slice", &self.time_slice)
+            .field("no_preempt_ticks", &self.no_preempt_ticks)
+            .field("cpu_ticks", &self.cpu_ticks)
+            .field("switch_count", &self.switch_count)
             .field("children_count", &self.children.len())
             .field("exit_code", &self.exit_code)
+            .field("term_signal", &self.term_signal)
             .finish()
     }
 }
@@ -355,6 +716,17 @@ mod tests {
         assert_eq!(pcb.exit_code(), Some(0));
     }
 
+    #[test_case]
+    fn test_kernel_stack_is_private_and_stable() {
+        let mut a = ProcessControlBlock::new("a", None);
+        let mut b = ProcessControlBlock::new("b", None);
+
+        let top = a.kernel_stack_top();
+        assert_eq!(top % 16, 0);
+        assert_eq!(a.kernel_stack_top(), top);
+        assert_ne!(b.kernel_stack_top(), top);
+    }
+
     #[test_case]
     fn test_pcb_time_slice() {
         let mut pcb = ProcessControlBlock::new("test", None);
@@ -369,6 +741,61 @@ mod tests {
         assert!(pcb.tick());
     }
 
+    #[test_case]
+    fn test_pcb_no_preempt() {
+        let mut pcb = ProcessControlBlock::new("critical", None);
+        pcb.reset_time_slice();
+        pcb.disable_preempt();
+
+        // 超过正常时间片仍不被抢占
+        for _ in 0..6 {
+            assert!(!pcb.tick());
+        }
+
+        // 主动恢复后，已用完的时间片立即触发调度
+        assert!(pcb.enable_preempt());
+        assert!(pcb.tick());
+
+        // 一直不恢复时，达到上限后强制允许抢占
+        pcb.reset_time_slice();
+        pcb.disable_preempt();
+        for _ in 0..MAX_NO_PREEMPT_TICKS - 1 {
+            assert!(!pcb.tick());
+        }
+        assert!(pcb.tick());
+        assert!(!pcb.preempt_disabled());
+    }
+
+    #[test_case]
+    fn test_pcb_umask() {
+        let mut pcb = ProcessControlBlock::new("test", None);
+        assert_eq!(pcb.umask(), DEFAULT_UMASK);
+
+        assert_eq!(pcb.set_umask(0o077), DEFAULT_UMASK);
+        assert_eq!(pcb.umask(), 0o077);
+
+        // 只保留权限位
+        pcb.set_umask(0o7777);
+        assert_eq!(pcb.umask(), 0o777);
+    }
+
+    #[test_case]
+    fn test_pcb_fork_rate_limit() {
+        let mut pcb = ProcessControlBlock::new("forker", None);
+        let start = FORK_RATE_WINDOW;
+
+        // 同一窗口内快速 fork，超过阈值后被拒绝
+        for i in 0..FORK_RATE_LIMIT {
+            assert!(pcb.record_child_creation(start + i as u64));
+        }
+        assert!(!pcb.may_create_child(start + FORK_RATE_LIMIT as u64));
+        assert!(!pcb.record_child_creation(start + FORK_RATE_LIMIT as u64));
+
+        // 窗口过去后恢复
+        assert!(pcb.may_create_child(start + FORK_RATE_WINDOW));
+        assert!(pcb.record_child_creation(start + FORK_RATE_WINDOW));
+    }
+
     #[test_case]
     fn test_pcb_children_management() {
         let mut parent = ProcessControlBlock::new("parent", None);
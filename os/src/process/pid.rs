@@ -6,7 +6,7 @@
  *
  * 设计要点：
  * - 使用原子计数器确保PID唯一性
- * - PID从1开始（0保留给内核）
+ * - PID从1开始（0保留给内核的 idle 进程）
  * - 线程安全，支持多核环境
  * ============================================
  */
@@ -18,6 +18,9 @@ use core::sync::atomic::{AtomicUsize, Ordering};
 pub struct ProcessId(usize);
 
 impl ProcessId {
+    /// idle 进程的PID
+    pub const IDLE: ProcessId = ProcessId(0);
+
     /// 创建一个新的进程ID
     ///
     /// # 说明
@@ -46,6 +49,11 @@ impl ProcessId {
     pub fn is_init(self) -> bool {
         self.0 == 1
     }
+
+    /// 检查是否为idle进程（PID = 0）
+    pub fn is_idle(self) -> bool {
+        self == Self::IDLE
+    }
 }
 
 impl core::fmt::Display for ProcessId {
@@ -0,0 +1,143 @@
+/*
+ * ============================================
+ * 进程调试（ptrace，需要 ptrace 特性）
+ * ============================================
+ * 功能：让调试器单步执行用户进程
+ *
+ * 单步流程：
+ * 1. 调试器调用 request_single_step(pid)，登记请求并唤醒停下的进程
+ * 2. 进程返回用户态前（trap_handler 末尾）arm_single_step
+ *    在当前指令的后继处放临时断点（见 trap::breakpoint::single_step）
+ * 3. 执行一条指令后命中临时断点，breakpoint_handler 调用 step_stopped：
+ *    记录停下的 PC，进程阻塞，等待调试器继续单步或 resume
+ *
+ * 观察点：set_watchpoint 在进程地址空间中设置数据观察点
+ * （见 trap::watchpoint），被捕获的写入用 watch_hits 读取
+ * ============================================
+ */
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use crate::sync::IrqSpinLock;
+use crate::trap::{breakpoint, watchpoint, TrapFrame};
+use super::{scheduler, ProcessId};
+
+/// 等待单步的进程（下次返回用户态时放置临时断点）
+static STEP_REQUESTS: IrqSpinLock<Vec<ProcessId>> = IrqSpinLock::new(Vec::new());
+
+/// 单步完成后停下的进程 → 停下时的 PC
+static STOPS: IrqSpinLock<BTreeMap<ProcessId, usize>> = IrqSpinLock::new(BTreeMap::new());
+
+/// 请求单步执行进程 pid 的下一条指令
+///
+/// # 返回
+/// 进程不存在时返回 false
+pub fn request_single_step(pid: ProcessId) -> bool {
+    if scheduler::get_process(pid).is_none() {
+        return false;
+    }
+    {
+        let mut requests = STEP_REQUESTS.lock();
+        if !requests.contains(&pid) {
+            requests.push(pid);
+        }
+    }
+    resume(pid);
+    true
+}
+
+/// 让停下的进程继续运行（不再单步）
+pub fn resume(pid: ProcessId) {
+    if STOPS.lock().remove(&pid).is_some() {
+        scheduler::wake_up(pid);
+    }
+}
+
+/// 进程单步后停下的 PC（没有停下时返回 None）
+pub fn stopped_at(pid: ProcessId) -> Option<usize> {
+    STOPS.lock().get(&pid).copied()
+}
+
+/// 在进程 pid 的 [addr, addr + len) 上设置数据观察点
+///
+/// # 返回
+/// 进程不存在、没有用户地址空间或地址未映射时返回 false
+pub fn set_watchpoint(pid: ProcessId, addr: usize, len: usize) -> bool {
+    let Some(process) = scheduler::get_process(pid) else {
+        return false;
+    };
+    let pcb = process.lock();
+    let Some(space) = pcb.address_space() else {
+        return false;
+    };
+    // 页表由进程的地址空间持有，在 pcb 锁内有效
+    unsafe { watchpoint::set_watchpoint(space.page_table_paddr(), addr, len) }
+}
+
+/// 删除进程 pid 在 addr 处的数据观察点
+pub fn remove_watchpoint(pid: ProcessId, addr: usize) -> bool {
+    let Some(process) = scheduler::get_process(pid) else {
+        return false;
+    };
+    let pcb = process.lock();
+    let Some(space) = pcb.address_space() else {
+        return false;
+    };
+    unsafe { watchpoint::remove_watchpoint(space.page_table_paddr(), addr) }
+}
+
+/// 取出观察点捕获到的写入
+pub fn watch_hits() -> Vec<watchpoint::WatchHit> {
+    watchpoint::take_hits()
+}
+
+/// 返回用户态前：当前进程有单步请求时放置临时断点
+///
+/// # 参数
+/// - `frame`: 即将恢复的用户现场
+pub(crate) fn arm_single_step(frame: &TrapFrame) {
+    let Some(pid) = scheduler::cached_current_pid() else {
+        return;
+    };
+    {
+        let mut requests = STEP_REQUESTS.lock();
+        let Some(index) = requests.iter().position(|&requested| requested == pid) else {
+            return;
+        };
+        requests.swap_remove(index);
+    }
+
+    // 用户进程的代码页由内核映射，sepc 及其后继都是它自己的指令
+    if !unsafe { breakpoint::single_step(frame) } {
+        crate::serial_println!("[PTRACE] PID={} cannot step: another step is in progress", pid);
+    }
+}
+
+/// 单步完成：记录停下的 PC 并阻塞当前进程，等待调试器
+///
+/// # 参数
+/// - `frame`: 命中临时断点时的用户现场
+pub(crate) fn step_stopped(frame: &TrapFrame) {
+    let Some(pid) = scheduler::cached_current_pid() else {
+        return;
+    };
+    STOPS.lock().insert(pid, frame.sepc);
+    scheduler::block_current();
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_single_step_request_needs_process() {
+        let pid = ProcessId::new();
+        assert!(!request_single_step(pid));
+        assert_eq!(stopped_at(pid), None);
+        assert!(!set_watchpoint(pid, 0x4000_0000, 8));
+    }
+}
@@ -4,29 +4,40 @@
  * ============================================
  * 功能：管理进程调度和切换
  *
- * 调度算法：Round-Robin（时间片轮转）
+ * 调度算法：优先级 + Round-Robin（时间片轮转）
+ * - 优先选择 nice 值最小的就绪进程，同优先级之间轮转
  * - 每个进程分配固定时间片
  * - 时间片用完后切换到下一个就绪进程
  * - 公平调度，避免饥饿
  *
  * 数据结构：
  * - 进程表：所有进程的PCB（PID -> PCB映射）
- * - 就绪队列：等待执行的进程PID列表
+ * - 就绪队列：按 nice 值分组的 FIFO 队列（nice -> PID 队列），
+ *   入队和选择都是 O(log n)
  * - 当前进程：正在执行的进程PID
+ * - idle 进程：PID 0，不在就绪队列中，没有就绪进程时运行，
+ *   因此初始化之后 current 总是有效
+ * - 睡眠队列：按唤醒时间（tick）排序，每个 tick 把到期的进程放回就绪队列
  * ============================================
  */
 
 extern crate alloc;
 use alloc::collections::{BTreeMap, VecDeque};
 use alloc::sync::Arc;
-use spin::Mutex;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
 use lazy_static::lazy_static;
 
 use super::pid::ProcessId;
 use super::pcb::{ProcessState, ProcessHandle};
 use super::context::{ProcessContext, switch_context};
+use super::trace::{self, SchedEvent};
+use super::table::{ProcessTable, PROCESS_TABLE};
+use crate::trap::TrapFrame;
+use crate::trap::frame;
 
 use crate::serial_println;
+use crate::sync::{IrqSpinLock, IrqSpinLockGuard};
 
 // ============================================
 // 调试输出开关
@@ -50,8 +61,154 @@ lazy_static! {
     /// 全局调度器
     ///
     /// 使用 lazy_static 确保在运行时初始化
-    /// 使用 Mutex 保证线程安全
-    pub static ref SCHEDULER: Mutex<Scheduler> = Mutex::new(Scheduler::new());
+    /// 使用 IrqSpinLock 保证线程安全，持锁期间自动关闭中断
+    pub static ref SCHEDULER: IrqSpinLock<Scheduler> =
+        IrqSpinLock::new(Scheduler::with_table(PROCESS_TABLE.clone()));
+}
+
+/// 开机以来的上下文切换次数（所有调度器实例合计）
+static CONTEXT_SWITCHES: AtomicU64 = AtomicU64::new(0);
+
+/// 开机以来的上下文切换次数（所有调度器实例合计，供检查器显示）
+pub fn context_switches() -> u64 {
+    CONTEXT_SWITCHES.load(Ordering::Relaxed)
+}
+
+// ============================================
+// 上下文切换
+// ============================================
+
+/// 调度器选定、还没有执行的上下文切换
+///
+/// 生成它时状态转换（进程状态、就绪队列、当前进程）已经完成，
+/// 执行时只剩保存和恢复寄存器
+enum ContextSwitch {
+    /// 从当前进程切换到下一个进程
+    Switch {
+        /// 保存当前进程寄存器的位置
+        from: *mut ProcessContext,
+        /// 下一个进程的寄存器
+        to: *const ProcessContext,
+        /// 保存当前进程用户现场（USER_TRAP_FRAME）的位置
+        saved_frame: *mut TrapFrame,
+        /// 下一个进程的内核陷阱栈栈顶
+        trap_stack_top: usize,
+    },
+    /// 第一次调度：内核初始化上下文不需要保存
+    Start {
+        to: *const ProcessContext,
+        trap_stack_top: usize,
+    },
+}
+
+// 由于我们存储的是原始指针，需要手动实现 Send
+unsafe impl Send for ContextSwitch {}
+
+impl ContextSwitch {
+    /// 执行切换
+    ///
+    /// # Safety
+    /// 指针指向的 PCB 必须仍然存在（由进程表持有）
+    ///
+    /// # 说明
+    /// 切换可能发生在处理用户态陷阱的途中（trap 出口的 resched、阻塞、kill），
+    /// USER_TRAP_FRAME 只有一份，因此切换前把它存进当前进程的 PCB，切回后再恢复；
+    /// 下一个进程的用户态陷阱改用它自己的内核陷阱栈，不会覆盖当前进程留在栈上的调用帧
+    unsafe fn run(self) {
+        match self {
+            ContextSwitch::Switch { from, to, saved_frame, trap_stack_top } => {
+                saved_frame.write(frame::user_trap_frame());
+                frame::set_user_trap_stack_top(trap_stack_top);
+
+                // 执行上下文切换（汇编实现）
+                switch_context(from, to);
+
+                // 注意：这里不会返回，直到下次调度回到此进程；
+                // 切回本进程时陷阱栈已经设回本进程的栈，这里只需恢复用户现场
+                frame::set_user_trap_frame(saved_frame.read());
+            }
+            ContextSwitch::Start { to, trap_stack_top } => {
+                frame::set_user_trap_stack_top(trap_stack_top);
+
+                // 进入新进程（不保存当前上下文）
+                // 内核线程经 ra 中的跳板进入，由跳板释放调度器锁（见 kernel_thread_start）
+                // TODO: 用户进程使用 enter_user_mode 或类似机制
+                // 完整实现需要使用 sret 进入用户态
+                core::arch::asm!(
+                    "mv sp, {sp}",
+                    "jr {ra}",
+                    sp = in(reg) (*to).sp,
+                    ra = in(reg) (*to).ra,
+                    in("a0") (*to).a0,
+                    options(noreturn),
+                );
+            }
+        }
+    }
+}
+
+// ============================================
+// 就绪队列
+// ============================================
+
+/// 按优先级分组的就绪队列
+///
+/// 每个 nice 值一个 FIFO 队列，选择时取 nice 最小的非空队列的队首；
+/// 空队列随即删除，因此第一个键总是最高优先级
+#[derive(Debug, Default)]
+struct ReadyQueue {
+    queues: BTreeMap<i32, VecDeque<ProcessId>>,
+}
+
+impl ReadyQueue {
+    const fn new() -> Self {
+        ReadyQueue { queues: BTreeMap::new() }
+    }
+
+    /// 加入 nice 值为 `nice` 的队列末尾
+    fn push_back(&mut self, pid: ProcessId, nice: i32) {
+        self.queues.entry(nice).or_default().push_back(pid);
+    }
+
+    /// 取出优先级最高的队列的队首
+    fn pop_front(&mut self) -> Option<ProcessId> {
+        let mut entry = self.queues.first_entry()?;
+        let pid = entry.get_mut().pop_front();
+        if entry.get().is_empty() {
+            entry.remove();
+        }
+        pid
+    }
+
+    /// 移除进程
+    ///
+    /// # 返回
+    /// 进程是否在队列中
+    fn remove(&mut self, pid: ProcessId) -> bool {
+        let found = self
+            .queues
+            .iter_mut()
+            .find_map(|(&nice, queue)| queue.iter().position(|&p| p == pid).map(|index| (nice, index)));
+        let Some((nice, index)) = found else {
+            return false;
+        };
+        let queue = self.queues.get_mut(&nice).unwrap();
+        queue.remove(index);
+        if queue.is_empty() {
+            self.queues.remove(&nice);
+        }
+        true
+    }
+
+    /// 队列中的进程数
+    fn len(&self) -> usize {
+        self.queues.values().map(VecDeque::len).sum()
+    }
+
+    #[cfg(test)]
+    fn clear(&mut self) {
+        self.queues.clear();
+    }
 }
 
 // ============================================
@@ -61,30 +218,97 @@ lazy_static! {
 pub struct Scheduler {
     /// 进程表：PID -> PCB 映射
     ///
-    /// 使用 BTreeMap 保证 PID 有序
+    /// 由读写锁单独保护，检查器读取时不需要调度器锁
     /// 使用 Arc<Mutex<>> 允许多处共享 PCB
-    processes: BTreeMap<ProcessId, ProcessHandle>,
+    processes: Arc<ProcessTable>,
 
-    /// 就绪队列（Round-Robin队列）
+    /// 就绪队列（每个优先级一个 Round-Robin 队列）
     ///
-    /// 存储等待执行的进程PID
-    /// 队首是下一个要执行的进程
-    ready_queue: VecDeque<ProcessId>,
+    /// 存储等待执行的进程PID，按入队时的 nice 值分组；
+    /// 进程在队列中时修改 nice 应通过 set_nice 重新入队
+    ready_queue: ReadyQueue,
 
     /// 当前运行的进程PID
     ///
-    /// None 表示没有进程在运行（idle状态）
+    /// None 表示还没有开始调度（内核初始化上下文）
     current: Option<ProcessId>,
+
+    /// idle 进程的PID（登记后 pick_next 在就绪队列为空时选择它）
+    idle: Option<ProcessId>,
+
+    /// 睡眠队列：唤醒时间（开机以来的 tick，见 trap::uptime_ticks）-> 到期时唤醒的进程
+    sleep_queue: BTreeMap<u64, Vec<ProcessId>>,
+
+    /// 最大进程数（init 进程不计入）
+    max_processes: usize,
+
+    /// schedule 选定、等待 finish_switch 执行的上下文切换
+    pending_switch: Option<ContextSwitch>,
+}
+
+/// 默认最大进程数
+pub const DEFAULT_MAX_PROCESSES: usize = 64;
+
+/// 进程数已达上限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessLimitError {
+    /// 当前的进程数上限
+    pub limit: usize,
+}
+
+impl core::fmt::Display for ProcessLimitError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "process limit reached ({})", self.limit)
+    }
 }
 
 impl Scheduler {
-    /// 创建新的调度器
+    /// 创建新的调度器（使用独立的进程表）
     pub fn new() -> Self {
+        Self::with_table(Arc::new(ProcessTable::new()))
+    }
+
+    /// 使用给定的进程表创建调度器
+    pub fn with_table(processes: Arc<ProcessTable>) -> Self {
         Scheduler {
-            processes: BTreeMap::new(),
-            ready_queue: VecDeque::new(),
+            processes,
+            ready_queue: ReadyQueue::new(),
             current: None,
+            idle: None,
+            sleep_queue: BTreeMap::new(),
+            max_processes: DEFAULT_MAX_PROCESSES,
+            pending_switch: None,
+        }
+    }
+
+    /// 设置最大进程数
+    pub fn set_max_processes(&mut self, max: usize) {
+        self.max_processes = max;
+    }
+
+    /// 获取最大进程数
+    pub fn max_processes(&self) -> usize {
+        self.max_processes
+    }
+
+    /// 计入上限的进程数（不含 init 和 idle 进程）
+    fn limited_process_count(&self) -> usize {
+        self.processes
+            .read()
+            .keys()
+            .filter(|pid| !pid.is_init() && !pid.is_idle())
+            .count()
+    }
+
+    /// 检查是否还能加入新的（非 init）进程
+    ///
+    /// # 返回
+    /// 进程数已达上限时返回 `ProcessLimitError`
+    pub fn check_capacity(&self) -> Result<(), ProcessLimitError> {
+        if self.limited_process_count() >= self.max_processes {
+            return Err(ProcessLimitError { limit: self.max_processes });
         }
+        Ok(())
     }
 
     // ============================================
@@ -96,13 +320,21 @@ impl Scheduler {
     /// # 参数
     /// - `process`: 进程句柄
     ///
+    /// # 返回
+    /// 进程数已达上限时返回 `ProcessLimitError`，进程不会被加入
+    ///
     /// # 说明
     /// - 将进程加入进程表
     /// - 如果进程状态为 Ready，加入就绪队列
-    pub fn add_process(&mut self, process: ProcessHandle) {
+    /// - init 进程不受进程数上限限制
+    pub fn add_process(&mut self, process: ProcessHandle) -> Result<(), ProcessLimitError> {
         let pid = process.lock().pid();
         let state = process.lock().state();
 
+        if !pid.is_init() {
+            self.check_capacity()?;
+        }
+
         scheduler_debug!("[SCHEDULER] Add process: PID={}, State={:?}", pid, state);
 
         // 加入进程表
@@ -110,9 +342,12 @@ impl Scheduler {
 
         // 如果进程就绪，加入就绪队列
         if state == ProcessState::Ready {
-            self.ready_queue.push_back(pid);
+            let nice = process.lock().nice();
+            self.ready_queue.push_back(pid, nice);
             scheduler_debug!("[SCHEDULER] Process PID={} added to ready queue", pid);
         }
+
+        Ok(())
     }
 
     /// 移除进程
@@ -124,24 +359,51 @@ impl Scheduler {
     /// - 从进程表移除
     /// - 从就绪队列移除
     /// - 如果是当前进程，清空 current
+    /// - 丢弃还没有执行的上下文切换（其中的指针可能指向被移除的 PCB）
     pub fn remove_process(&mut self, pid: ProcessId) {
         scheduler_debug!("[SCHEDULER] Remove process: PID={}", pid);
 
-        // 从就绪队列移除
-        self.ready_queue.retain(|&p| p != pid);
+        // 从就绪队列和睡眠队列移除
+        self.ready_queue.remove(pid);
+        self.remove_sleeper(pid);
 
         // 从进程表移除
-        self.processes.remove(&pid);
+        self.processes.remove(pid);
 
         // 如果是当前进程，清空
         if self.current == Some(pid) {
-            self.current = None;
+            self.set_current(None);
         }
+        self.pending_switch = None;
+    }
+
+    /// 登记 idle 进程
+    ///
+    /// # 说明
+    /// idle 进程加入进程表但不进入就绪队列，只在没有其他就绪进程时被选中
+    pub fn set_idle(&mut self, process: ProcessHandle) {
+        let pid = {
+            let mut pcb = process.lock();
+            pcb.set_state(ProcessState::Ready);
+            pcb.pid()
+        };
+        self.processes.insert(pid, process);
+        self.idle = Some(pid);
+    }
+
+    /// idle 进程的PID（尚未登记时为 None）
+    pub fn idle_pid(&self) -> Option<ProcessId> {
+        self.idle
+    }
+
+    /// 当前是否在运行 idle 进程
+    pub fn is_idle(&self) -> bool {
+        self.current.is_some() && self.current == self.idle
     }
 
     /// 获取进程句柄
     pub fn get_process(&self, pid: ProcessId) -> Option<ProcessHandle> {
-        self.processes.get(&pid).cloned()
+        self.processes.get(pid)
     }
 
     /// 获取当前进程PID
@@ -149,14 +411,35 @@ impl Scheduler {
         self.current
     }
 
+    /// 可运行的进程数（就绪队列中的加上正在运行的，不含 idle 进程）
+    pub fn nr_running(&self) -> usize {
+        self.ready_queue.len() + usize::from(self.current.is_some() && !self.is_idle())
+    }
+
+    /// 设置当前进程，同时更新本 hart 控制块中的当前PID
+    fn set_current(&mut self, pid: Option<ProcessId>) {
+        self.current = pid;
+        crate::percpu::current().set_current_pid(pid);
+    }
+
+    /// 模拟进程 `pid` 被调度上 CPU：移出就绪队列并设为当前进程
+    #[cfg(test)]
+    pub(crate) fn run_for_test(&mut self, pid: ProcessId) {
+        self.ready_queue.remove(pid);
+        if let Some(process) = self.get_process(pid) {
+            process.lock().set_state(ProcessState::Running);
+        }
+        self.set_current(Some(pid));
+    }
+
     /// 获取当前进程句柄
     pub fn current_process(&self) -> Option<ProcessHandle> {
         self.current.and_then(|pid| self.get_process(pid))
     }
 
-    /// 获取所有进程的迭代器（用于状态检查和可视化）
-    pub fn processes(&self) -> impl Iterator<Item = (&ProcessId, &ProcessHandle)> {
-        self.processes.iter()
+    /// 获取进程表（用于状态检查和可视化）
+    pub fn process_table(&self) -> &ProcessTable {
+        &self.processes
     }
 
     // ============================================
@@ -169,11 +452,40 @@ impl Scheduler {
     /// - Some(pid): 下一个进程的PID
     /// - None: 没有就绪进程
     ///
-    /// # Round-Robin 算法
-    /// 1. 从就绪队列头取出一个进程
-    /// 2. 如果队列为空，返回 None
+    /// # 优先级 + Round-Robin 算法
+    /// 1. 取 nice 值最小（优先级最高）的非空队列
+    /// 2. 取该队列的队首，同优先级之间仍是轮转
+    /// 3. 如果队列为空，当前进程仍可运行时继续选它，
+    ///    否则返回 idle 进程（未登记时返回 None）
     fn pick_next(&mut self) -> Option<ProcessId> {
-        self.ready_queue.pop_front()
+        if let Some(pid) = self.ready_queue.pop_front() {
+            return Some(pid);
+        }
+        let current_runnable = self
+            .current_process()
+            .is_some_and(|process| process.lock().state() == ProcessState::Running);
+        if current_runnable {
+            self.current
+        } else {
+            self.idle
+        }
+    }
+
+    /// 修改进程的 nice 值
+    ///
+    /// # 返回
+    /// 之前的 nice 值；进程不存在时返回 None
+    ///
+    /// # 说明
+    /// 进程在就绪队列中时移到新优先级的队列末尾
+    pub fn set_nice(&mut self, pid: ProcessId, nice: i32) -> Option<i32> {
+        let process = self.get_process(pid)?;
+        let old = process.lock().set_nice(nice);
+        if self.ready_queue.remove(pid) {
+            let nice = process.lock().nice();
+            self.ready_queue.push_back(pid, nice);
+        }
+        Some(old)
     }
 
     /// 将进程放回就绪队列
@@ -182,25 +494,45 @@ impl Scheduler {
     /// - `pid`: 进程PID
     ///
     /// # 说明
-    /// 用于时间片用完的进程
+    /// 用于时间片用完的进程；idle 进程不入队
     fn enqueue(&mut self, pid: ProcessId) {
+        if Some(pid) == self.idle {
+            return;
+        }
+
         // 检查进程状态
         if let Some(process) = self.get_process(pid) {
-            let state = process.lock().state();
+            let (state, nice) = {
+                let pcb = process.lock();
+                (pcb.state(), pcb.nice())
+            };
             if state == ProcessState::Ready {
-                self.ready_queue.push_back(pid);
-                scheduler_debug!("[SCHEDULER] Process PID={} enqueued", pid);
+                self.enqueue_ready(pid, nice);
             }
         }
     }
 
+    /// 把已知处于 Ready 状态的进程加入就绪队列（不锁 PCB）
+    ///
+    /// # 说明
+    /// 调用者负责提供进程当前的 nice 值，用于持有或刚释放 PCB 锁的路径（如 prepare_switch）
+    fn enqueue_ready(&mut self, pid: ProcessId, nice: i32) {
+        if Some(pid) == self.idle {
+            return;
+        }
+        self.ready_queue.push_back(pid, nice);
+        scheduler_debug!("[SCHEDULER] Process PID={} enqueued", pid);
+    }
+
     /// 调度新进程
     ///
     /// # 说明
-    /// 1. 保存当前进程上下文
-    /// 2. 选择下一个进程
-    /// 3. 恢复下一个进程上下文
-    /// 4. 执行上下文切换
+    /// 1. 选择下一个进程
+    /// 2. 完成状态转换（进程状态、就绪队列、当前进程）
+    /// 3. 记下待执行的上下文切换，由全局接口在释放调度器锁之前执行
+    ///    （见 finish_switch）
+    ///
+    /// 调度器方法本身不切换上下文，因此测试可以直接驱动真实的调度逻辑
     pub fn schedule(&mut self) {
         // 选择下一个进程
         let next_pid = match self.pick_next() {
@@ -212,6 +544,11 @@ impl Scheduler {
             }
         };
 
+        // 还没有开始调度时，内核初始化上下文本身就相当于 idle，不切换到 idle 进程
+        if self.current.is_none() && Some(next_pid) == self.idle {
+            return;
+        }
+
         let next_process = match self.get_process(next_pid) {
             Some(p) => p,
             None => {
@@ -238,92 +575,106 @@ impl Scheduler {
             next_pid
         );
 
-        // 执行上下文切换
-        match current_pid {
+        trace::record(SchedEvent::Switch { from: current_pid, to: next_pid });
+        CONTEXT_SWITCHES.fetch_add(1, Ordering::Relaxed);
+
+        let switch = match current_pid {
             Some(current_pid) => {
                 // 有当前进程，需要保存状态
                 let current_process = self.get_process(current_pid).unwrap();
-                self.switch_to(current_process, next_process, next_pid);
+                self.prepare_switch(current_process, next_process, next_pid)
             }
             None => {
                 // 没有当前进程（初次调度），直接启动新进程
-                self.start_process(next_process, next_pid);
+                self.prepare_start(next_process, next_pid)
             }
-        }
+        };
+        self.pending_switch = Some(switch);
     }
 
-    /// 从当前进程切换到新进程
-    fn switch_to(
+    /// 从当前进程切换到新进程的状态转换
+    ///
+    /// # 返回
+    /// 需要执行的上下文切换
+    ///
+    /// # 说明
+    /// 加锁的不变式：
+    /// - 两个 PCB 锁只在开头的一个作用域内同时持有，期间只读写 PCB 字段，
+    ///   不调用任何会再去锁 PCB 的调度器方法（如 enqueue）
+    /// - 之后需要的信息（是否放回就绪队列及其 nice 值、两个上下文指针）都在持锁时取出，
+    ///   解锁后不再重新加锁，因此不会看到被其他路径改过的中间状态
+    /// - 释放 PCB 锁之后只修改调度器自身的数据（就绪队列、current），
+    ///   switch_context 在不持有任何 PCB 锁的情况下调用
+    ///
+    /// 上下文指针在解锁后仍然有效：PCB 由进程表和这里的句柄共同持有，不会被释放或移动
+    fn prepare_switch(
         &mut self,
         current_process: ProcessHandle,
         next_process: ProcessHandle,
         next_pid: ProcessId,
-    ) {
-        let mut current = current_process.lock();
-        let mut next = next_process.lock();
+    ) -> ContextSwitch {
+        let (requeue, switch) = {
+            let mut current = current_process.lock();
+            let mut next = next_process.lock();
 
-        // 更新进程状态
-        if current.state() == ProcessState::Running {
-            current.set_state(ProcessState::Ready);
-            // 将当前进程放回就绪队列（时间片轮转）
-            let current_pid = current.pid();
-            drop(current);
-            drop(next);
-            self.enqueue(current_pid);
+            // 被抢占的进程放回就绪队列（时间片轮转）；阻塞或退出的进程不放回
+            let requeue = if current.state() == ProcessState::Running {
+                current.set_state(ProcessState::Ready);
+                Some((current.pid(), current.nice()))
+            } else {
+                None
+            };
 
-            // 重新获取锁
-            current = current_process.lock();
-            next = next_process.lock();
-        }
+            next.set_state(ProcessState::Running);
+            next.reset_time_slice();
+            next.account_switch();
+
+            let switch = ContextSwitch::Switch {
+                from: current.context_mut() as *mut ProcessContext,
+                to: next.context() as *const ProcessContext,
+                saved_frame: current.trap_frame_mut() as *mut TrapFrame,
+                trap_stack_top: next.kernel_stack_top(),
+            };
+            (requeue, switch)
+        };
 
-        next.set_state(ProcessState::Running);
-        next.reset_time_slice();
+        if let Some((pid, nice)) = requeue {
+            self.enqueue_ready(pid, nice);
+        }
 
         // 更新当前进程
-        self.current = Some(next_pid);
-
-        // 获取上下文指针
-        let current_ctx = current.context_mut() as *mut ProcessContext;
-        let next_ctx = next.context() as *const ProcessContext;
+        self.set_current(Some(next_pid));
 
-        // 释放锁（避免死锁）
-        drop(current);
-        drop(next);
-
-        // 执行上下文切换（汇编实现）
-        unsafe {
-            switch_context(current_ctx, next_ctx);
-        }
-
-        // 注意：这里不会返回，直到下次调度回到此进程
+        switch
     }
 
-    /// 启动新进程（首次调度）
-    fn start_process(&mut self, next_process: ProcessHandle, next_pid: ProcessId) {
+    /// 启动新进程（首次调度）的状态转换
+    fn prepare_start(&mut self, next_process: ProcessHandle, next_pid: ProcessId) -> ContextSwitch {
         let mut next = next_process.lock();
 
         next.set_state(ProcessState::Running);
         next.reset_time_slice();
+        next.account_switch();
 
-        self.current = Some(next_pid);
+        self.set_current(Some(next_pid));
 
         scheduler_debug!("[SCHEDULER] Starting first process: PID={}", next_pid);
 
-        // 获取上下文
-        let next_ctx = next.context() as *const ProcessContext;
-
-        drop(next);
+        ContextSwitch::Start {
+            to: next.context() as *const ProcessContext,
+            trap_stack_top: next.kernel_stack_top(),
+        }
+    }
 
-        // 进入新进程（不保存当前上下文）
-        // TODO: 使用 enter_user_mode 或类似机制
-        unsafe {
-            // 临时实现：直接跳转
-            // 完整实现需要使用 sret 进入用户态
-            core::arch::asm!(
-                "mv sp, {0}",
-                "ret",
-                in(reg) (*next_ctx).sp,
-            );
+    /// 执行状态转换时记下的上下文切换（没有时什么也不做）
+    ///
+    /// # 说明
+    /// 由全局接口在调度器方法返回后、释放调度器锁之前调用；
+    /// 切换回来时（下次调度到本进程）才从这里返回
+    pub fn finish_switch(&mut self) {
+        if let Some(switch) = self.pending_switch.take() {
+            // 指针来自进程表中仍然存在的 PCB（见 prepare_switch）
+            unsafe { switch.run() };
         }
     }
 
@@ -335,47 +686,212 @@ impl Scheduler {
     ///
     /// # 说明
     /// 在时钟中断处理函数中调用
-    /// 减少当前进程时间片，时间片用完时触发调度
+    /// 减少当前进程时间片，时间片用完时只设置 need_resched，
+    /// 不在中断处理中途切换上下文，真正的调度推迟到返回用户态前
+    /// （见 resched_if_needed）
     pub fn tick(&mut self) {
+        trace::record(SchedEvent::Tick(self.current));
+
+        self.wake_sleepers();
+
+        // 运行中的进程（包括 idle）记一个 tick 的 CPU 时间
+        if let Some(process) = self.current_process() {
+            process.lock().account_tick();
+        }
+
+        // idle 进程在有就绪进程时立即让出，不等时间片用完
+        if self.is_idle() {
+            if self.ready_queue.len() > 0 {
+                crate::percpu::current().set_need_resched();
+            }
+            return;
+        }
+
         if let Some(current_pid) = self.current {
             if let Some(process) = self.get_process(current_pid) {
-                let mut pcb = process.lock();
-
-                // 减少时间片
-                let should_schedule = pcb.tick();
+                // 减少时间片（禁止抢占期间不会要求调度）
+                let should_schedule = process.lock().tick();
 
                 if should_schedule {
                     scheduler_debug!("[SCHEDULER] Time slice expired for PID={}", current_pid);
-                    drop(pcb);
-
-                    // 触发调度
-                    self.schedule();
+                    crate::percpu::current().set_need_resched();
                 }
             }
         }
     }
 
+    /// 如果有待处理的重新调度请求，执行一次调度
+    ///
+    /// # 返回
+    /// 是否执行了调度
+    ///
+    /// # 说明
+    /// 请求在检查时被清除，同一个请求只会触发一次调度；
+    /// 禁止抢占期间（preempt_disable）请求保留，等恢复抢占后再处理
+    pub fn resched_if_needed(&mut self) -> bool {
+        let hart = crate::percpu::current();
+        if !hart.preemptible() || !hart.take_need_resched() {
+            return false;
+        }
+        self.schedule();
+        true
+    }
+
     // ============================================
     // 进程状态转换
     // ============================================
 
     /// 阻塞当前进程
     ///
+    /// # 返回
+    /// - `true`: 当前进程已阻塞并触发调度
+    /// - `false`: 没有当前进程（内核上下文）或当前是 idle 进程，无法阻塞
+    ///
     /// # 说明
-    /// 将当前进程状态设置为 Blocked，触发调度
-    pub fn block_current(&mut self) {
-        if let Some(current_pid) = self.current {
-            if let Some(process) = self.get_process(current_pid) {
-                let mut pcb = process.lock();
-                pcb.set_state(ProcessState::Blocked);
-                drop(pcb);
+    /// 等于 prepare_block 之后立即 finish_block；
+    /// 需要等待某个条件的调用者应分开调用两步（见 prepare_block）
+    pub fn block_current(&mut self) -> bool {
+        if !self.prepare_block() {
+            return false;
+        }
+        self.finish_block();
+        true
+    }
 
-                scheduler_debug!("[SCHEDULER] Process PID={} blocked", current_pid);
+    /// 阻塞的第一步：把当前进程标记为 Blocked，但不调度
+    ///
+    /// # 返回
+    /// 没有当前进程（内核上下文）或当前是 idle 进程时返回 `false`
+    ///
+    /// # 说明
+    /// 在持有唤醒者也要获取的锁（文件锁、管道缓冲区锁等）时调用，
+    /// 登记为等待者之后、释放锁之前完成标记；此后到来的 wake_up 会把进程改回 Ready，
+    /// finish_block 看到后不再让出 CPU，唤醒不会在"登记"和"阻塞"之间丢失
+    pub fn prepare_block(&mut self) -> bool {
+        if self.is_idle() {
+            return false;
+        }
+        let Some(current_pid) = self.current else {
+            return false;
+        };
+        let Some(process) = self.get_process(current_pid) else {
+            return false;
+        };
+        process.lock().set_state(ProcessState::Blocked);
 
-                // 触发调度
-                self.schedule();
-            }
+        trace::record(SchedEvent::Block(current_pid));
+        scheduler_debug!("[SCHEDULER] Process PID={} blocked", current_pid);
+        true
+    }
+
+    /// 阻塞的第二步：仍是 Blocked 时调度，已经被唤醒时继续运行
+    ///
+    /// # 返回
+    /// 是否让出了 CPU
+    pub fn finish_block(&mut self) -> bool {
+        let Some(process) = self.current_process() else {
+            return false;
+        };
+        if process.lock().state() == ProcessState::Blocked {
+            self.schedule();
+            return true;
+        }
+        self.cancel_block();
+        false
+    }
+
+    /// 放弃 prepare_block 开始的阻塞（等待的条件已经满足）
+    ///
+    /// # 说明
+    /// 进程回到 Running；期间被唤醒放进就绪队列的话从队列中移除
+    pub fn cancel_block(&mut self) {
+        let Some(current_pid) = self.current else {
+            return;
+        };
+        if let Some(process) = self.get_process(current_pid) {
+            process.lock().set_state(ProcessState::Running);
         }
+        self.ready_queue.remove(current_pid);
+    }
+
+    /// 当前进程主动让出 CPU
+    ///
+    /// # 返回
+    /// 是否切换到了其他进程
+    ///
+    /// # 说明
+    /// 当前进程变回 Ready，排到其优先级队列的末尾后立即调度；
+    /// 没有其他就绪进程时继续运行（保持 Running，不进入就绪队列）
+    pub fn yield_current(&mut self) -> bool {
+        let Some(current_pid) = self.current else {
+            return false;
+        };
+        if self.ready_queue.len() == 0 {
+            return false;
+        }
+        let Some(process) = self.get_process(current_pid) else {
+            return false;
+        };
+
+        process.lock().set_state(ProcessState::Ready);
+        self.enqueue(current_pid);
+        scheduler_debug!("[SCHEDULER] Process PID={} yielded", current_pid);
+
+        self.schedule();
+        self.current != Some(current_pid)
+    }
+
+    /// 让进程睡眠到指定的 tick
+    ///
+    /// # 参数
+    /// - `pid`: 要睡眠的进程PID
+    /// - `deadline`: 唤醒时间（开机以来的时钟中断数，与 trap::uptime_ticks 比较）
+    ///
+    /// # 返回
+    /// 进程不存在、是 idle 进程或 deadline 已经过去时返回 `false`
+    ///
+    /// # 说明
+    /// 进程变为 Blocked 并进入睡眠队列，tick() 到达 deadline 时唤醒；
+    /// 睡眠的是当前进程时触发调度
+    pub fn sleep_until(&mut self, pid: ProcessId, deadline: u64) -> bool {
+        if deadline <= crate::trap::uptime_ticks() || self.idle == Some(pid) {
+            return false;
+        }
+        let Some(process) = self.get_process(pid) else {
+            return false;
+        };
+        process.lock().set_state(ProcessState::Blocked);
+        self.ready_queue.remove(pid);
+        self.sleep_queue.entry(deadline).or_default().push(pid);
+
+        trace::record(SchedEvent::Block(pid));
+        scheduler_debug!("[SCHEDULER] Process PID={} sleeps until tick {}", pid, deadline);
+
+        if self.current == Some(pid) {
+            self.schedule();
+        }
+        true
+    }
+
+    /// 唤醒睡眠时间已到的进程
+    ///
+    /// # 说明
+    /// 提前被其他途径唤醒的进程已经由 wake_up 移出睡眠队列
+    fn wake_sleepers(&mut self) {
+        let now = crate::trap::uptime_ticks();
+        let pending = self.sleep_queue.split_off(&(now + 1));
+        let expired = core::mem::replace(&mut self.sleep_queue, pending);
+        for pid in expired.into_values().flatten() {
+            self.wake_up(pid);
+        }
+    }
+
+    /// 把进程移出睡眠队列（不在队列中时什么也不做）
+    fn remove_sleeper(&mut self, pid: ProcessId) {
+        self.sleep_queue.retain(|_, sleepers| {
+            sleepers.retain(|&sleeper| sleeper != pid);
+            !sleepers.is_empty()
+        });
     }
 
     /// 唤醒进程
@@ -384,14 +900,18 @@ impl Scheduler {
     /// - `pid`: 要唤醒的进程PID
     ///
     /// # 说明
-    /// 将进程状态从 Blocked 改为 Ready，加入就绪队列
+    /// 将进程状态从 Blocked 改为 Ready，加入就绪队列；
+    /// 提前唤醒睡眠中的进程时同时移出睡眠队列，
+    /// 过期的唤醒时间不会在之后打断它的其他阻塞
     pub fn wake_up(&mut self, pid: ProcessId) {
+        self.remove_sleeper(pid);
         if let Some(process) = self.get_process(pid) {
             let mut pcb = process.lock();
             if pcb.state() == ProcessState::Blocked {
                 pcb.set_state(ProcessState::Ready);
                 drop(pcb);
 
+                trace::record(SchedEvent::Wake(pid));
                 self.enqueue(pid);
                 scheduler_debug!("[SCHEDULER] Process PID={} woken up", pid);
             }
@@ -411,7 +931,7 @@ impl Scheduler {
         scheduler_debug!("就绪队列: {:?}", self.ready_queue);
         scheduler_debug!("进程总数: {}", self.processes.len());
 
-        for (pid, process) in &self.processes {
+        for (pid, process) in self.processes.read().iter() {
             let pcb = process.lock();
             scheduler_debug!(
                 "  PID={}: {} [{}]",
@@ -428,38 +948,698 @@ impl Scheduler {
 // 全局接口函数
 // ============================================
 
-/// 初始化调度器
+/// 获取调度器锁
+///
+/// # 说明
+/// 时钟中断会调用 tick → schedule 再次获取调度器锁，
+/// 如果持锁期间允许中断，同一个核会在自旋锁上死锁。
+/// SCHEDULER 是 IrqSpinLock，守卫存在期间中断保持关闭，
+/// 守卫释放时恢复加锁前的中断状态。
+pub fn lock_scheduler() -> IrqSpinLockGuard<'static, Scheduler> {
+    SCHEDULER.lock()
+}
+
+/// 在调度器锁内执行 `f`，再执行它选定的上下文切换
+///
+/// # 说明
+/// 切换期间一直持有调度器锁（中断保持关闭），切回本进程、从这里返回时才释放；
+/// 切换到第一次运行的内核线程时由它的入口跳板释放（见 kernel_thread_start）
+fn with_switch<R>(f: impl FnOnce(&mut Scheduler) -> R) -> R {
+    let mut scheduler = lock_scheduler();
+    let result = f(&mut scheduler);
+    scheduler.finish_switch();
+    result
+}
+
+/// 内核线程第一次被调度时的入口（见 ProcessContext::new_kernel_context）
+///
+/// # 参数
+/// - `entry`: 线程入口函数（`fn() -> !`）的地址，由 switch_context 恢复到 a0
+///
+/// # 说明
+/// 切换方在 with_switch 中持有调度器锁、关着中断进入 switch_context。
+/// 切回一个被切走过的进程时，它从自己的 with_switch 返回，drop 守卫释放锁；
+/// 新线程没有这样的调用帧，守卫留在切换方的栈上，因此在这里接手：
+/// 释放锁，像 with_switch 的返回路径一样执行 finish_switch，
+/// 再打开中断进入入口函数。否则新线程会一直持锁、关着中断运行，
+/// 下一次获取调度器锁（阻塞、让出、时钟中断）就会死锁
+pub(super) extern "C" fn kernel_thread_start(entry: usize) -> ! {
+    // 锁由切换方获取，它的守卫不会再被 drop
+    unsafe { SCHEDULER.force_unlock() };
+    lock_scheduler().finish_switch();
+    crate::trap::enable_interrupts();
+
+    let entry: fn() -> ! = unsafe { core::mem::transmute(entry) };
+    entry()
+}
+
+/// 初始化调度器：创建并登记 idle 进程
+///
+/// # Panics
+/// idle 进程的内核栈分配失败时 panic（此时系统无法继续运行）
 pub fn init() {
     scheduler_debug!("[SCHEDULER] Initializing scheduler");
+    let idle = super::create_idle_thread().expect("failed to create idle process");
+    lock_scheduler().set_idle(idle);
 }
 
 /// 添加进程到全局调度器
-pub fn add_process(process: ProcessHandle) {
-    SCHEDULER.lock().add_process(process);
+///
+/// # 返回
+/// 进程数已达上限时返回 `ProcessLimitError`
+pub fn add_process(process: ProcessHandle) -> Result<(), ProcessLimitError> {
+    lock_scheduler().add_process(process)
+}
+
+/// 检查全局调度器是否还能加入新的（非 init）进程
+pub fn check_capacity() -> Result<(), ProcessLimitError> {
+    lock_scheduler().check_capacity()
+}
+
+/// 设置全局调度器的最大进程数
+pub fn set_max_processes(max: usize) {
+    lock_scheduler().set_max_processes(max);
 }
 
 /// 启动调度
 pub fn start_scheduling() {
     scheduler_debug!("[SCHEDULER] Starting scheduling");
-    SCHEDULER.lock().schedule();
+    with_switch(Scheduler::schedule);
+}
+
+/// 触发一次调度
+pub fn schedule() {
+    with_switch(Scheduler::schedule);
+}
+
+/// 当前是否在运行 idle 进程
+pub fn is_idle() -> bool {
+    lock_scheduler().is_idle()
 }
 
 /// 时钟中断回调
 pub fn tick() {
-    SCHEDULER.lock().tick();
+    lock_scheduler().tick();
+}
+
+/// 处理延迟的重新调度请求
+///
+/// # 说明
+/// 由陷阱处理在返回用户态前调用；
+/// 没有请求时只读一个原子标志，不获取调度器锁
+pub fn resched_if_needed() -> bool {
+    let hart = crate::percpu::current();
+    if !hart.preemptible() || !hart.need_resched() {
+        return false;
+    }
+    with_switch(Scheduler::resched_if_needed)
+}
+
+/// 在长时间运行的内核循环中主动让出 CPU
+///
+/// # 返回
+/// 是否执行了调度
+///
+/// # 说明
+/// 时间片用完（need_resched）时在安全点调度一次，否则只读一个原子标志。
+/// 调用处不能持有自旋锁（文件锁、inode 锁等），否则切走的进程会让其他进程在锁上死等；
+/// 没有当前进程时（启动阶段）什么也不做，避免调度器直接跳进新进程不再返回
+pub fn maybe_yield() -> bool {
+    if cached_current_pid().is_none() {
+        return false;
+    }
+    resched_if_needed()
+}
+
+/// 禁止内核抢占（可嵌套）
+///
+/// # 说明
+/// 用于持锁、访问 per-hart 数据等不能被切走的临界区；
+/// 期间 need_resched 只记录不处理。
+/// 与 sys_sched_disable_preempt（用户进程的 PCB 标志）相互独立
+pub fn preempt_disable() {
+    crate::percpu::current().preempt_disable();
+}
+
+/// 恢复内核抢占
+///
+/// # 说明
+/// 最外层的 preempt_enable 会处理期间推迟的 need_resched
+pub fn preempt_enable() {
+    if crate::percpu::current().preempt_enable() {
+        resched_if_needed();
+    }
+}
+
+/// 阻塞当前进程
+///
+/// # 返回
+/// 没有当前进程可阻塞时返回 `false`
+pub fn block_current() -> bool {
+    with_switch(Scheduler::block_current)
+}
+
+/// 阻塞的第一步：把当前进程标记为 Blocked（见 Scheduler::prepare_block）
+pub fn prepare_block() -> bool {
+    lock_scheduler().prepare_block()
+}
+
+/// 阻塞的第二步：仍是 Blocked 时让出 CPU，已经被唤醒时继续运行
+pub fn finish_block() -> bool {
+    with_switch(Scheduler::finish_block)
+}
+
+/// 放弃 prepare_block 开始的阻塞
+pub fn cancel_block() {
+    lock_scheduler().cancel_block();
+}
+
+/// 唤醒进程
+pub fn wake_up(pid: ProcessId) {
+    lock_scheduler().wake_up(pid);
+}
+
+/// 当前进程主动让出 CPU
+///
+/// # 返回
+/// 是否切换到了其他进程
+pub fn yield_current() -> bool {
+    with_switch(Scheduler::yield_current)
+}
+
+/// 让当前进程睡眠 ticks 个 tick
+///
+/// # 返回
+/// 没有当前进程（或当前是 idle 进程）时返回 `false`
+pub fn sleep_current(ticks: u64) -> bool {
+    with_switch(|scheduler| {
+        let Some(pid) = scheduler.current_pid() else {
+            return false;
+        };
+        let deadline = crate::trap::uptime_ticks() + ticks;
+        scheduler.sleep_until(pid, deadline)
+    })
+}
+
+/// 修改进程的 nice 值（进程在就绪队列中时按新优先级重新入队）
+pub fn set_nice(pid: ProcessId, nice: i32) -> Option<i32> {
+    lock_scheduler().set_nice(pid, nice)
+}
+
+/// 按PID查找进程
+pub fn get_process(pid: ProcessId) -> Option<ProcessHandle> {
+    lock_scheduler().get_process(pid)
 }
 
 /// 获取当前进程PID
 pub fn current_pid() -> Option<ProcessId> {
-    SCHEDULER.lock().current_pid()
+    lock_scheduler().current_pid()
+}
+
+/// 无锁读取当前进程PID
+///
+/// # 说明
+/// 读取上下文切换时更新的本 hart 控制块（通过 tp），不获取调度器锁、不关中断，
+/// 适用于 sys_getpid 等热点只读路径
+pub fn cached_current_pid() -> Option<ProcessId> {
+    crate::percpu::current().current_pid()
 }
 
 /// 获取当前进程句柄
 pub fn current_process() -> Option<ProcessHandle> {
-    SCHEDULER.lock().current_process()
+    lock_scheduler().current_process()
 }
 
 /// 打印调度器状态
 pub fn print_status() {
-    SCHEDULER.lock().print_status();
+    lock_scheduler().print_status();
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::create_process;
+
+    #[test_case]
+    fn test_add_process_respects_limit() {
+        const LIMIT: usize = 4;
+
+        let mut scheduler = Scheduler::new();
+        scheduler.set_max_processes(LIMIT);
+
+        for _ in 0..LIMIT {
+            let process = create_process("limited", 0x1000, 0x2000, None).unwrap();
+            assert!(scheduler.add_process(process).is_ok());
+        }
+
+        // 达到上限后再创建应失败，且进程不会进入进程表
+        let extra = create_process("extra", 0x1000, 0x2000, None).unwrap();
+        let extra_pid = extra.lock().pid();
+        assert_eq!(
+            scheduler.add_process(extra),
+            Err(ProcessLimitError { limit: LIMIT })
+        );
+        assert!(scheduler.get_process(extra_pid).is_none());
+        assert_eq!(scheduler.process_table().len(), LIMIT);
+    }
+
+    #[test_case]
+    fn test_trace_records_events_in_order() {
+        let mut scheduler = Scheduler::new();
+        let process = create_process("traced", 0x1000, 0x2000, None).unwrap();
+        let pid = process.lock().pid();
+        scheduler.add_process(process).unwrap();
+
+        // 模拟该进程正在运行（就绪队列为空，阻塞后不会真正切换）
+        scheduler.ready_queue.clear();
+        scheduler.current = Some(pid);
+
+        trace::clear();
+        trace::set_enabled(true);
+
+        assert!(scheduler.block_current());
+        scheduler.wake_up(pid);
+        scheduler.tick();
+
+        trace::set_enabled(false);
+
+        let events: alloc::vec::Vec<SchedEvent> =
+            trace::snapshot().iter().map(|record| record.event).collect();
+        assert_eq!(
+            events,
+            [SchedEvent::Block(pid), SchedEvent::Wake(pid), SchedEvent::Tick(Some(pid))]
+        );
+
+        // 关闭后不再记录
+        scheduler.tick();
+        assert_eq!(trace::snapshot().len(), 3);
+    }
+
+    #[test_case]
+    fn test_cached_pid_follows_current_process() {
+        let mut scheduler = Scheduler::new();
+        let first = create_process("cached_a", 0x1000, 0x2000, None).unwrap();
+        let second = create_process("cached_b", 0x1000, 0x2000, None).unwrap();
+        let first_pid = first.lock().pid();
+        let second_pid = second.lock().pid();
+        scheduler.add_process(first).unwrap();
+        scheduler.add_process(second).unwrap();
+
+        // 模拟 prepare_switch/prepare_start 中的当前进程切换
+        for pid in [first_pid, second_pid, first_pid] {
+            scheduler.set_current(Some(pid));
+            assert_eq!(cached_current_pid(), scheduler.current_pid());
+        }
+
+        // 当前进程被移除后缓存随之清空
+        scheduler.remove_process(first_pid);
+        assert_eq!(scheduler.current_pid(), None);
+        assert_eq!(cached_current_pid(), None);
+    }
+
+    #[test_case]
+    fn test_niced_process_scheduled_after_peer() {
+        let mut scheduler = Scheduler::new();
+        let niced = create_process("niced", 0x1000, 0x2000, None).unwrap();
+        let peer = create_process("peer", 0x1000, 0x2000, None).unwrap();
+        let niced_pid = niced.lock().pid();
+        let peer_pid = peer.lock().pid();
+
+        // niced 先入队，但降低优先级后应排在 peer 之后
+        niced.lock().set_nice(5);
+        scheduler.add_process(niced).unwrap();
+        scheduler.add_process(peer).unwrap();
+
+        assert_eq!(scheduler.pick_next(), Some(peer_pid));
+        assert_eq!(scheduler.pick_next(), Some(niced_pid));
+        assert_eq!(scheduler.pick_next(), None);
+    }
+
+    #[test_case]
+    fn test_picks_processes_in_priority_order() {
+        use crate::process::pcb::priority_to_nice;
+
+        let mut scheduler = Scheduler::new();
+        let spawn = |name, priority| {
+            let process = create_process(name, 0x1000, 0x2000, None).unwrap();
+            process.lock().set_priority(priority);
+            process
+        };
+        let low = spawn("prio_low", 5);
+        let mid = spawn("prio_mid", 20);
+        let high = spawn("prio_high", 30);
+        let peer = spawn("prio_peer", 20);
+        let pids = [&low, &mid, &high, &peer].map(|p| p.lock().pid());
+
+        // 按优先级从低到高入队
+        for process in [low, mid, high, peer] {
+            scheduler.add_process(process).unwrap();
+        }
+
+        // 高优先级先出队，同优先级（mid、peer）按入队顺序轮转
+        assert_eq!(scheduler.pick_next(), Some(pids[2]));
+        assert_eq!(scheduler.pick_next(), Some(pids[1]));
+        assert_eq!(scheduler.pick_next(), Some(pids[3]));
+        assert_eq!(scheduler.pick_next(), Some(pids[0]));
+        assert_eq!(scheduler.pick_next(), None);
+
+        // 在队列中修改优先级后按新优先级出队
+        scheduler.enqueue(pids[0]);
+        scheduler.enqueue(pids[1]);
+        assert_eq!(scheduler.set_nice(pids[0], priority_to_nice(39)), Some(priority_to_nice(5)));
+        assert_eq!(scheduler.pick_next(), Some(pids[0]));
+        assert_eq!(scheduler.pick_next(), Some(pids[1]));
+    }
+
+    #[test_case]
+    fn test_blocked_high_priority_does_not_starve_others() {
+        let mut scheduler = Scheduler::new();
+        let spawn = |name, priority| {
+            let process = create_process(name, 0x1000, 0x2000, None).unwrap();
+            process.lock().set_priority(priority);
+            process
+        };
+        let low = spawn("starve_low", 1);
+        let high = spawn("starve_high", 5);
+        let mid = spawn("starve_mid", 3);
+        let [low_pid, high_pid, mid_pid] = [&low, &high, &mid].map(|p| p.lock().pid());
+        for process in [low, high, mid] {
+            scheduler.add_process(process).unwrap();
+        }
+
+        // 优先级 5 的进程先运行
+        assert_eq!(scheduler.pick_next(), Some(high_pid));
+        scheduler.run_for_test(high_pid);
+
+        // 它阻塞后不在就绪队列中，低优先级的进程依次得到运行
+        assert!(scheduler.block_current());
+        assert_eq!(scheduler.current_pid(), Some(mid_pid));
+        assert_eq!(scheduler.pick_next(), Some(low_pid));
+        assert_eq!(scheduler.pick_next(), Some(mid_pid));
+
+        // 唤醒后重新排到最前面
+        scheduler.enqueue(low_pid);
+        scheduler.wake_up(high_pid);
+        assert_eq!(scheduler.pick_next(), Some(high_pid));
+        assert_eq!(scheduler.pick_next(), Some(low_pid));
+
+        scheduler.set_current(None);
+    }
+
+    #[test_case]
+    fn test_idle_runs_when_all_processes_block() {
+        let mut scheduler = Scheduler::new();
+        scheduler.set_idle(crate::process::create_idle_thread().unwrap());
+        assert_eq!(scheduler.idle_pid(), Some(ProcessId::IDLE));
+
+        let spawn = |name| create_process(name, 0x1000, 0x2000, None).unwrap();
+        let (a, b) = (spawn("idle_a"), spawn("idle_b"));
+        let [a_pid, b_pid] = [&a, &b].map(|p| p.lock().pid());
+        scheduler.add_process(a.clone()).unwrap();
+        scheduler.add_process(b).unwrap();
+
+        // 两个进程依次阻塞后切到 idle 进程
+        scheduler.run_for_test(a_pid);
+        assert!(scheduler.block_current());
+        assert_eq!(scheduler.current_pid(), Some(b_pid));
+        assert!(scheduler.block_current());
+        assert_eq!(scheduler.current_pid(), Some(ProcessId::IDLE));
+        assert!(scheduler.is_idle());
+        assert_eq!(scheduler.nr_running(), 0);
+        assert!(!scheduler.block_current());
+
+        // 有进程被唤醒时 idle 在下一个 tick 让出，idle 本身不进入就绪队列
+        let hart = crate::percpu::current();
+        hart.take_need_resched();
+        scheduler.wake_up(a_pid);
+        scheduler.tick();
+        assert!(scheduler.resched_if_needed());
+        assert_eq!(scheduler.current_pid(), Some(a_pid));
+        assert!(!scheduler.is_idle());
+
+        // 唯一可运行的进程用完时间片后继续运行，不会被换成 idle
+        for _ in 0..5 {
+            scheduler.tick();
+        }
+        assert!(scheduler.resched_if_needed());
+        assert_eq!(scheduler.current_pid(), Some(a_pid));
+        assert_eq!(a.lock().state(), ProcessState::Running);
+
+        scheduler.set_current(None);
+    }
+
+    #[test_case]
+    fn test_schedule_prepares_switch_between_pcbs() {
+        let mut scheduler = Scheduler::new();
+        let spawn = |name| create_process(name, 0x1000, 0x2000, None).unwrap();
+        let (a, b) = (spawn("switch_a"), spawn("switch_b"));
+        let [a_pid, b_pid] = [&a, &b].map(|p| p.lock().pid());
+        scheduler.add_process(a.clone()).unwrap();
+        scheduler.add_process(b.clone()).unwrap();
+        scheduler.run_for_test(a_pid);
+
+        // 状态转换在 schedule 中完成，寄存器的保存/恢复留给 finish_switch
+        scheduler.schedule();
+        assert_eq!(scheduler.current_pid(), Some(b_pid));
+        assert_eq!(a.lock().state(), ProcessState::Ready);
+        assert_eq!(b.lock().state(), ProcessState::Running);
+        assert_eq!(b.lock().switch_count(), 1);
+        assert_eq!(scheduler.ready_queue.len(), 1);
+
+        let Some(ContextSwitch::Switch { from, to, saved_frame, trap_stack_top }) =
+            scheduler.pending_switch.take()
+        else {
+            panic!("expected a pending switch from a to b");
+        };
+        assert_eq!(from as *const ProcessContext, a.lock().context() as *const ProcessContext);
+        assert_eq!(to, b.lock().context() as *const ProcessContext);
+        assert_eq!(saved_frame as *const TrapFrame, a.lock().trap_frame() as *const TrapFrame);
+        assert_eq!(trap_stack_top, b.lock().kernel_stack_top());
+
+        // 切换到自己时不留下待执行的切换
+        scheduler.remove_process(a_pid);
+        scheduler.schedule();
+        assert_eq!(scheduler.current_pid(), Some(b_pid));
+        assert!(scheduler.pending_switch.is_none());
+
+        scheduler.set_current(None);
+    }
+
+    #[test_case]
+    fn test_yielding_processes_alternate() {
+        let mut scheduler = Scheduler::new();
+        let spawn = |name| create_process(name, 0x1000, 0x2000, None).unwrap();
+        let (a, b) = (spawn("yield_a"), spawn("yield_b"));
+        let [a_pid, b_pid] = [&a, &b].map(|p| p.lock().pid());
+        scheduler.add_process(a.clone()).unwrap();
+        scheduler.add_process(b.clone()).unwrap();
+        scheduler.run_for_test(a_pid);
+
+        // 每次让出都切换到另一个进程，让出者回到就绪队列
+        for (from, to) in [(&a, b_pid), (&b, a_pid), (&a, b_pid)] {
+            assert!(scheduler.yield_current());
+            assert_eq!(scheduler.current_pid(), Some(to));
+            assert_eq!(from.lock().state(), ProcessState::Ready);
+            assert_eq!(scheduler.ready_queue.len(), 1);
+        }
+
+        // 只剩一个进程时让出后继续运行，不会丢失
+        scheduler.remove_process(a_pid);
+        assert!(!scheduler.yield_current());
+        assert_eq!(scheduler.current_pid(), Some(b_pid));
+        assert_eq!(b.lock().state(), ProcessState::Running);
+        assert_eq!(scheduler.ready_queue.len(), 0);
+
+        scheduler.set_current(None);
+    }
+
+    #[test_case]
+    fn test_three_process_rotation() {
+        let mut scheduler = Scheduler::new();
+        let processes: Vec<_> = ["rot_a", "rot_b", "rot_c"]
+            .into_iter()
+            .map(|name| create_process(name, 0x1000, 0x2000, None).unwrap())
+            .collect();
+        let pids: Vec<_> = processes.iter().map(|p| p.lock().pid()).collect();
+        for process in &processes {
+            scheduler.add_process(process.clone()).unwrap();
+        }
+        scheduler.run_for_test(pids[0]);
+
+        // 时间片轮转：被抢占的进程（仍是 Running）由 prepare_switch 放回队尾
+        for round in 1..=6 {
+            scheduler.schedule();
+            let running = round % 3;
+            assert_eq!(scheduler.current_pid(), Some(pids[running]));
+            for (i, process) in processes.iter().enumerate() {
+                let expected = if i == running { ProcessState::Running } else { ProcessState::Ready };
+                assert_eq!(process.lock().state(), expected);
+            }
+            assert_eq!(scheduler.ready_queue.len(), 2);
+        }
+        for process in &processes {
+            assert_eq!(process.lock().switch_count(), 2);
+        }
+
+        // 阻塞的进程不放回就绪队列
+        processes[0].lock().set_state(ProcessState::Blocked);
+        scheduler.schedule();
+        assert_eq!(scheduler.current_pid(), Some(pids[1]));
+        assert_eq!(scheduler.ready_queue.len(), 1);
+        assert_eq!(processes[0].lock().state(), ProcessState::Blocked);
+
+        scheduler.set_current(None);
+    }
+
+    #[test_case]
+    fn test_cpu_ticks_and_switches_are_accounted() {
+        let mut scheduler = Scheduler::new();
+        let spawn = |name| create_process(name, 0x1000, 0x2000, None).unwrap();
+        let (a, b) = (spawn("cpu_a"), spawn("cpu_b"));
+        let [a_pid, b_pid] = [&a, &b].map(|p| p.lock().pid());
+        scheduler.add_process(a.clone()).unwrap();
+        scheduler.add_process(b.clone()).unwrap();
+        scheduler.run_for_test(a_pid);
+
+        // 只有运行中的进程记 CPU 时间
+        for _ in 0..3 {
+            scheduler.tick();
+        }
+        assert_eq!(a.lock().cpu_ticks(), 3);
+        assert_eq!(b.lock().cpu_ticks(), 0);
+
+        // 每次切换上 CPU 记一次
+        let switches = context_switches();
+        assert!(scheduler.yield_current());
+        assert_eq!(scheduler.current_pid(), Some(b_pid));
+        scheduler.tick();
+        assert!(scheduler.yield_current());
+        assert_eq!(b.lock().switch_count(), 1);
+        assert_eq!(b.lock().cpu_ticks(), 1);
+        assert_eq!(a.lock().cpu_ticks(), 3);
+        assert_eq!(context_switches(), switches + 2);
+
+        scheduler.set_current(None);
+        crate::percpu::current().take_need_resched();
+    }
+
+    #[test_case]
+    fn test_sleeping_process_wakes_after_deadline() {
+        use crate::trap::{advance_ticks, uptime_ticks, without_interrupts};
+
+        let mut scheduler = Scheduler::new();
+        let process = create_process("sleeper", 0x1000, 0x2000, None).unwrap();
+        let pid = process.lock().pid();
+        scheduler.add_process(process.clone()).unwrap();
+        scheduler.run_for_test(pid);
+
+        // 关中断，只有模拟的时钟中断推进开机 tick 数
+        without_interrupts(|| {
+            let mut tick = |scheduler: &mut Scheduler| {
+                advance_ticks(1);
+                scheduler.tick();
+            };
+
+            // 睡眠 3 个 tick：立即阻塞，不在就绪队列中
+            let deadline = uptime_ticks() + 3;
+            assert!(scheduler.sleep_until(pid, deadline));
+            assert!(!scheduler.sleep_until(pid, uptime_ticks()));
+            assert_eq!(process.lock().state(), ProcessState::Blocked);
+            assert_eq!(scheduler.ready_queue.len(), 0);
+
+            for _ in 0..2 {
+                tick(&mut scheduler);
+                assert_eq!(process.lock().state(), ProcessState::Blocked);
+            }
+
+            // 第 3 个 tick 到期，回到就绪队列
+            tick(&mut scheduler);
+            assert_eq!(uptime_ticks(), deadline);
+            assert_eq!(process.lock().state(), ProcessState::Ready);
+            assert_eq!(scheduler.pick_next(), Some(pid));
+            assert!(scheduler.sleep_queue.is_empty());
+
+            // 提前唤醒时移出睡眠队列，之后的阻塞不会被过期的唤醒时间打断
+            scheduler.run_for_test(pid);
+            assert!(scheduler.sleep_until(pid, uptime_ticks() + 2));
+            scheduler.wake_up(pid);
+            assert!(scheduler.sleep_queue.is_empty());
+            scheduler.run_for_test(pid);
+            assert!(scheduler.block_current());
+            for _ in 0..3 {
+                tick(&mut scheduler);
+            }
+            assert_eq!(process.lock().state(), ProcessState::Blocked);
+        });
+
+        scheduler.set_current(None);
+    }
+
+    #[test_case]
+    fn test_tick_defers_schedule_to_resched_point() {
+        let mut scheduler = Scheduler::new();
+        let process = create_process("resched", 0x1000, 0x2000, None).unwrap();
+        let pid = process.lock().pid();
+        scheduler.add_process(process.clone()).unwrap();
+
+        // 模拟该进程正在运行（就绪队列为空，调度时不会真正切换）
+        scheduler.ready_queue.clear();
+        scheduler.current = Some(pid);
+        process.lock().set_state(ProcessState::Running);
+        process.lock().reset_time_slice();
+        crate::percpu::current().take_need_resched();
+
+        // 时间片用完前不请求调度
+        for _ in 0..4 {
+            scheduler.tick();
+            assert!(!crate::percpu::current().need_resched());
+        }
+
+        // 时间片用完：只设置标志，tick 内不切换
+        trace::clear();
+        trace::set_enabled(true);
+        scheduler.tick();
+        trace::set_enabled(false);
+        assert!(crate::percpu::current().need_resched());
+        assert_eq!(scheduler.current_pid(), Some(pid));
+        assert_eq!(trace::snapshot().len(), 1);
+
+        // 延迟检查恰好触发一次调度
+        assert!(scheduler.resched_if_needed());
+        assert!(!scheduler.resched_if_needed());
+        assert!(!crate::percpu::current().need_resched());
+    }
+
+    #[test_case]
+    fn test_preempt_disable_defers_resched() {
+        let mut scheduler = Scheduler::new();
+        let process = create_process("preempt", 0x1000, 0x2000, None).unwrap();
+        let pid = process.lock().pid();
+        scheduler.add_process(process).unwrap();
+        scheduler.ready_queue.clear();
+        scheduler.current = Some(pid);
+
+        let hart = crate::percpu::current();
+        hart.take_need_resched();
+
+        // 禁止抢占（嵌套两层）期间请求被保留，不发生调度
+        hart.preempt_disable();
+        hart.preempt_disable();
+        hart.set_need_resched();
+        assert!(!scheduler.resched_if_needed());
+        assert!(!hart.preempt_enable());
+        assert!(!scheduler.resched_if_needed());
+        assert!(hart.need_resched());
+        assert_eq!(scheduler.current_pid(), Some(pid));
+
+        // 最外层恢复后处理推迟的请求，且只处理一次
+        assert!(hart.preempt_enable());
+        assert!(scheduler.resched_if_needed());
+        assert!(!scheduler.resched_if_needed());
+        assert!(hart.preemptible());
+    }
 }
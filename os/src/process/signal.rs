@@ -0,0 +1,393 @@
+/*
+ * ============================================
+ * 信号
+ * ============================================
+ * 功能：进程间的异步通知
+ *
+ * - 发送：在目标进程的 pending 位图中置位，目标阻塞时唤醒它
+ *   （如父进程阻塞在 waitpid 中时收到 SIGCHLD）
+ * - 递送：陷阱（系统调用、时钟中断等）返回用户态前检查 pending，
+ *   对安装了处理函数的信号保存现场，sret 到处理函数（a0 = 信号编号，
+ *   ra = 跳板 SIGRETURN_TRAMPOLINE）
+ * - 返回：处理函数 ret 到跳板，跳板执行 sys_sigreturn，
+ *   恢复保存的现场（包括被打断的系统调用的返回值 a0）
+ * - 默认动作：SIGCHLD 忽略，其余信号终止进程
+ *   （SIGKILL 由 kill_process 直接终止进程，不经过递送）
+ *
+ * 说明：处理函数执行期间不递送其他处理函数（不支持嵌套），
+ * 这些信号留到 sigreturn 之后
+ * ============================================
+ */
+
+use super::exec::USER_STACK_TOP;
+use super::{scheduler, ProcessHandle, ProcessId};
+use crate::memory::{AddressSpace, PageTableFlags, SimpleFrameAllocator, VirtAddr};
+use crate::trap::TrapFrame;
+
+/// 信号个数（编号 1..NSIG）
+pub const NSIG: usize = 64;
+
+/// 访问文件映射中越过文件末尾的部分
+pub const SIGBUS: i32 = 7;
+/// 强制结束进程，不能被捕获
+pub const SIGKILL: i32 = 9;
+/// 非法内存访问
+pub const SIGSEGV: i32 = 11;
+/// 请求进程结束（关机时先发送，宽限期后再发送 SIGKILL）
+pub const SIGTERM: i32 = 15;
+/// 子进程退出
+pub const SIGCHLD: i32 = 17;
+
+/// 处理函数：默认动作
+pub const SIG_DFL: usize = 0;
+/// 处理函数：忽略
+pub const SIG_IGN: usize = 1;
+
+/// 信号跳板的用户地址（用户栈顶之上的一页，exec 时映射）
+pub const SIGRETURN_TRAMPOLINE: usize = USER_STACK_TOP;
+
+/// 跳板代码：li a7, 139 (SYS_SIGRETURN); ecall
+const TRAMPOLINE_CODE: [u32; 2] = [0x08b0_0893, 0x0000_0073];
+
+/// 信号的默认动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefaultAction {
+    /// 清除信号，不做其他事
+    Ignore,
+    /// 终止进程
+    Terminate,
+}
+
+/// 信号的默认动作（SIGCHLD 忽略，其余终止进程，与 Linux 相同）
+pub fn default_action(signal: i32) -> DefaultAction {
+    match signal {
+        SIGCHLD => DefaultAction::Ignore,
+        _ => DefaultAction::Terminate,
+    }
+}
+
+/// 一次递送的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    /// 现场已改为执行该信号的处理函数
+    Handler(i32),
+    /// 该信号的默认动作是终止进程
+    Terminate(i32),
+}
+
+/// 信号对应的 pending 位；编号不合法时返回 None
+fn signal_bit(signal: i32) -> Option<u64> {
+    if signal >= 1 && signal as usize <= NSIG {
+        Some(1 << (signal - 1))
+    } else {
+        None
+    }
+}
+
+/// 进程的信号状态（存放在 PCB 中）
+#[derive(Debug, Clone)]
+pub struct SignalState {
+    /// 待处理信号位图（第 n-1 位对应信号 n）
+    pending: u64,
+    /// 各信号的处理函数（用户地址，或 SIG_DFL / SIG_IGN）
+    handlers: [usize; NSIG],
+    /// 转去执行处理函数前被打断的用户现场
+    saved_frame: Option<TrapFrame>,
+}
+
+impl SignalState {
+    pub const fn new() -> Self {
+        SignalState {
+            pending: 0,
+            handlers: [SIG_DFL; NSIG],
+            saved_frame: None,
+        }
+    }
+
+    /// 待处理信号位图
+    pub fn pending(&self) -> u64 {
+        self.pending
+    }
+
+    /// 信号是否待处理
+    pub fn is_pending(&self, signal: i32) -> bool {
+        signal_bit(signal).is_some_and(|bit| self.pending & bit != 0)
+    }
+
+    /// 置位待处理信号
+    ///
+    /// # 返回
+    /// 信号编号不合法时返回 false
+    pub fn raise(&mut self, signal: i32) -> bool {
+        match signal_bit(signal) {
+            Some(bit) => {
+                self.pending |= bit;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 信号的处理函数
+    pub fn handler(&self, signal: i32) -> usize {
+        signal_bit(signal).map_or(SIG_DFL, |_| self.handlers[signal as usize - 1])
+    }
+
+    /// 安装处理函数
+    ///
+    /// # 返回
+    /// 之前的处理函数；编号不合法或试图捕获 SIGKILL 时返回 None
+    pub fn set_handler(&mut self, signal: i32, handler: usize) -> Option<usize> {
+        signal_bit(signal)?;
+        if signal == SIGKILL {
+            return None;
+        }
+        Some(core::mem::replace(&mut self.handlers[signal as usize - 1], handler))
+    }
+
+    /// 取出编号最小的待处理信号
+    pub fn take_pending(&mut self) -> Option<i32> {
+        if self.pending == 0 {
+            return None;
+        }
+        let signal = self.pending.trailing_zeros() as i32 + 1;
+        self.pending &= !(1 << (signal - 1));
+        Some(signal)
+    }
+
+    /// 转去执行处理函数前保存的现场
+    pub fn saved_frame(&self) -> Option<&TrapFrame> {
+        self.saved_frame.as_ref()
+    }
+
+    /// 取出保存的现场（sigreturn 时调用）
+    pub fn take_saved_frame(&mut self) -> Option<TrapFrame> {
+        self.saved_frame.take()
+    }
+}
+
+impl Default for SignalState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 向进程发送信号
+///
+/// # 返回
+/// 进程不存在或信号编号不合法时返回 false
+///
+/// # 说明
+/// 进程阻塞时唤醒它：被打断的等待（如 waitpid）醒来后重新检查条件
+pub fn send_signal(pid: ProcessId, signal: i32) -> bool {
+    let Some(process) = scheduler::get_process(pid) else {
+        return false;
+    };
+    if !process.lock().signals_mut().raise(signal) {
+        return false;
+    }
+    scheduler::wake_up(pid);
+    true
+}
+
+/// 在用户地址空间中映射信号跳板
+///
+/// # 说明
+/// 跳板页只读可执行，处理函数返回（ret）时跳到这里执行 sys_sigreturn
+pub fn map_trampoline(
+    space: &mut AddressSpace,
+    allocator: &mut SimpleFrameAllocator,
+) -> Result<(), &'static str> {
+    let flags = PageTableFlags::Read as usize
+        | PageTableFlags::Execute as usize
+        | PageTableFlags::User as usize;
+    let paddr = space.map_zeroed_page(VirtAddr::new(SIGRETURN_TRAMPOLINE), flags, allocator)?;
+    let code = paddr.as_usize() as *mut u32;
+    for (i, &insn) in TRAMPOLINE_CODE.iter().enumerate() {
+        unsafe { code.add(i).write_volatile(insn) };
+    }
+    Ok(())
+}
+
+/// 在返回用户态前递送待处理信号
+///
+/// # 参数
+/// - `signals`: 当前进程的信号状态
+/// - `frame`: 即将恢复的用户现场
+///
+/// # 返回
+/// 转去执行的处理函数，或需要终止进程的信号；都没有时返回 None
+///
+/// # 说明
+/// SIG_IGN 和默认动作为忽略的信号直接清除；找到安装了处理函数的信号时
+/// 保存整个现场，让 sret 跳到处理函数、处理函数返回到跳板，
+/// 剩余信号留到下次递送。上一个处理函数还没有返回时，
+/// 有处理函数的信号保持 pending，默认终止的信号照常生效
+pub fn deliver(signals: &mut SignalState, frame: &mut TrapFrame) -> Option<Delivery> {
+    let mut deferred = 0;
+    let mut delivery = None;
+
+    while let Some(signal) = signals.take_pending() {
+        match signals.handler(signal) {
+            SIG_IGN => {}
+            SIG_DFL => {
+                if default_action(signal) == DefaultAction::Terminate {
+                    delivery = Some(Delivery::Terminate(signal));
+                    break;
+                }
+            }
+            _ if signals.saved_frame.is_some() => deferred |= 1 << (signal - 1),
+            handler => {
+                signals.saved_frame = Some(*frame);
+                frame.sepc = handler;
+                frame.regs[1] = SIGRETURN_TRAMPOLINE; // ra
+                frame.regs[10] = signal as usize; // a0
+                delivery = Some(Delivery::Handler(signal));
+                break;
+            }
+        }
+    }
+
+    signals.pending |= deferred;
+    delivery
+}
+
+/// 从处理函数返回：恢复保存的现场
+///
+/// # 返回
+/// 不在处理函数中（没有保存的现场）时返回 false，现场不变
+pub fn sigreturn(signals: &mut SignalState, frame: &mut TrapFrame) -> bool {
+    match signals.take_saved_frame() {
+        Some(saved) => {
+            *frame = saved;
+            true
+        }
+        None => false,
+    }
+}
+
+/// 向进程递送待处理信号，默认动作为终止时用该信号终止进程
+///
+/// # 说明
+/// 只改变进程状态，不触发调度
+pub fn deliver_to(process: &ProcessHandle, frame: &mut TrapFrame) -> Option<Delivery> {
+    let delivery = deliver(process.lock().signals_mut(), frame);
+    if let Some(Delivery::Terminate(signal)) = delivery {
+        super::kill_process(process, signal);
+    }
+    delivery
+}
+
+/// 向当前进程递送待处理信号（陷阱返回用户态前调用）
+///
+/// # 说明
+/// 当前进程被终止时触发调度，不再返回用户态
+pub fn deliver_current(frame: &mut TrapFrame) -> Option<Delivery> {
+    let process = scheduler::current_process()?;
+    let delivery = deliver_to(&process, frame);
+    if let Some(Delivery::Terminate(_)) = delivery {
+        scheduler::schedule();
+    }
+    delivery
+}
+
+/// 当前进程的 sigreturn（见 sigreturn）
+pub fn sigreturn_current(frame: &mut TrapFrame) -> bool {
+    scheduler::current_process().is_some_and(|process| sigreturn(process.lock().signals_mut(), frame))
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_deliver_runs_installed_handler() {
+        let mut signals = SignalState::new();
+        assert_eq!(signals.set_handler(SIGCHLD, 0x1_2340), Some(SIG_DFL));
+        assert_eq!(signals.set_handler(SIGKILL, 0x1_2340), None);
+        assert_eq!(signals.set_handler(1, SIG_IGN), Some(SIG_DFL));
+
+        // 忽略的信号被清除，不改变现场
+        let mut frame = TrapFrame::new();
+        frame.sepc = 0x1_0000;
+        frame.regs[1] = 0x1_0100;
+        frame.set_return_value(42);
+        signals.raise(1);
+        assert_eq!(deliver(&mut signals, &mut frame), None);
+        assert_eq!(signals.pending(), 0);
+        assert_eq!(frame.sepc, 0x1_0000);
+
+        signals.raise(SIGCHLD);
+        assert!(signals.is_pending(SIGCHLD));
+        assert_eq!(deliver(&mut signals, &mut frame), Some(Delivery::Handler(SIGCHLD)));
+        assert_eq!(frame.sepc, 0x1_2340);
+        assert_eq!(frame.arg(0), SIGCHLD as usize);
+        assert_eq!(frame.regs[1], SIGRETURN_TRAMPOLINE);
+        assert_eq!(signals.saved_frame().unwrap().sepc, 0x1_0000);
+        assert!(!signals.is_pending(SIGCHLD));
+
+        // 处理函数执行期间再来的信号留到 sigreturn 之后
+        signals.raise(SIGCHLD);
+        assert_eq!(deliver(&mut signals, &mut frame), None);
+        assert!(signals.is_pending(SIGCHLD));
+
+        // sigreturn 恢复被打断时的现场，包括系统调用的返回值
+        assert!(sigreturn(&mut signals, &mut frame));
+        assert!(signals.saved_frame().is_none());
+        assert_eq!(frame.sepc, 0x1_0000);
+        assert_eq!(frame.regs[1], 0x1_0100);
+        assert_eq!(frame.arg(0), 42);
+        assert!(!sigreturn(&mut signals, &mut frame));
+
+        // 之后可以再次递送
+        assert_eq!(deliver(&mut signals, &mut frame), Some(Delivery::Handler(SIGCHLD)));
+    }
+
+    #[test_case]
+    fn test_default_action_terminates_on_sigterm_and_sigsegv() {
+        let mut signals = SignalState::new();
+        let mut frame = TrapFrame::new();
+        frame.sepc = 0x1_0000;
+
+        // SIGCHLD 默认忽略
+        signals.raise(SIGCHLD);
+        assert_eq!(deliver(&mut signals, &mut frame), None);
+        assert_eq!(signals.pending(), 0);
+
+        for signal in [SIGSEGV, SIGTERM] {
+            signals.raise(signal);
+            assert_eq!(deliver(&mut signals, &mut frame), Some(Delivery::Terminate(signal)));
+            assert_eq!(frame.sepc, 0x1_0000);
+        }
+
+        // 忽略 SIGTERM 后不再终止
+        signals.set_handler(SIGTERM, SIG_IGN);
+        signals.raise(SIGTERM);
+        assert_eq!(deliver(&mut signals, &mut frame), None);
+    }
+
+    #[test_case]
+    fn test_child_exit_sends_sigchld_to_parent() {
+        use crate::process::{exit_process, reap_child, spawn_test_process, ProcessState};
+
+        let parent = spawn_test_process("sigchld_parent", None);
+        let parent_pid = parent.lock().pid();
+        let child = spawn_test_process("sigchld_child", Some(&parent));
+
+        // 父进程阻塞中（如在 waitpid 里），SIGCHLD 的默认动作是忽略，但仍然唤醒它
+        parent.lock().set_state(ProcessState::Blocked);
+        assert!(!parent.lock().signals().is_pending(SIGCHLD));
+
+        exit_process(&child, 0);
+
+        assert!(parent.lock().signals().is_pending(SIGCHLD));
+        assert_eq!(parent.lock().state(), ProcessState::Ready);
+
+        reap_child(parent_pid, None).unwrap();
+        scheduler::lock_scheduler().remove_process(parent_pid);
+    }
+}
@@ -0,0 +1,163 @@
+/*
+ * ============================================
+ * 用户栈按需增长
+ * ============================================
+ * 功能：为用户栈保留一大段虚拟地址，只在访问时映射页面
+ *
+ * 布局：
+ *   user_stack_top    ← 栈顶（创建进程时给定）
+ *   user_stack_bottom ← 已映射部分的底部，增长时下移
+ *   user_stack_limit  ← 保留范围的下界（top - USER_STACK_MAX）
+ *
+ * 增长规则：
+ * - 缺页地址在 bottom 下方 STACK_GROWTH_WINDOW 以内，且不低于 limit
+ * - 映射缺页地址所在的页（清零），bottom 下移到该页
+ * - 返回后重新执行出错指令
+ *
+ * 其他未映射访问（离栈太远、超过上限）仍按段错误处理
+ *
+ * 陷入内核时用 user_sp_in_range 检查用户 sp，越界说明栈已损坏或溢出
+ * ============================================
+ */
+
+use super::pcb::ProcessControlBlock;
+use crate::memory::{PageTableFlags, PhysAddr, SimpleFrameAllocator, VirtAddr, PAGE_SIZE};
+
+/// 用户栈的最大大小（8MB）
+pub const USER_STACK_MAX: usize = 8 * 1024 * 1024;
+
+/// 栈底下方多远以内的访问视为栈增长（一页）
+pub const STACK_GROWTH_WINDOW: usize = PAGE_SIZE;
+
+/// 栈增长失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackGrowthError {
+    /// 访问地址不在栈底正下方，不是栈增长
+    NotStackAccess,
+    /// 超过了最大栈大小
+    LimitExceeded,
+    /// 进程没有自己的页表
+    NoAddressSpace,
+    /// 分配或映射页面失败
+    OutOfMemory,
+}
+
+/// 判断缺页地址是否属于栈增长
+///
+/// # 参数
+/// - `fault_addr`: 缺页地址
+/// - `bottom`: 已映射部分的底部
+/// - `limit`: 允许增长到的最低地址
+///
+/// # 返回
+/// 需要映射的页的起始地址
+pub fn growth_page(fault_addr: usize, bottom: usize, limit: usize) -> Result<usize, StackGrowthError> {
+    if fault_addr >= bottom || bottom - fault_addr > STACK_GROWTH_WINDOW {
+        return Err(StackGrowthError::NotStackAccess);
+    }
+    if fault_addr < limit {
+        return Err(StackGrowthError::LimitExceeded);
+    }
+    Ok(fault_addr & !(PAGE_SIZE - 1))
+}
+
+/// 判断用户栈指针是否在进程的用户栈范围内
+///
+/// # 参数
+/// - `pcb`: 陷入内核的进程
+/// - `sp`: 陷入时的用户栈指针
+///
+/// # 说明
+/// 合法范围是整个保留范围 [limit, top]：sp 可以先于访问移到未映射的部分，
+/// 真正访问时由缺页处理增长栈。
+/// 没有用户栈的进程（top 为 0）不检查
+pub fn user_sp_in_range(pcb: &ProcessControlBlock, sp: usize) -> bool {
+    let top = pcb.user_stack_top();
+    if top == 0 {
+        return true;
+    }
+    (pcb.user_stack_limit()..=top).contains(&sp)
+}
+
+/// 为进程的用户栈映射缺页地址所在的页
+///
+/// # 返回
+/// 新页面的物理地址
+pub fn grow_user_stack(
+    pcb: &mut ProcessControlBlock,
+    fault_addr: usize,
+    allocator: &mut SimpleFrameAllocator,
+) -> Result<PhysAddr, StackGrowthError> {
+    let page = growth_page(fault_addr, pcb.user_stack_bottom(), pcb.user_stack_limit())?;
+
+    let flags = PageTableFlags::Read as usize
+        | PageTableFlags::Write as usize
+        | PageTableFlags::User as usize;
+    let space = pcb.address_space_mut().ok_or(StackGrowthError::NoAddressSpace)?;
+    let paddr = space
+        .map_zeroed_page(VirtAddr::new(page), flags, allocator)
+        .map_err(|_| StackGrowthError::OutOfMemory)?;
+
+    let top = pcb.user_stack_top();
+    pcb.set_user_stack(page, top);
+    Ok(paddr)
+}
+
+/// 缺页处理入口：尝试为当前进程增长用户栈
+///
+/// # 返回
+/// 是否已映射新页（true 时返回用户态重新执行出错指令）
+pub fn handle_user_stack_fault(fault_addr: usize) -> bool {
+    let process = match super::current_process() {
+        Some(process) => process,
+        None => return false,
+    };
+    let mut pcb = process.lock();
+    crate::memory::with_frame_allocator(|allocator| grow_user_stack(&mut pcb, fault_addr, allocator))
+        .is_some_and(|result| result.is_ok())
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{walk_page_table, AddressSpace, TestMemory};
+
+    #[test_case]
+    fn test_fault_below_stack_maps_page() {
+        const FRAMES: usize = 8;
+
+        // 先填满"旧数据"
+        let mut memory = TestMemory::new(FRAMES, 0xAA);
+        let allocator = &mut memory.allocator;
+
+        let top = 0x4000_0000;
+        let bottom = top - 0x10000;
+        let mut pcb = ProcessControlBlock::new("stack", None);
+        pcb.set_user_stack(bottom, top);
+        pcb.set_user_stack_limit(top - USER_STACK_MAX);
+        pcb.set_address_space(AddressSpace::new(allocator).unwrap());
+        let root = pcb.address_space().unwrap().page_table_paddr();
+
+        // 栈底正下方的访问：映射新页，栈底下移，可以继续执行
+        let fault = bottom - 8;
+        let paddr = grow_user_stack(&mut pcb, fault, allocator).unwrap();
+        assert_eq!(pcb.user_stack_bottom(), bottom - PAGE_SIZE);
+        assert_eq!(walk_page_table(root, VirtAddr::new(bottom - PAGE_SIZE)), Some(paddr));
+        let page = unsafe { core::slice::from_raw_parts(paddr.as_usize() as *const u8, PAGE_SIZE) };
+        assert!(page.iter().all(|&byte| byte == 0));
+
+        // 离栈底太远的访问不是栈增长
+        assert_eq!(
+            grow_user_stack(&mut pcb, bottom - 4 * PAGE_SIZE, allocator),
+            Err(StackGrowthError::NotStackAccess)
+        );
+
+        // 不能超过最大栈大小
+        let limit = pcb.user_stack_limit();
+        assert_eq!(growth_page(limit - 8, limit, limit), Err(StackGrowthError::LimitExceeded));
+    }
+}
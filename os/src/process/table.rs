@@ -0,0 +1,122 @@
+/*
+ * ============================================
+ * 进程表（Process Table）
+ * ============================================
+ * 功能：PID -> PCB 映射，用读写锁保护
+ *
+ * 进程表以读为主：调度、系统调用、检查器都只查询，
+ * 只有创建/移除进程才修改。用 RwLock 后，
+ * 检查器读取进程表不需要持有调度器锁，不会阻塞调度。
+ *
+ * 中断约定：
+ * - 写锁只在 insert / remove 内部、关中断时持有，
+ *   因此中断处理函数（tick → schedule）读进程表时
+ *   不会在同一个核上等待一个被打断的写者
+ * - 读锁可以在开中断时持有：中断处理函数只读，读者之间互不阻塞
+ * - 注意：持有读锁期间锁 PCB 仍需关中断（时钟中断也会锁 PCB）
+ * ============================================
+ */
+
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use lazy_static::lazy_static;
+use spin::{RwLock, RwLockReadGuard};
+
+use super::pid::ProcessId;
+use super::pcb::ProcessHandle;
+use crate::trap::without_interrupts;
+
+/// 进程表
+pub struct ProcessTable {
+    processes: RwLock<BTreeMap<ProcessId, ProcessHandle>>,
+}
+
+impl ProcessTable {
+    /// 创建空的进程表
+    pub fn new() -> Self {
+        ProcessTable {
+            processes: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// 获取读锁
+    ///
+    /// # 说明
+    /// 多个读者可以同时持有，用于遍历进程表
+    pub fn read(&self) -> RwLockReadGuard<'_, BTreeMap<ProcessId, ProcessHandle>> {
+        self.processes.read()
+    }
+
+    /// 按PID查找进程
+    pub fn get(&self, pid: ProcessId) -> Option<ProcessHandle> {
+        self.read().get(&pid).cloned()
+    }
+
+    /// 进程数
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// 进程表是否为空
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// 加入进程（关中断时持有写锁）
+    pub fn insert(&self, pid: ProcessId, process: ProcessHandle) {
+        without_interrupts(|| {
+            self.processes.write().insert(pid, process);
+        });
+    }
+
+    /// 移除进程（关中断时持有写锁）
+    pub fn remove(&self, pid: ProcessId) -> Option<ProcessHandle> {
+        without_interrupts(|| self.processes.write().remove(&pid))
+    }
+}
+
+impl Default for ProcessTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static! {
+    /// 全局进程表（由全局调度器维护，检查器直接读取）
+    pub static ref PROCESS_TABLE: Arc<ProcessTable> = Arc::new(ProcessTable::new());
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::create_process_handle;
+
+    #[test_case]
+    fn test_concurrent_readers_do_not_block() {
+        let table = ProcessTable::new();
+        let process = create_process_handle("table_test", None);
+        let pid = process.lock().pid();
+        table.insert(pid, process);
+
+        // 两个读者同时持有读锁
+        let first = table.read();
+        let second = table.read();
+        assert!(first.contains_key(&pid));
+        assert_eq!(second.len(), 1);
+
+        // 读锁存在时写者拿不到锁，但新读者可以
+        assert!(table.processes.try_write().is_none());
+        assert!(table.processes.try_read().is_some());
+        assert!(table.get(pid).is_some());
+
+        drop(first);
+        drop(second);
+        assert!(table.remove(pid).is_some());
+        assert!(table.is_empty());
+    }
+}
@@ -0,0 +1,144 @@
+/*
+ * ============================================
+ * 调度器追踪（Scheduler Trace）
+ * ============================================
+ * 功能：用环形缓冲区记录调度事件，便于分析调度行为
+ *
+ * 记录的事件：
+ * - Tick：时钟中断
+ * - Switch：上下文切换（from → to）
+ * - Block：进程阻塞
+ * - Wake：进程被唤醒
+ *
+ * 设计要点：
+ * - 默认关闭，通过 set_enabled 开启
+ * - 容量固定，写满后覆盖最旧的记录
+ * - 在持有调度器锁（已关中断）时调用，不分配内存
+ * ============================================
+ */
+
+extern crate alloc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use spin::Mutex;
+
+use super::pid::ProcessId;
+
+/// 环形缓冲区容量
+pub const TRACE_CAPACITY: usize = 64;
+
+/// 调度事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchedEvent {
+    /// 时钟中断（当前进程）
+    Tick(Option<ProcessId>),
+    /// 上下文切换
+    Switch {
+        from: Option<ProcessId>,
+        to: ProcessId,
+    },
+    /// 进程阻塞
+    Block(ProcessId),
+    /// 进程被唤醒
+    Wake(ProcessId),
+}
+
+/// 带时间戳的追踪记录
+#[derive(Debug, Clone, Copy)]
+pub struct TraceRecord {
+    /// 时间戳（time 寄存器 tick）
+    pub timestamp: u64,
+    /// 事件
+    pub event: SchedEvent,
+}
+
+/// 追踪环形缓冲区
+struct TraceBuffer {
+    records: [Option<TraceRecord>; TRACE_CAPACITY],
+    /// 下一个写入位置
+    head: usize,
+    /// 有效记录数
+    len: usize,
+}
+
+impl TraceBuffer {
+    const fn new() -> Self {
+        TraceBuffer {
+            records: [None; TRACE_CAPACITY],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, record: TraceRecord) {
+        self.records[self.head] = Some(record);
+        self.head = (self.head + 1) % TRACE_CAPACITY;
+        self.len = (self.len + 1).min(TRACE_CAPACITY);
+    }
+
+    /// 按时间顺序（从旧到新）遍历
+    fn iter(&self) -> impl Iterator<Item = &TraceRecord> {
+        let start = (self.head + TRACE_CAPACITY - self.len) % TRACE_CAPACITY;
+        (0..self.len).filter_map(move |i| self.records[(start + i) % TRACE_CAPACITY].as_ref())
+    }
+}
+
+/// 是否启用追踪
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// 全局追踪缓冲区
+static TRACE: Mutex<TraceBuffer> = Mutex::new(TraceBuffer::new());
+
+/// 开启或关闭调度追踪
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// 调度追踪是否开启
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// 记录一个调度事件（未开启时直接返回）
+pub fn record(event: SchedEvent) {
+    if !is_enabled() {
+        return;
+    }
+
+    let timestamp = riscv::register::time::read64();
+    TRACE.lock().push(TraceRecord { timestamp, event });
+}
+
+/// 清空追踪记录
+pub fn clear() {
+    *TRACE.lock() = TraceBuffer::new();
+}
+
+/// 获取追踪记录快照（从旧到新）
+pub fn snapshot() -> Vec<TraceRecord> {
+    TRACE.lock().iter().copied().collect()
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_ring_buffer_keeps_newest() {
+        let mut buffer = TraceBuffer::new();
+        let pid = ProcessId::new();
+
+        for i in 0..(TRACE_CAPACITY + 3) as u64 {
+            buffer.push(TraceRecord { timestamp: i, event: SchedEvent::Wake(pid) });
+        }
+
+        let timestamps: Vec<u64> = buffer.iter().map(|r| r.timestamp).collect();
+        assert_eq!(timestamps.len(), TRACE_CAPACITY);
+        assert_eq!(timestamps[0], 3);
+        assert_eq!(*timestamps.last().unwrap(), (TRACE_CAPACITY + 2) as u64);
+    }
+}
@@ -0,0 +1,100 @@
+/*
+ * ============================================
+ * 进程等待状态（wait status）
+ * ============================================
+ * 功能：按 Unix 的约定编码子进程的结束方式，
+ *       sys_waitpid 把编码后的值写给用户
+ *
+ * 编码（低 16 位）：
+ * - 正常退出：低 7 位为 0，第 8-15 位为退出码（exit_code & 0xff）
+ * - 被信号终止：低 7 位为信号编号
+ *
+ * 用户用 wifexited / wexitstatus / wifsignaled / wtermsig 解码
+ * ============================================
+ */
+
+/// 低 7 位：终止信号
+const TERMSIG_MASK: i32 = 0x7F;
+
+/// 低 7 位全 1 表示进程被暂停（本内核不会产生，解码时排除）
+const STOPPED: i32 = 0x7F;
+
+/// 编码后的等待状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitStatus(i32);
+
+impl WaitStatus {
+    /// 正常退出
+    ///
+    /// # 参数
+    /// - `code`: 退出码（只保留低 8 位）
+    pub const fn exited(code: i32) -> Self {
+        WaitStatus((code & 0xFF) << 8)
+    }
+
+    /// 被信号终止
+    ///
+    /// # 参数
+    /// - `signal`: 信号编号（1..=126）
+    pub const fn signaled(signal: i32) -> Self {
+        WaitStatus(signal & TERMSIG_MASK)
+    }
+
+    /// 写给用户的原始值
+    pub const fn raw(self) -> i32 {
+        self.0
+    }
+}
+
+/// 是否正常退出
+pub const fn wifexited(status: i32) -> bool {
+    status & TERMSIG_MASK == 0
+}
+
+/// 退出码（wifexited 为真时有意义）
+pub const fn wexitstatus(status: i32) -> i32 {
+    (status >> 8) & 0xFF
+}
+
+/// 是否被信号终止
+pub const fn wifsignaled(status: i32) -> bool {
+    let signal = status & TERMSIG_MASK;
+    signal != 0 && signal != STOPPED
+}
+
+/// 终止信号（wifsignaled 为真时有意义）
+pub const fn wtermsig(status: i32) -> i32 {
+    status & TERMSIG_MASK
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::signal::SIGKILL;
+
+    #[test_case]
+    fn test_encode_normal_exit() {
+        let status = WaitStatus::exited(0).raw();
+        assert!(wifexited(status));
+        assert!(!wifsignaled(status));
+        assert_eq!(wexitstatus(status), 0);
+
+        // 只保留低 8 位
+        let status = WaitStatus::exited(-1).raw();
+        assert!(wifexited(status));
+        assert_eq!(wexitstatus(status), 255);
+    }
+
+    #[test_case]
+    fn test_encode_signal() {
+        let status = WaitStatus::signaled(SIGKILL).raw();
+        assert_eq!(status, 9);
+        assert!(wifsignaled(status));
+        assert!(!wifexited(status));
+        assert_eq!(wtermsig(status), SIGKILL);
+    }
+}
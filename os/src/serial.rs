@@ -2,7 +2,7 @@
  * ============================================
  * RISC-V 串口驱动模块
  * ============================================
- * 功能：提供 UART 16550 串口输出功能
+ * 功能：提供 UART 16550 串口输出功能，以及中断驱动的输入
  * 用途：调试输出、日志记录、与 QEMU 通信
  *
  * RISC-V QEMU virt 机器的串口地址：0x10000000
@@ -10,7 +10,7 @@
  */
 
 use core::fmt;
-use spin::Mutex;
+use crate::sync::IrqSpinLock;
 use lazy_static::lazy_static;
 use volatile::Volatile;
 
@@ -18,11 +18,18 @@ use volatile::Volatile;
 const UART_BASE_ADDRESS: usize = 0x1000_0000;
 
 /// UART 16550 寄存器偏移
+const UART_RBR: usize = 0; // Receiver Buffer Register（读，与 THR 同一偏移）
 const UART_THR: usize = 0; // Transmitter Holding Register
+const UART_IER: usize = 1; // Interrupt Enable Register
 const UART_LSR: usize = 5; // Line Status Register
 
+/// Interrupt Enable Register 位定义
+const UART_IER_RDI: u8 = 1 << 0; // Received Data Available Interrupt
+
 /// Line Status Register 位定义
+const UART_LSR_DR: u8 = 1 << 0;   // Data Ready（RBR 中有数据）
 const UART_LSR_THRE: u8 = 1 << 5; // Transmitter Holding Register Empty
+const UART_LSR_TEMT: u8 = 1 << 6; // Transmitter Empty（移位寄存器也已发送完）
 
 /// 简单的 UART 串口驱动
 pub struct SerialPort {
@@ -43,8 +50,10 @@ impl SerialPort {
     /// 发送一个字节
     fn send(&mut self, byte: u8) {
         unsafe {
-            // 等待发送缓冲区为空
-            while !self.is_transmit_empty() {}
+            // 等待发送缓冲区为空（spin_loop 提示处理器这是忙等）
+            while !self.is_transmit_empty() {
+                core::hint::spin_loop();
+            }
 
             // 写入数据
             let thr = (self.base_address + UART_THR) as *mut Volatile<u8>;
@@ -52,6 +61,39 @@ impl SerialPort {
         }
     }
 
+    /// 读取一个已收到的字节
+    ///
+    /// # 返回
+    /// RBR 中没有数据（LSR.DR 为 0）时返回 None
+    pub fn receive(&mut self) -> Option<u8> {
+        unsafe {
+            let lsr = (self.base_address + UART_LSR) as *const Volatile<u8>;
+            if (*lsr).read() & UART_LSR_DR == 0 {
+                return None;
+            }
+            let rbr = (self.base_address + UART_RBR) as *const Volatile<u8>;
+            Some((*rbr).read())
+        }
+    }
+
+    /// 开启接收中断（RBR 有数据时经 PLIC 触发外部中断）
+    pub fn enable_rx_interrupt(&mut self) {
+        unsafe {
+            let ier = (self.base_address + UART_IER) as *mut Volatile<u8>;
+            (*ier).write(UART_IER_RDI);
+        }
+    }
+
+    /// 等待已写入的字节全部发送完
+    pub fn flush(&mut self) {
+        unsafe {
+            let lsr = (self.base_address + UART_LSR) as *const Volatile<u8>;
+            while (*lsr).read() & UART_LSR_TEMT == 0 {
+                core::hint::spin_loop();
+            }
+        }
+    }
+
     /// 检查发送缓冲区是否为空
     fn is_transmit_empty(&self) -> bool {
         unsafe {
@@ -73,12 +115,13 @@ impl fmt::Write for SerialPort {
 lazy_static! {
     /// 全局串口实例（UART0）
     ///
-    /// 使用 Mutex 保护以支持多核访问
+    /// 使用 IrqSpinLock 保护：持锁期间关闭中断，
+    /// 中断处理函数中打印不会在同一个核上死锁
     /// 在 RISC-V QEMU virt 机器中，UART 映射到 0x10000000
-    pub static ref SERIAL1: Mutex<SerialPort> = {
+    pub static ref SERIAL1: IrqSpinLock<SerialPort> = {
         let mut serial_port = unsafe { SerialPort::new(UART_BASE_ADDRESS) };
         serial_port.init();
-        Mutex::new(serial_port)
+        IrqSpinLock::new(serial_port)
     };
 }
 
@@ -86,7 +129,7 @@ lazy_static! {
 ///
 /// # 功能
 /// - 格式化输出到串口
-/// - 在临界区内执行，禁用中断以防止死锁
+/// - SERIAL1 持锁期间自动关闭中断，防止死锁
 ///
 /// # 参数
 /// - `args`: 格式化参数
@@ -94,14 +137,25 @@ lazy_static! {
 pub fn _print(args: ::core::fmt::Arguments) {
     use core::fmt::Write;
 
-    // RISC-V 中断禁用/启用
-    // 使用自旋锁时禁用中断，防止死锁
-    crate::interrupts::without_interrupts(|| {
-        SERIAL1
-            .lock()
-            .write_fmt(args)
-            .expect("Printing to serial failed");
-    });
+    SERIAL1
+        .lock()
+        .write_fmt(args)
+        .expect("Printing to serial failed");
+}
+
+/// 等待串口输出发送完（关机前调用，避免丢失最后的日志）
+pub fn flush() {
+    SERIAL1.lock().flush();
+}
+
+/// 读取一个已收到的字节（没有数据时返回 None）
+pub fn receive() -> Option<u8> {
+    SERIAL1.lock().receive()
+}
+
+/// 开启串口接收中断
+pub fn enable_rx_interrupt() {
+    SERIAL1.lock().enable_rx_interrupt();
 }
 
 /// 串口打印宏
@@ -133,3 +187,22 @@ macro_rules! serial_println {
     ($fmt:expr, $($arg:tt)*) => ($crate::serial_print!(
         concat!($fmt, "\n"), $($arg)*));
 }
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Write;
+
+    #[test_case]
+    fn test_send_with_spin_hint() {
+        // 连续写入会多次进入等待发送缓冲区的忙等循环
+        let mut serial = SERIAL1.lock();
+        for _ in 0..4 {
+            assert!(serial.write_str("spin_loop hint check\n").is_ok());
+        }
+    }
+}
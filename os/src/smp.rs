@@ -0,0 +1,248 @@
+/*
+ * ============================================
+ * 多核启动（SBI HSM 扩展）
+ * ============================================
+ * 功能：通过 SBI Hart State Management 扩展启动从核
+ *
+ * SBI HSM 扩展：
+ * - Extension ID (EID): 0x48534D ("HSM")
+ * - FID 0: hart_start(hartid, start_addr, opaque)
+ * - FID 1: hart_stop()
+ * - FID 2: hart_get_status(hartid)
+ * - FID 3: hart_suspend(suspend_type, resume_addr, opaque)
+ *
+ * 从核启动流程：
+ * 1. 主核对每个处于 Stopped 状态的 hart 调用 hart_start
+ * 2. 从核从 _secondary_start 开始执行（a0=hartid, a1=opaque）
+ * 3. 按 hartid 选择独立的启动栈，并设置 tp 指向本 hart 的控制块
+ * 4. 进入 secondary_main，在 idle 循环中停靠
+ *
+ * 说明：调度器目前只在主核上运行，从核只是被干净地停靠，
+ * 为之后的 SMP 调度做准备
+ * ============================================
+ */
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use crate::serial_println;
+
+/// HSM 扩展 ID（"HSM"）
+pub const SBI_EXT_HSM: usize = 0x48534D;
+
+/// HSM 功能号：启动 hart
+const HSM_HART_START: usize = 0;
+
+/// HSM 功能号：查询 hart 状态
+const HSM_HART_GET_STATUS: usize = 2;
+
+/// HSM 功能号：挂起当前 hart
+const HSM_HART_SUSPEND: usize = 3;
+
+/// 保持型挂起：寄存器状态保留，中断到来后从 ecall 的下一条指令继续
+const HSM_SUSPEND_RETENTIVE: usize = 0x0000_0000;
+
+/// 支持的最大 hart 数
+pub const MAX_HARTS: usize = 4;
+
+/// 每个从核的启动栈大小（16KB）
+const SECONDARY_STACK_SIZE: usize = 16 * 1024;
+
+/// SBI 调用失败时返回的错误码（SBI_ERR_*，均为负数）
+pub type SbiError = isize;
+
+/// hart 状态（hart_get_status 的返回值）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HartState {
+    /// 正在运行
+    Started,
+    /// 已停止，可以被 hart_start 启动
+    Stopped,
+    /// 启动中
+    StartPending,
+    /// 停止中
+    StopPending,
+    /// 已挂起
+    Suspended,
+    /// 挂起中
+    SuspendPending,
+    /// 恢复中
+    ResumePending,
+}
+
+impl HartState {
+    fn from_raw(value: usize) -> Option<Self> {
+        match value {
+            0 => Some(HartState::Started),
+            1 => Some(HartState::Stopped),
+            2 => Some(HartState::StartPending),
+            3 => Some(HartState::StopPending),
+            4 => Some(HartState::Suspended),
+            5 => Some(HartState::SuspendPending),
+            6 => Some(HartState::ResumePending),
+            _ => None,
+        }
+    }
+}
+
+/// 已进入 idle 循环的从核数量
+static ONLINE_SECONDARY_HARTS: AtomicUsize = AtomicUsize::new(0);
+
+/// 从核启动栈（按 hartid 索引）
+#[repr(C, align(16))]
+struct SecondaryStacks([[u8; SECONDARY_STACK_SIZE]; MAX_HARTS]);
+
+static mut SECONDARY_STACKS: SecondaryStacks =
+    SecondaryStacks([[0; SECONDARY_STACK_SIZE]; MAX_HARTS]);
+
+// 从核入口：a0 = hartid，a1 = opaque
+// hartid 超出范围时没有可用的栈，直接停靠
+core::arch::global_asm!(
+    ".section .text",
+    ".globl _secondary_start",
+    "_secondary_start:",
+    "   li t0, {max_harts}",
+    "   bgeu a0, t0, 2f",
+    // sp = SECONDARY_STACKS + (hartid + 1) * SECONDARY_STACK_SIZE
+    "   la sp, {stacks}",
+    "   addi t0, a0, 1",
+    "   li t1, {stack_size}",
+    "   mul t0, t0, t1",
+    "   add sp, sp, t0",
+    // 设置 tp 指向本 hart 的控制块（保存 a0/a1 以便传给 secondary_main）
+    "   mv s0, a0",
+    "   mv s1, a1",
+    "   call {percpu_init}",
+    "   mv a0, s0",
+    "   mv a1, s1",
+    "   call {main}",
+    "2:",
+    "   wfi",
+    "   j 2b",
+    max_harts = const MAX_HARTS,
+    stack_size = const SECONDARY_STACK_SIZE,
+    stacks = sym SECONDARY_STACKS,
+    percpu_init = sym crate::percpu::percpu_init_hart,
+    main = sym secondary_main,
+);
+
+extern "C" {
+    fn _secondary_start();
+}
+
+/// 发起 SBI 调用
+///
+/// # 返回
+/// (error, value)，error 为 0 表示成功
+fn sbi_call(eid: usize, fid: usize, arg0: usize, arg1: usize, arg2: usize) -> (isize, usize) {
+    let error: isize;
+    let value: usize;
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            inlateout("a0") arg0 => error,
+            inlateout("a1") arg1 => value,
+            in("a2") arg2,
+            in("a6") fid,
+            in("a7") eid,
+            options(nostack)
+        );
+    }
+    (error, value)
+}
+
+/// 启动指定 hart
+///
+/// # 参数
+/// - `hartid`: 要启动的 hart
+/// - `start_addr`: 入口物理地址（以 S 模式、关闭分页进入）
+/// - `opaque`: 传给入口的 a1
+///
+/// # 返回
+/// 失败时返回 SBI 错误码
+pub fn hart_start(hartid: usize, start_addr: usize, opaque: usize) -> Result<(), SbiError> {
+    match sbi_call(SBI_EXT_HSM, HSM_HART_START, hartid, start_addr, opaque) {
+        (0, _) => Ok(()),
+        (error, _) => Err(error),
+    }
+}
+
+/// 查询指定 hart 的状态
+///
+/// # 返回
+/// 失败（如 hartid 无效）时返回 SBI 错误码
+pub fn hart_status(hartid: usize) -> Result<HartState, SbiError> {
+    match sbi_call(SBI_EXT_HSM, HSM_HART_GET_STATUS, hartid, 0, 0) {
+        (0, value) => HartState::from_raw(value).ok_or(-1),
+        (error, _) => Err(error),
+    }
+}
+
+/// 以保持型挂起暂停当前 hart，直到有中断到来
+///
+/// # 返回
+/// SBI 不支持挂起时返回错误码（调用者可退回 wfi）
+pub fn hart_suspend_retentive() -> Result<(), SbiError> {
+    match sbi_call(SBI_EXT_HSM, HSM_HART_SUSPEND, HSM_SUSPEND_RETENTIVE, 0, 0) {
+        (0, _) => Ok(()),
+        (error, _) => Err(error),
+    }
+}
+
+/// 启动所有处于 Stopped 状态的从核
+///
+/// # 返回
+/// 成功发起启动的 hart 数
+///
+/// # 说明
+/// 主核自身处于 Started 状态，不会被重复启动
+pub fn start_secondary_harts() -> usize {
+    let mut started = 0;
+
+    for hartid in 0..MAX_HARTS {
+        if hart_status(hartid) != Ok(HartState::Stopped) {
+            continue;
+        }
+
+        match hart_start(hartid, _secondary_start as *const () as usize, 0) {
+            Ok(()) => started += 1,
+            Err(e) => {
+                serial_println!("[SMP] Failed to start hart {}: error {}", hartid, e);
+            }
+        }
+    }
+
+    serial_println!("[SMP] Started {} secondary hart(s)", started);
+    started
+}
+
+/// 已进入 idle 循环的从核数量
+pub fn online_secondary_harts() -> usize {
+    ONLINE_SECONDARY_HARTS.load(Ordering::Acquire)
+}
+
+/// 从核 Rust 入口：登记上线后在 idle 循环中停靠
+///
+/// # 说明
+/// 从核启动时 sstatus.SIE 为 0，不会响应中断
+extern "C" fn secondary_main(hartid: usize, _opaque: usize) -> ! {
+    ONLINE_SECONDARY_HARTS.fetch_add(1, Ordering::Release);
+    serial_println!("[SMP] Hart {} online, parking", hartid);
+
+    loop {
+        riscv::asm::wfi();
+    }
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_boot_hart_reports_started() {
+        // 测试在单核下运行，hart 0 就是当前正在执行的主核
+        assert_eq!(hart_status(0), Ok(HartState::Started));
+    }
+}
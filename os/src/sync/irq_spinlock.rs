@@ -0,0 +1,153 @@
+/*
+ * ============================================
+ * 关中断自旋锁（IrqSpinLock）
+ * ============================================
+ * 功能：加锁时保存并关闭中断，释放锁时恢复之前的中断状态
+ *
+ * 为什么需要：
+ * - 普通自旋锁在开中断时持有，如果中断处理函数再次获取同一把锁，
+ *   同一个核会在自旋锁上死锁（例如时钟中断 tick → schedule）
+ * - 之前依赖调用者手动包一层 without_interrupts，容易遗漏
+ *
+ * 释放顺序：先释放内部锁，再恢复中断，
+ * 保证中断处理函数不会看到仍被持有的锁
+ * ============================================
+ */
+
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+use riscv::register::sstatus;
+use spin::{Mutex, MutexGuard};
+
+/// 持锁期间关闭中断的自旋锁
+pub struct IrqSpinLock<T: ?Sized> {
+    inner: Mutex<T>,
+}
+
+/// IrqSpinLock 的守卫，drop 时释放锁并恢复中断状态
+pub struct IrqSpinLockGuard<'a, T: ?Sized + 'a> {
+    guard: ManuallyDrop<MutexGuard<'a, T>>,
+    /// 加锁前 sstatus.SIE 的值
+    irq_enabled: bool,
+}
+
+/// 保存并关闭中断
+///
+/// # 返回
+/// 关闭之前中断是否开启
+fn save_and_disable() -> bool {
+    let enabled = sstatus::read().sie();
+    if enabled {
+        unsafe { sstatus::clear_sie(); }
+    }
+    enabled
+}
+
+/// 恢复 save_and_disable 保存的中断状态
+fn restore(enabled: bool) {
+    if enabled {
+        unsafe { sstatus::set_sie(); }
+    }
+}
+
+impl<T> IrqSpinLock<T> {
+    /// 创建新的锁
+    pub const fn new(value: T) -> Self {
+        IrqSpinLock {
+            inner: Mutex::new(value),
+        }
+    }
+}
+
+impl<T: ?Sized> IrqSpinLock<T> {
+    /// 关闭中断并获取锁
+    pub fn lock(&self) -> IrqSpinLockGuard<'_, T> {
+        let irq_enabled = save_and_disable();
+        IrqSpinLockGuard {
+            guard: ManuallyDrop::new(self.inner.lock()),
+            irq_enabled,
+        }
+    }
+
+    /// 尝试获取锁
+    ///
+    /// # 返回
+    /// 锁已被持有时返回 None，中断状态保持不变
+    pub fn try_lock(&self) -> Option<IrqSpinLockGuard<'_, T>> {
+        let irq_enabled = save_and_disable();
+        match self.inner.try_lock() {
+            Some(guard) => Some(IrqSpinLockGuard {
+                guard: ManuallyDrop::new(guard),
+                irq_enabled,
+            }),
+            None => {
+                restore(irq_enabled);
+                None
+            }
+        }
+    }
+
+    /// 释放由别的执行流获取、守卫无法在这里 drop 的锁，不改变中断状态
+    ///
+    /// # Safety
+    /// 锁必须被持有，并且持有者的守卫不会再被 drop（例如它留在了切走的栈上）
+    pub unsafe fn force_unlock(&self) {
+        self.inner.force_unlock();
+    }
+}
+
+impl<T: ?Sized> Deref for IrqSpinLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for IrqSpinLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized> Drop for IrqSpinLockGuard<'_, T> {
+    fn drop(&mut self) {
+        // 先释放锁，再恢复中断
+        unsafe { ManuallyDrop::drop(&mut self.guard); }
+        restore(self.irq_enabled);
+    }
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_guard_restores_interrupt_state() {
+        let lock = IrqSpinLock::new(0usize);
+
+        // 开中断时加锁：持锁期间关闭，释放后恢复开启
+        unsafe { sstatus::set_sie(); }
+        {
+            let mut guard = lock.lock();
+            *guard += 1;
+            assert!(!sstatus::read().sie());
+        }
+        assert!(sstatus::read().sie());
+
+        // 关中断时加锁：释放后保持关闭
+        unsafe { sstatus::clear_sie(); }
+        {
+            let _guard = lock.lock();
+            assert!(!sstatus::read().sie());
+            assert!(lock.try_lock().is_none());
+            assert!(!sstatus::read().sie());
+        }
+        assert!(!sstatus::read().sie());
+        assert_eq!(*lock.lock(), 1);
+    }
+}
@@ -0,0 +1,18 @@
+/*
+ * ============================================
+ * 同步原语
+ * ============================================
+ * 功能：内核使用的锁
+ *
+ * - IrqSpinLock：持锁期间关闭中断的自旋锁，
+ *   用于与中断处理函数共享的数据（调度器、串口）
+ * - TicketLock：先来先得的排队自旋锁，
+ *   用于竞争激烈、需要公平性的数据（控制台）
+ * ============================================
+ */
+
+mod irq_spinlock;
+mod ticket_lock;
+
+pub use irq_spinlock::{IrqSpinLock, IrqSpinLockGuard};
+pub use ticket_lock::{TicketLock, TicketLockGuard};
@@ -0,0 +1,170 @@
+/*
+ * ============================================
+ * 排队自旋锁（TicketLock）
+ * ============================================
+ * 功能：按先来先得（FIFO）的顺序授予锁
+ *
+ * 原理：
+ * - next_ticket：下一个发放的号码，加锁时取号（fetch_add）
+ * - now_serving：当前可以进入的号码，释放锁时加一
+ * - 只有号码等于 now_serving 的等待者能进入
+ *
+ * 普通自旋锁谁先抢到谁进入，高频加锁的一方刚释放就能立刻再次抢到，
+ * 可能让其他等待者一直拿不到锁；排队锁中再次加锁必须重新取号，
+ * 排在已经等待的人后面
+ * ============================================
+ */
+
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// 先来先得的自旋锁
+pub struct TicketLock<T: ?Sized> {
+    next_ticket: AtomicUsize,
+    now_serving: AtomicUsize,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Sync for TicketLock<T> {}
+unsafe impl<T: ?Sized + Send> Send for TicketLock<T> {}
+
+/// 排队号码（由 TicketLock::ticket 发放）
+///
+/// # 说明
+/// 号码不能复制，也不能丢弃不用：取了号就必须用它拿到锁，
+/// 否则后面所有等待者都会卡住。因此只在本模块内使用，
+/// 外部只能通过 lock 加锁（取号和等待在同一个调用中完成）
+#[must_use]
+struct Ticket(usize);
+
+/// TicketLock 的守卫，drop 时把锁交给下一个号码
+pub struct TicketLockGuard<'a, T: ?Sized + 'a> {
+    lock: &'a TicketLock<T>,
+}
+
+impl<T> TicketLock<T> {
+    /// 创建新的锁
+    pub const fn new(value: T) -> Self {
+        TicketLock {
+            next_ticket: AtomicUsize::new(0),
+            now_serving: AtomicUsize::new(0),
+            data: UnsafeCell::new(value),
+        }
+    }
+}
+
+impl<T: ?Sized> TicketLock<T> {
+    /// 取号并自旋等待，直到轮到自己
+    pub fn lock(&self) -> TicketLockGuard<'_, T> {
+        let mut ticket = self.ticket();
+        loop {
+            match self.try_acquire(ticket) {
+                Ok(guard) => return guard,
+                Err(t) => {
+                    ticket = t;
+                    core::hint::spin_loop();
+                }
+            }
+        }
+    }
+
+    /// 取号（排到队尾）
+    fn ticket(&self) -> Ticket {
+        Ticket(self.next_ticket.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// 用号码尝试进入
+    ///
+    /// # 返回
+    /// - `Ok(guard)`: 轮到该号码
+    /// - `Err(ticket)`: 还没轮到，号码原样返回，之后再试
+    fn try_acquire(&self, ticket: Ticket) -> Result<TicketLockGuard<'_, T>, Ticket> {
+        if self.now_serving.load(Ordering::Acquire) == ticket.0 {
+            Ok(TicketLockGuard { lock: self })
+        } else {
+            Err(ticket)
+        }
+    }
+
+    /// 正在等待（已取号但还没进入）的数量，不含持锁者
+    pub fn waiters(&self) -> usize {
+        let next = self.next_ticket.load(Ordering::Relaxed);
+        let serving = self.now_serving.load(Ordering::Relaxed);
+        next.wrapping_sub(serving).saturating_sub(1)
+    }
+}
+
+impl<T: ?Sized> Deref for TicketLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for TicketLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for TicketLockGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.now_serving.fetch_add(1, Ordering::Release);
+    }
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{Task, simple_executor::{yield_once, SimpleExecutor}};
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+
+    #[test_case]
+    fn test_interleaved_writers_acquire_in_turn() {
+        const WRITERS: usize = 3;
+        const ROUNDS: usize = 4;
+
+        let log = Arc::new(TicketLock::new(Vec::new()));
+        let mut executor = SimpleExecutor::new();
+
+        for id in 0..WRITERS {
+            let log = log.clone();
+            executor.spawn(Task::new(async move {
+                for _ in 0..ROUNDS {
+                    let mut ticket = log.ticket();
+                    let mut guard = loop {
+                        match log.try_acquire(ticket) {
+                            Ok(guard) => break guard,
+                            Err(t) => {
+                                ticket = t;
+                                yield_once().await;
+                            }
+                        }
+                    };
+                    guard.push(id);
+
+                    // 持锁期间让出（模拟一次较长的格式化写入），
+                    // 释放后立即重新取号也只能排在其他写者后面
+                    yield_once().await;
+                    drop(guard);
+                }
+            }));
+        }
+        executor.run();
+
+        // 每个写者按顺序轮流拿到锁，没有谁独占
+        let order = log.lock();
+        assert_eq!(order.len(), WRITERS * ROUNDS);
+        for (i, &id) in order.iter().enumerate() {
+            assert_eq!(id, i % WRITERS);
+        }
+        assert_eq!(log.waiters(), 0);
+    }
+}
@@ -0,0 +1,133 @@
+/*
+ * ============================================
+ * 系统调用错误类型
+ * ============================================
+ * 功能：统一系统调用层的错误表示
+ *
+ * 设计要点：
+ * - sys_* 实现返回 SysResult，用 ? 传播错误
+ * - 每个 SysError 对应一个 Linux errno
+ * - 只在分发边界（syscall_dispatcher）转换为 isize：
+ *   成功返回非负值，失败返回 -errno
+ * ============================================
+ */
+
+use core::fmt;
+use crate::fs::FileError;
+use crate::process::exec::ExecError;
+use crate::process::ProcessError;
+
+/// 系统调用错误（值为对应的 errno）
+#[repr(isize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SysError {
+    /// EPERM：操作不允许
+    NotPermitted = 1,
+    /// ENOENT：文件或目录不存在
+    NoEntry = 2,
+    /// ESRCH：进程不存在
+    NoProcess = 3,
+    /// EIO：I/O 错误
+    Io = 5,
+    /// EBADF：错误的文件描述符
+    BadFd = 9,
+    /// ECHILD：没有子进程
+    NoChild = 10,
+    /// EAGAIN：资源暂时不可用，稍后重试
+    Again = 11,
+    /// ENOMEM：内存不足
+    NoMemory = 12,
+    /// EACCES：权限不足
+    AccessDenied = 13,
+    /// EFAULT：用户指针无效
+    BadAddress = 14,
+    /// EEXIST：文件已存在
+    Exists = 17,
+    /// ENOTDIR：不是目录
+    NotDirectory = 20,
+    /// EISDIR：是目录
+    IsDirectory = 21,
+    /// EINVAL：参数无效
+    InvalidArgument = 22,
+    /// EMFILE：打开的文件过多
+    TooManyFiles = 24,
+    /// ESPIPE：不支持定位
+    IllegalSeek = 29,
+    /// EPIPE：管道的读端已关闭
+    BrokenPipe = 32,
+    /// ENAMETOOLONG：路径过长
+    NameTooLong = 36,
+    /// ENOSYS：系统调用未实现
+    NotImplemented = 38,
+    /// ELOOP：符号链接层数过多
+    Loop = 40,
+}
+
+/// 系统调用结果
+pub type SysResult<T = usize> = Result<T, SysError>;
+
+impl SysError {
+    /// 对应的 errno（正数）
+    pub fn errno(self) -> isize {
+        self as isize
+    }
+}
+
+impl fmt::Display for SysError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} (errno {})", self, self.errno())
+    }
+}
+
+impl From<FileError> for SysError {
+    fn from(e: FileError) -> Self {
+        match e {
+            FileError::NotFound => SysError::NoEntry,
+            FileError::PermissionDenied => SysError::AccessDenied,
+            FileError::EndOfFile | FileError::IoError => SysError::Io,
+            FileError::InvalidOperation => SysError::InvalidArgument,
+            FileError::AlreadyExists => SysError::Exists,
+            FileError::NotDirectory => SysError::NotDirectory,
+            FileError::IsDirectory => SysError::IsDirectory,
+            FileError::WouldBlock => SysError::Again,
+            FileError::NotSeekable => SysError::IllegalSeek,
+            FileError::BadFileDescriptor => SysError::BadFd,
+            FileError::BrokenPipe => SysError::BrokenPipe,
+            FileError::TooManyLinks => SysError::Loop,
+        }
+    }
+}
+
+impl From<ProcessError> for SysError {
+    fn from(e: ProcessError) -> Self {
+        match e {
+            ProcessError::OutOfMemory => SysError::NoMemory,
+            ProcessError::LimitExceeded(_) => SysError::Again,
+            ProcessError::InvalidEntry(_) => SysError::InvalidArgument,
+            ProcessError::NoChild => SysError::NoChild,
+            ProcessError::RateLimited => SysError::Again,
+        }
+    }
+}
+
+impl From<ExecError> for SysError {
+    fn from(e: ExecError) -> Self {
+        match e {
+            ExecError::File(e) => e.into(),
+            ExecError::BadElf(_) => SysError::InvalidArgument,
+            ExecError::OutOfMemory | ExecError::NoFrameAllocator => SysError::NoMemory,
+            ExecError::Process(e) => e.into(),
+        }
+    }
+}
+
+/// 把系统调用结果转换为 ABI 返回值
+///
+/// # 返回
+/// 成功时为返回值本身，失败时为 -errno
+pub fn to_abi(result: SysResult) -> isize {
+    match result {
+        Ok(value) => value as isize,
+        Err(e) => -e.errno(),
+    }
+}
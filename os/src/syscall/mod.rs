@@ -8,16 +8,39 @@
  * - 用户态通过 ecall 指令触发系统调用
  * - 系统调用号通过 a7 寄存器传递
  * - 参数通过 a0-a5 寄存器传递（最多6个参数）
- * - 返回值通过 a0 寄存器返回
+ * - 返回值通过 a0 寄存器返回（失败时为 -errno，见 error.rs）
  *
  * 支持的系统调用：
  * - sys_write: 写入数据到文件描述符
  * - sys_exit: 退出进程
  * - sys_getpid: 获取当前进程ID
+ * - sys_get_time: 获取当前时间
+ * - sys_sleep: 睡眠指定的 tick 数
+ * - sys_yield: 主动让出 CPU
+ * - sys_sched_disable_preempt / sys_sched_enable_preempt: 短暂禁止抢占
+ * - sys_nice / sys_setpriority: 调整进程优先级
+ * - sys_umask: 设置文件创建掩码
+ * - sys_fstatat: 相对目录描述符获取文件状态
+ * - sys_lstat: 获取文件状态，不跟随最后一个符号链接
+ * - sys_unlink: 删除文件的目录项
+ * - sys_sync: 把所有打开文件的缓冲数据写回
+ * - sys_dup: 复制文件描述符（共享偏移）
+ * - sys_dup2: 复制文件描述符到指定编号（重定向）
+ * - sys_eventfd: 创建事件通知文件（计数器）
+ * - sys_timerfd_create / sys_timerfd_settime: 定时器文件
+ * - sys_sysinfo: 开机时间、内存和进程数
+ * - sys_lseek: 移动文件读写偏移
+ * - sys_chroot: 设置进程的根目录
+ * - sys_pipe: 创建匿名管道
+ * - sys_sbrk: 移动进程的堆顶
+ * - sys_sigreturn: 从信号处理函数返回（由信号跳板调用）
  * ============================================
  */
 
 pub mod syscall_impl;
+pub mod error;
+
+pub use error::{SysError, SysResult};
 
 use crate::serial_println;
 
@@ -28,29 +51,75 @@ pub enum SyscallId {
     Read = 63,       // sys_read（第7章新增）
     Write = 64,      // sys_write
     Exit = 93,       // sys_exit
+    Sleep = 101,     // sys_sleep（按 tick 计时，对应 Linux 的 nanosleep）
+    Yield = 124,     // sys_yield（对应 Linux 的 sched_yield）
+    SigReturn = 139, // sys_sigreturn（对应 Linux 的 rt_sigreturn，由信号跳板调用）
+    SetPriority = 140, // sys_setpriority
+    Umask = 166,     // sys_umask
+    GetTime = 169,   // sys_get_time
     GetPid = 172,    // sys_getpid
+    Sysinfo = 179,   // sys_sysinfo
     Fork = 220,      // sys_fork（第6章新增）
     Exec = 221,      // sys_exec（第6章新增）
     WaitPid = 260,   // sys_waitpid（第6章新增）
+    SchedDisablePreempt = 500, // sys_sched_disable_preempt（本内核自定义）
+    SchedEnablePreempt = 501,  // sys_sched_enable_preempt（本内核自定义）
+    Nice = 502,                // sys_nice（本内核自定义，RISC-V Linux 没有 nice 调用号）
+    Lstat = 503,               // sys_lstat（本内核自定义，RISC-V Linux 用 fstatat + AT_SYMLINK_NOFOLLOW）
+    Sbrk = 504,                // sys_sbrk（本内核自定义，参数为增量；Linux 的 brk(214) 参数是新的堆顶地址）
     Open = 56,       // sys_open（第7章新增）
     Close = 57,      // sys_close（第7章新增）
+    Pipe = 59,       // sys_pipe（对应 Linux 的 pipe2，不支持 flags）
     Mkdir = 34,      // sys_mkdir（第7章新增）
+    Unlink = 35,     // sys_unlink（对应 Linux 的 unlinkat，固定 AT_FDCWD，不支持 flags）
+    Chroot = 51,     // sys_chroot
+    Eventfd = 19,    // sys_eventfd（对应 Linux 的 eventfd2）
+    Dup = 23,        // sys_dup
+    Dup2 = 24,       // sys_dup2（对应 Linux 的 dup3，不支持 flags）
+    Lseek = 62,      // sys_lseek
+    Fstatat = 79,    // sys_fstatat
+    Sync = 81,       // sys_sync
+    TimerfdCreate = 85,  // sys_timerfd_create
+    TimerfdSettime = 86, // sys_timerfd_settime
     Unknown = 9999,
 }
 
 impl From<usize> for SyscallId {
     fn from(id: usize) -> Self {
         match id {
+            19 => SyscallId::Eventfd,
+            23 => SyscallId::Dup,
+            24 => SyscallId::Dup2,
             34 => SyscallId::Mkdir,
+            35 => SyscallId::Unlink,
+            51 => SyscallId::Chroot,
             56 => SyscallId::Open,
             57 => SyscallId::Close,
+            59 => SyscallId::Pipe,
+            62 => SyscallId::Lseek,
             63 => SyscallId::Read,
             64 => SyscallId::Write,
+            79 => SyscallId::Fstatat,
+            81 => SyscallId::Sync,
+            85 => SyscallId::TimerfdCreate,
+            86 => SyscallId::TimerfdSettime,
             93 => SyscallId::Exit,
+            101 => SyscallId::Sleep,
+            124 => SyscallId::Yield,
+            139 => SyscallId::SigReturn,
+            140 => SyscallId::SetPriority,
+            166 => SyscallId::Umask,
+            169 => SyscallId::GetTime,
             172 => SyscallId::GetPid,
+            179 => SyscallId::Sysinfo,
             220 => SyscallId::Fork,
             221 => SyscallId::Exec,
             260 => SyscallId::WaitPid,
+            500 => SyscallId::SchedDisablePreempt,
+            501 => SyscallId::SchedEnablePreempt,
+            502 => SyscallId::Nice,
+            503 => SyscallId::Lstat,
+            504 => SyscallId::Sbrk,
             _ => SyscallId::Unknown,
         }
     }
@@ -124,6 +193,23 @@ impl SyscallContext {
         }
     }
 
+    /// 从陷阱现场创建系统调用上下文
+    ///
+    /// # 参数
+    /// - `frame`: trap.S 保存的现场（a7 为调用号，a0-a5 为参数）
+    pub fn from_trap_frame(frame: &crate::trap::TrapFrame) -> Self {
+        Self {
+            syscall_id: frame.arg(7),
+            arg0: frame.arg(0),
+            arg1: frame.arg(1),
+            arg2: frame.arg(2),
+            arg3: frame.arg(3),
+            arg4: frame.arg(4),
+            arg5: frame.arg(5),
+            sepc: frame.sepc,
+        }
+    }
+
     /// 设置返回值
     ///
     /// # Safety
@@ -146,12 +232,16 @@ impl SyscallContext {
 pub fn syscall_dispatcher(context: &SyscallContext) -> isize {
     let syscall_id = SyscallId::from(context.syscall_id);
 
+    if let Some(result) = fast_path(syscall_id) {
+        return result;
+    }
+
     // 可视化输出：显示系统调用信息
     if cfg!(feature = "verbose_syscall") {
         print_syscall_entry(context, syscall_id);
     }
 
-    let result = match syscall_id {
+    let result: SysResult = match syscall_id {
         SyscallId::Read => {
             syscall_impl::sys_read(
                 context.arg0,
@@ -175,15 +265,79 @@ pub fn syscall_dispatcher(context: &SyscallContext) -> isize {
         SyscallId::Close => {
             syscall_impl::sys_close(context.arg0)
         }
+        SyscallId::Dup => {
+            syscall_impl::sys_dup(context.arg0)
+        }
+        SyscallId::Pipe => {
+            syscall_impl::sys_pipe(context.arg0 as *mut [usize; 2])
+        }
+        SyscallId::Chroot => {
+            syscall_impl::sys_chroot(context.arg0 as *const u8)
+        }
+        SyscallId::Dup2 => {
+            syscall_impl::sys_dup2(context.arg0, context.arg1)
+        }
+        SyscallId::Eventfd => {
+            syscall_impl::sys_eventfd(context.arg0 as u32, context.arg1)
+        }
         SyscallId::Mkdir => {
             syscall_impl::sys_mkdir(context.arg0 as *const u8)
         }
+        SyscallId::Unlink => {
+            syscall_impl::sys_unlink(context.arg0 as *const u8)
+        }
+        SyscallId::Lseek => {
+            syscall_impl::sys_lseek(context.arg0, context.arg1 as isize, context.arg2)
+        }
+        SyscallId::Fstatat => {
+            syscall_impl::sys_fstatat(
+                context.arg0 as isize,
+                context.arg1 as *const u8,
+                context.arg2 as *mut crate::fs::Stat,
+                context.arg3,
+            )
+        }
+        SyscallId::Lstat => {
+            syscall_impl::sys_lstat(context.arg0 as *const u8, context.arg1 as *mut crate::fs::Stat)
+        }
+        SyscallId::Sync => {
+            syscall_impl::sys_sync()
+        }
+        SyscallId::Sleep => {
+            syscall_impl::sys_sleep(context.arg0)
+        }
+        SyscallId::Yield => {
+            syscall_impl::sys_yield()
+        }
+        SyscallId::Sbrk => {
+            syscall_impl::sys_sbrk(context.arg0 as isize)
+        }
+        SyscallId::TimerfdCreate => {
+            syscall_impl::sys_timerfd_create(context.arg0, context.arg1)
+        }
+        SyscallId::TimerfdSettime => {
+            syscall_impl::sys_timerfd_settime(
+                context.arg0,
+                context.arg1,
+                context.arg2 as *const crate::fs::TimerSpec,
+                context.arg3 as *mut crate::fs::TimerSpec,
+            )
+        }
         SyscallId::Exit => {
             syscall_impl::sys_exit(context.arg0 as i32)
         }
+        SyscallId::Umask => {
+            syscall_impl::sys_umask(context.arg0 as u32)
+        }
         SyscallId::GetPid => {
             syscall_impl::sys_getpid()
         }
+        SyscallId::GetTime => {
+            syscall_impl::sys_get_time()
+        }
+        SyscallId::Sysinfo => {
+            syscall_impl::sys_sysinfo(context.arg0 as *mut syscall_impl::Sysinfo)
+        }
         SyscallId::Fork => {
             syscall_impl::sys_fork()
         }
@@ -196,6 +350,27 @@ pub fn syscall_dispatcher(context: &SyscallContext) -> isize {
             syscall_impl::sys_waitpid(
                 context.arg0 as isize,
                 context.arg1 as *mut i32,
+                context.arg2,
+            )
+        }
+        SyscallId::SchedDisablePreempt => {
+            syscall_impl::sys_sched_disable_preempt()
+        }
+        SyscallId::SchedEnablePreempt => {
+            syscall_impl::sys_sched_enable_preempt()
+        }
+        SyscallId::Nice => {
+            syscall_impl::sys_nice(context.arg0 as isize)
+        }
+        SyscallId::SigReturn => {
+            // 在处理函数中时已由 trap::syscall_handler 恢复现场，走到这里说明不在处理函数中
+            Err(SysError::InvalidArgument)
+        }
+        SyscallId::SetPriority => {
+            syscall_impl::sys_setpriority(
+                context.arg0,
+                context.arg1,
+                context.arg2 as isize,
             )
         }
         SyscallId::Unknown => {
@@ -204,10 +379,13 @@ pub fn syscall_dispatcher(context: &SyscallContext) -> isize {
                 context.syscall_id,
                 context.syscall_id
             );
-            -1 // ENOSYS
+            Err(SysError::NotImplemented)
         }
     };
 
+    // 唯一的 ABI 转换点：成功返回值，失败返回 -errno
+    let result = error::to_abi(result);
+
     // 可视化输出：显示返回结果
     if cfg!(feature = "verbose_syscall") {
         print_syscall_exit(syscall_id, result);
@@ -216,6 +394,23 @@ pub fn syscall_dispatcher(context: &SyscallContext) -> isize {
     result
 }
 
+/// 只读系统调用的快速路径
+///
+/// # 返回
+/// - Some(result): 已在快速路径处理
+/// - None: 需要走完整的分发流程
+///
+/// # 说明
+/// getpid 读取无锁PID缓存，get_time 直接读 time CSR，
+/// 两者都不获取任何锁，也跳过可视化输出
+fn fast_path(syscall_id: SyscallId) -> Option<isize> {
+    match syscall_id {
+        SyscallId::GetPid => Some(error::to_abi(syscall_impl::sys_getpid())),
+        SyscallId::GetTime => Some(error::to_abi(syscall_impl::sys_get_time())),
+        _ => None,
+    }
+}
+
 /// 打印系统调用入口信息（可视化）
 fn print_syscall_entry(context: &SyscallContext, syscall_id: SyscallId) {
     serial_println!("\n╔════════════════════════════════════════╗");
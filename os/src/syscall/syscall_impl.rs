@@ -2,167 +2,1463 @@
  * ============================================
  * 系统调用具体实现
  * ============================================
+ * 所有实现返回 SysResult，由 syscall_dispatcher 统一转换为 isize
  */
 
 use crate::serial_println;
-use crate::fs::{RAMFS, FD_TABLE};
+use crate::fs::{RAMFS, FD_TABLE, File, FileType, Inode, Stat};
+use super::error::{SysError, SysResult};
+use crate::process::{ProcessId, NICE_MIN, NICE_MAX};
 use alloc::string::String;
 use alloc::sync::Arc;
 use spin::Mutex;
 
+/// 路径最大长度
+const MAX_PATH_LEN: usize = 256;
+
+/// 从用户指针读取以 0 结尾的路径字符串
+///
+/// # 返回
+/// - `BadAddress`: 指针为空
+/// - `NameTooLong`: 超过 MAX_PATH_LEN
+/// - `InvalidArgument`: 不是合法 UTF-8
+fn read_path(path: *const u8) -> SysResult<String> {
+    if path.is_null() {
+        return Err(SysError::BadAddress);
+    }
+
+    unsafe {
+        let mut len = 0;
+        while *path.add(len) != 0 {
+            len += 1;
+            if len > MAX_PATH_LEN {
+                return Err(SysError::NameTooLong);
+            }
+        }
+        let slice = core::slice::from_raw_parts(path, len);
+        core::str::from_utf8(slice)
+            .map(String::from)
+            .map_err(|_| SysError::InvalidArgument)
+    }
+}
+
+/// 按文件描述符取得打开的文件
+fn get_file(fd: usize) -> SysResult<Arc<Mutex<dyn File>>> {
+    FD_TABLE.lock().get(fd).ok_or(SysError::BadFd)
+}
+
+/// 按文件描述符取得打开文件描述（读写和定位使用其中的偏移）
+fn get_description(fd: usize) -> SysResult<Arc<crate::fs::OpenFileDescription>> {
+    FD_TABLE.lock().description(fd).ok_or(SysError::BadFd)
+}
+
+/// 当前进程句柄
+fn current_process() -> SysResult<crate::process::ProcessHandle> {
+    crate::process::current_process().ok_or(SysError::NoProcess)
+}
+
 /// sys_write - 写入数据到文件描述符
-pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
+pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> SysResult {
     if buf.is_null() {
-        return -1;
+        return Err(SysError::BadAddress);
     }
 
     let slice = unsafe { core::slice::from_raw_parts(buf, len) };
 
-    // 获取文件并写入
-    match FD_TABLE.lock().get(fd) {
-        Some(file) => match file.lock().write(slice) {
-            Ok(n) => n as isize,
-            Err(_) => -1,
-        },
-        None => {
-            serial_println!("[SYSCALL] sys_write: invalid fd={}", fd);
-            -1
-        }
-    }
+    // 获取打开文件描述并在其偏移处写入
+    let description = get_description(fd).inspect_err(|_| {
+        serial_println!("[SYSCALL] sys_write: invalid fd={}", fd);
+    })?;
+    let written = description.write(slice)?;
+    Ok(written)
 }
 
 /// sys_read - 从文件描述符读取数据
-pub fn sys_read(fd: usize, buf: *mut u8, len: usize) -> isize {
+pub fn sys_read(fd: usize, buf: *mut u8, len: usize) -> SysResult {
     if buf.is_null() {
-        return -1;
+        return Err(SysError::BadAddress);
     }
 
     let buffer = unsafe { core::slice::from_raw_parts_mut(buf, len) };
 
-    // 获取文件并读取
-    match FD_TABLE.lock().get(fd) {
-        Some(file) => match file.lock().read(buffer) {
-            Ok(n) => n as isize,
-            Err(_) => -1,
-        },
-        None => -1,
+    // 获取打开文件描述并从其偏移处读取
+    let description = get_description(fd)?;
+    let file = description.file();
+    loop {
+        let mut guard = file.lock();
+        match description.read_from(&mut *guard, buffer) {
+            // 可以等待的文件（如 eventfd）：register_reader 已把当前进程标记为阻塞，
+            // 释放文件锁后才让出 CPU（期间已被唤醒则不让出），唤醒后重试
+            Err(crate::fs::FileError::WouldBlock) if guard.register_reader() => {
+                drop(guard);
+                crate::process::finish_block_current_process();
+            }
+            result => return Ok(result?),
+        }
     }
 }
 
+/// lseek whence：从文件开头
+pub const SEEK_SET: usize = 0;
+/// lseek whence：从当前偏移
+pub const SEEK_CUR: usize = 1;
+/// lseek whence：从文件末尾
+pub const SEEK_END: usize = 2;
+
+/// sys_lseek - 移动文件读写偏移
+///
+/// # 参数
+/// - `fd`: 文件描述符
+/// - `offset`: 相对 `whence` 的偏移
+/// - `whence`: SEEK_SET / SEEK_CUR / SEEK_END
+///
+/// # 返回
+/// 新的偏移；whence 未知或 SEEK_SET 的偏移为负时返回 EINVAL，
+/// 不支持定位的文件（终端、管道等）返回 ESPIPE
+pub fn sys_lseek(fd: usize, offset: isize, whence: usize) -> SysResult {
+    use crate::fs::SeekFrom;
+
+    let pos = match whence {
+        SEEK_SET if offset >= 0 => SeekFrom::Start(offset as usize),
+        SEEK_CUR => SeekFrom::Current(offset),
+        SEEK_END => SeekFrom::End(offset),
+        _ => return Err(SysError::InvalidArgument),
+    };
+
+    let new_offset = get_description(fd)?.seek(pos)?;
+    Ok(new_offset)
+}
+
 /// sys_open - 打开文件
-pub fn sys_open(path: *const u8, flags: usize) -> isize {
-    if path.is_null() {
-        return -1;
-    }
+///
+/// # 说明
+/// `flags` 目前只识别 O_APPEND，记录在新的打开文件描述中
+pub fn sys_open(path: *const u8, flags: usize) -> SysResult {
+    let flags = flags as u32 & crate::fs::fd_table::O_APPEND;
 
     // 读取路径字符串
-    let path_str = unsafe {
-        let mut len = 0;
-        while *path.add(len) != 0 {
-            len += 1;
-            if len > 256 {
-                return -1;
-            }
-        }
-        let slice = core::slice::from_raw_parts(path, len);
-        match core::str::from_utf8(slice) {
-            Ok(s) => String::from(s),
-            Err(_) => return -1,
+    let path_str = read_path(path)?;
+
+    // 进程的根目录（chroot 之后是其子树）
+    let root = crate::process::current_root();
+
+    // 设备文件（如调试构建中的 /dev/mem），chroot 之后不可见
+    if Arc::ptr_eq(&root, &RAMFS.root()) {
+        if let Ok(device) = crate::fs::open_device(&path_str) {
+            return FD_TABLE.lock().alloc_with_flags(device, flags).ok_or(SysError::TooManyFiles);
         }
-    };
+    }
 
     // 在根目录查找或创建文件
-    let root = RAMFS.root();
-    let inode = {
-        let root_guard = root.lock();
-        match root_guard.lookup(&path_str) {
-            Ok(inode) => inode,
-            Err(_) => {
-                drop(root_guard);
-                // 文件不存在，创建新文件
-                match RAMFS.create_file(root.clone(), path_str) {
-                    Ok(inode) => inode,
-                    Err(_) => return -1,
-                }
-            }
-        }
+    let existing = root.lock().lookup(&path_str);
+    let inode = match existing {
+        Ok(inode) => inode,
+        // 文件不存在，创建新文件
+        Err(_) => RAMFS.create_file(root.clone(), path_str)?,
     };
 
-    // 打开文件
-    match RAMFS.open_file(inode) {
-        Ok(file) => {
-            let file_arc: Arc<Mutex<dyn crate::fs::File>> = Arc::new(Mutex::new(file));
-            match FD_TABLE.lock().alloc(file_arc) {
-                Some(fd) => fd as isize,
-                None => -1,
-            }
-        }
-        Err(_) => -1,
-    }
+    // 打开文件（目录以目录句柄打开，可用作 *at 调用的 dirfd）
+    let is_dir = inode.lock().file_type() == FileType::Directory;
+    let file_arc: Arc<Mutex<dyn File>> = if is_dir {
+        Arc::new(Mutex::new(RAMFS.open_dir(inode)?))
+    } else {
+        Arc::new(Mutex::new(RAMFS.open_file(inode)?))
+    };
+
+    FD_TABLE.lock().alloc_with_flags(file_arc, flags).ok_or(SysError::TooManyFiles)
 }
 
 /// sys_close - 关闭文件描述符
-pub fn sys_close(fd: usize) -> isize {
+pub fn sys_close(fd: usize) -> SysResult {
     if FD_TABLE.lock().dealloc(fd) {
-        0
+        Ok(0)
+    } else {
+        Err(SysError::BadFd)
+    }
+}
+
+/// sys_dup - 复制文件描述符
+///
+/// # 说明
+/// 新描述符与 `fd` 共享同一个打开文件描述，读写偏移一起前进
+pub fn sys_dup(fd: usize) -> SysResult {
+    let mut table = FD_TABLE.lock();
+    if !table.is_valid(fd) {
+        return Err(SysError::BadFd);
+    }
+    table.dup(fd).ok_or(SysError::TooManyFiles)
+}
+
+/// sys_dup2 - 复制文件描述符到指定编号
+///
+/// # 参数
+/// - `old`: 要复制的描述符
+/// - `new`: 目标描述符（已打开时先关闭）
+///
+/// # 返回
+/// `new`；`old` 无效或 `new` 超出上限时返回 EBADF
+pub fn sys_dup2(old: usize, new: usize) -> SysResult {
+    if FD_TABLE.lock().dup2(old, new) {
+        Ok(new)
     } else {
-        -1
+        Err(SysError::BadFd)
     }
 }
 
+/// sys_eventfd - 创建事件通知文件
+///
+/// # 参数
+/// - `initval`: 计数器初值
+/// - `flags`: EFD_NONBLOCK / EFD_CLOEXEC 的组合
+///
+/// # 返回
+/// 新文件描述符；含有未知标志时返回 EINVAL
+pub fn sys_eventfd(initval: u32, flags: usize) -> SysResult {
+    use crate::fs::eventfd::{EventFd, EFD_CLOEXEC, EFD_NONBLOCK};
+
+    if flags & !(EFD_NONBLOCK | EFD_CLOEXEC) != 0 {
+        return Err(SysError::InvalidArgument);
+    }
+
+    let file: Arc<Mutex<dyn File>> =
+        Arc::new(Mutex::new(EventFd::new(initval as u64, flags & EFD_NONBLOCK != 0)));
+    FD_TABLE.lock().alloc(file).ok_or(SysError::TooManyFiles)
+}
+
+/// sys_pipe - 创建匿名管道
+///
+/// # 参数
+/// - `fds`: 写入 [读端描述符, 写端描述符]
+///
+/// # 返回
+/// 成功返回 0；指针为空返回 EFAULT
+///
+/// # 说明
+/// 读空管道时阻塞，直到有数据写入或写端全部关闭（此时读到 0）
+pub fn sys_pipe(fds: *mut [usize; 2]) -> SysResult {
+    if fds.is_null() {
+        return Err(SysError::BadAddress);
+    }
+
+    let (reader, writer) = crate::fs::pipe::pipe();
+    let reader: Arc<Mutex<dyn File>> = Arc::new(Mutex::new(reader));
+    let writer: Arc<Mutex<dyn File>> = Arc::new(Mutex::new(writer));
+
+    let mut table = FD_TABLE.lock();
+    let read_fd = table.alloc(reader).ok_or(SysError::TooManyFiles)?;
+    let Some(write_fd) = table.alloc(writer) else {
+        table.dealloc(read_fd);
+        return Err(SysError::TooManyFiles);
+    };
+    drop(table);
+
+    unsafe { fds.write([read_fd, write_fd]) };
+    Ok(0)
+}
+
+/// sys_timerfd_create - 创建定时器文件
+///
+/// # 参数
+/// - `clockid`: CLOCK_REALTIME / CLOCK_MONOTONIC（都按 tick 计时）
+/// - `flags`: TFD_NONBLOCK / TFD_CLOEXEC 的组合
+///
+/// # 返回
+/// 新文件描述符；时钟或标志不合法时返回 EINVAL
+pub fn sys_timerfd_create(clockid: usize, flags: usize) -> SysResult {
+    use crate::fs::timerfd::{TimerFd, CLOCK_MONOTONIC, CLOCK_REALTIME, TFD_CLOEXEC, TFD_NONBLOCK};
+
+    if clockid != CLOCK_REALTIME && clockid != CLOCK_MONOTONIC {
+        return Err(SysError::InvalidArgument);
+    }
+    if flags & !(TFD_NONBLOCK | TFD_CLOEXEC) != 0 {
+        return Err(SysError::InvalidArgument);
+    }
+
+    let file: Arc<Mutex<dyn File>> = Arc::new(Mutex::new(TimerFd::new(flags & TFD_NONBLOCK != 0)));
+    FD_TABLE.lock().alloc(file).ok_or(SysError::TooManyFiles)
+}
+
+/// sys_timerfd_settime - 设置定时器
+///
+/// # 参数
+/// - `fd`: 定时器文件描述符
+/// - `flags`: 目前只支持 0（相对时间）
+/// - `new_value`: 新的设置（tick）
+/// - `old_value`: 之前的设置写入位置（可以为空）
+///
+/// # 返回
+/// 不是定时器文件时返回 EINVAL
+pub fn sys_timerfd_settime(
+    fd: usize,
+    flags: usize,
+    new_value: *const crate::fs::TimerSpec,
+    old_value: *mut crate::fs::TimerSpec,
+) -> SysResult {
+    if new_value.is_null() {
+        return Err(SysError::BadAddress);
+    }
+    if flags != 0 {
+        return Err(SysError::InvalidArgument);
+    }
+
+    let spec = unsafe { new_value.read() };
+    let file = get_file(fd)?;
+    let (value, interval) = file.lock().set_timer(spec.value, spec.interval)?;
+    if !old_value.is_null() {
+        unsafe { old_value.write(crate::fs::TimerSpec { interval, value }) };
+    }
+    Ok(0)
+}
+
 /// sys_mkdir - 创建目录
-pub fn sys_mkdir(path: *const u8) -> isize {
-    if path.is_null() {
-        return -1;
+pub fn sys_mkdir(path: *const u8) -> SysResult {
+    let path_str = read_path(path)?;
+
+    let root = crate::process::current_root();
+    RAMFS.create_directory(root, path_str)?;
+    Ok(0)
+}
+
+/// sys_unlink - 删除文件的目录项
+///
+/// # 参数
+/// - `path`: 路径（相对路径从当前工作目录开始，即根目录）
+///
+/// # 返回
+/// 成功返回 0；失败时：
+/// - `NoEntry`: 文件不存在
+/// - `IsDirectory`: 路径是目录
+/// - `InvalidArgument`: 最后一个分量为空、`.` 或 `..`
+///
+/// # 说明
+/// 只跟随父目录路径中的符号链接，最后一个分量是符号链接时删除链接本身。
+/// 文件的链接数减一；降为 0 后数据在最后一个打开的描述符关闭时释放
+pub fn sys_unlink(path: *const u8) -> SysResult {
+    let path_str = read_path(path)?;
+    let (parent_path, name) = match path_str.rsplit_once('/') {
+        Some(("", name)) => ("/", name),
+        Some((parent, name)) => (parent, name),
+        None => ("", path_str.as_str()),
+    };
+    if matches!(name, "" | "." | "..") {
+        return Err(SysError::InvalidArgument);
     }
 
-    let path_str = unsafe {
-        let mut len = 0;
-        while *path.add(len) != 0 {
-            len += 1;
-            if len > 256 {
-                return -1;
-            }
+    let root = crate::process::current_root();
+    let parent = RAMFS.resolve_in(root.clone(), root, parent_path)?;
+    RAMFS.unlink(parent, name)?;
+    Ok(0)
+}
+
+/// sys_chroot - 设置当前进程的根目录
+///
+/// # 参数
+/// - `path`: 新的根目录（相对当前的根解析）
+///
+/// # 返回
+/// 成功返回 0；失败时：
+/// - `NotPermitted`: 调用者不是特权进程
+/// - `NotDirectory`: 路径不是目录
+/// - `NoProcess`: 没有当前进程
+///
+/// # 说明
+/// 之后的绝对路径从新根开始解析；路径不支持 `..`，
+/// 因此进程无法访问新根之外的文件。子进程继承根目录
+pub fn sys_chroot(path: *const u8) -> SysResult {
+    let path_str = read_path(path)?;
+    let process = current_process()?;
+    if !process.lock().is_privileged() {
+        return Err(SysError::NotPermitted);
+    }
+
+    let root = crate::process::current_root();
+    let dir = RAMFS.resolve_in(root.clone(), root, &path_str)?;
+    if dir.lock().file_type() != FileType::Directory {
+        return Err(SysError::NotDirectory);
+    }
+    process.lock().set_root_dir(dir);
+    Ok(0)
+}
+
+/// *at 系列调用：以当前工作目录为起点（当前没有 cwd，即根目录）
+pub const AT_FDCWD: isize = -100;
+
+/// *at 系列调用：不跟随最后一级符号链接
+pub const AT_SYMLINK_NOFOLLOW: usize = 0x100;
+
+/// *at 系列调用：路径为空时作用于 dirfd 本身
+pub const AT_EMPTY_PATH: usize = 0x1000;
+
+/// sys_fstatat - 相对目录描述符获取文件状态
+///
+/// # 参数
+/// - `dirfd`: 起始目录的文件描述符，或 AT_FDCWD
+/// - `path`: 路径（绝对路径时忽略 dirfd）
+/// - `statbuf`: 结果写入位置
+/// - `flags`: AT_EMPTY_PATH / AT_SYMLINK_NOFOLLOW
+///
+/// # 说明
+/// 路径中的符号链接都会被跟随；AT_SYMLINK_NOFOLLOW 时最后一个分量是符号链接则报告链接本身
+pub fn sys_fstatat(dirfd: isize, path: *const u8, statbuf: *mut Stat, flags: usize) -> SysResult {
+    if statbuf.is_null() {
+        return Err(SysError::BadAddress);
+    }
+    if flags & !(AT_EMPTY_PATH | AT_SYMLINK_NOFOLLOW) != 0 {
+        return Err(SysError::InvalidArgument);
+    }
+
+    let path_str = read_path(path)?;
+
+    // 起始目录
+    let root = crate::process::current_root();
+    let start = if dirfd == AT_FDCWD {
+        root.clone()
+    } else {
+        let file = get_file(dirfd as usize)?;
+        let ino = file.lock().ino().ok_or(SysError::BadFd)?;
+        RAMFS.get_by_ino(ino)?
+    };
+
+    let target = if path_str.is_empty() {
+        // 空路径只有在 AT_EMPTY_PATH 时才表示 dirfd 本身
+        if flags & AT_EMPTY_PATH == 0 {
+            return Err(SysError::NoEntry);
         }
-        let slice = core::slice::from_raw_parts(path, len);
-        match core::str::from_utf8(slice) {
-            Ok(s) => String::from(s),
-            Err(_) => return -1,
+        start
+    } else {
+        if start.lock().file_type() != FileType::Directory {
+            return Err(SysError::NotDirectory);
+        }
+        if flags & AT_SYMLINK_NOFOLLOW != 0 {
+            RAMFS.resolve_in_nofollow(root, start, &path_str)?
+        } else {
+            RAMFS.resolve_in(root, start, &path_str)?
         }
     };
 
-    let root = RAMFS.root();
-    match RAMFS.create_directory(root, path_str) {
-        Ok(_) => 0,
-        Err(_) => -1,
+    let stat = target.lock().stat();
+    unsafe {
+        *statbuf = stat;
     }
+    Ok(0)
+}
+
+/// sys_lstat - 获取文件状态，不跟随最后一个符号链接
+///
+/// # 参数
+/// - `path`: 路径（相对路径从当前工作目录开始，即根目录）
+/// - `statbuf`: 结果写入位置
+///
+/// # 返回
+/// 成功返回 0
+///
+/// # 说明
+/// 中间分量的符号链接照常跟随；最后一个分量是符号链接时报告链接本身
+/// （类型为 SymbolicLink，大小为目标路径的长度）
+pub fn sys_lstat(path: *const u8, statbuf: *mut Stat) -> SysResult {
+    sys_fstatat(AT_FDCWD, path, statbuf, AT_SYMLINK_NOFOLLOW)
+}
+
+/// sys_sync - 把所有缓冲的文件数据写回底层存储
+///
+/// # 说明
+/// 所有打开的文件都会被刷新，即使其中某个失败；返回第一个错误
+pub fn sys_sync() -> SysResult {
+    crate::fs::sync()?;
+    Ok(0)
 }
 
 /// sys_exit - 退出进程
-pub fn sys_exit(exit_code: i32) -> isize {
+///
+/// # 说明
+/// 当前进程变为 Zombie，由父进程通过 waitpid 回收；
+/// 没有当前进程（内核上下文）时停在这里
+pub fn sys_exit(exit_code: i32) -> SysResult {
     serial_println!("[SYSCALL] sys_exit({})", exit_code);
-    loop {}
+    crate::process::exit_current_process(exit_code);
+    loop {
+        core::hint::spin_loop();
+    }
 }
 
 /// sys_getpid - 获取当前进程ID
-pub fn sys_getpid() -> isize {
-    1
+///
+/// # 说明
+/// 读取调度器维护的无锁PID缓存，不获取调度器锁；
+/// 没有当前进程（内核上下文）时返回 0
+pub fn sys_getpid() -> SysResult {
+    Ok(crate::process::scheduler::cached_current_pid().map_or(0, |pid| pid.as_usize()))
+}
+
+/// sys_yield - 主动让出 CPU
+///
+/// # 返回
+/// 总是返回 0
+///
+/// # 说明
+/// 当前进程排到同优先级就绪队列的末尾；没有其他就绪进程时立即返回继续运行
+pub fn sys_yield() -> SysResult {
+    crate::process::yield_now();
+    Ok(0)
+}
+
+/// sys_sleep - 让当前进程睡眠
+///
+/// # 参数
+/// - `ticks`: 睡眠的时钟中断数（每秒 trap::TICKS_PER_SECOND 个，与 trap::uptime_ticks 同一时钟）
+///
+/// # 返回
+/// 成功返回 0；没有当前进程可睡眠（内核上下文）时返回 EAGAIN
+///
+/// # 说明
+/// 进程阻塞并进入调度器的睡眠队列，到期后由时钟中断放回就绪队列；
+/// ticks 为 0 时立即返回
+pub fn sys_sleep(ticks: usize) -> SysResult {
+    if ticks == 0 {
+        return Ok(0);
+    }
+    if !crate::process::sleep_current_process(ticks as u64) {
+        return Err(SysError::Again);
+    }
+    Ok(0)
+}
+
+/// sys_sbrk - 移动当前进程的堆顶
+///
+/// # 参数
+/// - `increment`: 堆顶的增量（可以为负，收缩堆）
+///
+/// # 返回
+/// 成功返回之前的堆顶；没有当前进程时返回 ESRCH。
+/// 进程没有堆、堆顶会低于堆底、越过用户栈的增长下界或溢出时返回 ENOMEM
+///
+/// # 说明
+/// increment 为 0 时只查询当前堆顶。
+/// 堆从程序映像末尾开始（见 exec::load_image），目前只移动边界，不映射新页
+pub fn sys_sbrk(increment: isize) -> SysResult {
+    let process = current_process()?;
+    let mut pcb = process.lock();
+    let old_top = pcb.heap_top();
+    let new_top = old_top.checked_add_signed(increment).ok_or(SysError::NoMemory)?;
+    if !pcb.set_heap_top(new_top) {
+        return Err(SysError::NoMemory);
+    }
+    Ok(old_top)
+}
+
+/// sys_get_time - 获取当前时间
+///
+/// # 返回
+/// time 寄存器的 tick 数（QEMU virt 为 10MHz）
+pub fn sys_get_time() -> SysResult {
+    Ok(riscv::register::time::read64() as usize)
+}
+
+/// sys_sysinfo 的结果
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sysinfo {
+    /// 开机以来的秒数
+    pub uptime: u64,
+    /// 物理内存总量（字节）
+    pub totalram: u64,
+    /// 空闲内存（字节）：未分配的物理帧加上内核堆中的空闲空间
+    pub freeram: u64,
+    /// 进程数
+    pub procs: u64,
+}
+
+/// sys_sysinfo - 获取系统信息
+///
+/// # 参数
+/// - `info`: 用户缓冲区，写入 Sysinfo
+///
+/// # 说明
+/// 开机时间由时钟中断计数换算（还没有 RTC）；
+/// 帧分配器尚未安装时内存只统计内核堆
+pub fn sys_sysinfo(info: *mut Sysinfo) -> SysResult {
+    if info.is_null() {
+        return Err(SysError::BadAddress);
+    }
+
+    let (total_frames, free_frames) = crate::memory::with_frame_allocator(|frames| {
+        (frames.total_frames(), frames.free_frames())
+    })
+    .unwrap_or((0, 0));
+    let heap_free = crate::allocator::heap_stats().free_bytes;
+    let procs = crate::process::scheduler::lock_scheduler().process_table().len();
+
+    let sysinfo = Sysinfo {
+        uptime: crate::trap::uptime_seconds(),
+        totalram: (total_frames * crate::memory::PAGE_SIZE) as u64,
+        freeram: (free_frames * crate::memory::PAGE_SIZE + heap_free) as u64,
+        procs: procs as u64,
+    };
+    unsafe { info.write(sysinfo) };
+    Ok(0)
+}
+
+/// sys_umask - 设置文件创建掩码
+///
+/// # 返回
+/// 之前的掩码（总是成功）
+pub fn sys_umask(mask: u32) -> SysResult {
+    Ok(crate::process::set_current_umask(mask) as usize)
+}
+
+/// sys_sched_disable_preempt - 短暂禁止抢占当前进程
+///
+/// # 返回
+/// 成功返回 0，没有当前进程时返回 ESRCH
+///
+/// # 说明
+/// 最多持续 MAX_NO_PREEMPT_TICKS 个时钟中断，之后自动恢复抢占
+pub fn sys_sched_disable_preempt() -> SysResult {
+    current_process()?.lock().disable_preempt();
+    Ok(0)
+}
+
+/// sys_sched_enable_preempt - 恢复当前进程的抢占
+///
+/// # 返回
+/// 成功返回 0，没有当前进程时返回 ESRCH
+pub fn sys_sched_enable_preempt() -> SysResult {
+    current_process()?.lock().enable_preempt();
+    Ok(0)
+}
+
+/// getpriority/nice 返回值的偏移：返回 NICE_BIAS - nice（1..=40），
+/// 与 Linux getpriority 的约定一致，避免负的 nice 值与 -errno 混淆
+pub const NICE_BIAS: i32 = 20;
+
+/// setpriority 的 which：按进程设置
+pub const PRIO_PROCESS: usize = 0;
+
+/// 把进程的 nice 值设置为 `nice`
+///
+/// # 参数
+/// - `process`: 目标进程
+/// - `nice`: 新的 nice 值（超出范围时截断）
+/// - `privileged`: 调用者是否为特权进程
+///
+/// # 返回
+/// - 成功时返回新的 nice 值
+/// - `NotPermitted`: 非特权调用者试图提高优先级（降低 nice 值）
+fn renice(process: &crate::process::ProcessHandle, nice: isize, privileged: bool) -> SysResult<i32> {
+    let nice = nice.clamp(NICE_MIN as isize, NICE_MAX as isize) as i32;
+    let pid = {
+        let pcb = process.lock();
+        if nice < pcb.nice() && !privileged {
+            return Err(SysError::NotPermitted);
+        }
+        pcb.pid()
+    };
+
+    // 在调度器中的进程要按新优先级重新入队
+    if crate::process::scheduler::set_nice(pid, nice).is_none() {
+        process.lock().set_nice(nice);
+    }
+    Ok(nice)
+}
+
+/// sys_nice - 调整当前进程的 nice 值
+///
+/// # 参数
+/// - `increment`: 增量（正数降低优先级，负数提高优先级）
+///
+/// # 返回
+/// 新的 nice 值，编码为 NICE_BIAS - nice；
+/// 非特权进程传入负增量时返回 EPERM
+pub fn sys_nice(increment: isize) -> SysResult {
+    let process = current_process()?;
+    let (current, privileged) = {
+        let pcb = process.lock();
+        (pcb.nice() as isize, pcb.is_privileged())
+    };
+    let nice = renice(&process, current.saturating_add(increment), privileged)?;
+    Ok((NICE_BIAS - nice) as usize)
+}
+
+/// sys_setpriority - 设置进程的 nice 值
+///
+/// # 参数
+/// - `which`: 只支持 PRIO_PROCESS
+/// - `who`: 目标进程PID，0 表示当前进程
+/// - `prio`: 新的 nice 值（超出范围时截断）
+///
+/// # 返回
+/// 成功返回 0；目标不存在返回 ESRCH；
+/// 非特权调用者修改自己及后代以外的进程，或要提高优先级时返回 EPERM
+pub fn sys_setpriority(which: usize, who: usize, prio: isize) -> SysResult {
+    if which != PRIO_PROCESS {
+        return Err(SysError::InvalidArgument);
+    }
+
+    let caller = current_process()?;
+    let target = if who == 0 {
+        caller.clone()
+    } else {
+        crate::process::scheduler::get_process(ProcessId::from_usize(who))
+            .ok_or(SysError::NoProcess)?
+    };
+
+    // 权限取决于调用者而不是目标进程
+    let (caller_pid, privileged) = {
+        let pcb = caller.lock();
+        (pcb.pid(), pcb.is_privileged())
+    };
+    if !privileged && !is_self_or_descendant(&target, caller_pid) {
+        return Err(SysError::NotPermitted);
+    }
+
+    renice(&target, prio, privileged)?;
+    Ok(0)
+}
+
+/// 目标进程是否是 ancestor 本身或它的后代
+///
+/// # 说明
+/// 沿父进程链向上查找；链上的进程已退出时视为不是后代
+fn is_self_or_descendant(target: &crate::process::ProcessHandle, ancestor: ProcessId) -> bool {
+    let (pid, mut parent) = {
+        let pcb = target.lock();
+        (pcb.pid(), pcb.parent_pid())
+    };
+    if pid == ancestor {
+        return true;
+    }
+
+    while let Some(pid) = parent {
+        if pid == ancestor {
+            return true;
+        }
+        parent = match crate::process::scheduler::get_process(pid) {
+            Some(process) => process.lock().parent_pid(),
+            None => return false,
+        };
+    }
+    false
 }
 
 /// sys_fork - 创建子进程
-pub fn sys_fork() -> isize {
+pub fn sys_fork() -> SysResult {
     serial_println!("[SYSCALL] sys_fork: not implemented yet");
-    -1
+    Err(SysError::NotImplemented)
 }
 
 /// sys_exec - 执行程序
-pub fn sys_exec(path: *const u8) -> isize {
-    serial_println!("[SYSCALL] sys_exec: not implemented yet");
-    -1
+///
+/// # 参数
+/// - `path`: 程序名（见 process::builtin）
+///
+/// # 返回
+/// 新进程的 PID；名字没有登记时返回 ENOENT
+///
+/// # 说明
+/// 还不能替换当前进程的映像：已登记的内置程序作为当前进程的子进程启动，
+/// 调用者用 waitpid 等待它结束
+pub fn sys_exec(path: *const u8) -> SysResult {
+    let name = read_path(path)?;
+    let parent = current_process()?.lock().pid();
+
+    let process = crate::process::builtin::spawn(&name, Some(parent))?;
+    let pid = process.lock().pid();
+    if let Err(e) = crate::process::scheduler::add_process(process) {
+        if let Some(parent) = crate::process::scheduler::get_process(parent) {
+            parent.lock().remove_child(pid);
+        }
+        return Err(crate::process::ProcessError::LimitExceeded(e).into());
+    }
+    Ok(pid.as_usize())
 }
 
+/// waitpid 选项：没有已退出的子进程时立即返回 0
+pub const WNOHANG: usize = 1;
+
 /// sys_waitpid - 等待子进程退出
-pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
-    serial_println!("[SYSCALL] sys_waitpid: not implemented yet");
-    -1
+///
+/// # 参数
+/// - `pid`: 子进程 PID；-1 表示任意子进程
+/// - `exit_code_ptr`: 等待状态写入位置（可以为空），用 wifexited 等解码
+/// - `options`: 0 或 WNOHANG
+///
+/// # 返回
+/// 被回收的子进程 PID；没有符合条件的子进程时返回 ECHILD；
+/// WNOHANG 且子进程都还在运行时返回 0
+///
+/// # 说明
+/// 子进程都还在运行时阻塞当前进程，子进程退出时被唤醒
+pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32, options: usize) -> SysResult {
+    let parent = current_process()?.lock().pid();
+    wait_child(parent, pid, exit_code_ptr, options)
+}
+
+/// 回收 `parent` 的子进程（sys_waitpid 的实现）
+///
+/// # 说明
+/// 需要等待时阻塞的是当前进程，调用者应保证 `parent` 就是当前进程
+fn wait_child(parent: ProcessId, pid: isize, exit_code_ptr: *mut i32, options: usize) -> SysResult {
+    let target = match pid {
+        -1 => None,
+        pid if pid > 0 => Some(pid as usize),
+        _ => return Err(SysError::InvalidArgument),
+    };
+    if options & !WNOHANG != 0 {
+        return Err(SysError::InvalidArgument);
+    }
+
+    loop {
+        // 先标记阻塞再检查：检查之后才退出的子进程会把本进程改回就绪，唤醒不会丢失
+        let blocking = options & WNOHANG == 0 && crate::process::prepare_block_current_process();
+        let reaped = crate::process::reap_child(parent, target);
+        if blocking && !matches!(reaped, Ok(None)) {
+            crate::process::cancel_block_current_process();
+        }
+
+        match reaped {
+            Ok(Some((child, status))) => {
+                if !exit_code_ptr.is_null() {
+                    unsafe { exit_code_ptr.write(status.raw()) };
+                }
+                return Ok(child.as_usize());
+            }
+            Ok(None) if options & WNOHANG != 0 => return Ok(0),
+            Ok(None) if !blocking => return Err(SysError::Again),
+            Ok(None) => {
+                crate::process::finish_block_current_process();
+            }
+            Err(_) => return Err(SysError::NoChild),
+        }
+    }
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::permissions::{S_IFDIR, S_IFMT, S_IFREG};
+
+    #[test_case]
+    fn test_fstatat_relative_and_empty_path() {
+        let dir = RAMFS.create_directory(RAMFS.root(), String::from("fstatat_dir")).unwrap();
+        let file = RAMFS.create_file(dir.clone(), String::from("data")).unwrap();
+        file.lock().write_at(0, b"hello").unwrap();
+
+        let dirfd = sys_open(b"fstatat_dir\0".as_ptr(), 0).unwrap() as isize;
+        assert!(dirfd >= 3);
+
+        // 相对目录描述符查找文件
+        let mut stat = Stat::default();
+        assert_eq!(sys_fstatat(dirfd, b"data\0".as_ptr(), &mut stat, 0), Ok(0));
+        assert_eq!(stat.ino, file.lock().ino() as u64);
+        assert_eq!(stat.mode & S_IFMT, S_IFREG);
+        assert_eq!(stat.size, 5);
+
+        // AT_EMPTY_PATH：stat 目录描述符本身
+        let mut stat = Stat::default();
+        assert_eq!(sys_fstatat(dirfd, b"\0".as_ptr(), &mut stat, AT_EMPTY_PATH), Ok(0));
+        assert_eq!(stat.ino, dir.lock().ino() as u64);
+        assert_eq!(stat.mode & S_IFMT, S_IFDIR);
+
+        // 没有 AT_EMPTY_PATH 时空路径是错误
+        assert_eq!(sys_fstatat(dirfd, b"\0".as_ptr(), &mut stat, 0), Err(SysError::NoEntry));
+
+        sys_close(dirfd as usize).unwrap();
+        RAMFS.remove(RAMFS.root(), "fstatat_dir").unwrap();
+    }
+
+    #[test_case]
+    fn test_unlinked_open_file_survives_until_close() {
+        use crate::fs::FileError;
+        use alloc::sync::Arc;
+
+        let dir = RAMFS.create_directory(RAMFS.root(), String::from("unlink_dir")).unwrap();
+        let inode = RAMFS.create_file(dir.clone(), String::from("data")).unwrap();
+        inode.lock().write_at(0, b"survivor").unwrap();
+        assert_eq!(inode.lock().nlinks(), 1);
+        let ino = inode.lock().ino();
+        let weak = Arc::downgrade(&inode);
+        drop(inode);
+
+        let fd = FD_TABLE.lock().alloc(Arc::new(Mutex::new(RAMFS.open_by_ino(ino).unwrap()))).unwrap();
+
+        // 删除目录项：路径消失，链接数为 0
+        assert_eq!(sys_unlink(b"/unlink_dir/data\0".as_ptr()), Ok(0));
+        assert_eq!(RAMFS.resolve(RAMFS.root(), "/unlink_dir/data").err(), Some(FileError::NotFound));
+        assert_eq!(sys_unlink(b"/unlink_dir/data\0".as_ptr()), Err(SysError::NoEntry));
+        assert_eq!(weak.upgrade().unwrap().lock().nlinks(), 0);
+
+        // 仍打开的描述符可以读到数据
+        let mut buf = [0u8; 16];
+        assert_eq!(sys_read(fd, buf.as_mut_ptr(), buf.len()), Ok(8));
+        assert_eq!(&buf[..8], b"survivor");
+        assert!(RAMFS.get_by_ino(ino).is_ok());
+
+        // 关闭最后一个描述符后数据释放
+        sys_close(fd).unwrap();
+        assert!(weak.upgrade().is_none());
+        assert_eq!(RAMFS.get_by_ino(ino).err(), Some(FileError::NotFound));
+
+        // 目录不能 unlink
+        assert_eq!(sys_unlink(b"/unlink_dir\0".as_ptr()), Err(SysError::IsDirectory));
+        assert_eq!(sys_unlink(b"/unlink_dir/\0".as_ptr()), Err(SysError::InvalidArgument));
+        RAMFS.remove(RAMFS.root(), "unlink_dir").unwrap();
+    }
+
+    #[test_case]
+    fn test_lstat_reports_symlink_itself() {
+        use crate::fs::permissions::S_IFLNK;
+
+        let dir = RAMFS.create_directory(RAMFS.root(), String::from("lstat_dir")).unwrap();
+        let file = RAMFS.create_file(dir.clone(), String::from("target")).unwrap();
+        file.lock().write_at(0, b"hello").unwrap();
+        let link = RAMFS.create_symlink(dir.clone(), String::from("link"), "target").unwrap();
+
+        // stat 跟随链接，得到目标文件
+        let mut stat = Stat::default();
+        assert_eq!(sys_fstatat(AT_FDCWD, b"/lstat_dir/link\0".as_ptr(), &mut stat, 0), Ok(0));
+        assert_eq!(stat.ino, file.lock().ino() as u64);
+        assert_eq!(stat.mode & S_IFMT, S_IFREG);
+        assert_eq!(stat.size, 5);
+
+        // lstat 报告链接本身：大小是目标路径的长度
+        let mut lstat = Stat::default();
+        assert_eq!(sys_lstat(b"/lstat_dir/link\0".as_ptr(), &mut lstat), Ok(0));
+        assert_eq!(lstat.ino, link.lock().ino() as u64);
+        assert_eq!(lstat.mode & S_IFMT, S_IFLNK);
+        assert_eq!(lstat.size, "target".len() as u64);
+
+        // 不是符号链接时与 stat 相同
+        assert_eq!(sys_lstat(b"/lstat_dir/target\0".as_ptr(), &mut lstat), Ok(0));
+        assert_eq!(lstat, stat);
+
+        // 悬空链接：lstat 成功，stat 找不到目标
+        RAMFS.remove(dir.clone(), "target").unwrap();
+        assert_eq!(sys_lstat(b"/lstat_dir/link\0".as_ptr(), &mut lstat), Ok(0));
+        assert_eq!(sys_fstatat(AT_FDCWD, b"/lstat_dir/link\0".as_ptr(), &mut stat, 0), Err(SysError::NoEntry));
+
+        RAMFS.remove(dir, "link").unwrap();
+        RAMFS.remove(RAMFS.root(), "lstat_dir").unwrap();
+    }
+
+    #[test_case]
+    fn test_chroot_confines_path_resolution() {
+        use crate::process::{create_process, scheduler};
+
+        // /chroot_home/user/README.txt
+        let home = RAMFS.create_directory(RAMFS.root(), String::from("chroot_home")).unwrap();
+        let user = RAMFS.create_directory(home.clone(), String::from("user")).unwrap();
+        let readme = RAMFS.create_file(user.clone(), String::from("README.txt")).unwrap();
+
+        let jailed = create_process("chroot_jailed", 0x1000, 0x2000, None).unwrap();
+        let jailed_pid = jailed.lock().pid();
+        scheduler::add_process(jailed).unwrap();
+        scheduler::lock_scheduler().run_for_test(jailed_pid);
+        assert_eq!(sys_chroot(b"/chroot_home\0".as_ptr()), Ok(0));
+
+        // 进程的 / 是原来的 /chroot_home
+        let mut stat = Stat::default();
+        assert_eq!(sys_fstatat(AT_FDCWD, b"/user/README.txt\0".as_ptr(), &mut stat, 0), Ok(0));
+        assert_eq!(stat.ino, readme.lock().ino() as u64);
+
+        // 新根之外的路径不可见，也不能用 .. 离开
+        for path in [&b"/chroot_home/user\0"[..], b"/../chroot_home\0", b"/user/../../\0"] {
+            assert!(sys_fstatat(AT_FDCWD, path.as_ptr(), &mut stat, 0).is_err());
+        }
+        assert_eq!(sys_chroot(b"/user/README.txt\0".as_ptr()), Err(SysError::NotDirectory));
+
+        // 新建的文件落在新根之下
+        let fd = sys_open(b"jailed_file\0".as_ptr(), 0).unwrap();
+        sys_close(fd).unwrap();
+        assert!(RAMFS.resolve(RAMFS.root(), "/chroot_home/jailed_file").is_ok());
+
+        // 子进程继承根目录，但非特权进程不能 chroot
+        let child = create_process("chroot_child", 0x1000, 0x2000, Some(jailed_pid)).unwrap();
+        let child_pid = child.lock().pid();
+        scheduler::add_process(child).unwrap();
+        scheduler::lock_scheduler().run_for_test(child_pid);
+        assert_eq!(sys_fstatat(AT_FDCWD, b"/user\0".as_ptr(), &mut stat, 0), Ok(0));
+        assert_eq!(sys_chroot(b"/user\0".as_ptr()), Err(SysError::NotPermitted));
+
+        {
+            let mut scheduler = scheduler::lock_scheduler();
+            scheduler.remove_process(child_pid);
+            scheduler.remove_process(jailed_pid);
+        }
+        RAMFS.remove(user, "README.txt").unwrap();
+        RAMFS.remove(home.clone(), "user").unwrap();
+        RAMFS.remove(home, "jailed_file").unwrap();
+        RAMFS.remove(RAMFS.root(), "chroot_home").unwrap();
+    }
+
+    #[test_case]
+    fn test_sbrk_advances_program_break() {
+        use crate::process::{create_process, scheduler};
+
+        const HEAP: usize = 0x10000;
+        const STACK_LIMIT: usize = 0x20000;
+
+        let process = create_process("sbrk", 0x1000, 0x2000, None).unwrap();
+        let pid = process.lock().pid();
+        scheduler::add_process(process.clone()).unwrap();
+        scheduler::lock_scheduler().run_for_test(pid);
+
+        // 没有装载程序映像的进程没有堆
+        assert_eq!(sys_sbrk(0), Ok(0));
+        assert_eq!(sys_sbrk(0x1000), Err(SysError::NoMemory));
+
+        {
+            let mut pcb = process.lock();
+            pcb.set_heap(HEAP);
+            pcb.set_user_stack_limit(STACK_LIMIT);
+        }
+
+        // 每次返回移动之前的堆顶
+        assert_eq!(sys_sbrk(0x1000), Ok(HEAP));
+        assert_eq!(sys_sbrk(0x800), Ok(HEAP + 0x1000));
+        assert_eq!(sys_sbrk(0), Ok(HEAP + 0x1800));
+
+        // 不能收缩到堆底以下
+        assert_eq!(sys_sbrk(-0x2000), Err(SysError::NoMemory));
+        assert_eq!(sys_sbrk(-0x1800), Ok(HEAP + 0x1800));
+        assert_eq!(sys_sbrk(0), Ok(HEAP));
+
+        // 不能伸进为用户栈保留的范围
+        assert_eq!(sys_sbrk((STACK_LIMIT - HEAP + 1) as isize), Err(SysError::NoMemory));
+        assert_eq!(sys_sbrk((STACK_LIMIT - HEAP) as isize), Ok(HEAP));
+        assert_eq!(sys_sbrk(0), Ok(STACK_LIMIT));
+
+        scheduler::lock_scheduler().remove_process(pid);
+    }
+
+    #[test_case]
+    fn test_pipe_syscall_round_trip() {
+        let mut fds = [0usize; 2];
+        assert_eq!(sys_pipe(&mut fds), Ok(0));
+        let [read_fd, write_fd] = fds;
+
+        assert_eq!(sys_write(write_fd, b"piped".as_ptr(), 5), Ok(5));
+        let mut buf = [0u8; 8];
+        assert_eq!(sys_read(read_fd, buf.as_mut_ptr(), buf.len()), Ok(5));
+        assert_eq!(&buf[..5], b"piped");
+
+        // 两端方向固定，也不支持定位
+        assert_eq!(sys_write(read_fd, b"x".as_ptr(), 1), Err(SysError::BadFd));
+        assert_eq!(sys_lseek(write_fd, 0, SEEK_SET), Err(SysError::IllegalSeek));
+
+        // 写端关闭后读到文件末尾；读端关闭后写入返回 EPIPE
+        let dup_reader = sys_dup(read_fd).unwrap();
+        sys_close(write_fd).unwrap();
+        assert_eq!(sys_read(read_fd, buf.as_mut_ptr(), buf.len()), Ok(0));
+        sys_close(read_fd).unwrap();
+        sys_close(dup_reader).unwrap();
+
+        assert_eq!(sys_pipe(&mut fds), Ok(0));
+        let [read_fd, write_fd] = fds;
+        sys_close(read_fd).unwrap();
+        assert_eq!(sys_write(write_fd, b"x".as_ptr(), 1), Err(SysError::BrokenPipe));
+        sys_close(write_fd).unwrap();
+
+        assert_eq!(sys_pipe(core::ptr::null_mut()), Err(SysError::BadAddress));
+    }
+
+    #[test_case]
+    fn test_lseek_rewinds_written_file() {
+        use crate::syscall::{test_syscall, SyscallId};
+
+        let fd = sys_open(b"lseek_file\0".as_ptr(), 0).unwrap();
+        assert_eq!(sys_write(fd, b"hello".as_ptr(), 5), Ok(5));
+
+        // 经过分发器回到文件开头，再读回写入的内容
+        assert_eq!(test_syscall(SyscallId::Lseek as usize, fd, 0, SEEK_SET), 0);
+        let mut buf = [0u8; 8];
+        assert_eq!(sys_read(fd, buf.as_mut_ptr(), buf.len()), Ok(5));
+        assert_eq!(&buf[..5], b"hello");
+
+        assert_eq!(sys_lseek(fd, -2, SEEK_END), Ok(3));
+        assert_eq!(sys_lseek(fd, 1, SEEK_CUR), Ok(4));
+        assert_eq!(sys_read(fd, buf.as_mut_ptr(), buf.len()), Ok(1));
+        assert_eq!(buf[0], b'o');
+
+        assert_eq!(sys_lseek(fd, -1, SEEK_SET), Err(SysError::InvalidArgument));
+        assert_eq!(sys_lseek(fd, 0, 3), Err(SysError::InvalidArgument));
+        assert_eq!(sys_lseek(99, 0, SEEK_SET), Err(SysError::BadFd));
+
+        sys_close(fd).unwrap();
+        RAMFS.remove(RAMFS.root(), "lseek_file").unwrap();
+    }
+
+    #[test_case]
+    fn test_dup2_shares_file_with_target_fd() {
+        use crate::syscall::{test_syscall, SyscallId};
+
+        const TARGET: usize = 20;
+
+        let fd = sys_open(b"dup2_file\0".as_ptr(), 0).unwrap();
+        assert_eq!(test_syscall(SyscallId::Dup2 as usize, fd, TARGET, 0), TARGET as isize);
+
+        // 两个描述符共享偏移：交替写入依次追加
+        assert_eq!(sys_write(fd, b"ab".as_ptr(), 2), Ok(2));
+        assert_eq!(sys_write(TARGET, b"cd".as_ptr(), 2), Ok(2));
+        assert_eq!(sys_lseek(fd, 0, SEEK_SET), Ok(0));
+        let mut buf = [0u8; 4];
+        assert_eq!(sys_read(TARGET, buf.as_mut_ptr(), buf.len()), Ok(4));
+        assert_eq!(&buf, b"abcd");
+
+        assert_eq!(sys_dup2(99, TARGET), Err(SysError::BadFd));
+        sys_close(TARGET).unwrap();
+        sys_close(fd).unwrap();
+        RAMFS.remove(RAMFS.root(), "dup2_file").unwrap();
+    }
+
+    #[test_case]
+    fn test_syscalls_return_specific_errno() {
+        use crate::syscall::{test_syscall, SyscallId};
+
+        let mut buf = [0u8; 4];
+
+        // 未打开的描述符：EBADF
+        assert_eq!(sys_read(99, buf.as_mut_ptr(), buf.len()), Err(SysError::BadFd));
+        assert_eq!(sys_close(99), Err(SysError::BadFd));
+
+        // 空指针：EFAULT
+        assert_eq!(sys_write(1, core::ptr::null(), 1), Err(SysError::BadAddress));
+
+        // 未知 flags：EINVAL
+        let mut stat = Stat::default();
+        assert_eq!(
+            sys_fstatat(AT_FDCWD, b"\0".as_ptr(), &mut stat, 0x8000),
+            Err(SysError::InvalidArgument)
+        );
+
+        // 分发边界统一转换为 -errno
+        assert_eq!(test_syscall(SyscallId::Close as usize, 99, 0, 0), -9);
+        assert_eq!(test_syscall(SyscallId::Fork as usize, 0, 0, 0), -38);
+        assert_eq!(test_syscall(SyscallId::Exec as usize, 0, 0, 0), -14);
+        assert_eq!(test_syscall(12345, 0, 0, 0), -38);
+    }
+
+    #[test_case]
+    fn test_unprivileged_renice_only_lowers_priority() {
+        let parent = crate::process::create_process_handle("nice_parent", None);
+        let child = crate::process::create_process_handle("nice_child", Some(parent.lock().pid()));
+        assert!(parent.lock().is_privileged());
+        assert!(!child.lock().is_privileged());
+
+        // 非特权进程可以降低自己的优先级，超出范围时截断
+        assert_eq!(renice(&child, 5, false), Ok(5));
+        assert_eq!(renice(&child, 100, false), Ok(NICE_MAX));
+
+        // 但不能再提高回去
+        assert_eq!(renice(&child, 0, false), Err(SysError::NotPermitted));
+        assert_eq!(child.lock().nice(), NICE_MAX);
+
+        // 特权调用者可以
+        assert_eq!(renice(&child, -100, true), Ok(NICE_MIN));
+        assert_eq!(sys_setpriority(7, 0, 0), Err(SysError::InvalidArgument));
+    }
+
+    #[test_case]
+    fn test_setpriority_limited_to_self_and_descendants() {
+        use crate::process::{scheduler, spawn_test_process};
+
+        let root = spawn_test_process("prio_root", None);
+        let caller = spawn_test_process("prio_caller", Some(&root));
+        let child = spawn_test_process("prio_child", Some(&caller));
+        let grandchild = spawn_test_process("prio_grandchild", Some(&child));
+        let sibling = spawn_test_process("prio_sibling", Some(&root));
+        let [root_pid, caller_pid, child_pid, grandchild_pid, sibling_pid] =
+            [&root, &caller, &child, &grandchild, &sibling].map(|process| process.lock().pid());
+
+        // 非特权进程可以修改自己和后代
+        scheduler::lock_scheduler().run_for_test(caller_pid);
+        assert_eq!(sys_setpriority(PRIO_PROCESS, 0, 1), Ok(0));
+        assert_eq!(sys_setpriority(PRIO_PROCESS, caller_pid.as_usize(), 2), Ok(0));
+        assert_eq!(sys_setpriority(PRIO_PROCESS, grandchild_pid.as_usize(), 5), Ok(0));
+        assert_eq!(caller.lock().nice(), 2);
+        assert_eq!(grandchild.lock().nice(), 5);
+
+        // 兄弟进程和祖先不行
+        assert_eq!(sys_setpriority(PRIO_PROCESS, sibling_pid.as_usize(), 19), Err(SysError::NotPermitted));
+        assert_eq!(sys_setpriority(PRIO_PROCESS, root_pid.as_usize(), 19), Err(SysError::NotPermitted));
+        assert_eq!(sibling.lock().nice(), 0);
+        assert_eq!(root.lock().nice(), 0);
+
+        // 特权进程可以修改任意进程
+        scheduler::lock_scheduler().run_for_test(root_pid);
+        assert_eq!(sys_setpriority(PRIO_PROCESS, sibling_pid.as_usize(), 19), Ok(0));
+        assert_eq!(sibling.lock().nice(), 19);
+
+        {
+            let mut scheduler = scheduler::lock_scheduler();
+            for pid in [grandchild_pid, child_pid, sibling_pid, caller_pid, root_pid] {
+                scheduler.remove_process(pid);
+            }
+        }
+
+        // 当前进程被移除后 nice/setpriority 返回 ESRCH
+        assert_eq!(sys_nice(1), Err(SysError::NoProcess));
+        assert_eq!(sys_setpriority(PRIO_PROCESS, 0, 0), Err(SysError::NoProcess));
+    }
+
+    #[test_case]
+    fn test_sync_flushes_buffered_files() {
+        use crate::fs::{FileError, RamInode};
+        use alloc::vec::Vec;
+
+        /// 带写回缓冲的文件：write 只写入缓冲，flush 时落到 inode
+        struct BufferedFile {
+            inode: Arc<Mutex<RamInode>>,
+            pending: Vec<u8>,
+            offset: usize,
+        }
+
+        impl File for BufferedFile {
+            fn read(&mut self, _buf: &mut [u8]) -> Result<usize, FileError> {
+                Err(FileError::InvalidOperation)
+            }
+
+            fn write(&mut self, buf: &[u8]) -> Result<usize, FileError> {
+                self.pending.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> Result<(), FileError> {
+                let written = self.inode.lock().write_at(self.offset, &self.pending)?;
+                self.offset += written;
+                self.pending.clear();
+                Ok(())
+            }
+        }
+
+        let a = RAMFS.create_file(RAMFS.root(), String::from("sync_a")).unwrap();
+        let b = RAMFS.create_file(RAMFS.root(), String::from("sync_b")).unwrap();
+        let open = |inode: &Arc<Mutex<RamInode>>| {
+            let file: Arc<Mutex<dyn File>> = Arc::new(Mutex::new(BufferedFile {
+                inode: inode.clone(),
+                pending: Vec::new(),
+                offset: 0,
+            }));
+            FD_TABLE.lock().alloc(file).unwrap()
+        };
+        let fd_a = open(&a);
+        let fd_b = open(&b);
+
+        sys_write(fd_a, b"hello ".as_ptr(), 6).unwrap();
+        sys_write(fd_b, b"other".as_ptr(), 5).unwrap();
+        sys_write(fd_a, b"world".as_ptr(), 5).unwrap();
+
+        // 数据还在缓冲中，inode 没有变化
+        assert_eq!(a.lock().size(), 0);
+        assert_eq!(b.lock().size(), 0);
+
+        assert_eq!(sys_sync(), Ok(0));
+
+        let mut buf = [0u8; 16];
+        let n = a.lock().read_at(0, &mut buf).unwrap();
+        assert_eq!(&buf[..n], b"hello world");
+        let n = b.lock().read_at(0, &mut buf).unwrap();
+        assert_eq!(&buf[..n], b"other");
+
+        sys_close(fd_a).unwrap();
+        sys_close(fd_b).unwrap();
+        RAMFS.remove(RAMFS.root(), "sync_a").unwrap();
+        RAMFS.remove(RAMFS.root(), "sync_b").unwrap();
+    }
+
+    #[test_case]
+    fn test_eventfd_syscalls() {
+        use crate::fs::eventfd::EFD_NONBLOCK;
+
+        let fd = sys_eventfd(0, 0).unwrap();
+        for value in [4u64, 5, 6] {
+            assert_eq!(sys_write(fd, value.to_le_bytes().as_ptr(), 8), Ok(8));
+        }
+        let mut buf = [0u8; 8];
+        assert_eq!(sys_read(fd, buf.as_mut_ptr(), 8), Ok(8));
+        assert_eq!(u64::from_le_bytes(buf), 15);
+
+        // 计数器为零：内核上下文无法阻塞，返回 EAGAIN 而不是挂起
+        if crate::process::current_process().is_none() {
+            assert_eq!(sys_read(fd, buf.as_mut_ptr(), 8), Err(SysError::Again));
+        }
+
+        // 非阻塞模式直接返回 EAGAIN；初值可以直接读出
+        let nonblocking = sys_eventfd(3, EFD_NONBLOCK).unwrap();
+        assert_eq!(sys_read(nonblocking, buf.as_mut_ptr(), 8), Ok(8));
+        assert_eq!(u64::from_le_bytes(buf), 3);
+        assert_eq!(sys_read(nonblocking, buf.as_mut_ptr(), 8), Err(SysError::Again));
+
+        assert_eq!(sys_eventfd(0, 1), Err(SysError::InvalidArgument));
+
+        sys_close(fd).unwrap();
+        sys_close(nonblocking).unwrap();
+    }
+
+    #[test_case]
+    fn test_waitpid_reaps_zombie_child_with_exit_code() {
+        use crate::process::{exit_process, scheduler, spawn_test_process};
+
+        let parent = spawn_test_process("wait_parent", None);
+        let parent_pid = parent.lock().pid();
+        let child = spawn_test_process("wait_child", Some(&parent));
+        let child_pid = child.lock().pid();
+
+        exit_process(&child, 42);
+
+        let mut code = 0;
+        assert_eq!(wait_child(parent_pid, -1, &mut code, 0), Ok(child_pid.as_usize()));
+        assert!(crate::process::wifexited(code));
+        assert_eq!(crate::process::wexitstatus(code), 42);
+
+        // 僵尸进程已被回收
+        assert!(scheduler::get_process(child_pid).is_none());
+        assert!(parent.lock().children().is_empty());
+
+        // 没有符合条件的子进程：ECHILD
+        assert_eq!(wait_child(parent_pid, -1, &mut code, 0), Err(SysError::NoChild));
+        assert_eq!(wait_child(parent_pid, child_pid.as_usize() as isize, &mut code, 0), Err(SysError::NoChild));
+        assert_eq!(wait_child(parent_pid, 0, &mut code, 0), Err(SysError::InvalidArgument));
+
+        scheduler::lock_scheduler().remove_process(parent_pid);
+    }
+
+    #[test_case]
+    fn test_waitpid_status_distinguishes_exit_and_signal() {
+        use crate::process::{
+            exit_process, kill_process, scheduler, spawn_test_process, wexitstatus, wifexited,
+            wifsignaled, wtermsig, SIGKILL,
+        };
+
+        let parent = spawn_test_process("status_parent", None);
+        let parent_pid = parent.lock().pid();
+        let exited = spawn_test_process("status_exit", Some(&parent));
+        let killed = spawn_test_process("status_kill", Some(&parent));
+
+        exit_process(&exited, 0);
+        kill_process(&killed, SIGKILL);
+
+        let mut status = -1;
+        let pid = exited.lock().pid().as_usize();
+        assert_eq!(wait_child(parent_pid, pid as isize, &mut status, 0), Ok(pid));
+        assert!(wifexited(status) && !wifsignaled(status));
+        assert_eq!(wexitstatus(status), 0);
+
+        let pid = killed.lock().pid().as_usize();
+        assert_eq!(wait_child(parent_pid, pid as isize, &mut status, 0), Ok(pid));
+        assert!(wifsignaled(status) && !wifexited(status));
+        assert_eq!(wtermsig(status), SIGKILL);
+
+        scheduler::lock_scheduler().remove_process(parent_pid);
+    }
+
+    #[test_case]
+    fn test_timerfd_syscalls() {
+        use crate::fs::timerfd::{TimerSpec, CLOCK_MONOTONIC, TFD_NONBLOCK};
+
+        let fd = sys_timerfd_create(CLOCK_MONOTONIC, TFD_NONBLOCK).unwrap();
+        let mut buf = [0u8; 8];
+        assert_eq!(sys_read(fd, buf.as_mut_ptr(), 8), Err(SysError::Again));
+
+        let spec = TimerSpec { interval: 0, value: 4 };
+        let mut old = TimerSpec { interval: 9, value: 9 };
+        assert_eq!(sys_timerfd_settime(fd, 0, &spec, &mut old), Ok(0));
+        assert_eq!(old, TimerSpec::default());
+
+        crate::trap::advance_ticks(4);
+        assert_eq!(sys_read(fd, buf.as_mut_ptr(), 8), Ok(8));
+        assert_eq!(u64::from_le_bytes(buf), 1);
+
+        // 普通文件不是定时器；未知时钟
+        assert_eq!(sys_timerfd_settime(1, 0, &spec, core::ptr::null_mut()), Err(SysError::InvalidArgument));
+        assert_eq!(sys_timerfd_create(7, 0), Err(SysError::InvalidArgument));
+
+        sys_close(fd).unwrap();
+    }
+
+    #[test_case]
+    fn test_sysinfo_reports_uptime_and_processes() {
+        use crate::process::{create_process, scheduler};
+
+        let process = create_process("sysinfo", 0x1000, 0x2000, None).unwrap();
+        let pid = process.lock().pid();
+        scheduler::add_process(process).unwrap();
+        crate::trap::advance_ticks(crate::trap::TICKS_PER_SECOND);
+
+        let mut info = Sysinfo::default();
+        assert_eq!(sys_sysinfo(&mut info), Ok(0));
+        assert!(info.uptime >= 1);
+        let max = scheduler::lock_scheduler().max_processes() as u64;
+        assert!(info.procs >= 1 && info.procs <= max);
+        assert!(info.freeram > 0);
+
+        assert_eq!(sys_sysinfo(core::ptr::null_mut()), Err(SysError::BadAddress));
+        scheduler::lock_scheduler().remove_process(pid);
+    }
+
+    #[test_case]
+    fn test_waitpid_wnohang_polls_without_blocking() {
+        use crate::process::{exit_process, scheduler, spawn_test_process, wexitstatus};
+
+        let parent = spawn_test_process("wnohang_parent", None);
+        let parent_pid = parent.lock().pid();
+        let child = spawn_test_process("wnohang_child", Some(&parent));
+        let child_pid = child.lock().pid();
+
+        // 子进程还在运行：立即返回 0，状态不写入
+        let mut status = -1;
+        assert_eq!(wait_child(parent_pid, -1, &mut status, WNOHANG), Ok(0));
+        assert_eq!(status, -1);
+        assert!(scheduler::get_process(child_pid).is_some());
+
+        // 子进程退出后回收它
+        exit_process(&child, 3);
+        assert_eq!(wait_child(parent_pid, -1, &mut status, WNOHANG), Ok(child_pid.as_usize()));
+        assert_eq!(wexitstatus(status), 3);
+        assert!(scheduler::get_process(child_pid).is_none());
+        assert!(parent.lock().children().is_empty());
+
+        assert_eq!(wait_child(parent_pid, -1, &mut status, WNOHANG), Err(SysError::NoChild));
+        assert_eq!(wait_child(parent_pid, -1, &mut status, 0x8), Err(SysError::InvalidArgument));
+
+        scheduler::lock_scheduler().remove_process(parent_pid);
+    }
+
+    #[test_case]
+    fn test_exec_spawns_builtin_by_name() {
+        use crate::process::{builtin, scheduler, spawn_test_process};
+
+        fn exec_main() -> ! {
+            loop {
+                core::hint::spin_loop();
+            }
+        }
+
+        assert!(builtin::register("exec_by_name", exec_main));
+        let parent = spawn_test_process("exec_parent", None);
+        let parent_pid = parent.lock().pid();
+        scheduler::lock_scheduler().run_for_test(parent_pid);
+
+        // 按名字启动的程序成为当前进程的子进程
+        assert!(parent.lock().children().is_empty());
+        let pid = sys_exec(b"exec_by_name\0".as_ptr()).unwrap();
+        let child_pid = *parent.lock().children().first().unwrap();
+        assert_eq!(child_pid.as_usize(), pid);
+        let child = scheduler::get_process(child_pid).unwrap();
+        {
+            let pcb = child.lock();
+            assert_eq!(pcb.name(), "exec_by_name");
+            assert_eq!(pcb.parent_pid(), Some(parent_pid));
+            assert_eq!(pcb.context().a0, exec_main as fn() -> ! as usize);
+        }
+
+        // 没有注册的名字
+        assert_eq!(sys_exec(b"exec_missing\0".as_ptr()), Err(SysError::NoEntry));
+
+        scheduler::lock_scheduler().remove_process(child_pid);
+        scheduler::lock_scheduler().remove_process(parent_pid);
+    }
 }
@@ -0,0 +1,307 @@
+/*
+ * ============================================
+ * 关机
+ * ============================================
+ * 功能：有序地关闭系统
+ *
+ * 关机流程：
+ * 1. 向所有存活的进程发送 SIGTERM（默认动作是终止，进程下次返回用户态时生效）
+ * 2. 等待宽限期（SHUTDOWN_GRACE_TICKS），进程全部退出时提前结束
+ * 3. 仍未退出的进程用 SIGKILL 终止
+ * 4. 同步文件系统（RamFS 上是空操作，有持久化后才真正写回）
+ * 5. 等待串口输出发送完
+ * 6. 通过 SBI 关机
+ *
+ * 触发方式：SysRq o 请求内核执行器退出，kernel_main 随后进入上述流程
+ *
+ * 各步骤通过 ShutdownOps 执行，测试中可以替换掉同步和关机
+ * ============================================
+ */
+
+use crate::fs::FileError;
+use crate::process::{self, scheduler, ProcessHandle, SIGKILL, SIGTERM};
+use crate::serial_println;
+use crate::task::executor::Executor;
+use crate::trap::TICKS_PER_SECOND;
+use alloc::vec::Vec;
+
+/// SIGTERM 之后等待进程自行退出的时间（tick）
+pub const SHUTDOWN_GRACE_TICKS: u64 = 3 * TICKS_PER_SECOND;
+
+/// 关机流程中与硬件和文件系统打交道的步骤
+pub trait ShutdownOps {
+    /// 需要终止的进程（不含当前进程；idle 进程即使列出也会被跳过）
+    fn processes(&self) -> Vec<ProcessHandle>;
+    /// 当前时间（tick）
+    fn now(&self) -> u64;
+    /// 宽限期内等待一会儿（让出 CPU 或等待中断）
+    fn idle(&mut self);
+    /// 同步文件系统
+    fn sync(&mut self) -> Result<(), FileError>;
+    /// 等待日志输出完
+    fn flush_log(&mut self);
+    /// 关闭电源
+    fn poweroff(&mut self);
+}
+
+/// 实际机器上的关机步骤
+struct Machine;
+
+impl ShutdownOps for Machine {
+    fn processes(&self) -> Vec<ProcessHandle> {
+        let scheduler = scheduler::lock_scheduler();
+        let current = scheduler.current_pid();
+        let processes = scheduler
+            .process_table()
+            .read()
+            .iter()
+            .filter(|(&pid, _)| Some(pid) != current && !pid.is_idle())
+            .map(|(_, process)| process.clone())
+            .collect();
+        processes
+    }
+
+    fn now(&self) -> u64 {
+        crate::trap::uptime_ticks()
+    }
+
+    fn idle(&mut self) {
+        if !process::maybe_yield() {
+            riscv::asm::wfi();
+        }
+    }
+
+    fn sync(&mut self) -> Result<(), FileError> {
+        crate::fs::sync()
+    }
+
+    fn flush_log(&mut self) {
+        crate::serial::flush();
+    }
+
+    fn poweroff(&mut self) {
+        crate::exit_qemu(crate::QemuExitCode::Success);
+    }
+}
+
+/// 按 ShutdownOps 执行关机流程
+///
+/// # 返回
+/// 宽限期后被 SIGKILL 终止的进程数
+///
+/// # 说明
+/// 同步失败只打印警告，不中断关机
+pub fn run_shutdown(ops: &mut dyn ShutdownOps) -> usize {
+    let processes: Vec<ProcessHandle> = ops
+        .processes()
+        .into_iter()
+        .filter(|process| {
+            let pcb = process.lock();
+            !pcb.is_zombie() && !pcb.pid().is_idle()
+        })
+        .collect();
+    let alive = || processes.iter().filter(|process| !process.lock().is_zombie());
+
+    serial_println!("[SHUTDOWN] Sending SIGTERM to {} processes", processes.len());
+    for process in &processes {
+        let pid = process.lock().pid();
+        process::send_signal(pid, SIGTERM);
+    }
+
+    let deadline = ops.now() + SHUTDOWN_GRACE_TICKS;
+    while alive().next().is_some() && ops.now() < deadline {
+        ops.idle();
+    }
+
+    let survivors: Vec<&ProcessHandle> = alive().collect();
+    for process in &survivors {
+        process::kill_process(process, SIGKILL);
+    }
+    if !survivors.is_empty() {
+        serial_println!("[SHUTDOWN] Killed {} processes with SIGKILL", survivors.len());
+    }
+
+    serial_println!("[SHUTDOWN] Syncing filesystems");
+    if let Err(e) = ops.sync() {
+        serial_println!("[SHUTDOWN] Warning: sync failed: {}", e);
+    }
+
+    serial_println!("[SHUTDOWN] Powering off");
+    ops.flush_log();
+    ops.poweroff();
+    survivors.len()
+}
+
+/// 关闭系统
+///
+/// # 说明
+/// 流程见模块说明；正常启动的系统经 run_until_shutdown 调用到这里
+pub fn shutdown() -> ! {
+    run_shutdown(&mut Machine);
+    crate::hlt_loop();
+}
+
+/// 运行内核执行器，收到关机请求后关闭系统（kernel_main 的最后一步）
+pub fn run_until_shutdown(executor: &mut Executor) -> ! {
+    serve_until_shutdown(executor, &mut Machine);
+    crate::hlt_loop();
+}
+
+/// 运行执行器直到收到关机请求，再按 ops 执行关机流程
+///
+/// # 返回
+/// 宽限期后被 SIGKILL 终止的进程数
+fn serve_until_shutdown(executor: &mut Executor, ops: &mut dyn ShutdownOps) -> usize {
+    executor.run();
+    serial_println!("[SHUTDOWN] Executor stopped");
+    run_shutdown(ops)
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::signal::{self, SIG_IGN};
+    use crate::process::create_process;
+    use crate::trap::TrapFrame;
+
+    /// 记录调用的关机步骤；每次等待时各进程"运行"一次，
+    /// 像返回用户态前那样递送待处理信号
+    struct MockOps {
+        processes: Vec<ProcessHandle>,
+        now: u64,
+        synced: bool,
+        flushed: bool,
+        powered_off: bool,
+    }
+
+    impl ShutdownOps for MockOps {
+        fn processes(&self) -> Vec<ProcessHandle> {
+            self.processes.clone()
+        }
+
+        fn now(&self) -> u64 {
+            self.now
+        }
+
+        fn idle(&mut self) {
+            for process in &self.processes {
+                if !process.lock().is_zombie() {
+                    signal::deliver_to(process, &mut TrapFrame::new());
+                }
+            }
+            self.now += 1;
+        }
+
+        fn sync(&mut self) -> Result<(), FileError> {
+            self.synced = true;
+            Ok(())
+        }
+
+        fn flush_log(&mut self) {
+            assert!(self.synced);
+            self.flushed = true;
+        }
+
+        fn poweroff(&mut self) {
+            assert!(self.flushed);
+            self.powered_off = true;
+        }
+    }
+
+    #[test_case]
+    fn test_shutdown_terminates_processes_then_powers_off() {
+        let processes: Vec<ProcessHandle> = ["shutdown_a", "shutdown_b", "shutdown_c"]
+            .into_iter()
+            .map(|name| {
+                let process = create_process(name, 0x1000, 0x2000, None).unwrap();
+                scheduler::add_process(process.clone()).unwrap();
+                process
+            })
+            .collect();
+
+        let mut ops = MockOps {
+            processes: processes.clone(),
+            now: 0,
+            synced: false,
+            flushed: false,
+            powered_off: false,
+        };
+
+        // 第一个进程按 SIGTERM 的默认动作终止；第二个忽略 SIGTERM，
+        // 第三个的处理函数没有退出，两者宽限期后被 SIGKILL 终止
+        processes[1].lock().signals_mut().set_handler(SIGTERM, SIG_IGN);
+        processes[2].lock().signals_mut().set_handler(SIGTERM, 0x1_2340);
+        assert_eq!(run_shutdown(&mut ops), 2);
+        assert_eq!(ops.now, SHUTDOWN_GRACE_TICKS);
+        assert!(ops.synced && ops.flushed && ops.powered_off);
+
+        assert!(processes.iter().all(|process| process.lock().is_zombie()));
+        assert_eq!(processes[0].lock().term_signal(), Some(SIGTERM));
+        for process in &processes[1..] {
+            assert_eq!(process.lock().term_signal(), Some(SIGKILL));
+        }
+        assert_eq!(processes[2].lock().signals().saved_frame().unwrap().sepc, 0);
+
+        for process in &processes {
+            scheduler::lock_scheduler().remove_process(process.lock().pid());
+        }
+    }
+
+    #[test_case]
+    fn test_shutdown_never_signals_idle() {
+        use crate::process::{ProcessControlBlock, ProcessId};
+        use alloc::sync::Arc;
+        use spin::Mutex;
+
+        let worker = create_process("shutdown_worker", 0x1000, 0x2000, None).unwrap();
+        let idle: ProcessHandle = Arc::new(Mutex::new(ProcessControlBlock::with_pid(
+            ProcessId::IDLE,
+            "idle",
+            None,
+        )));
+
+        let mut ops = MockOps {
+            processes: alloc::vec![worker.clone(), idle.clone()],
+            now: 0,
+            synced: false,
+            flushed: false,
+            powered_off: false,
+        };
+
+        // 只有 worker 需要终止，它在宽限期内退出
+        assert_eq!(run_shutdown(&mut ops), 0);
+        assert!(worker.lock().is_zombie());
+        let idle = idle.lock();
+        assert!(!idle.is_zombie());
+        assert!(!idle.signals().is_pending(SIGTERM));
+    }
+
+    #[test_case]
+    fn test_sysrq_shutdown_stops_executor_and_runs_cleanup() {
+        use crate::task::{executor, keyboard, sysrq::SYSRQ_PREFIX, Task};
+
+        let mut ops = MockOps {
+            processes: Vec::new(),
+            now: 0,
+            synced: false,
+            flushed: false,
+            powered_off: false,
+        };
+
+        // 键盘输入 SysRq o，执行器退出后执行 kernel_main 的关机收尾
+        let mut executor = Executor::new();
+        executor.spawn(Task::new(async {
+            for &byte in SYSRQ_PREFIX.iter().chain(b"o") {
+                keyboard::add_scancode(byte);
+            }
+        }));
+        assert_eq!(serve_until_shutdown(&mut executor, &mut ops), 0);
+
+        assert!(ops.synced && ops.flushed && ops.powered_off);
+        assert!(!executor::shutdown_requested());
+    }
+}
@@ -5,8 +5,12 @@
 
 use crate::println;
 use crate::fs::{RAMFS, File, Inode};
-use crate::process::{create_process, SCHEDULER};
+use crate::fs::tar::{self, ExtractStats};
+use crate::process::exec::{self, ExecError};
+use crate::process::{create_process, scheduler, ProcessHandle};
 use alloc::string::String;
+use alloc::vec::Vec;
+use spin::Mutex;
 
 /// Delay function (for visualization demo)
 /// Note: Delay is completely disabled for fast demonstration
@@ -15,9 +19,10 @@ fn delay() {
 }
 
 /// Short delay (for quick demonstration)
-/// Note: Delay is completely disabled for fast demonstration
+/// Note: Delay is completely disabled for fast demonstration; the demo steps
+/// only use it as a yield point, so a pending reschedule is honoured between steps
 fn short_delay() {
-    // Delay completely disabled
+    crate::process::maybe_yield();
 }
 
 /// Print separator line
@@ -30,6 +35,128 @@ fn print_small_separator() {
     println!("----------------------------------------");
 }
 
+/// Init program path used when the bootargs don't give one
+pub const DEFAULT_INIT_PATH: &str = "/sbin/init";
+
+/// Built-in init entry point (used when there is no init program)
+const BUILTIN_INIT_ENTRY: usize = 0x8000_0000;
+
+/// init stack top
+const INIT_STACK_TOP: usize = 0x8001_0000;
+
+/// Configured init program path (None means DEFAULT_INIT_PATH)
+static INIT_PATH: Mutex<Option<String>> = Mutex::new(None);
+
+/// Parse init=<path> from the boot arguments
+pub fn init_path_from_bootargs(bootargs: &str) -> Option<&str> {
+    bootargs
+        .split_whitespace()
+        .find_map(|arg| arg.strip_prefix("init="))
+        .filter(|path| !path.is_empty())
+}
+
+/// Set (or reset to the default with None) the init program path
+pub fn set_init_path(path: Option<&str>) {
+    *INIT_PATH.lock() = path.map(String::from);
+}
+
+/// Path the init program is loaded from
+pub fn init_path() -> String {
+    INIT_PATH
+        .lock()
+        .clone()
+        .unwrap_or_else(|| String::from(DEFAULT_INIT_PATH))
+}
+
+/// Called at boot: read init=<path> and idle=<strategy> from the device tree bootargs
+///
+/// # Parameters
+/// - `dtb`: device tree physical address (a1 at _start), 0 if there is none
+pub fn configure(dtb: usize) {
+    let fdt = unsafe { crate::fdt::Fdt::from_addr(dtb) };
+    let bootargs = fdt
+        .as_ref()
+        .and_then(|fdt| fdt.property_str("/chosen", "bootargs"));
+    let path = bootargs.and_then(init_path_from_bootargs);
+    if let Some(path) = path {
+        crate::serial_println!("[INIT] init program: {}", path);
+    }
+    set_init_path(path);
+
+    if let Some(strategy) = bootargs.and_then(crate::idle::strategy_from_bootargs) {
+        crate::serial_println!("[INIT] idle strategy: {:?}", strategy);
+        crate::idle::set_strategy(strategy);
+    }
+}
+
+/// Where the init process came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InitSource {
+    /// Loaded from the ELF at the configured path, with this entry point
+    Program(usize),
+    /// Built-in setup (no usable init program)
+    BuiltIn,
+}
+
+/// Read the init program
+///
+/// Returns None (after printing the reason) if the file is missing
+/// or cannot be read
+fn read_init_program(path: &str) -> Option<Vec<u8>> {
+    let inode = match RAMFS.resolve(RAMFS.root(), path) {
+        Ok(inode) => inode,
+        Err(_) => {
+            println!("  [INFO] {} not found, using built-in init", path);
+            return None;
+        }
+    };
+
+    match RAMFS.open_file(inode).and_then(|mut file| file.read_all()) {
+        Ok(data) => Some(data),
+        Err(e) => {
+            println!("  [ERR] Failed to read {}: {}, using built-in init", path, e);
+            None
+        }
+    }
+}
+
+/// Load the init program into the process (address space, user stack, context)
+///
+/// Returns the entry point, or None (after printing the reason) if the
+/// program is not a RISC-V executable or cannot be loaded; the process
+/// is left untouched in that case
+fn load_init_image(process: &ProcessHandle, path: &str, data: &[u8]) -> Option<usize> {
+    let loaded = crate::memory::with_frame_allocator(|allocator| {
+        exec::load_image(&mut process.lock(), data, allocator)
+    })
+    .unwrap_or(Err(ExecError::NoFrameAllocator));
+
+    match loaded {
+        Ok(entry) => Some(entry),
+        Err(e) => {
+            println!("  [ERR] Failed to load {}: {}, using built-in init", path, e);
+            None
+        }
+    }
+}
+
+/// Create the init process (PID 1)
+///
+/// Loads the program at init_path() with exec::load_image if it exists,
+/// otherwise falls back to the built-in entry point. The process is not
+/// added to the scheduler.
+pub fn create_init_process() -> (ProcessHandle, InitSource) {
+    let process = create_process("init", BUILTIN_INIT_ENTRY, INIT_STACK_TOP, None)
+        .expect("init process has a valid entry and is exempt from the process limit");
+
+    let path = init_path();
+    let source = match read_init_program(&path).and_then(|data| load_init_image(&process, &path, &data)) {
+        Some(entry) => InitSource::Program(entry),
+        None => InitSource::BuiltIn,
+    };
+    (process, source)
+}
+
 /// Initialize system processes (with visualization)
 ///
 /// Create real initial processes needed by the system (such as init process)
@@ -45,18 +172,19 @@ pub fn init_system_processes() {
     print_small_separator();
     println!("  Configuration:");
     println!("    - Process name: init");
-    println!("    - Entry address: 0x80000000");
-    println!("    - Stack top: 0x80010000");
+    println!("    - Program: {}", init_path());
+    println!("    - Stack top: {:#x}", INIT_STACK_TOP);
     println!("    - Parent process: None (this is the first process)");
     short_delay();
 
-    let init_proc = create_process(
-        "init",
-        0x8000_0000,
-        0x8001_0000,
-        None,
-    );
-    SCHEDULER.lock().add_process(init_proc.clone());
+    let (init_proc, source) = create_init_process();
+    match source {
+        InitSource::Program(entry) => println!("    - Entry address: {:#x} (from ELF)", entry),
+        InitSource::BuiltIn => println!("    - Entry address: {:#x} (built-in)", BUILTIN_INIT_ENTRY),
+    }
+    // init 进程不受进程数上限限制
+    scheduler::add_process(init_proc.clone())
+        .expect("init process is exempt from the process limit");
 
     println!("\n  [OK] init process created successfully!");
     println!("    - PID: {}", init_proc.lock().pid().as_usize());
@@ -79,13 +207,22 @@ pub fn init_system_processes() {
     println!("    - Parent process: init (PID={})", init_proc.lock().pid().as_usize());
     short_delay();
 
-    let shell_proc = create_process(
+    let shell_proc = match create_process(
         "shell",
         0x8010_0000,
         0x8011_0000,
         Some(init_proc.lock().pid()),
-    );
-    SCHEDULER.lock().add_process(shell_proc.clone());
+    ) {
+        Ok(process) => process,
+        Err(e) => {
+            println!("\n  [ERR] Failed to create shell process: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = scheduler::add_process(shell_proc.clone()) {
+        println!("\n  [ERR] Failed to add shell process: {}", e);
+        return;
+    }
 
     println!("\n  [OK] shell process created successfully!");
     println!("    - PID: {}", shell_proc.lock().pid().as_usize());
@@ -114,13 +251,22 @@ pub fn init_system_processes() {
         println!("    - Stack top: {:#x}", 0x8021_0000 + i * 0x1000);
         println!("    - Parent process: init (PID={})", init_proc.lock().pid().as_usize());
 
-        let proc = create_process(
+        let proc = match create_process(
             name,
             0x8020_0000 + i * 0x1000,
             0x8021_0000 + i * 0x1000,
             Some(init_proc.lock().pid()),
-        );
-        SCHEDULER.lock().add_process(proc.clone());
+        ) {
+            Ok(process) => process,
+            Err(e) => {
+                println!("  [ERR] Failed to create {}: {}", name, e);
+                continue;
+            }
+        };
+        if let Err(e) = scheduler::add_process(proc.clone()) {
+            println!("  [ERR] Failed to add {}: {}", name, e);
+            continue;
+        }
 
         println!("  [OK] {} created successfully (PID={})", name, proc.lock().pid().as_usize());
         short_delay();
@@ -165,7 +311,7 @@ pub fn init_filesystem_content() {
             if let Ok(passwd) = RAMFS.create_file(etc_dir.clone(), String::from("passwd")) {
                 let mut file = RAMFS.open_file(passwd).unwrap();
                 let content = b"root:x:0:0:root:/root:/bin/sh\n";
-                file.write(content).ok();
+                file.write_all(content).ok();
                 println!("    [OK] /etc/passwd");
                 println!("      - Size: {} bytes", content.len());
                 println!("      - Content: User account info");
@@ -176,7 +322,7 @@ pub fn init_filesystem_content() {
             if let Ok(hostname) = RAMFS.create_file(etc_dir.clone(), String::from("hostname")) {
                 let mut file = RAMFS.open_file(hostname).unwrap();
                 let content = b"error-os\n";
-                file.write(content).ok();
+                file.write_all(content).ok();
                 println!("    [OK] /etc/hostname");
                 println!("      - Size: {} bytes", content.len());
                 println!("      - Content: Hostname");
@@ -199,7 +345,7 @@ pub fn init_filesystem_content() {
         if let Ok(user_dir) = RAMFS.create_directory(home_dir, String::from("user")) {
             if let Ok(readme) = RAMFS.create_file(user_dir.clone(), String::from("README.txt")) {
                 let mut file = RAMFS.open_file(readme).unwrap();
-                file.write(b"Welcome to Error OS!\n").ok();
+                file.write_all(b"Welcome to Error OS!\n").ok();
             }
         }
     }
@@ -208,19 +354,19 @@ pub fn init_filesystem_content() {
     if let Ok(tmp_dir) = RAMFS.create_directory(root.clone(), String::from("tmp")) {
         if let Ok(temp_file) = RAMFS.create_file(tmp_dir, String::from("test.log")) {
             let mut file = RAMFS.open_file(temp_file).unwrap();
-            file.write(b"[INFO] System initialized\n").ok();
+            file.write_all(b"[INFO] System initialized\n").ok();
         }
     }
 
     // Root directory files
     if let Ok(version) = RAMFS.create_file(root.clone(), String::from("version")) {
         let mut file = RAMFS.open_file(version).unwrap();
-        file.write(b"Error OS v0.1.0\n").ok();
+        file.write_all(b"Error OS v0.1.0\n").ok();
     }
 
     if let Ok(motd) = RAMFS.create_file(root.clone(), String::from("motd")) {
         let mut file = RAMFS.open_file(motd).unwrap();
-        file.write(b"Message of the Day: Welcome!\n").ok();
+        file.write_all(b"Message of the Day: Welcome!\n").ok();
     }
 
     println!("\n[Filesystem creation complete] Final state:");
@@ -228,12 +374,134 @@ pub fn init_filesystem_content() {
     short_delay();
 }
 
+/// Where the initial filesystem content came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsSource {
+    /// Extracted from the initrd found at boot
+    Initrd(ExtractStats),
+    /// Built-in content from init_filesystem_content
+    BuiltIn,
+}
+
+/// Populate the filesystem
+///
+/// Uses the initrd found at boot (see crate::initrd) when there is one,
+/// otherwise falls back to the built-in content. If the initrd is malformed,
+/// whatever was extracted before the error is kept and the built-in content
+/// is added on top.
+pub fn init_filesystem() -> FsSource {
+    if let Some(initrd) = crate::initrd::get() {
+        println!("\n[initrd] Extracting {} bytes at {:#x}", initrd.size, initrd.start);
+        match unsafe { tar::extract_at(&RAMFS, initrd.start, initrd.size) } {
+            Ok(stats) => {
+                println!(
+                    "  [OK] {} files, {} directories ({} entries skipped)",
+                    stats.files, stats.directories, stats.skipped
+                );
+                return FsSource::Initrd(stats);
+            }
+            Err(e) => println!("  [ERR] initrd extraction failed: {:?}, using built-in content", e),
+        }
+    }
+
+    init_filesystem_content();
+    FsSource::BuiltIn
+}
+
 /// Complete system initialization
 pub fn initialize_system() {
     println!("\n=== System Initialization ===\n");
 
+    // The filesystem comes first: the init program is loaded from it
+    init_filesystem();
+    match crate::user_programs::install() {
+        Ok(count) => println!("[init] Installed {} user programs in /bin", count),
+        Err(e) => println!("[init] Failed to install user programs: {}", e),
+    }
+    let builtins = crate::user_programs::register_builtins();
+    println!("[init] Registered {} built-in programs", builtins);
     init_system_processes();
-    init_filesystem_content();
 
     println!("\n=== System Initialization Complete ===\n");
 }
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::initrd::{self, Initrd};
+
+    #[test_case]
+    fn test_filesystem_populated_from_initrd() {
+        let archive = tar::build_archive(&[
+            ("initrd_boot/hello.txt", b"from initrd\n"),
+            ("initrd_boot/etc/rc", b"#!/bin/sh\n"),
+        ]);
+
+        initrd::set(Some(Initrd { start: archive.as_ptr() as usize, size: archive.len() }));
+        let source = init_filesystem();
+        initrd::set(None);
+
+        assert_eq!(source, FsSource::Initrd(ExtractStats { files: 2, directories: 2, skipped: 0 }));
+
+        let hello = RAMFS.resolve(RAMFS.root(), "/initrd_boot/hello.txt").unwrap();
+        let mut buf = [0u8; 32];
+        let n = hello.lock().read_at(0, &mut buf).unwrap();
+        assert_eq!(&buf[..n], b"from initrd\n");
+
+        // 内置内容没有被创建
+        assert!(RAMFS.resolve(RAMFS.root(), "/motd").is_err());
+
+        let dir = RAMFS.resolve(RAMFS.root(), "/initrd_boot").unwrap();
+        RAMFS.remove(dir.clone(), "hello.txt").unwrap();
+        let etc = RAMFS.resolve(dir.clone(), "etc").unwrap();
+        RAMFS.remove(etc, "rc").unwrap();
+        RAMFS.remove(dir, "etc").unwrap();
+        RAMFS.remove(RAMFS.root(), "initrd_boot").unwrap();
+    }
+
+    #[test_case]
+    fn test_init_loaded_from_configured_path() {
+        assert_eq!(init_path_from_bootargs("console=ttyS0 init=/bin/myinit"), Some("/bin/myinit"));
+        assert_eq!(init_path_from_bootargs("console=ttyS0 init="), None);
+        assert_eq!(init_path(), DEFAULT_INIT_PATH);
+
+        let program = crate::user_programs::find("exit7").unwrap();
+        let entry = crate::elf::parse(program).unwrap().entry;
+        let dir = RAMFS.create_directory(RAMFS.root(), String::from("init_test")).unwrap();
+        let file = RAMFS.create_file(dir.clone(), String::from("init")).unwrap();
+        file.lock().write_at(0, program).unwrap();
+        let bad = RAMFS.create_file(dir.clone(), String::from("bad")).unwrap();
+        bad.lock().write_at(0, b"not an executable").unwrap();
+
+        // init 进程由 load_image 装载：有自己的地址空间和用户栈，从 ELF 入口开始
+        set_init_path(Some("/init_test/init"));
+        let (process, source) = create_init_process();
+        assert_eq!(source, InitSource::Program(entry));
+        {
+            let pcb = process.lock();
+            assert_eq!(pcb.context().sepc, entry);
+            assert!(pcb.address_space().is_some());
+            assert!(exec::translate(&pcb, entry).is_some());
+            assert!(pcb.parent_pid().is_none());
+        }
+
+        // 文件不存在或不是可执行文件时退回内置设置
+        for path in ["/init_test/missing", "/init_test/bad"] {
+            set_init_path(Some(path));
+            let (process, source) = create_init_process();
+            assert_eq!(source, InitSource::BuiltIn);
+            let pcb = process.lock();
+            assert_eq!(pcb.context().sepc, BUILTIN_INIT_ENTRY);
+            assert!(pcb.address_space().is_none());
+        }
+
+        set_init_path(None);
+        RAMFS.remove(dir.clone(), "init").unwrap();
+        RAMFS.remove(dir, "bad").unwrap();
+        RAMFS.remove(RAMFS.root(), "init_test").unwrap();
+    }
+}
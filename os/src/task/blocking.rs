@@ -0,0 +1,232 @@
+/*
+ * ============================================
+ * 阻塞任务卸载（spawn_blocking）
+ * ============================================
+ * 功能：把耗时的同步操作交给内核工作进程执行
+ *
+ * 设计要点：
+ * - 异步执行器是单线程协作式的，任务里的长时间同步操作
+ *   （如大文件拷贝）会卡住所有其他任务
+ * - spawn_blocking 把闭包放入工作队列，由被抢占式调度的
+ *   内核工作进程（kworker）执行
+ * - 异步任务通过返回的 BlockingHandle 等待结果，
+ *   结果就绪时由工作进程唤醒
+ * - 工作进程同时执行中断处理函数推迟的下半部（trap::irq）
+ * ============================================
+ */
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use spin::Mutex;
+
+use crate::process::{self, scheduler, ProcessId};
+use crate::sync::IrqSpinLock;
+use crate::trap::without_interrupts;
+use crate::serial_println;
+
+/// 待执行的阻塞任务
+type Job = Box<dyn FnOnce() + Send>;
+
+/// 工作队列
+static JOBS: Mutex<VecDeque<Job>> = Mutex::new(VecDeque::new());
+
+/// 内核工作进程的 PID（init 之前为 None）
+///
+/// 中断处理函数也会读取（schedule_bottom_half），因此持锁时关中断
+static WORKER: IrqSpinLock<Option<ProcessId>> = IrqSpinLock::new(None);
+
+/// 结果槽：工作进程写入结果，等待者取走
+struct Slot<R> {
+    result: Option<R>,
+    waker: Option<Waker>,
+}
+
+/// 阻塞任务的结果句柄
+///
+/// 实现 Future，结果就绪前返回 Pending，不会阻塞执行器
+pub struct BlockingHandle<R> {
+    slot: Arc<Mutex<Slot<R>>>,
+}
+
+impl<R> Future for BlockingHandle<R> {
+    type Output = R;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<R> {
+        let mut slot = self.slot.lock();
+        match slot.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// 把同步闭包交给内核工作进程执行
+///
+/// # 参数
+/// - `f`: 要执行的闭包
+///
+/// # 返回
+/// 可在异步任务中 `.await` 的结果句柄
+///
+/// # 示例
+/// ```rust
+/// let sum = spawn_blocking(|| expensive_copy()).await;
+/// ```
+pub fn spawn_blocking<F, R>(f: F) -> BlockingHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let slot = Arc::new(Mutex::new(Slot { result: None, waker: None }));
+    let job_slot = slot.clone();
+
+    JOBS.lock().push_back(Box::new(move || {
+        let result = f();
+        let waker = {
+            let mut slot = job_slot.lock();
+            slot.result = Some(result);
+            slot.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }));
+
+    wake_worker();
+
+    BlockingHandle { slot }
+}
+
+/// 唤醒内核工作进程（有新的任务或下半部）
+pub(crate) fn wake_worker() {
+    let worker = *WORKER.lock();
+    if let Some(pid) = worker {
+        process::wake_up_process(pid);
+    }
+}
+
+/// 执行队列中所有待处理的任务
+///
+/// # 返回
+/// 本次执行的任务数量
+pub fn run_pending_jobs() -> usize {
+    let mut count = 0;
+    loop {
+        let job = JOBS.lock().pop_front();
+        match job {
+            Some(job) => {
+                job();
+                count += 1;
+            }
+            None => return count,
+        }
+    }
+}
+
+/// 内核工作进程主循环
+fn worker_main() -> ! {
+    loop {
+        run_pending_jobs();
+        crate::trap::irq::run_bottom_halves();
+
+        // 关中断后再检查队列，避免"检查为空"与"阻塞"之间
+        // 有新任务入队而丢失唤醒
+        without_interrupts(|| {
+            if JOBS.lock().is_empty() && !crate::trap::irq::has_pending_bottom_halves() {
+                process::block_current_process();
+            }
+        });
+    }
+}
+
+/// 启动内核工作进程
+pub fn init() {
+    let worker = match process::create_kernel_thread("kworker", worker_main) {
+        Ok(worker) => worker,
+        Err(e) => {
+            serial_println!("[BLOCKING] Failed to create kworker: {}", e);
+            return;
+        }
+    };
+    let pid = worker.lock().pid();
+
+    match scheduler::add_process(worker) {
+        Ok(()) => *WORKER.lock() = Some(pid),
+        Err(e) => {
+            serial_println!("[BLOCKING] Failed to start kworker: {}", e);
+        }
+    }
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{Task, simple_executor::SimpleExecutor};
+    use core::sync::atomic::{AtomicBool, Ordering};
+    use futures_util::task::noop_waker_ref;
+
+    #[test_case]
+    fn test_offloaded_job_does_not_starve_executor() {
+        static OTHER_RAN: AtomicBool = AtomicBool::new(false);
+
+        let mut handle = spawn_blocking(|| (1..=100u64).sum::<u64>());
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        // 工作进程尚未运行：等待者挂起，而不是卡住执行器
+        assert!(Pin::new(&mut handle).poll(&mut cx).is_pending());
+
+        // 其他异步任务照常推进
+        let mut executor = SimpleExecutor::new();
+        executor.spawn(Task::new(async {
+            OTHER_RAN.store(true, Ordering::SeqCst);
+        }));
+        executor.run();
+        assert!(OTHER_RAN.load(Ordering::SeqCst));
+
+        // 模拟工作进程被调度执行
+        assert_eq!(run_pending_jobs(), 1);
+        assert_eq!(Pin::new(&mut handle).poll(&mut cx), Poll::Ready(5050));
+    }
+
+    #[test_case]
+    fn test_fresh_worker_runs_job_and_blocks() {
+        use crate::process::ProcessState;
+
+        // 测试本身作为当前进程运行
+        let runner = process::spawn_test_process("kworker_runner", None);
+        let runner_pid = runner.lock().pid();
+        scheduler::lock_scheduler().run_for_test(runner_pid);
+
+        // 新建、还没有运行过的工作进程
+        let worker = process::create_kernel_thread("kworker_test", worker_main).unwrap();
+        let worker_pid = worker.lock().pid();
+        scheduler::add_process(worker.clone()).unwrap();
+        let previous = WORKER.lock().replace(worker_pid);
+
+        let mut handle = spawn_blocking(|| (1..=10u64).product::<u64>());
+        let mut cx = Context::from_waker(noop_waker_ref());
+        assert!(Pin::new(&mut handle).poll(&mut cx).is_pending());
+
+        // 让出 CPU：工作进程第一次运行，执行任务后在空队列上阻塞，切回这里
+        assert!(process::yield_now());
+        assert_eq!(Pin::new(&mut handle).poll(&mut cx), Poll::Ready(3_628_800));
+        assert_eq!(worker.lock().state(), ProcessState::Blocked);
+        assert_eq!(scheduler::current_pid(), Some(runner_pid));
+
+        *WORKER.lock() = previous;
+        let mut scheduler = scheduler::lock_scheduler();
+        scheduler.remove_process(worker_pid);
+        scheduler.remove_process(runner_pid);
+    }
+}
@@ -1,6 +1,7 @@
 use super::{Task, TaskId};
 use alloc::{collections::BTreeMap, sync::Arc};
 use core::task::Waker;
+use core::sync::atomic::{AtomicBool, Ordering};
 use crossbeam_queue::ArrayQueue;
 
 pub struct Executor {
@@ -90,10 +91,33 @@ impl TaskWaker {
         }))
     }
 }
+/// 关机请求标志
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// 请求执行器退出（关机流程的第一步，由 SysRq o 触发）
+///
+/// # 说明
+/// 可以在中断上下文中调用：只设置标志，执行器在当前一轮任务执行完后返回
+pub fn request_shutdown() {
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// 是否已经请求关机
+pub fn shutdown_requested() -> bool {
+    SHUTDOWN_REQUESTED.load(Ordering::SeqCst)
+}
+
 impl Executor {
-    pub fn run(&mut self) -> ! {
+    /// 运行执行器，直到有任务请求关机
+    ///
+    /// # 说明
+    /// 返回时清除关机请求，之后再运行的执行器不会立即返回
+    pub fn run(&mut self) {
         loop {
             self.run_ready_tasks();
+            if SHUTDOWN_REQUESTED.swap(false, Ordering::SeqCst) {
+                return;
+            }
             self.sleep_if_idle();
         }
     }
@@ -101,14 +125,57 @@ fn sleep_if_idle(&self) {
         use crate::interrupts;
 
         interrupts::disable_interrupts();
-        if self.task_queue.is_empty() {
-            // RISC-V: 启用中断并执行 wfi (Wait For Interrupt)
+        if self.task_queue.is_empty() && !shutdown_requested() {
+            // RISC-V: 启用中断后按配置的策略等待（默认 wfi，见 idle 模块）
             interrupts::enable_interrupts();
-            unsafe {
-                riscv::asm::wfi();
-            }
+            crate::idle::wait();
         } else {
             interrupts::enable_interrupts();
         }
     }
-}
\ No newline at end of file
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_run_returns_after_shutdown_request() {
+        let mut executor = Executor::new();
+        executor.spawn(Task::new(async {
+            request_shutdown();
+        }));
+
+        // run 返回意味着 kernel_main 的关机清理代码可以执行到
+        executor.run();
+
+        // 请求已被消费，不会影响之后的执行器
+        assert!(!shutdown_requested());
+    }
+
+    #[test_case]
+    fn test_idle_strategy_runs_only_when_queue_empty() {
+        use crate::idle::{self, IdleStrategy};
+
+        // 用自旋策略，测试中不会停在 wfi 上
+        idle::set_strategy(IdleStrategy::Spin);
+        let mut executor = Executor::new();
+
+        let before = idle::waits(IdleStrategy::Spin);
+        let wfi_before = idle::waits(IdleStrategy::Wfi);
+        executor.sleep_if_idle();
+        assert_eq!(idle::waits(IdleStrategy::Spin), before + 1);
+        assert_eq!(idle::waits(IdleStrategy::Wfi), wfi_before);
+
+        // 有就绪任务时不等待
+        executor.spawn(Task::new(async {}));
+        executor.sleep_if_idle();
+        assert_eq!(idle::waits(IdleStrategy::Spin), before + 1);
+
+        idle::set_strategy(IdleStrategy::Wfi);
+    }
+}
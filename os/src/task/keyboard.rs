@@ -5,18 +5,21 @@
  * 功能：处理键盘输入（通过 SBI console）
  *
  * RISC-V 键盘输入方案：
- * - 使用 SBI (Supervisor Binary Interface) 的 console_getchar
- * - 轮询方式读取字符
+ * - 默认：UART 接收中断经 PLIC 送达，外部中断处理中读出 RBR 的全部字节
+ * - uart_polling 特性（没有 PLIC 的板子）：时钟中断中通过 SBI console_getchar 轮询
  * - 支持异步任务
  * ============================================
  */
 
 use conquer_once::spin::OnceCell;
 use crossbeam_queue::ArrayQueue;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use core::task::{Context, Poll};
 use core::pin::Pin;
 use futures_util::stream::Stream;
 use futures_util::task::AtomicWaker;
+use crate::process::ProcessId;
+use crate::sync::IrqSpinLock;
 
 /// 扫描码队列（用于存储输入字符）
 static SCANCODE_QUEUE: OnceCell<ArrayQueue<u8>> = OnceCell::uninit();
@@ -24,22 +27,68 @@ static SCANCODE_QUEUE: OnceCell<ArrayQueue<u8>> = OnceCell::uninit();
 /// 唤醒器
 static WAKER: AtomicWaker = AtomicWaker::new();
 
+/// 因等待键盘输入而阻塞的进程
+///
+/// add_scancode 可能在中断上下文中运行，使用 IrqSpinLock 避免
+/// 登记读者时被输入中断打断而在同一把锁上死锁
+static BLOCKED_READER: IrqSpinLock<Option<ProcessId>> = IrqSpinLock::new(None);
+
 /// 添加字符到队列
 ///
 /// # 功能
-/// - 被输入处理器调用
+/// - 被输入处理器调用（包括中断上下文）
 /// - 不能阻塞或分配内存
+/// - 先入队再唤醒，与 poll_queue 的"注册后再检查"配合不会丢失唤醒
+/// - SysRq 序列在此拦截，不进入输入队列（队列未初始化时同样生效）
 pub(crate) fn add_scancode(scancode: u8) {
+    if super::sysrq::feed(scancode) {
+        return;
+    }
+
     if let Ok(queue) = SCANCODE_QUEUE.try_get() {
         if queue.push(scancode).is_err() {
             // 队列满时静默丢弃，避免频繁输出
         } else {
             WAKER.wake(); // 唤醒等待的任务
+
+            // 唤醒阻塞在 stdin 上的进程（先释放锁再唤醒）
+            let reader = BLOCKED_READER.lock().take();
+            if let Some(pid) = reader {
+                crate::process::wake_up_process(pid);
+            }
         }
     }
     // 如果队列未初始化，静默忽略（在键盘任务启动前可能发生）
 }
 
+/// 非阻塞地从队列读取已到达的字符
+///
+/// # 返回
+/// 实际读取的字节数（队列为空或未初始化时为 0）
+pub fn read_available(buf: &mut [u8]) -> usize {
+    let queue = match SCANCODE_QUEUE.try_get() {
+        Ok(queue) => queue,
+        Err(_) => return 0,
+    };
+
+    let mut n = 0;
+    while n < buf.len() {
+        match queue.pop() {
+            Some(byte) => {
+                buf[n] = byte;
+                n += 1;
+            }
+            None => break,
+        }
+    }
+    n
+}
+
+/// 登记等待键盘输入的进程，下一个字符到达时将其唤醒
+pub fn register_blocked_reader(pid: ProcessId) {
+    *BLOCKED_READER.lock() = Some(pid);
+}
+
 /// 扫描码流（实现 Stream trait）
 pub struct ScancodeStream {
     _private: (),
@@ -50,6 +99,8 @@ impl ScancodeStream {
     pub fn new() -> Self {
         // 尝试初始化队列，如果已经初始化则忽略错误
         let _ = SCANCODE_QUEUE.try_init_once(|| ArrayQueue::new(100));
+        // 唤醒在队列初始化之前就开始等待的任务
+        WAKER.wake();
         ScancodeStream { _private: () }
     }
 }
@@ -58,26 +109,67 @@ impl Stream for ScancodeStream {
     type Item = u8;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<u8>> {
-        let queue = SCANCODE_QUEUE
-            .try_get()
-            .expect("scancode queue not initialized");
+        poll_queue(SCANCODE_QUEUE.try_get().ok(), cx)
+    }
+}
+
+/// 从扫描码队列中取一个字符
+///
+/// # 参数
+/// - `queue`: 扫描码队列（None 表示尚未初始化）
+/// - `cx`: 异步上下文
+///
+/// # 说明
+/// 队列未初始化时（启动阶段的竞争）不 panic，而是注册唤醒器并返回
+/// Pending，等队列初始化后由 ScancodeStream::new 唤醒
+fn poll_queue(queue: Option<&ArrayQueue<u8>>, cx: &mut Context) -> Poll<Option<u8>> {
+    poll_queue_with(queue, &WAKER, cx, || {})
+}
 
-        // 尝试从队列中读取
-        if let Some(scancode) = queue.pop() {
-            return Poll::Ready(Some(scancode));
+/// poll_queue 的实现
+///
+/// # 参数
+/// - `waker`: 生产者入队后调用 wake 的唤醒器
+/// - `before_register`: 在第一次取空之后、注册唤醒器之前调用，
+///   测试用它模拟中断恰好在这个窗口内入队
+///
+/// # 说明
+/// 不会丢失唤醒：生产者总是先入队再 wake。
+/// - 入队发生在注册之前：wake 时还没有唤醒器，但注册后的第二次检查能取到
+/// - 入队发生在注册之后：wake 一定能看到刚注册的唤醒器
+///
+/// AtomicWaker 的 register/wake 不加锁，可以在中断上下文中调用
+fn poll_queue_with(
+    queue: Option<&ArrayQueue<u8>>,
+    waker: &AtomicWaker,
+    cx: &mut Context,
+    before_register: impl FnOnce(),
+) -> Poll<Option<u8>> {
+    let queue = match queue {
+        Some(queue) => queue,
+        None => {
+            waker.register(cx.waker());
+            return Poll::Pending;
         }
+    };
 
-        // 注册唤醒器
-        WAKER.register(cx.waker());
+    // 尝试从队列中读取
+    if let Some(scancode) = queue.pop() {
+        return Poll::Ready(Some(scancode));
+    }
 
-        // 再次检查（防止竞争条件）
-        match queue.pop() {
-            Some(scancode) => {
-                WAKER.take();
-                Poll::Ready(Some(scancode))
-            }
-            None => Poll::Pending,
+    before_register();
+
+    // 注册唤醒器
+    waker.register(cx.waker());
+
+    // 再次检查（防止竞争条件）
+    match queue.pop() {
+        Some(scancode) => {
+            waker.take();
+            Poll::Ready(Some(scancode))
         }
+        None => Poll::Pending,
     }
 }
 
@@ -105,24 +197,82 @@ fn sbi_console_getchar() -> Option<u8> {
     }
 }
 
+/// 默认每次轮询最多读取的字符数
+pub const DEFAULT_MAX_READS_PER_POLL: usize = 10;
+
+/// 快速排空模式下每次轮询的绝对上限，防止持续输入导致活锁
+pub const FAST_DRAIN_LIMIT: usize = 1024;
+
+/// 每次轮询最多读取的字符数
+static MAX_READS_PER_POLL: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_READS_PER_POLL);
+
+/// 是否启用快速排空模式
+static FAST_DRAIN: AtomicBool = AtomicBool::new(false);
+
+/// 设置每次轮询最多读取的字符数（至少为 1）
+pub fn set_max_reads_per_poll(max: usize) {
+    MAX_READS_PER_POLL.store(max.max(1), Ordering::Relaxed);
+}
+
+/// 开启或关闭快速排空模式
+///
+/// # 说明
+/// 开启后每次轮询一直读取到 SBI 返回"无字符"为止（最多 FAST_DRAIN_LIMIT 个），
+/// 大量粘贴时不会因为每次只读少量字符而丢失输入
+pub fn set_fast_drain(enabled: bool) {
+    FAST_DRAIN.store(enabled, Ordering::Relaxed);
+}
+
+/// 本次轮询允许读取的字符数上限
+fn poll_limit() -> usize {
+    if FAST_DRAIN.load(Ordering::Relaxed) {
+        FAST_DRAIN_LIMIT
+    } else {
+        MAX_READS_PER_POLL.load(Ordering::Relaxed)
+    }
+}
+
+/// 从输入源读取字符直到没有字符或达到上限
+///
+/// # 参数
+/// - `getchar`: 输入源（返回 None 表示暂无字符）
+/// - `sink`: 读到的字符交给它处理
+///
+/// # 返回
+/// 本次读取的字符数
+fn drain_input(getchar: impl FnMut() -> Option<u8>, sink: impl FnMut(u8)) -> usize {
+    drain_up_to(poll_limit(), getchar, sink)
+}
+
+/// 从输入源读取字符直到没有字符或读满 limit 个
+fn drain_up_to(
+    limit: usize,
+    mut getchar: impl FnMut() -> Option<u8>,
+    mut sink: impl FnMut(u8),
+) -> usize {
+    let mut count = 0;
+
+    while count < limit {
+        match getchar() {
+            Some(ch) => {
+                sink(ch);
+                count += 1;
+            }
+            // 没有更多字符可读，退出
+            None => break,
+        }
+    }
+    count
+}
+
 /// 轮询键盘输入
 ///
 /// # 功能
 /// - 定期调用以检查键盘输入
 /// - 应该在定时器中断中调用
-/// - 限制每次最多读取的字符数，防止阻塞
+/// - 限制每次最多读取的字符数（见 set_max_reads_per_poll / set_fast_drain），防止阻塞
 pub fn poll_keyboard() {
-    // 限制每次中断最多读取 10 个字符，防止无限循环
-    const MAX_READS_PER_POLL: usize = 10;
-
-    for _ in 0..MAX_READS_PER_POLL {
-        if let Some(ch) = sbi_console_getchar() {
-            add_scancode(ch);
-        } else {
-            // 没有更多字符可读，退出
-            break;
-        }
-    }
+    drain_input(sbi_console_getchar, add_scancode);
 }
 
 /// 异步键盘任务
@@ -167,3 +317,106 @@ pub async fn print_keypresses() {
 pub fn keyboard_interrupt_handler() {
     poll_keyboard();
 }
+
+/// UART 接收中断处理
+///
+/// # 功能
+/// - 由外部中断处理在 PLIC claim 到 UART 中断后调用
+/// - 读出 RBR 中的全部字节（最多 FAST_DRAIN_LIMIT 个，防止持续输入导致活锁）
+///
+/// # 返回
+/// 本次读取的字符数
+pub fn uart_interrupt_handler() -> usize {
+    drain_up_to(FAST_DRAIN_LIMIT, crate::serial::receive, add_scancode)
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+    use alloc::task::Wake;
+    use core::task::Waker;
+    use futures_util::task::noop_waker_ref;
+
+    /// 记录被唤醒次数的唤醒器
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// 模拟 add_scancode：先入队再唤醒
+    fn push_and_wake(queue: &ArrayQueue<u8>, waker: &AtomicWaker, byte: u8) {
+        queue.push(byte).unwrap();
+        waker.wake();
+    }
+
+    #[test_case]
+    fn test_poll_before_queue_init_is_pending() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        // 队列未初始化：不 panic，返回 Pending 等待唤醒
+        assert_eq!(poll_queue(None, &mut cx), Poll::Pending);
+
+        // 初始化后可以正常取到字符
+        let queue = ArrayQueue::new(4);
+        queue.push(b'x').unwrap();
+        assert_eq!(poll_queue(Some(&queue), &mut cx), Poll::Ready(Some(b'x')));
+        assert_eq!(poll_queue(Some(&queue), &mut cx), Poll::Pending);
+    }
+
+    #[test_case]
+    fn test_push_before_waker_registration_is_not_lost() {
+        let queue = ArrayQueue::new(4);
+        let waker = AtomicWaker::new();
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let task_waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&task_waker);
+
+        // 第一次取空之后、注册之前到达的字符：wake 时还没有唤醒器，
+        // 由注册后的第二次检查取到，而不是返回 Pending 永远睡下去
+        let poll = poll_queue_with(Some(&queue), &waker, &mut cx, || {
+            push_and_wake(&queue, &waker, b'k');
+        });
+        assert_eq!(poll, Poll::Ready(Some(b'k')));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+
+        // 注册之后到达的字符：唤醒刚注册的唤醒器，再次 poll 取到
+        assert_eq!(poll_queue_with(Some(&queue), &waker, &mut cx, || {}), Poll::Pending);
+        push_and_wake(&queue, &waker, b'b');
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(poll_queue_with(Some(&queue), &waker, &mut cx, || {}), Poll::Ready(Some(b'b')));
+    }
+
+    #[test_case]
+    fn test_fast_drain_consumes_whole_burst() {
+        const BURST: usize = 50;
+
+        let mut pending = BURST;
+        let mut source = || {
+            if pending == 0 {
+                return None;
+            }
+            pending -= 1;
+            Some(b'a')
+        };
+
+        // 默认上限：一次只读 DEFAULT_MAX_READS_PER_POLL 个
+        let mut received = 0;
+        assert_eq!(drain_input(&mut source, |_| received += 1), DEFAULT_MAX_READS_PER_POLL);
+
+        // 快速排空：剩余字符在一次轮询内全部读完
+        set_fast_drain(true);
+        let drained = drain_input(&mut source, |_| received += 1);
+        set_fast_drain(false);
+
+        assert_eq!(drained, BURST - DEFAULT_MAX_READS_PER_POLL);
+        assert_eq!(received, BURST);
+    }
+}
@@ -0,0 +1,199 @@
+/*
+ * ============================================
+ * 行缓冲输入（行规程）
+ * ============================================
+ * 功能：把键盘输入的字符组装成行，类似终端的规范模式（canonical mode）
+ *
+ * 处理规则：
+ * - 回车（\r）或换行（\n）：结束当前行，行尾统一为 \n
+ * - 退格（0x08 / 0x7f）：删除当前行的最后一个字符，回显 "\x08 \x08"
+ * - 其他字符：加入当前行并回显
+ *
+ * 只有完成的行才能被读取：
+ * - Stdin::read（sys_read(0, ...)）从 stdin_read 取已完成的行
+ * - 异步任务可以用 read_line 等待一整行
+ * ============================================
+ */
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use spin::Mutex;
+use super::keyboard;
+
+/// 退格
+const BACKSPACE: u8 = 0x08;
+/// 删除（多数终端的退格键发送这个）
+const DELETE: u8 = 0x7f;
+
+/// 退格的回显：左移、用空格覆盖、再左移
+pub const ERASE_ECHO: &[u8] = b"\x08 \x08";
+
+/// 行规程
+pub struct LineDiscipline {
+    /// 正在编辑的行
+    editing: Vec<u8>,
+    /// 已完成、等待读取的行
+    completed: VecDeque<u8>,
+}
+
+impl LineDiscipline {
+    /// 创建空的行规程
+    pub const fn new() -> Self {
+        LineDiscipline {
+            editing: Vec::new(),
+            completed: VecDeque::new(),
+        }
+    }
+
+    /// 处理一个输入字符
+    ///
+    /// # 参数
+    /// - `byte`: 输入字符
+    /// - `echo`: 回显输出
+    ///
+    /// # 返回
+    /// 是否完成了一行
+    pub fn feed(&mut self, byte: u8, mut echo: impl FnMut(&[u8])) -> bool {
+        match byte {
+            b'\r' | b'\n' => {
+                self.editing.push(b'\n');
+                self.completed.extend(self.editing.drain(..));
+                echo(b"\n");
+                true
+            }
+            BACKSPACE | DELETE => {
+                if self.editing.pop().is_some() {
+                    echo(ERASE_ECHO);
+                }
+                false
+            }
+            _ => {
+                self.editing.push(byte);
+                echo(&[byte]);
+                false
+            }
+        }
+    }
+
+    /// 是否有已完成的行可读
+    pub fn has_line(&self) -> bool {
+        !self.completed.is_empty()
+    }
+
+    /// 读取已完成的行
+    ///
+    /// # 返回
+    /// 实际读取的字节数；buf 放不下时剩余部分留给下次读取
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        let n = buf.len().min(self.completed.len());
+        for (dst, src) in buf.iter_mut().zip(self.completed.drain(..n)) {
+            *dst = src;
+        }
+        n
+    }
+}
+
+impl Default for LineDiscipline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 标准输入的行规程
+static STDIN_LINE: Mutex<LineDiscipline> = Mutex::new(LineDiscipline::new());
+
+/// 回显到控制台
+fn echo_console(bytes: &[u8]) {
+    for &byte in bytes {
+        crate::print!("{}", byte as char);
+    }
+}
+
+/// 从标准输入读取已完成的行
+///
+/// # 返回
+/// 实际读取的字节数（还没有完成的行时为 0）
+///
+/// # 说明
+/// 先把键盘队列中已到达的字符交给行规程（并回显），再读取完成的行
+pub fn stdin_read(buf: &mut [u8]) -> usize {
+    let mut line = STDIN_LINE.lock();
+    let mut chunk = [0u8; 32];
+    loop {
+        let n = keyboard::read_available(&mut chunk);
+        if n == 0 {
+            break;
+        }
+        for &byte in &chunk[..n] {
+            line.feed(byte, echo_console);
+        }
+    }
+    line.read(buf)
+}
+
+/// 异步读取一整行
+///
+/// # 返回
+/// 以 \n 结尾的一行（已处理退格）
+pub async fn read_line() -> Vec<u8> {
+    use futures_util::stream::StreamExt;
+
+    let mut line = LineDiscipline::new();
+    let mut scancodes = keyboard::ScancodeStream::new();
+    while let Some(byte) = scancodes.next().await {
+        if line.feed(byte, echo_console) {
+            break;
+        }
+    }
+
+    let mut buf = alloc::vec![0u8; line.completed.len()];
+    line.read(&mut buf);
+    buf
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_backspace_edits_line_before_it_completes() {
+        let mut line = LineDiscipline::new();
+        let mut echoed = Vec::new();
+
+        // 行首的退格什么也不做；0x08 和 0x7f 都删除一个字符
+        for &byte in b"\x7fhelo\x08lx\x7fo" {
+            assert!(!line.feed(byte, |bytes| echoed.extend_from_slice(bytes)));
+            assert!(!line.has_line());
+        }
+        assert!(line.feed(b'\r', |bytes| echoed.extend_from_slice(bytes)));
+
+        let mut buf = [0u8; 16];
+        assert_eq!(line.read(&mut buf), 6);
+        assert_eq!(&buf[..6], b"hello\n");
+        assert_eq!(echoed, b"helo\x08 \x08lx\x08 \x08o\n");
+        assert!(!line.has_line());
+    }
+
+    #[test_case]
+    fn test_stdin_reads_completed_line_from_keyboard_queue() {
+        use crate::fs::{File, FileError, Stdin};
+
+        let _ = keyboard::ScancodeStream::new();
+        let mut stdin = Stdin::new();
+        let mut buf = [0u8; 16];
+
+        // 行还没有结束：没有可读的内容（内核上下文中不阻塞）
+        for &byte in b"ls -x\x08l" {
+            keyboard::add_scancode(byte);
+        }
+        assert_eq!(stdin.read(&mut buf), Err(FileError::WouldBlock));
+
+        keyboard::add_scancode(b'\n');
+        assert_eq!(stdin.read(&mut buf), Ok(6));
+        assert_eq!(&buf[..6], b"ls -l\n");
+    }
+}
@@ -22,6 +22,9 @@ impl Task {
 }
 pub mod simple_executor;
 pub mod keyboard;
+pub mod line;
+pub mod blocking;
+pub mod sysrq;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 struct TaskId(u64);
 use core::sync::atomic::{AtomicU64, Ordering};
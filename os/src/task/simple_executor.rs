@@ -46,4 +46,25 @@ impl SimpleExecutor {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// 让出一次执行权，使同一执行器上的多个任务交错执行（测试用）
+#[cfg(test)]
+pub(crate) async fn yield_once() {
+    struct YieldOnce(bool);
+
+    impl core::future::Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: core::pin::Pin<&mut Self>, _cx: &mut Context) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                Poll::Pending
+            }
+        }
+    }
+
+    YieldOnce(false).await
+}
@@ -0,0 +1,173 @@
+/*
+ * ============================================
+ * 紧急按键处理（SysRq）
+ * ============================================
+ * 功能：系统卡死时通过键盘输入直接触发调试动作
+ *
+ * 触发方式：
+ * - 连续输入两次 Ctrl-\（0x1c 0x1c），再输入命令键
+ *
+ * 命令键：
+ * - p：打印进程列表
+ * - s：打印调度器状态
+ * - r：请求一次调度（设置 need_resched，在返回用户态前的安全点处理）
+ * - h：检查内核堆的一致性
+ * - o：关机（请求内核执行器退出，由 kernel_main 执行关机流程）
+ * - 其他：打印帮助
+ *
+ * 设计要点：
+ * - 在键盘输入路径（中断/轮询）中直接处理，不依赖 shell
+ * - 处理时可能处于中断上下文，不能直接调度或关机，重新调度和关机只做标记
+ * - 前缀和命令字节会被吞掉，不进入普通输入队列
+ * - 不完整的前缀后跟普通字符时，前缀字节被丢弃
+ * ============================================
+ */
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use spin::Mutex;
+use crate::process::{inspector, scheduler};
+use crate::serial_println;
+
+/// 触发前缀
+pub const SYSRQ_PREFIX: [u8; 2] = [0x1c, 0x1c];
+
+/// SysRq 动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SysRqAction {
+    /// 打印进程列表
+    ProcessList,
+    /// 打印调度器状态
+    SchedulerState,
+    /// 请求重新调度
+    Reschedule,
+    /// 检查内核堆
+    HeapCheck,
+    /// 关机
+    Shutdown,
+    /// 打印帮助
+    Help,
+}
+
+impl SysRqAction {
+    /// 根据命令键解析动作
+    pub fn from_key(key: u8) -> Self {
+        match key {
+            b'p' => SysRqAction::ProcessList,
+            b's' => SysRqAction::SchedulerState,
+            b'r' => SysRqAction::Reschedule,
+            b'h' => SysRqAction::HeapCheck,
+            b'o' => SysRqAction::Shutdown,
+            _ => SysRqAction::Help,
+        }
+    }
+}
+
+/// 已匹配的前缀字节数
+static MATCHED: AtomicUsize = AtomicUsize::new(0);
+
+/// 最近一次触发的动作
+static LAST_ACTION: Mutex<Option<SysRqAction>> = Mutex::new(None);
+
+/// 累计触发次数
+static TRIGGER_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// 处理一个输入字节
+///
+/// # 返回
+/// - `true`: 字节属于 SysRq 序列，已被消费
+/// - `false`: 普通输入，调用者应继续正常处理
+pub fn feed(byte: u8) -> bool {
+    let matched = MATCHED.load(Ordering::Relaxed);
+
+    if matched == SYSRQ_PREFIX.len() {
+        MATCHED.store(0, Ordering::Relaxed);
+        trigger(SysRqAction::from_key(byte));
+        return true;
+    }
+
+    if byte == SYSRQ_PREFIX[matched] {
+        MATCHED.store(matched + 1, Ordering::Relaxed);
+        return true;
+    }
+
+    MATCHED.store(0, Ordering::Relaxed);
+    false
+}
+
+/// 执行 SysRq 动作
+pub fn trigger(action: SysRqAction) {
+    *LAST_ACTION.lock() = Some(action);
+    TRIGGER_COUNT.fetch_add(1, Ordering::Relaxed);
+
+    serial_println!("[SYSRQ] {:?}", action);
+
+    match action {
+        SysRqAction::ProcessList => inspector::show_process_list(),
+        SysRqAction::SchedulerState => scheduler::print_status(),
+        SysRqAction::Reschedule => crate::percpu::current().set_need_resched(),
+        SysRqAction::HeapCheck => crate::allocator::report_integrity(),
+        SysRqAction::Shutdown => crate::task::executor::request_shutdown(),
+        SysRqAction::Help => {
+            serial_println!(
+                "[SYSRQ] Commands: p=process list, s=scheduler state, r=reschedule, h=heap check, o=shutdown"
+            );
+        }
+    }
+}
+
+/// 最近一次触发的动作
+pub fn last_action() -> Option<SysRqAction> {
+    *LAST_ACTION.lock()
+}
+
+/// 累计触发次数
+pub fn trigger_count() -> usize {
+    TRIGGER_COUNT.load(Ordering::Relaxed)
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::keyboard;
+
+    #[test_case]
+    fn test_magic_sequence_dumps_process_list() {
+        let before = trigger_count();
+
+        for &byte in SYSRQ_PREFIX.iter().chain(b"p") {
+            keyboard::add_scancode(byte);
+        }
+
+        assert_eq!(trigger_count(), before + 1);
+        assert_eq!(last_action(), Some(SysRqAction::ProcessList));
+
+        // 序列被完全消费，没有进入普通输入队列
+        let mut buf = [0u8; 4];
+        assert_eq!(keyboard::read_available(&mut buf), 0);
+    }
+
+    #[test_case]
+    fn test_reschedule_is_deferred_to_safe_point() {
+        let hart = crate::percpu::current();
+        hart.take_need_resched();
+
+        // 不在这里调度，只留给陷阱返回前的 resched_if_needed
+        trigger(SysRqAction::Reschedule);
+        assert_eq!(last_action(), Some(SysRqAction::Reschedule));
+        assert!(hart.take_need_resched());
+    }
+
+    #[test_case]
+    fn test_incomplete_prefix_is_not_triggered() {
+        let before = trigger_count();
+
+        assert!(feed(SYSRQ_PREFIX[0]));
+        assert!(!feed(b'p'));
+
+        assert_eq!(trigger_count(), before);
+    }
+}
@@ -0,0 +1,572 @@
+//! 断点处理
+//!
+//! ebreak 有两种编码：4 字节的 `ebreak` 和 2 字节的 `c.ebreak`（C 扩展）。
+//! 指令长度由第一个半字的低 2 位决定（0b11 为 4 字节，其余为 2 字节），
+//! 处理完断点后按实际长度推进 sepc，而不是假定总是压缩指令。
+//!
+//! 调试器设置的软件断点记录在断点表中：
+//! - 设置时保存原指令，写入同样长度的 ebreak
+//! - 命中时恢复原指令，并在它的后继指令处放临时断点，sepc 不变，
+//!   返回后执行的是原指令（相当于单步越过）
+//! - 临时断点命中时恢复它们覆盖的指令，重新写回原断点，继续执行
+//!
+//! 基础 ISA 没有硬件单步，单步同样用临时断点实现：解码当前指令得到
+//! 所有可能的后继（顺序执行的下一条、跳转目标、分支的两个方向），
+//! 在每个后继处放临时断点，命中任意一个即单步完成（见 single_step）
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use crate::sync::IrqSpinLock;
+use super::TrapFrame;
+
+/// 4 字节 ebreak 指令
+pub const EBREAK: u32 = 0x0010_0073;
+
+/// 2 字节 c.ebreak 指令
+pub const C_EBREAK: u16 = 0x9002;
+
+/// 根据指令的第一个半字计算指令长度
+///
+/// # 返回
+/// 低 2 位为 0b11 时为 4（标准指令），否则为 2（压缩指令）
+pub fn instruction_len(first_half: u16) -> usize {
+    if first_half & 0b11 == 0b11 { 4 } else { 2 }
+}
+
+/// 读取 addr 处指令的长度
+///
+/// # Safety
+/// addr 必须指向可读的指令
+unsafe fn instruction_len_at(addr: usize) -> usize {
+    instruction_len(core::ptr::read_unaligned(addr as *const u16))
+}
+
+/// 读取 addr 处的指令（压缩指令只占低 16 位）
+///
+/// # Safety
+/// addr 必须指向可读的指令
+unsafe fn read_instruction(addr: usize) -> (u32, usize) {
+    let len = instruction_len_at(addr);
+    let insn = if len == 4 {
+        core::ptr::read_unaligned(addr as *const u32)
+    } else {
+        core::ptr::read_unaligned(addr as *const u16) as u32
+    };
+    (insn, len)
+}
+
+/// 在 addr 处写入长度为 len 的指令，并刷新指令缓存
+///
+/// # Safety
+/// addr 开始的 len 个字节必须可写
+unsafe fn write_instruction(addr: usize, insn: u32, len: usize) {
+    if len == 4 {
+        core::ptr::write_unaligned(addr as *mut u32, insn);
+    } else {
+        core::ptr::write_unaligned(addr as *mut u16, insn as u16);
+    }
+    core::arch::asm!("fence.i");
+}
+
+/// 在 addr 处写入与原指令等长的 ebreak
+///
+/// # Safety
+/// 同 write_instruction
+unsafe fn plant_ebreak(addr: usize, len: usize) {
+    let ebreak = if len == 4 { EBREAK } else { C_EBREAK as u32 };
+    write_instruction(addr, ebreak, len);
+}
+
+/// 把 value 的低 bits 位作为有符号数扩展
+fn sign_extend(value: u32, bits: u32) -> isize {
+    ((value << (32 - bits)) as i32 >> (32 - bits)) as isize
+}
+
+/// 计算一条指令执行后可能到达的地址
+///
+/// # 参数
+/// - `pc`: 指令地址
+/// - `insn`: 指令（压缩指令只占低 16 位）
+/// - `len`: 指令长度（2 或 4）
+/// - `regs`: 执行前的通用寄存器（间接跳转的目标取决于寄存器）
+///
+/// # 返回
+/// 后继地址（无重复）：条件分支有两个，其余指令一个
+///
+/// # 说明
+/// 支持 jal/jalr/条件分支，以及 c.j/c.jr/c.jalr/c.beqz/c.bnez；
+/// 其他指令都顺序执行到 pc + len
+pub fn successors(pc: usize, insn: u32, len: usize, regs: &[usize; 32]) -> Vec<usize> {
+    let next = pc + len;
+    let reg = |index: u32| if index == 0 { 0 } else { regs[index as usize] };
+    let offset = |imm: isize| pc.wrapping_add_signed(imm);
+
+    let target = if len == 4 {
+        match insn & 0x7f {
+            // JAL：imm[20|10:1|11|19:12]
+            0x6f => {
+                let imm = (insn >> 31 & 1) << 20
+                    | (insn >> 21 & 0x3ff) << 1
+                    | (insn >> 20 & 1) << 11
+                    | (insn >> 12 & 0xff) << 12;
+                return alloc::vec![offset(sign_extend(imm, 21))];
+            }
+            // JALR：(rs1 + imm) & !1
+            0x67 => {
+                let base = reg(insn >> 15 & 0x1f);
+                let imm = (insn as i32 >> 20) as isize;
+                return alloc::vec![base.wrapping_add_signed(imm) & !1];
+            }
+            // BRANCH：imm[12|10:5] 在 31:25，imm[4:1|11] 在 11:7
+            0x63 => {
+                let imm = (insn >> 31 & 1) << 12
+                    | (insn >> 25 & 0x3f) << 5
+                    | (insn >> 8 & 0xf) << 1
+                    | (insn >> 7 & 1) << 11;
+                Some(offset(sign_extend(imm, 13)))
+            }
+            _ => None,
+        }
+    } else {
+        let op = insn & 0b11;
+        let funct3 = insn >> 13 & 0b111;
+        match (op, funct3) {
+            // C.J：offset[11|4|9:8|10|6|7|3:1|5]
+            (0b01, 0b101) => {
+                let imm = (insn >> 12 & 1) << 11
+                    | (insn >> 11 & 1) << 4
+                    | (insn >> 9 & 0b11) << 8
+                    | (insn >> 8 & 1) << 10
+                    | (insn >> 7 & 1) << 6
+                    | (insn >> 6 & 1) << 7
+                    | (insn >> 3 & 0b111) << 1
+                    | (insn >> 2 & 1) << 5;
+                return alloc::vec![offset(sign_extend(imm, 12))];
+            }
+            // C.BEQZ / C.BNEZ：offset[8|4:3] 在 12:10，offset[7:6|2:1|5] 在 6:2
+            (0b01, 0b110) | (0b01, 0b111) => {
+                let imm = (insn >> 12 & 1) << 8
+                    | (insn >> 10 & 0b11) << 3
+                    | (insn >> 5 & 0b11) << 6
+                    | (insn >> 3 & 0b11) << 1
+                    | (insn >> 2 & 1) << 5;
+                Some(offset(sign_extend(imm, 9)))
+            }
+            // C.JR / C.JALR：rs2 为 0、rs1 非 0（rs1 也为 0 时是 c.ebreak）
+            (0b10, 0b100) if insn >> 2 & 0x1f == 0 && insn >> 7 & 0x1f != 0 => {
+                return alloc::vec![reg(insn >> 7 & 0x1f) & !1];
+            }
+            _ => None,
+        }
+    };
+
+    match target {
+        Some(target) if target != next => alloc::vec![next, target],
+        _ => alloc::vec![next],
+    }
+}
+
+/// 被 ebreak 覆盖的原指令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SavedInstruction {
+    /// 原指令（压缩指令只占低 16 位）
+    insn: u32,
+    /// 指令长度（2 或 4）
+    len: usize,
+}
+
+/// 进行中的单步：后继指令处的临时断点
+#[derive(Debug)]
+struct Step {
+    /// 临时断点的地址 → 被覆盖的指令
+    temps: Vec<(usize, SavedInstruction)>,
+    /// 单步完成后要重新写回的断点（越过断点时）
+    rearm: Option<usize>,
+    /// 是否报告给调试器（single_step 为 true，越过断点为 false）
+    report: bool,
+}
+
+/// ebreak 的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakpointHit {
+    /// 不是断点表放置的（程序自己的 ebreak），sepc 应跳过它
+    Foreign,
+    /// 断点表的断点或越过断点的临时断点：原指令已恢复，sepc 不变，继续执行
+    Resume,
+    /// single_step 完成：临时断点已撤掉，sepc 不变，进程应停下交给调试器
+    Stepped,
+}
+
+/// 调试器设置的断点表
+pub struct BreakpointTable {
+    /// 断点地址 → 原指令
+    entries: BTreeMap<usize, SavedInstruction>,
+    /// 进行中的单步（越过断点或 single_step）
+    stepping: Option<Step>,
+}
+
+impl BreakpointTable {
+    /// 创建空的断点表
+    pub const fn new() -> Self {
+        BreakpointTable {
+            entries: BTreeMap::new(),
+            stepping: None,
+        }
+    }
+
+    /// addr 处是否有断点
+    pub fn contains(&self, addr: usize) -> bool {
+        self.entries.contains_key(&addr)
+    }
+
+    /// 在 addr 处设置断点
+    ///
+    /// # 返回
+    /// 已经存在断点时返回 false
+    ///
+    /// # Safety
+    /// addr 必须指向一条可写的指令
+    pub unsafe fn insert(&mut self, addr: usize) -> bool {
+        if self.contains(addr) {
+            return false;
+        }
+        let (insn, len) = read_instruction(addr);
+        self.entries.insert(addr, SavedInstruction { insn, len });
+        plant_ebreak(addr, len);
+        true
+    }
+
+    /// 删除 addr 处的断点并恢复原指令
+    ///
+    /// # 返回
+    /// 不存在断点时返回 false
+    ///
+    /// # Safety
+    /// 同 insert
+    pub unsafe fn remove(&mut self, addr: usize) -> bool {
+        match self.entries.remove(&addr) {
+            Some(saved) => {
+                write_instruction(addr, saved.insn, saved.len);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 是否有进行中的单步
+    pub fn is_stepping(&self) -> bool {
+        self.stepping.is_some()
+    }
+
+    /// 在 frame 停下的位置单步一条指令
+    ///
+    /// # 返回
+    /// 已有进行中的单步时返回 false
+    ///
+    /// # 说明
+    /// 在当前指令的所有后继处放临时断点；返回后执行一条指令，
+    /// 命中临时断点时 on_hit 返回 Stepped。当前位置是断点表中的断点时，
+    /// 先恢复原指令，单步完成后重新写回
+    ///
+    /// # Safety
+    /// frame.sepc 及其后继必须指向可写的指令
+    pub unsafe fn single_step(&mut self, frame: &TrapFrame) -> bool {
+        if self.is_stepping() {
+            return false;
+        }
+        let pc = frame.sepc;
+        let rearm = self.entries.get(&pc).copied().map(|saved| {
+            write_instruction(pc, saved.insn, saved.len);
+            pc
+        });
+        self.begin_step(pc, &frame.regs, rearm, true);
+        true
+    }
+
+    /// 在 pc 处指令的后继处放临时断点
+    ///
+    /// # Safety
+    /// 同 single_step
+    unsafe fn begin_step(&mut self, pc: usize, regs: &[usize; 32], rearm: Option<usize>, report: bool) {
+        let (insn, len) = read_instruction(pc);
+        let temps = successors(pc, insn, len, regs)
+            .into_iter()
+            .map(|addr| {
+                let (insn, len) = read_instruction(addr);
+                plant_ebreak(addr, len);
+                (addr, SavedInstruction { insn, len })
+            })
+            .collect();
+        self.stepping = Some(Step { temps, rearm, report });
+    }
+
+    /// 处理 frame.sepc 处的 ebreak
+    ///
+    /// # 返回
+    /// 见 BreakpointHit
+    ///
+    /// # Safety
+    /// 断点表中的地址必须仍然可写
+    pub unsafe fn on_hit(&mut self, frame: &TrapFrame) -> BreakpointHit {
+        let addr = frame.sepc;
+
+        // 单步结束
+        if self.stepping.as_ref().is_some_and(|step| step.temps.iter().any(|&(temp, _)| temp == addr)) {
+            let step = self.end_step().unwrap();
+            return if step.report { BreakpointHit::Stepped } else { BreakpointHit::Resume };
+        }
+
+        let Some(saved) = self.entries.get(&addr).copied() else {
+            return BreakpointHit::Foreign;
+        };
+
+        // 还有没完成的单步时先撤掉它的临时断点，否则它们留在内存里，
+        // 之后命中会被当成程序自己的 ebreak 跳过，丢掉被覆盖的指令
+        self.end_step();
+
+        // 恢复原指令，在它的后继处放临时断点（单步越过）
+        write_instruction(addr, saved.insn, saved.len);
+        self.begin_step(addr, &frame.regs, Some(addr), false);
+        BreakpointHit::Resume
+    }
+
+    /// 结束进行中的单步
+    ///
+    /// # 返回
+    /// 结束的单步；没有进行中的单步时返回 None
+    ///
+    /// # 说明
+    /// 恢复临时断点覆盖的指令（倒序，后继重叠时也能还原），重新写回原断点
+    ///
+    /// # Safety
+    /// 同 on_hit
+    unsafe fn end_step(&mut self) -> Option<Step> {
+        let step = self.stepping.take()?;
+        for &(temp, saved) in step.temps.iter().rev() {
+            write_instruction(temp, saved.insn, saved.len);
+        }
+        if let Some(rearm) = step.rearm {
+            if let Some(saved) = self.entries.get(&rearm) {
+                plant_ebreak(rearm, saved.len);
+            }
+        }
+        Some(step)
+    }
+}
+
+impl Default for BreakpointTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 调试器设置的断点
+static BREAKPOINTS: IrqSpinLock<BreakpointTable> = IrqSpinLock::new(BreakpointTable::new());
+
+/// 在 addr 处设置断点
+///
+/// # Safety
+/// addr 必须指向一条可写的指令
+pub unsafe fn insert_breakpoint(addr: usize) -> bool {
+    BREAKPOINTS.lock().insert(addr)
+}
+
+/// 删除 addr 处的断点
+///
+/// # Safety
+/// 同 insert_breakpoint
+pub unsafe fn remove_breakpoint(addr: usize) -> bool {
+    BREAKPOINTS.lock().remove(addr)
+}
+
+/// 从 frame 停下的位置单步一条指令（见 BreakpointTable::single_step）
+///
+/// # Safety
+/// 同 BreakpointTable::single_step
+pub unsafe fn single_step(frame: &TrapFrame) -> bool {
+    BREAKPOINTS.lock().single_step(frame)
+}
+
+/// 处理断点异常
+///
+/// # 参数
+/// - `frame`: 陷阱现场
+///
+/// # 返回
+/// ebreak 的来源（见 BreakpointHit）
+///
+/// # 说明
+/// 断点表中的断点和临时断点：sepc 不变，返回后执行恢复的原指令；
+/// 其他 ebreak：按指令长度推进 sepc（2 或 4 字节）
+pub fn handle(frame: &mut TrapFrame) -> BreakpointHit {
+    // 陷阱来自 sepc 处的 ebreak，该地址一定可读
+    unsafe {
+        let hit = BREAKPOINTS.lock().on_hit(frame);
+        if hit == BreakpointHit::Foreign {
+            frame.sepc += instruction_len_at(frame.sepc);
+        }
+        hit
+    }
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// addi a0, a0, 1
+    const ADDI: u32 = 0x0015_0513;
+    /// c.nop
+    const C_NOP: u16 = 0x0001;
+
+    #[test_case]
+    fn test_ebreak_advances_by_instruction_length() {
+        // 指令按半字存放（小端）
+        let code: [u16; 3] = [C_EBREAK, EBREAK as u16, (EBREAK >> 16) as u16];
+        let base = code.as_ptr() as usize;
+
+        let mut frame = TrapFrame::new();
+        frame.sepc = base;
+        assert_eq!(handle(&mut frame), BreakpointHit::Foreign);
+        assert_eq!(frame.sepc, base + 2);
+
+        assert_eq!(handle(&mut frame), BreakpointHit::Foreign);
+        assert_eq!(frame.sepc, base + 6);
+    }
+
+    #[test_case]
+    fn test_planted_breakpoint_restores_and_steps_over() {
+        let mut code: [u16; 4] = [ADDI as u16, (ADDI >> 16) as u16, C_NOP, C_NOP];
+        let base = code.as_mut_ptr() as usize;
+        let original = code;
+        let mut table = BreakpointTable::new();
+        let mut frame = TrapFrame::new();
+
+        unsafe {
+            // 4 字节指令上的断点写入 4 字节 ebreak
+            assert!(table.insert(base));
+            assert!(!table.insert(base));
+            assert_eq!(read_instruction(base), (EBREAK, 4));
+
+            // 命中：原指令恢复，下一条（压缩）指令处放 c.ebreak
+            frame.sepc = base;
+            assert_eq!(table.on_hit(&frame), BreakpointHit::Resume);
+            assert_eq!(read_instruction(base), (ADDI, 4));
+            assert_eq!(read_instruction(base + 4), (C_EBREAK as u32, 2));
+
+            // 越过后：临时断点撤掉，原断点重新写回
+            frame.sepc = base + 4;
+            assert_eq!(table.on_hit(&frame), BreakpointHit::Resume);
+            assert_eq!(read_instruction(base + 4), (C_NOP as u32, 2));
+            assert_eq!(read_instruction(base), (EBREAK, 4));
+
+            // 不在断点表中的地址交给普通处理
+            frame.sepc = base + 6;
+            assert_eq!(table.on_hit(&frame), BreakpointHit::Foreign);
+
+            assert!(table.remove(base));
+            assert!(!table.remove(base));
+            assert_eq!(core::ptr::read_volatile(&code), original);
+        }
+    }
+
+    #[test_case]
+    fn test_single_step_advances_one_instruction() {
+        // addi; c.nop; addi; c.nop
+        let mut code: [u16; 6] = [
+            ADDI as u16, (ADDI >> 16) as u16, C_NOP,
+            ADDI as u16, (ADDI >> 16) as u16, C_NOP,
+        ];
+        let base = code.as_mut_ptr() as usize;
+        let original = code;
+        let mut table = BreakpointTable::new();
+        let mut frame = TrapFrame::new();
+        frame.sepc = base;
+
+        unsafe {
+            for expected in [base + 4, base + 6, base + 10] {
+                assert!(table.single_step(&frame));
+                assert!(!table.single_step(&frame));
+
+                // 模拟执行一条指令后停在临时断点上
+                let (_, len) = read_instruction(expected);
+                assert_eq!(read_instruction(expected), (if len == 4 { EBREAK } else { C_EBREAK as u32 }, len));
+                frame.sepc = expected;
+                assert_eq!(table.on_hit(&frame), BreakpointHit::Stepped);
+                assert!(!table.is_stepping());
+            }
+            assert_eq!(core::ptr::read_volatile(&code), original);
+
+            // 从断点上单步：断点先让位给原指令，单步完成后写回
+            assert!(table.insert(base));
+            frame.sepc = base;
+            assert!(table.single_step(&frame));
+            assert_eq!(read_instruction(base), (ADDI, 4));
+            frame.sepc = base + 4;
+            assert_eq!(table.on_hit(&frame), BreakpointHit::Stepped);
+            assert_eq!(read_instruction(base), (EBREAK, 4));
+            assert!(table.remove(base));
+        }
+    }
+
+    #[test_case]
+    fn test_breakpoint_hit_cancels_pending_step() {
+        // addi; c.nop; addi; c.nop
+        let mut code: [u16; 6] = [
+            ADDI as u16, (ADDI >> 16) as u16, C_NOP,
+            ADDI as u16, (ADDI >> 16) as u16, C_NOP,
+        ];
+        let base = code.as_mut_ptr() as usize;
+        let original = code;
+        let mut table = BreakpointTable::new();
+        let mut frame = TrapFrame::new();
+
+        unsafe {
+            assert!(table.insert(base + 6));
+            frame.sepc = base;
+            assert!(table.single_step(&frame));
+            assert_eq!(read_instruction(base + 4), (C_EBREAK as u32, 2));
+
+            // 单步还没完成就命中另一个断点：旧的临时断点撤掉，改为越过新断点
+            frame.sepc = base + 6;
+            assert_eq!(table.on_hit(&frame), BreakpointHit::Resume);
+            assert_eq!(read_instruction(base + 4), (C_NOP as u32, 2));
+            assert_eq!(read_instruction(base + 6), (ADDI, 4));
+            assert_eq!(read_instruction(base + 10), (C_EBREAK as u32, 2));
+
+            frame.sepc = base + 10;
+            assert_eq!(table.on_hit(&frame), BreakpointHit::Resume);
+            assert_eq!(read_instruction(base + 6), (EBREAK, 4));
+            assert!(!table.is_stepping());
+
+            assert!(table.remove(base + 6));
+            assert_eq!(core::ptr::read_volatile(&code), original);
+        }
+    }
+
+    #[test_case]
+    fn test_successors_of_jumps_and_branches() {
+        let mut regs = [0usize; 32];
+        regs[1] = 0x8000_1235; // ra（最低位被清除）
+        let pc = 0x8000_0000;
+
+        // j 8
+        assert_eq!(successors(pc, 0x0080_006f, 4, &regs), [pc + 8]);
+        // ret（jalr x0, 0(ra)）
+        assert_eq!(successors(pc, 0x0000_8067, 4, &regs), [0x8000_1234]);
+        // beq a0, a1, 16：两个方向
+        assert_eq!(successors(pc, 0x00b5_0863, 4, &regs), [pc + 4, pc + 16]);
+        // c.j 6
+        assert_eq!(successors(pc, 0xa019, 2, &regs), [pc + 6]);
+        // c.beqz a0, 8
+        assert_eq!(successors(pc, 0xc501, 2, &regs), [pc + 2, pc + 8]);
+        // c.jr ra
+        assert_eq!(successors(pc, 0x8082, 2, &regs), [0x8000_1234]);
+        // 普通指令和 c.ebreak 顺序执行
+        assert_eq!(successors(pc, ADDI, 4, &regs), [pc + 4]);
+        assert_eq!(successors(pc, C_EBREAK as u32, 2, &regs), [pc + 2]);
+    }
+}
@@ -0,0 +1,155 @@
+/*
+ * ============================================
+ * 陷阱帧（Trap Frame）
+ * ============================================
+ * 功能：定义陷阱入口汇编（trap.S）保存的现场布局，
+ *       以及用户/内核陷阱各自使用的保存区和栈
+ *
+ * 保存位置：
+ * - 用户态陷阱：USER_TRAP_FRAME（固定保存区）+ 当前进程的内核陷阱栈
+ *   （USER_TRAP_STACK_TOP 指向栈顶；还没有进程运行时使用 USER_TRAP_STACK）
+ * - 内核态陷阱：被打断的栈（当前进程自己的内核栈）上分配的帧，
+ *   因此处理系统调用期间发生的内核陷阱不会覆盖用户现场
+ *
+ * 注意：字段布局必须与 trap.S 中的偏移量一致
+ * ============================================
+ */
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// 陷阱栈大小（16KB，与 trap.S 中的 TRAP_STACK_SIZE 一致）
+pub const TRAP_STACK_SIZE: usize = 16 * 1024;
+
+/// 陷阱现场
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct TrapFrame {
+    /// 通用寄存器 x0 - x31（x0 不使用）
+    pub regs: [usize; 32],
+    /// 陷阱发生时的 PC，sret 返回到这里
+    pub sepc: usize,
+    /// 陷阱发生时的 sstatus（SPP 记录来源特权级）
+    pub sstatus: usize,
+}
+
+/// sstatus.SPP 位
+const SSTATUS_SPP: usize = 1 << 8;
+
+/// 栈指针寄存器编号（x2）
+const REG_SP: usize = 2;
+
+/// 参数/返回值寄存器 a0 的编号（x10）
+const REG_A0: usize = 10;
+
+impl TrapFrame {
+    /// 创建空的陷阱帧
+    pub const fn new() -> Self {
+        TrapFrame {
+            regs: [0; 32],
+            sepc: 0,
+            sstatus: 0,
+        }
+    }
+
+    /// 陷阱是否来自用户态
+    pub fn from_user(&self) -> bool {
+        self.sstatus & SSTATUS_SPP == 0
+    }
+
+    /// 参数寄存器 a0 - a7
+    pub fn arg(&self, index: usize) -> usize {
+        self.regs[REG_A0 + index]
+    }
+
+    /// 设置返回值（写入 a0）
+    pub fn set_return_value(&mut self, value: isize) {
+        self.regs[REG_A0] = value as usize;
+    }
+
+    /// 陷阱发生时的栈指针
+    pub fn sp(&self) -> usize {
+        self.regs[REG_SP]
+    }
+}
+
+impl Default for TrapFrame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 陷阱栈（16 字节对齐）
+#[repr(C, align(16))]
+pub struct TrapStack([u8; TRAP_STACK_SIZE]);
+
+/// 用户态陷阱的现场保存区（由 trap.S 写入）
+#[no_mangle]
+pub static mut USER_TRAP_FRAME: TrapFrame = TrapFrame::new();
+
+/// 还没有进程运行时处理用户态陷阱使用的内核栈
+#[no_mangle]
+static mut USER_TRAP_STACK: TrapStack = TrapStack([0; TRAP_STACK_SIZE]);
+
+/// 当前进程内核陷阱栈的栈顶（0 表示使用 USER_TRAP_STACK，由 trap.S 读取）
+#[no_mangle]
+static mut USER_TRAP_STACK_TOP: usize = 0;
+
+/// 读取当前保存的用户现场
+pub fn user_trap_frame() -> TrapFrame {
+    unsafe { core::ptr::addr_of!(USER_TRAP_FRAME).read_volatile() }
+}
+
+/// 用保存的现场覆盖 USER_TRAP_FRAME（切换回进程时恢复它的用户现场）
+pub fn set_user_trap_frame(frame: TrapFrame) {
+    unsafe { core::ptr::addr_of_mut!(USER_TRAP_FRAME).write_volatile(frame) }
+}
+
+/// 设置之后的用户态陷阱使用的内核栈栈顶
+pub fn set_user_trap_stack_top(top: usize) {
+    unsafe { core::ptr::addr_of_mut!(USER_TRAP_STACK_TOP).write_volatile(top) }
+}
+
+/// 进程私有的内核陷阱栈
+///
+/// # 说明
+/// 进程可能在处理用户态陷阱的途中被切换出去（时间片用完、阻塞、退出），
+/// 此时它的内核调用栈还在陷阱栈上；每个进程使用自己的栈，
+/// 其他进程的陷阱才不会覆盖这些栈帧
+pub struct KernelStack(Box<[u128]>);
+
+impl KernelStack {
+    /// 分配一个清零的陷阱栈（u128 元素保证 16 字节对齐）
+    pub fn new() -> Self {
+        KernelStack(vec![0u128; TRAP_STACK_SIZE / 16].into_boxed_slice())
+    }
+
+    /// 分配陷阱栈，堆耗尽时返回 None 而不是 panic
+    ///
+    /// # 说明
+    /// 测试构建中是内存分配的故障注入点（Fault::FrameAlloc）
+    pub fn try_new() -> Option<Self> {
+        #[cfg(test)]
+        if crate::fault::should_fail(crate::fault::Fault::FrameAlloc) {
+            return None;
+        }
+
+        let mut stack = Vec::new();
+        stack.try_reserve_exact(TRAP_STACK_SIZE / 16).ok()?;
+        stack.resize(TRAP_STACK_SIZE / 16, 0u128);
+        Some(KernelStack(stack.into_boxed_slice()))
+    }
+
+    /// 栈顶地址
+    pub fn top(&self) -> usize {
+        self.0.as_ptr() as usize + TRAP_STACK_SIZE
+    }
+}
+
+impl Default for KernelStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,260 @@
+//! 中断处理耗时统计与下半部（bottom half）
+//!
+//! 中断处理函数运行时中断是关闭的，处理得太久会推迟时钟中断和其他中断。
+//! - run_timed 用 time 计数器测量每个处理函数的耗时，超过 IRQ_BUDGET 时打印警告
+//! - 耗时的工作应通过 schedule_bottom_half 推迟到中断返回之后执行，类似 Linux 的 softirq：
+//!   异步执行器中的 bottom_half_task 和内核工作进程（kworker）都会取出并执行，
+//!   谁先运行谁执行，两者都在开中断的上下文中
+
+use conquer_once::spin::OnceCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::task::{Context, Poll};
+use crossbeam_queue::ArrayQueue;
+use futures_util::task::AtomicWaker;
+use crate::serial_println;
+
+/// 单个中断处理函数的耗时上限（time 计数，QEMU virt 上为 10MHz，即 1ms）
+pub const IRQ_BUDGET: u64 = 10_000;
+
+/// 超过上限的次数
+static OVER_BUDGET: AtomicUsize = AtomicUsize::new(0);
+
+/// 下半部队列容量
+pub const BOTTOM_HALF_CAPACITY: usize = 64;
+
+/// 推迟执行的工作
+type Work = fn();
+
+/// 下半部队列（中断上下文入队，bottom_half_task / kworker 出队）
+///
+/// 容量固定、在 init 中一次性分配，入队时不分配内存也不加锁
+static BOTTOM_HALVES: OnceCell<ArrayQueue<Work>> = OnceCell::uninit();
+
+/// 等待下半部的异步任务
+static BOTTOM_HALF_WAKER: AtomicWaker = AtomicWaker::new();
+
+/// 下半部入队错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BottomHalfError {
+    /// 队列尚未初始化（堆初始化之前）
+    NotInitialized,
+    /// 队列已满
+    QueueFull,
+}
+
+impl core::fmt::Display for BottomHalfError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            BottomHalfError::NotInitialized => write!(f, "bottom half queue not initialized"),
+            BottomHalfError::QueueFull => write!(f, "bottom half queue full"),
+        }
+    }
+}
+
+/// 分配下半部队列
+///
+/// # 说明
+/// 必须在堆初始化之后、开中断之前调用；重复调用会被忽略
+pub fn init() {
+    let _ = BOTTOM_HALVES.try_init_once(|| ArrayQueue::new(BOTTOM_HALF_CAPACITY));
+}
+
+/// 执行中断处理函数并测量耗时
+///
+/// # 参数
+/// - `name`: 处理函数名称（用于警告信息）
+/// - `handler`: 处理函数
+///
+/// # 返回
+/// 耗时（time 计数）
+pub fn run_timed(name: &'static str, handler: impl FnOnce()) -> u64 {
+    let start = riscv::register::time::read64();
+    handler();
+    let elapsed = riscv::register::time::read64().wrapping_sub(start);
+
+    if elapsed > IRQ_BUDGET {
+        OVER_BUDGET.fetch_add(1, Ordering::Relaxed);
+        serial_println!(
+            "[IRQ] Warning: {} handler took {} ticks (budget {}), defer heavy work to a bottom half",
+            name,
+            elapsed,
+            IRQ_BUDGET
+        );
+    }
+    elapsed
+}
+
+/// 处理函数超过耗时上限的累计次数
+pub fn over_budget_count() -> usize {
+    OVER_BUDGET.load(Ordering::Relaxed)
+}
+
+/// 把耗时的工作推迟到下半部执行
+///
+/// # 参数
+/// - `work`: 推迟执行的函数
+///
+/// # 返回
+/// 队列未初始化或已满时返回错误，工作不会执行
+///
+/// # 说明
+/// 可以在中断处理函数中调用：入队不分配内存也不加锁；工作在处理函数返回之后，
+/// 由异步执行器（bottom_half_task）或 kworker 执行
+pub fn schedule_bottom_half(work: fn()) -> Result<(), BottomHalfError> {
+    let queue = BOTTOM_HALVES
+        .try_get()
+        .map_err(|_| BottomHalfError::NotInitialized)?;
+    queue.push(work).map_err(|_| BottomHalfError::QueueFull)?;
+
+    BOTTOM_HALF_WAKER.wake();
+    crate::task::blocking::wake_worker();
+    Ok(())
+}
+
+/// 是否有待执行的下半部
+pub fn has_pending_bottom_halves() -> bool {
+    BOTTOM_HALVES
+        .try_get()
+        .map_or(false, |queue| !queue.is_empty())
+}
+
+/// 执行所有待处理的下半部
+///
+/// # 返回
+/// 本次执行的数量
+///
+/// # 说明
+/// 执行期间中断是打开的（若调用者开着中断），工作中可以再次登记下半部
+pub fn run_bottom_halves() -> usize {
+    let queue = match BOTTOM_HALVES.try_get() {
+        Ok(queue) => queue,
+        Err(_) => return 0,
+    };
+
+    let mut count = 0;
+    while let Some(work) = queue.pop() {
+        work();
+        count += 1;
+    }
+    count
+}
+
+/// 等待下半部入队的 Future
+struct PendingBottomHalves;
+
+impl Future for PendingBottomHalves {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if has_pending_bottom_halves() {
+            return Poll::Ready(());
+        }
+
+        BOTTOM_HALF_WAKER.register(cx.waker());
+
+        // 再次检查（防止注册前刚好入队而丢失唤醒）
+        if has_pending_bottom_halves() {
+            BOTTOM_HALF_WAKER.take();
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// 执行下半部的异步任务（由内核执行器运行）
+pub async fn bottom_half_task() {
+    loop {
+        PendingBottomHalves.await;
+        run_bottom_halves();
+    }
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::AtomicBool;
+
+    #[test_case]
+    fn test_slow_handler_warns_and_defers_work() {
+        static HEAVY_DONE: AtomicBool = AtomicBool::new(false);
+
+        let before = over_budget_count();
+        let elapsed = run_timed("slow", || {
+            // 故意超时的处理函数：只做必要的部分，重活推迟
+            let start = riscv::register::time::read64();
+            while riscv::register::time::read64().wrapping_sub(start) <= IRQ_BUDGET {
+                core::hint::spin_loop();
+            }
+            schedule_bottom_half(|| HEAVY_DONE.store(true, Ordering::SeqCst)).unwrap();
+        });
+
+        assert!(elapsed > IRQ_BUDGET);
+        assert_eq!(over_budget_count(), before + 1);
+
+        // 重活没有在中断处理函数中执行
+        assert!(!HEAVY_DONE.load(Ordering::SeqCst));
+        assert!(has_pending_bottom_halves());
+
+        // kworker 执行下半部
+        assert_eq!(run_bottom_halves(), 1);
+        assert!(HEAVY_DONE.load(Ordering::SeqCst));
+
+        // 快速的处理函数不计入
+        run_timed("fast", || {});
+        assert_eq!(over_budget_count(), before + 1);
+    }
+
+    #[test_case]
+    fn test_bottom_half_runs_after_handler_returns() {
+        use alloc::boxed::Box;
+        use futures_util::task::noop_waker_ref;
+
+        static RAN: AtomicBool = AtomicBool::new(false);
+
+        let mut task = Box::pin(bottom_half_task());
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        // 没有待处理的工作时任务挂起
+        assert!(task.as_mut().poll(&mut cx).is_pending());
+
+        // 模拟中断：处理函数只登记工作，返回前工作没有执行
+        run_timed("simulated", || {
+            schedule_bottom_half(|| RAN.store(true, Ordering::SeqCst)).unwrap();
+            assert!(!RAN.load(Ordering::SeqCst));
+        });
+        assert!(!RAN.load(Ordering::SeqCst));
+
+        // 执行器再次轮询任务时执行下半部
+        assert!(task.as_mut().poll(&mut cx).is_pending());
+        assert!(RAN.load(Ordering::SeqCst));
+        assert!(!has_pending_bottom_halves());
+    }
+
+    #[test_case]
+    fn test_schedule_bottom_half_fails_when_full() {
+        static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+        run_bottom_halves();
+        for _ in 0..BOTTOM_HALF_CAPACITY {
+            schedule_bottom_half(|| {
+                RUNS.fetch_add(1, Ordering::SeqCst);
+            })
+            .unwrap();
+        }
+
+        // 队列满时拒绝，而不是在中断上下文中扩容
+        assert_eq!(schedule_bottom_half(|| {}), Err(BottomHalfError::QueueFull));
+
+        assert_eq!(run_bottom_halves(), BOTTOM_HALF_CAPACITY);
+        assert_eq!(RUNS.load(Ordering::SeqCst), BOTTOM_HALF_CAPACITY);
+        assert!(schedule_bottom_half(|| {}).is_ok());
+        assert_eq!(run_bottom_halves(), 1);
+    }
+}
@@ -24,23 +24,43 @@
  * ============================================
  */
 
+pub mod breakpoint;      // 断点与调试器断点表
+pub mod frame;           // 陷阱帧与陷阱栈
+pub mod irq;             // 中断处理耗时统计与下半部
+pub mod timer;           // 按 tick 到期的定时器队列
+pub mod watchpoint;      // 基于页保护的数据观察点
+
+pub use frame::TrapFrame;
+
 use crate::{serial_println, println};
+use crate::process::signal::{SIGBUS, SIGSEGV};
+use core::sync::atomic::{AtomicU64, Ordering};
+use crate::memory::{lookup_pte, PageTableEntry, PageTableFlags, PhysAddr, VirtAddr};
 use riscv::register::{
     scause::{self, Exception, Interrupt, Trap},
-    sepc, stval, stvec,
+    stval, stvec,
 };
 
+// 陷阱入口汇编：保存现场后调用 trap_handler
+core::arch::global_asm!(include_str!("trap.S"));
+
+extern "C" {
+    /// 陷阱入口（trap.S）
+    fn __trap_entry();
+}
+
 /// 初始化陷阱处理系统
 ///
 /// # 功能
-/// - 设置 stvec 寄存器指向陷阱处理入口
+/// - 设置 stvec 寄存器指向陷阱入口汇编
 /// - 启用定时器中断（用于进程调度）
 /// - 设置第一个定时器中断
+/// - 启用 UART 接收中断（经 PLIC 送达；uart_polling 特性下改为时钟中断中轮询）
 pub fn init() {
     unsafe {
         // 设置陷阱向量地址（Direct 模式）
-        // 所有中断和异常都跳转到 trap_handler
-        stvec::write(trap_handler as usize, stvec::TrapMode::Direct);
+        // 所有中断和异常都先进入 __trap_entry 保存现场，再调用 trap_handler
+        stvec::write(__trap_entry as *const () as usize, stvec::TrapMode::Direct);
     }
 
     serial_println!("[INTERRUPT] Trap vector initialized");
@@ -55,22 +75,45 @@ pub fn init() {
     set_next_timer();
 
     serial_println!("[INTERRUPT] Timer interrupt enabled");
+
+    #[cfg(not(feature = "uart_polling"))]
+    {
+        crate::plic::init();
+        crate::serial::enable_rx_interrupt();
+        unsafe {
+            // 设置 sie 寄存器的 SEIE 位（Supervisor External Interrupt Enable）
+            riscv::register::sie::set_sext();
+        }
+        serial_println!("[INTERRUPT] UART receive interrupt enabled (PLIC IRQ {})", crate::plic::UART0_IRQ);
+    }
 }
 
 /// 统一的陷阱处理入口
 ///
+/// # 参数
+/// - `frame`: 陷阱现场（用户态陷阱为 USER_TRAP_FRAME，内核态陷阱为被打断的栈上的帧）
+///
 /// # 功能
 /// - 读取 scause 寄存器判断陷阱类型
 /// - 分发到对应的处理函数
+/// - 返回用户态前处理 need_resched（时钟中断推迟的调度）
 ///
 /// # 调用约定
-/// - 由硬件自动调用（通过 stvec 寄存器）
-/// - 进入时硬件已自动保存部分上下文
+/// - 由 trap.S 中的 __trap_entry 在保存现场后调用
+/// - 返回后 __trap_entry 按 frame 恢复现场（包括 sepc）并 sret，
+///   因此处理函数应修改 frame.sepc 而不是直接写 sepc 寄存器
 #[no_mangle]
-pub extern "C" fn trap_handler() {
+pub extern "C" fn trap_handler(frame: &mut TrapFrame) {
     let scause = scause::read();
     let stval = stval::read();
-    let sepc = sepc::read();
+    let sepc = frame.sepc;
+
+    // 用户栈指针越界：栈已损坏或溢出，不再继续使用这个现场
+    if !check_user_stack(frame) {
+        crate::process::coredump::dump_current(SIGSEGV, stval, frame);
+        crate::process::kill_current_process(SIGSEGV);
+        return;
+    }
 
     match scause.cause() {
         // ============================================
@@ -79,21 +122,22 @@ pub extern "C" fn trap_handler() {
         Trap::Interrupt(interrupt) => {
             match interrupt {
                 Interrupt::SupervisorTimer => {
-                    timer_interrupt_handler();
+                    irq::run_timed("timer", timer_interrupt_handler);
                 }
                 Interrupt::SupervisorExternal => {
-                    external_interrupt_handler();
+                    irq::run_timed("external", external_interrupt_handler);
                 }
                 Interrupt::SupervisorSoft => {
-                    software_interrupt_handler();
+                    irq::run_timed("software", software_interrupt_handler);
                 }
                 _ => {
                     panic!(
                         "Unhandled interrupt!\n\
-                        scause: {:?}\n\
+                        scause: {:#x} ({})\n\
                         sepc: {:#x}\n\
                         stval: {:#x}",
-                        scause.cause(),
+                        scause.bits(),
+                        Cause(scause.cause()),
                         sepc,
                         stval
                     );
@@ -107,27 +151,32 @@ pub extern "C" fn trap_handler() {
         Trap::Exception(exception) => {
             match exception {
                 Exception::Breakpoint => {
-                    breakpoint_handler(sepc);
+                    breakpoint_handler(frame);
+                }
+                Exception::StorePageFault
+                    if watchpoint::handle_store_fault(stval, frame) => {
+                    // 观察页上的写入：已放行，单步执行存储指令
                 }
                 Exception::LoadPageFault |
                 Exception::StorePageFault |
                 Exception::InstructionPageFault => {
-                    page_fault_handler(scause.cause(), stval, sepc);
+                    page_fault_handler(scause.cause(), stval, frame);
                 }
                 Exception::IllegalInstruction => {
                     illegal_instruction_handler(sepc, stval);
                 }
                 Exception::UserEnvCall => {
                     // 系统调用处理入口
-                    syscall_handler(sepc);
+                    syscall_handler(frame);
                 }
                 _ => {
                     panic!(
                         "Unhandled exception!\n\
-                        scause: {:?}\n\
+                        scause: {:#x} ({})\n\
                         sepc: {:#x}\n\
                         stval: {:#x}",
-                        scause.cause(),
+                        scause.bits(),
+                        Cause(scause.cause()),
                         sepc,
                         stval
                     );
@@ -135,20 +184,129 @@ pub extern "C" fn trap_handler() {
             }
         }
     }
+
+    // 返回用户态前的安全点：处理时钟中断推迟的重新调度
+    if frame.from_user() {
+        crate::process::scheduler::resched_if_needed();
+
+        // 递送待处理信号：不做系统调用的进程也在下一次时钟中断时收到 SIGTERM
+        crate::process::signal::deliver_current(frame);
+
+        // 调试器请求单步时，在即将执行的指令的后继处放临时断点
+        #[cfg(feature = "ptrace")]
+        crate::process::ptrace::arm_single_step(frame);
+    }
+}
+
+// ============================================
+// 陷阱原因
+// ============================================
+
+/// 陷阱原因的可读描述
+///
+/// # 参数
+/// - `cause`: scause 解析出的陷阱原因
+///
+/// # 返回
+/// 如 "Store page fault"、"Supervisor timer interrupt"
+pub fn describe(cause: Trap) -> &'static str {
+    match cause {
+        Trap::Interrupt(interrupt) => match interrupt {
+            Interrupt::SupervisorSoft => "Supervisor software interrupt",
+            Interrupt::SupervisorTimer => "Supervisor timer interrupt",
+            Interrupt::SupervisorExternal => "Supervisor external interrupt",
+            Interrupt::Unknown => "Unknown interrupt",
+        },
+        Trap::Exception(exception) => match exception {
+            Exception::InstructionMisaligned => "Instruction address misaligned",
+            Exception::InstructionFault => "Instruction access fault",
+            Exception::IllegalInstruction => "Illegal instruction",
+            Exception::Breakpoint => "Breakpoint",
+            Exception::LoadMisaligned => "Load address misaligned",
+            Exception::LoadFault => "Load access fault",
+            Exception::StoreMisaligned => "Store address misaligned",
+            Exception::StoreFault => "Store access fault",
+            Exception::UserEnvCall => "Environment call from U-mode",
+            Exception::SupervisorEnvCall => "Environment call from S-mode",
+            Exception::InstructionPageFault => "Instruction page fault",
+            Exception::LoadPageFault => "Load page fault",
+            Exception::StorePageFault => "Store page fault",
+            Exception::Unknown => "Unknown exception",
+        },
+    }
+}
+
+/// 陷阱原因在 scause 中的编号（不含中断位）；未知原因返回 None
+pub fn cause_code(cause: Trap) -> Option<usize> {
+    match cause {
+        Trap::Interrupt(interrupt) => usize::try_from(interrupt).ok(),
+        Trap::Exception(exception) => usize::try_from(exception).ok(),
+    }
+}
+
+/// 打印陷阱原因：描述和编号，如 "Store page fault (exception 15)"
+pub struct Cause(pub Trap);
+
+impl core::fmt::Display for Cause {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let kind = match self.0 {
+            Trap::Interrupt(_) => "interrupt",
+            Trap::Exception(_) => "exception",
+        };
+        match cause_code(self.0) {
+            Some(code) => write!(f, "{} ({} {})", describe(self.0), kind, code),
+            None => f.write_str(describe(self.0)),
+        }
+    }
 }
 
 // ============================================
 // 中断处理函数
 // ============================================
 
+/// 每秒的时钟中断次数（定时器间隔 100ms，见 set_next_timer）
+pub const TICKS_PER_SECOND: u64 = 10;
+
+/// 开机以来的时钟中断次数
+static UPTIME_TICKS: AtomicU64 = AtomicU64::new(0);
+
+/// 开机以来的时钟中断次数（单调递增的 tick 时钟）
+///
+/// # 说明
+/// 与 time CSR 不同，这里的单位是时钟中断，不依赖时钟频率的假设；
+/// 64 位计数在 100ms 的间隔下不会回绕。睡眠、定时器等按 tick 计时的功能
+/// 应使用这个计数
+pub fn uptime_ticks() -> u64 {
+    UPTIME_TICKS.load(Ordering::Relaxed)
+}
+
+/// 开机以来的秒数（由 tick 计数换算，单调递增）
+pub fn uptime_seconds() -> u64 {
+    uptime_ticks() / TICKS_PER_SECOND
+}
+
 /// 时钟中断处理
 ///
 /// # 功能
-/// - 处理定时器中断
+/// - 推进 tick 计数，唤醒到期的定时器，定期采样系统负载
+/// - 当前进程的时间片记账（scheduler::tick）
 /// - 轮询键盘输入
 /// - 设置下一次定时器中断
 fn timer_interrupt_handler() {
-    // 轮询键盘输入（通过 SBI console）
+    let now = UPTIME_TICKS.fetch_add(1, Ordering::Relaxed) + 1;
+    timer::expire(now);
+    crate::process::loadavg::tick(now, || {
+        crate::process::scheduler::lock_scheduler().nr_running()
+    });
+
+    // 当前进程的时间片记账，用完时请求调度（返回用户态前切换）；
+    // 还没有进程被调度时不获取调度器锁
+    if crate::process::scheduler::cached_current_pid().is_some() {
+        crate::process::scheduler::tick();
+    }
+
+    // 没有 PLIC 时轮询键盘输入（通过 SBI console），否则由 UART 接收中断处理
+    #[cfg(feature = "uart_polling")]
     crate::task::keyboard::poll_keyboard();
 
     // 设置下一次定时器中断
@@ -159,9 +317,20 @@ fn timer_interrupt_handler() {
 ///
 /// # 功能
 /// - 处理外部设备中断（如 UART、网卡等）
-/// - 通过 PLIC（Platform-Level Interrupt Controller）管理
+/// - 通过 PLIC（Platform-Level Interrupt Controller）管理：
+///   逐个 claim 待处理的中断源，服务设备后 complete
 fn external_interrupt_handler() {
-    serial_println!("[INTERRUPT] External interrupt received");
+    while let Some(irq) = crate::plic::claim() {
+        match irq {
+            crate::plic::UART0_IRQ => {
+                crate::task::keyboard::uart_interrupt_handler();
+            }
+            _ => {
+                serial_println!("[INTERRUPT] Unexpected external interrupt: IRQ {}", irq);
+            }
+        }
+        crate::plic::complete(irq);
+    }
 }
 
 /// 软件中断处理
@@ -180,17 +349,116 @@ fn software_interrupt_handler() {
 /// 断点异常处理
 ///
 /// # 参数
-/// - `sepc`: 异常发生时的程序计数器
+/// - `frame`: 陷阱现场
 ///
 /// # 功能
 /// - 处理 ebreak 指令触发的断点异常
 /// - 用于调试
-fn breakpoint_handler(sepc: usize) {
-    serial_println!("[EXCEPTION] Breakpoint at {:#x}", sepc);
-    println!("EXCEPTION: BREAKPOINT at {:#x}", sepc);
+/// - 调试器设置的断点恢复原指令后单步越过，其他 ebreak 按指令长度跳过（见 breakpoint 模块）
+/// - 观察点放行的存储指令单步完成后重新保护页面（见 watchpoint 模块）
+/// - 用户进程单步完成时停下，等待调试器（ptrace 特性）
+fn breakpoint_handler(frame: &mut TrapFrame) {
+    serial_println!("[EXCEPTION] Breakpoint at {:#x}", frame.sepc);
+    println!("EXCEPTION: BREAKPOINT at {:#x}", frame.sepc);
+
+    let hit = breakpoint::handle(frame);
+    if hit == breakpoint::BreakpointHit::Stepped && watchpoint::step_done() {
+        return;
+    }
+    #[cfg(feature = "ptrace")]
+    if hit == breakpoint::BreakpointHit::Stepped && frame.from_user() {
+        crate::process::ptrace::step_stopped(frame);
+    }
+    #[cfg(not(feature = "ptrace"))]
+    let _ = hit;
+}
+
+/// 页错误的访问类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultAccess {
+    /// 读（LoadPageFault）
+    Read,
+    /// 写（StorePageFault）
+    Write,
+    /// 取指（InstructionPageFault）
+    Execute,
+}
 
-    // 断点指令后继续执行（跳过 ebreak 指令）
-    riscv::register::sepc::write(sepc + 2); // ebreak 是 2 字节压缩指令
+/// 页错误分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageFaultKind {
+    /// 访问未映射的页
+    NotMapped,
+    /// 写只读代码页（可执行但不可写）
+    WriteToCode,
+    /// 写只读数据页
+    WriteToReadOnly,
+    /// 读不可读的页（如仅执行页）
+    ReadNotReadable,
+    /// 执行不可执行的页
+    ExecuteNotExecutable,
+    /// 用户态访问内核页
+    UserAccessKernel,
+    /// 权限允许但仍然出错（如 A/D 位未设置）
+    Other,
+}
+
+impl PageFaultKind {
+    /// 是否为权限违规（页已映射但访问不被允许）
+    pub fn is_protection_violation(&self) -> bool {
+        !matches!(self, PageFaultKind::NotMapped | PageFaultKind::Other)
+    }
+}
+
+impl core::fmt::Display for PageFaultKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            PageFaultKind::NotMapped => write!(f, "access to unmapped page"),
+            PageFaultKind::WriteToCode => write!(f, "write to read-only code page"),
+            PageFaultKind::WriteToReadOnly => write!(f, "write to read-only page"),
+            PageFaultKind::ReadNotReadable => write!(f, "read from non-readable page"),
+            PageFaultKind::ExecuteNotExecutable => write!(f, "execute from non-executable page"),
+            PageFaultKind::UserAccessKernel => write!(f, "user access to kernel page"),
+            PageFaultKind::Other => write!(f, "page fault with sufficient permissions"),
+        }
+    }
+}
+
+/// 根据页表项权限位对页错误分类
+///
+/// # 参数
+/// - `access`: 访问类型
+/// - `pte`: 出错地址的叶子页表项（None 表示未映射）
+/// - `from_user`: 是否来自用户态
+pub fn classify_page_fault(
+    access: FaultAccess,
+    pte: Option<PageTableEntry>,
+    from_user: bool,
+) -> PageFaultKind {
+    let pte = match pte {
+        Some(pte) if pte.is_valid() => pte,
+        _ => return PageFaultKind::NotMapped,
+    };
+
+    let flags = pte.flags();
+    let has = |flag: PageTableFlags| flags & flag as usize != 0;
+
+    if from_user && !has(PageTableFlags::User) {
+        return PageFaultKind::UserAccessKernel;
+    }
+
+    match access {
+        FaultAccess::Write if !has(PageTableFlags::Write) => {
+            if has(PageTableFlags::Execute) {
+                PageFaultKind::WriteToCode
+            } else {
+                PageFaultKind::WriteToReadOnly
+            }
+        }
+        FaultAccess::Read if !has(PageTableFlags::Read) => PageFaultKind::ReadNotReadable,
+        FaultAccess::Execute if !has(PageTableFlags::Execute) => PageFaultKind::ExecuteNotExecutable,
+        _ => PageFaultKind::Other,
+    }
 }
 
 /// 页错误处理
@@ -198,30 +466,114 @@ fn breakpoint_handler(sepc: usize) {
 /// # 参数
 /// - `cause`: 异常类型（Load/Store/Instruction Page Fault）
 /// - `stval`: 触发异常的虚拟地址
-/// - `sepc`: 异常发生时的程序计数器
+/// - `frame`: 异常发生时的现场
 ///
 /// # 功能
-/// - 处理访问无效内存地址的异常
-/// - 未来可扩展为按需分页（Demand Paging）
-fn page_fault_handler(cause: Trap, stval: usize, sepc: usize) {
+/// - 查询出错地址的页表项，区分"未映射"和"权限违规"
+/// - 用户态出错：终止当前进程（开启核心转储时先写 /core.<pid>）；
+///   访问文件映射中被 truncate 截掉的部分按 SIGBUS，其他按 SIGSEGV
+/// - 内核态出错：停机
+/// - 用户栈底正下方的未映射访问：按需增长用户栈（见 process::stack）
+fn page_fault_handler(cause: Trap, stval: usize, frame: &TrapFrame) {
+    use riscv::register::satp;
+
+    let (sepc, from_user) = (frame.sepc, frame.from_user());
+
+    let access = match cause {
+        Trap::Exception(Exception::StorePageFault) => FaultAccess::Write,
+        Trap::Exception(Exception::InstructionPageFault) => FaultAccess::Execute,
+        _ => FaultAccess::Read,
+    };
+
+    // satp 为 Bare 模式时没有页表可查
+    let satp_value = satp::read();
+    let root = PhysAddr::new(satp_value.ppn() << 12);
+    let pte = if satp_value.mode() == satp::Mode::Bare {
+        None
+    } else {
+        lookup_pte(root, VirtAddr::new(stval))
+    };
+
+    let kind = classify_page_fault(access, pte, from_user);
+
+    // 用户栈底正下方的未映射访问：映射新页后重新执行
+    if from_user
+        && kind == PageFaultKind::NotMapped
+        && crate::process::stack::handle_user_stack_fault(stval)
+    {
+        return;
+    }
+
     serial_println!(
-        "[EXCEPTION] Page Fault\n\
-        Type: {:?}\n\
+        "[EXCEPTION] Page Fault: {}\n\
+        Type: {}\n\
         Address: {:#x}\n\
         PC: {:#x}",
-        cause,
+        kind,
+        Cause(cause),
         stval,
         sepc
     );
 
-    println!("EXCEPTION: PAGE FAULT");
+    println!("EXCEPTION: PAGE FAULT ({})", kind);
     println!("Accessed Address: {:#x}", stval);
     println!("Exception PC: {:#x}", sepc);
-    println!("Fault Type: {:?}", cause);
+    println!("Fault Type: {}", Cause(cause));
+
+    if from_user {
+        if let Some(pid) = crate::process::current_pid() {
+            serial_println!("[EXCEPTION] Killing process PID={}: {}", pid, kind);
+            let signal = if kind == PageFaultKind::NotMapped
+                && crate::fs::mmap::is_truncated(root, stval)
+            {
+                SIGBUS
+            } else {
+                SIGSEGV
+            };
+            crate::process::coredump::dump_current(signal, stval, frame);
+            crate::process::exit_current_process(-signal);
+        }
+    }
 
     crate::hlt_loop();
 }
 
+/// 检查用户态陷阱时的栈指针
+///
+/// # 参数
+/// - `frame`: 陷入时保存的现场
+///
+/// # 返回
+/// sp 越界时打印诊断信息并返回 false；
+/// 来自内核态的陷阱或没有当前进程时返回 true
+///
+/// # 说明
+/// 只检查 sp 是否落在保留的栈范围 [limit, top] 内；
+/// 栈底下方尚未映射的部分由页错误处理按需增长
+fn check_user_stack(frame: &TrapFrame) -> bool {
+    if !frame.from_user() {
+        return true;
+    }
+    let process = match crate::process::current_process() {
+        Some(process) => process,
+        None => return true,
+    };
+    let pcb = process.lock();
+    if crate::process::stack::user_sp_in_range(&pcb, frame.sp()) {
+        return true;
+    }
+
+    serial_println!(
+        "[EXCEPTION] Killing process PID={}: user sp {:#x} outside stack [{:#x}, {:#x}] (PC: {:#x})",
+        pcb.pid(),
+        frame.sp(),
+        pcb.user_stack_limit(),
+        pcb.user_stack_top(),
+        frame.sepc
+    );
+    false
+}
+
 /// 非法指令处理
 ///
 /// # 参数
@@ -243,7 +595,7 @@ fn illegal_instruction_handler(sepc: usize, stval: usize) {
 /// 系统调用处理
 ///
 /// # 参数
-/// - `sepc`: 系统调用发生时的程序计数器
+/// - `frame`: 发起系统调用时保存的现场
 ///
 /// # 功能
 /// - 处理用户态程序通过 ecall 指令触发的系统调用
@@ -251,20 +603,26 @@ fn illegal_instruction_handler(sepc: usize, stval: usize) {
 ///   - a7: 系统调用号
 ///   - a0-a5: 参数
 ///   - a0: 返回值
-fn syscall_handler(sepc: usize) {
-    // 从寄存器读取系统调用上下文
-    let context = unsafe { crate::syscall::SyscallContext::from_registers() };
+fn syscall_handler(frame: &mut TrapFrame) {
+    // 从陷阱现场读取系统调用上下文
+    let context = crate::syscall::SyscallContext::from_trap_frame(frame);
+
+    // sigreturn 换回处理函数之前保存的整个现场（包括 a0 和 sepc），
+    // 不写返回值，也不跳过 ecall
+    if crate::syscall::SyscallId::from(context.syscall_id) == crate::syscall::SyscallId::SigReturn
+        && crate::process::signal::sigreturn_current(frame)
+    {
+        return;
+    }
 
     // 调用系统调用分发器
     let result = crate::syscall::syscall_dispatcher(&context);
 
-    // 设置返回值到 a0 寄存器
-    unsafe {
-        context.set_return_value(result);
-    }
+    // 返回值写入现场的 a0，sret 时恢复
+    frame.set_return_value(result);
 
     // 系统调用返回后需要跳过 ecall 指令
-    riscv::register::sepc::write(sepc + 4); // ecall 是 4 字节指令
+    frame.sepc += 4; // ecall 是 4 字节指令
 }
 
 // ============================================
@@ -345,7 +703,7 @@ pub fn disable_interrupts() {
 /// - 时间间隔：1,000,000 时钟周期（约 100ms @ 10MHz）
 fn set_next_timer() {
     // QEMU RISC-V virt 机器的时钟频率为 10MHz
-    const TIMER_INTERVAL: u64 = 1_000_000; // 100ms
+    const TIMER_INTERVAL: u64 = 10_000_000 / TICKS_PER_SECOND; // 100ms
 
     // 读取当前时间
     let time = riscv::register::time::read64();
@@ -394,3 +752,246 @@ fn test_breakpoint_exception() {
 
     serial_println!("[TEST] Breakpoint handled successfully");
 }
+
+#[cfg(test)]
+#[test_case]
+fn test_kernel_trap_preserves_user_context() {
+    use crate::syscall::SyscallId;
+    use frame::USER_TRAP_FRAME;
+
+    // 模拟一次正在处理的用户态系统调用：现场已保存在 USER_TRAP_FRAME
+    let mut user = TrapFrame::new();
+    for (i, reg) in user.regs.iter_mut().enumerate().skip(1) {
+        *reg = 0x1000 + i;
+    }
+    user.regs[17] = SyscallId::GetPid as usize; // a7
+    user.sepc = 0x1_0000;
+    user.sstatus = 0; // SPP = User
+    unsafe { core::ptr::addr_of_mut!(USER_TRAP_FRAME).write_volatile(user) };
+
+    // 处理系统调用期间触发内核态断点（嵌套陷阱的帧在当前栈上）
+    unsafe {
+        core::arch::asm!("ebreak");
+    }
+
+    let saved = frame::user_trap_frame();
+    assert_eq!(saved.regs, user.regs);
+    assert_eq!(saved.sepc, user.sepc);
+    assert!(saved.from_user());
+
+    // 用户现场完好，系统调用可以照常完成
+    let mut frame = saved;
+    syscall_handler(&mut frame);
+    assert_eq!(frame.sepc, user.sepc + 4);
+    assert_eq!(frame.sp(), user.sp());
+}
+
+#[cfg(test)]
+#[test_case]
+fn test_kernel_trap_frame_is_on_interrupted_stack() {
+    // 帧紧挨在被打断的 sp 之下，sret 之后帧里保存的 sp 还在原处
+    const SP_SLOT: usize = core::mem::size_of::<TrapFrame>() - 2 * 8;
+
+    let sp: usize;
+    let saved_sp: usize;
+    unsafe {
+        core::arch::asm!(
+            "mv {sp}, sp",
+            "ebreak",
+            "ld {saved}, -{slot}(sp)",
+            sp = out(reg) sp,
+            saved = out(reg) saved_sp,
+            slot = const SP_SLOT,
+        );
+    }
+
+    // 不再经过全局的内核陷阱栈，帧属于当前执行流自己的栈
+    assert_eq!(saved_sp, sp);
+}
+
+#[cfg(test)]
+#[test_case]
+fn test_write_to_read_only_page_is_protection_violation() {
+    use crate::memory::PageTable;
+    use alloc::boxed::Box;
+
+    const CODE_PAGE: usize = 0x4000_0000;
+    const DATA_PAGE: usize = 0x4000_1000;
+    const UNMAPPED: usize = 0x4000_2000;
+
+    // 在堆上搭建一套三级页表（恒等映射下堆地址即物理地址）
+    let mut root = Box::new(PageTable::new());
+    let mut table1 = Box::new(PageTable::new());
+    let mut table0 = Box::new(PageTable::new());
+    let valid = PageTableFlags::Valid as usize;
+
+    let code = VirtAddr::new(CODE_PAGE);
+    root.get_entry_mut(code.vpn2()).set(&*table1 as *const PageTable as usize >> 12, valid);
+    table1.get_entry_mut(code.vpn1()).set(&*table0 as *const PageTable as usize >> 12, valid);
+
+    // 只读代码页（R|X）和只读数据页（R）
+    let user = PageTableFlags::User as usize;
+    table0.get_entry_mut(code.vpn0()).set(
+        0x80000,
+        valid | user | PageTableFlags::Read as usize | PageTableFlags::Execute as usize,
+    );
+    table0.get_entry_mut(VirtAddr::new(DATA_PAGE).vpn0()).set(
+        0x80001,
+        valid | user | PageTableFlags::Read as usize,
+    );
+
+    let root_paddr = PhysAddr::new(&*root as *const PageTable as usize);
+    let fault = |addr: usize, access: FaultAccess| {
+        classify_page_fault(access, lookup_pte(root_paddr, VirtAddr::new(addr)), true)
+    };
+
+    let kind = fault(CODE_PAGE, FaultAccess::Write);
+    assert_eq!(kind, PageFaultKind::WriteToCode);
+    assert!(kind.is_protection_violation());
+
+    assert_eq!(fault(DATA_PAGE, FaultAccess::Write), PageFaultKind::WriteToReadOnly);
+    assert_eq!(fault(DATA_PAGE, FaultAccess::Execute), PageFaultKind::ExecuteNotExecutable);
+    assert_eq!(fault(CODE_PAGE, FaultAccess::Read), PageFaultKind::Other);
+
+    let kind = fault(UNMAPPED, FaultAccess::Write);
+    assert_eq!(kind, PageFaultKind::NotMapped);
+    assert!(!kind.is_protection_violation());
+
+    // 用户态访问没有 U 位的页
+    let kernel_pte = PageTableEntry::new();
+    assert_eq!(
+        classify_page_fault(FaultAccess::Read, Some(kernel_pte), true),
+        PageFaultKind::NotMapped
+    );
+    let mut kernel_pte = PageTableEntry::new();
+    kernel_pte.set(0x80002, valid | PageTableFlags::Read as usize);
+    assert_eq!(
+        classify_page_fault(FaultAccess::Read, Some(kernel_pte), true),
+        PageFaultKind::UserAccessKernel
+    );
+}
+
+#[cfg(test)]
+#[test_case]
+fn test_describe_trap_causes() {
+    use alloc::format;
+
+    let store = Trap::Exception(Exception::StorePageFault);
+    assert_eq!(describe(store), "Store page fault");
+    assert_eq!(cause_code(store), Some(15));
+    assert_eq!(format!("{}", Cause(store)), "Store page fault (exception 15)");
+
+    assert_eq!(describe(Trap::Exception(Exception::IllegalInstruction)), "Illegal instruction");
+    assert_eq!(describe(Trap::Exception(Exception::UserEnvCall)), "Environment call from U-mode");
+
+    let timer = Trap::Interrupt(Interrupt::SupervisorTimer);
+    assert_eq!(format!("{}", Cause(timer)), "Supervisor timer interrupt (interrupt 5)");
+
+    // 未知原因没有编号
+    let unknown = Trap::Exception(Exception::Unknown);
+    assert_eq!(cause_code(unknown), None);
+    assert_eq!(format!("{}", Cause(unknown)), "Unknown exception");
+}
+
+#[cfg(test)]
+#[test_case]
+fn test_trap_rejects_user_sp_outside_stack() {
+    use crate::process::{create_process, scheduler, stack::USER_STACK_MAX};
+
+    const STACK_TOP: usize = 0x4000_0000;
+
+    let process = create_process("bad_sp", 0x1000, STACK_TOP, None).unwrap();
+    let pid = process.lock().pid();
+    scheduler::add_process(process.clone()).unwrap();
+    scheduler::lock_scheduler().run_for_test(pid);
+
+    let mut frame = TrapFrame::new();
+    frame.sstatus = 0; // SPP = User
+    let mut check_sp = |sp: usize| {
+        frame.regs[2] = sp;
+        check_user_stack(&frame)
+    };
+
+    // 保留范围内都接受，包括栈底下方还没有映射的部分（访问时再按需增长）
+    let bottom = process.lock().user_stack_bottom();
+    assert!(check_sp(STACK_TOP - 64));
+    assert!(check_sp(STACK_TOP));
+    assert!(check_sp(bottom - 8));
+    assert!(check_sp(bottom - 4 * crate::memory::PAGE_SIZE));
+    assert!(check_sp(STACK_TOP - USER_STACK_MAX));
+
+    // 高于栈顶、超过最大栈大小都拒绝
+    assert!(!check_sp(STACK_TOP + 8));
+    assert!(!check_sp(STACK_TOP - USER_STACK_MAX - 8));
+
+    // 内核态陷阱不检查
+    frame.sstatus = 1 << 8; // SPP = Supervisor
+    frame.regs[2] = 0;
+    assert!(check_user_stack(&frame));
+
+    scheduler::lock_scheduler().remove_process(pid);
+}
+
+/// 模拟 `ticks` 次时钟中断（关中断执行，测试期间真正的时钟中断不会计数）
+#[cfg(test)]
+pub(crate) fn advance_ticks(ticks: u64) {
+    without_interrupts(|| {
+        for _ in 0..ticks {
+            timer_interrupt_handler();
+        }
+    });
+}
+
+#[cfg(test)]
+#[test_case]
+fn test_timer_interrupt_preempts_current_process() {
+    use crate::process::{create_process, scheduler, set_priority, PRIORITY_MAX};
+
+    let hart = crate::percpu::current();
+    hart.take_need_resched();
+
+    let spawn = |name| {
+        let process = create_process(name, 0x1000, 0x2000, None).unwrap();
+        let pid = process.lock().pid();
+        scheduler::add_process(process).unwrap();
+        pid
+    };
+    let running = spawn("tick_running");
+    let waiting = spawn("tick_waiting");
+    assert!(set_priority(waiting, PRIORITY_MAX));
+    scheduler::lock_scheduler().run_for_test(running);
+
+    // 时间片（5 个 tick）用完后请求调度，返回用户态前发生切换
+    let switches = scheduler::context_switches();
+    advance_ticks(5);
+    assert!(hart.need_resched());
+    assert!(scheduler::lock_scheduler().resched_if_needed());
+    assert!(scheduler::context_switches() > switches);
+    assert_eq!(scheduler::current_pid(), Some(waiting));
+
+    {
+        let mut scheduler = scheduler::lock_scheduler();
+        scheduler.remove_process(running);
+        scheduler.remove_process(waiting);
+    }
+
+    // 没有当前进程：时钟中断不做时间片记账
+    assert_eq!(scheduler::cached_current_pid(), None);
+    advance_ticks(10);
+    assert!(!hart.need_resched());
+}
+
+#[cfg(test)]
+#[test_case]
+fn test_uptime_ticks_counts_timer_interrupts() {
+    const TICKS: u64 = 5;
+
+    // 关中断，避免真正的时钟中断在测试期间计数
+    without_interrupts(|| {
+        let before = uptime_ticks();
+        for _ in 0..TICKS {
+            timer_interrupt_handler();
+        }
+        assert_eq!(uptime_ticks(), before + TICKS);
+    });
+}
@@ -0,0 +1,70 @@
+//! 定时器队列
+//!
+//! 按 tick（uptime_ticks）到期唤醒进程：进程登记到期时刻后阻塞，
+//! 时钟中断推进 tick 后调用 expire，唤醒所有已到期的进程。
+//! 队列是固定大小的数组，中断中的 expire 不分配也不释放堆内存。
+
+use crate::process::{scheduler, ProcessId};
+use crate::sync::IrqSpinLock;
+
+/// 同时登记的定时器上限
+pub const MAX_TIMERS: usize = 64;
+
+/// 一个登记的定时器
+#[derive(Debug, Clone, Copy)]
+struct Timer {
+    /// 到期的 tick
+    deadline: u64,
+    /// 到期时唤醒的进程
+    pid: ProcessId,
+}
+
+/// 定时器槽位（中断上下文访问）
+static TIMERS: IrqSpinLock<[Option<Timer>; MAX_TIMERS]> = IrqSpinLock::new([None; MAX_TIMERS]);
+
+/// 登记在 `deadline` 唤醒进程 `pid`
+///
+/// # 返回
+/// 队列已满时返回 false
+///
+/// # 说明
+/// 同一进程可以登记多次，多余的唤醒对未阻塞的进程没有影响
+pub fn wake_at(deadline: u64, pid: ProcessId) -> bool {
+    let mut timers = TIMERS.lock();
+    match timers.iter_mut().find(|slot| slot.is_none()) {
+        Some(slot) => {
+            *slot = Some(Timer { deadline, pid });
+            true
+        }
+        None => false,
+    }
+}
+
+/// 唤醒所有在 `now` 之前到期的进程（时钟中断中调用）
+///
+/// # 返回
+/// 到期的定时器数
+pub fn expire(now: u64) -> usize {
+    let mut expired = [None; MAX_TIMERS];
+    let mut count = 0;
+    {
+        let mut timers = TIMERS.lock();
+        for slot in timers.iter_mut() {
+            if slot.is_some_and(|timer| timer.deadline <= now) {
+                expired[count] = slot.take().map(|timer| timer.pid);
+                count += 1;
+            }
+        }
+    }
+
+    // 释放队列锁后再唤醒，避免与调度器锁嵌套
+    for pid in expired.iter().flatten() {
+        scheduler::wake_up(*pid);
+    }
+    count
+}
+
+/// 尚未到期的定时器数
+pub fn pending() -> usize {
+    TIMERS.lock().iter().flatten().count()
+}
@@ -0,0 +1,372 @@
+//! 数据观察点（watchpoint）
+//!
+//! 用页保护实现，捕获对指定地址的写入：
+//! - 设置观察点时保存所在页的页表项标志并清除 W 位，对该页的写入都会触发 StorePageFault
+//! - 页错误处理先交给 handle_store_fault：出错地址落在观察点内时记录并报告，
+//!   然后临时恢复 W 位，单步执行那条存储指令（见 breakpoint::single_step）
+//! - 单步完成（breakpoint_handler 调用 step_done）后重新清除 W 位
+//!
+//! 同一页上不在观察点内的写入同样要单步放行，只是不报告；
+//! 只比较存储的起始地址（stval），从观察点之前开始、跨进观察点的写入不会被报告
+//!
+//! 观察点和被保护的页都按（根页表, 地址）记录：同一虚拟地址在其他地址空间里
+//! 是另一块内存，其他页表上的写入错误不归观察点处理。页上最后一个观察点删除时
+//! 恢复保存的标志，原本只读的页保持只读，对它的写入仍按普通页错误处理
+
+use alloc::collections::btree_map::{BTreeMap, Entry};
+use alloc::vec::Vec;
+use crate::memory::{flush_tlb_page, lookup_pte_mut, PageTableFlags, PhysAddr, VirtAddr, PAGE_SIZE};
+use crate::serial_println;
+use crate::sync::IrqSpinLock;
+use super::{breakpoint, TrapFrame};
+
+/// 一次被观察点捕获的写入
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchHit {
+    /// 观察点的起始地址
+    pub watch: usize,
+    /// 写入的地址
+    pub addr: usize,
+    /// 存储指令的地址
+    pub pc: usize,
+}
+
+/// 观察页上一次写入的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreFault {
+    /// 所在页没有观察点，交给普通的页错误处理
+    NotWatched,
+    /// 写入观察点：已记录，写权限已临时恢复
+    Hit(WatchHit),
+    /// 写入观察页上的其他地址：写权限已临时恢复
+    SamePage,
+}
+
+/// 地址所在页的起始地址
+fn page_of(addr: usize) -> usize {
+    addr & !(PAGE_SIZE - 1)
+}
+
+/// 写权限位
+const WRITE: usize = PageTableFlags::Write as usize;
+
+/// 读取页的页表项标志
+///
+/// # 返回
+/// 页面未映射时返回 None
+///
+/// # Safety
+/// root 必须是有效的根页表
+unsafe fn page_flags(root: PhysAddr, page: usize) -> Option<usize> {
+    lookup_pte_mut(root, VirtAddr::new(page)).map(|pte| pte.flags())
+}
+
+/// 设置页的页表项标志
+///
+/// # Safety
+/// 同 page_flags
+unsafe fn set_page_flags(root: PhysAddr, page: usize, flags: usize) {
+    if let Some(pte) = lookup_pte_mut(root, VirtAddr::new(page)) {
+        pte.set(pte.ppn(), flags);
+        flush_tlb_page(VirtAddr::new(page));
+    }
+}
+
+/// 观察点表
+pub struct WatchpointTable {
+    /// 观察点：(根页表, 起始地址) → 长度
+    watches: BTreeMap<(PhysAddr, usize), usize>,
+    /// 被保护的页：(根页表, 页) → 设置观察点前的页表项标志
+    protected: BTreeMap<(PhysAddr, usize), usize>,
+    /// 单步放行中、临时恢复原标志的页
+    pending: Option<(PhysAddr, usize)>,
+    /// 捕获到的写入（等待调试器读取）
+    hits: Vec<WatchHit>,
+}
+
+impl WatchpointTable {
+    /// 创建空的观察点表
+    pub const fn new() -> Self {
+        WatchpointTable {
+            watches: BTreeMap::new(),
+            protected: BTreeMap::new(),
+            pending: None,
+            hits: Vec::new(),
+        }
+    }
+
+    /// root 页表中包含 addr 的观察点的起始地址
+    fn matching(&self, root: PhysAddr, addr: usize) -> Option<usize> {
+        self.watches
+            .range((root, 0)..=(root, addr))
+            .next_back()
+            .filter(|&(&(_, start), &len)| addr < start + len)
+            .map(|(&(_, start), _)| start)
+    }
+
+    /// root 页表中的页上是否有观察点
+    fn page_watched(&self, root: PhysAddr, page: usize) -> bool {
+        self.watches
+            .range((root, 0)..=(root, usize::MAX))
+            .any(|(&(_, start), &len)| start < page + PAGE_SIZE && page_of(start + len - 1) >= page)
+    }
+
+    /// 在 [addr, addr + len) 上设置观察点
+    ///
+    /// # 返回
+    /// 长度为 0、地址已有观察点或页面未映射时返回 false
+    ///
+    /// # Safety
+    /// root 必须是有效的根页表
+    pub unsafe fn watch(&mut self, root: PhysAddr, addr: usize, len: usize) -> bool {
+        if len == 0 || self.watches.contains_key(&(root, addr)) {
+            return false;
+        }
+        let pages = (page_of(addr)..addr + len).step_by(PAGE_SIZE);
+        if pages.clone().any(|page| page_flags(root, page).is_none()) {
+            return false;
+        }
+        self.watches.insert((root, addr), len);
+        for page in pages {
+            // 页上已有观察点时标志已经保存过，W 位也已清除
+            if let Entry::Vacant(entry) = self.protected.entry((root, page)) {
+                let flags = page_flags(root, page).unwrap();
+                entry.insert(flags);
+                set_page_flags(root, page, flags & !WRITE);
+            }
+        }
+        true
+    }
+
+    /// 删除 addr 处的观察点
+    ///
+    /// # 返回
+    /// 不存在观察点时返回 false
+    ///
+    /// # Safety
+    /// 同 watch
+    pub unsafe fn unwatch(&mut self, root: PhysAddr, addr: usize) -> bool {
+        let Some(len) = self.watches.remove(&(root, addr)) else {
+            return false;
+        };
+        // 页上没有其他观察点时恢复原来的标志
+        for page in (page_of(addr)..addr + len).step_by(PAGE_SIZE) {
+            if !self.page_watched(root, page) {
+                if let Some(flags) = self.protected.remove(&(root, page)) {
+                    set_page_flags(root, page, flags);
+                }
+            }
+        }
+        true
+    }
+
+    /// 处理对 addr 的写入触发的页错误
+    ///
+    /// # 参数
+    /// - `root`: 出错时的根页表
+    /// - `addr`: 写入地址（stval）
+    /// - `pc`: 存储指令的地址
+    ///
+    /// # 说明
+    /// 观察页上的写入会临时恢复原来的标志，调用者需要单步执行存储指令，
+    /// 完成后调用 step_done 重新保护。其他页表上的写入，以及原本就不可写的页，
+    /// 都交给普通的页错误处理
+    ///
+    /// # Safety
+    /// 同 watch
+    pub unsafe fn on_store_fault(&mut self, root: PhysAddr, addr: usize, pc: usize) -> StoreFault {
+        let page = page_of(addr);
+        let flags = match self.protected.get(&(root, page)) {
+            Some(&flags) if flags & WRITE != 0 => flags,
+            _ => return StoreFault::NotWatched,
+        };
+
+        set_page_flags(root, page, flags);
+        self.pending = Some((root, page));
+
+        match self.matching(root, addr) {
+            Some(watch) => {
+                let hit = WatchHit { watch, addr, pc };
+                self.hits.push(hit);
+                StoreFault::Hit(hit)
+            }
+            None => StoreFault::SamePage,
+        }
+    }
+
+    /// 存储指令单步完成：重新保护放行的页
+    ///
+    /// # 返回
+    /// 没有放行中的页（单步不是观察点发起的）时返回 false
+    ///
+    /// # Safety
+    /// 同 watch
+    pub unsafe fn step_done(&mut self) -> bool {
+        let Some((root, page)) = self.pending.take() else {
+            return false;
+        };
+        // 单步期间观察点被删除时页已经恢复，不再保护
+        if let Some(&flags) = self.protected.get(&(root, page)) {
+            set_page_flags(root, page, flags & !WRITE);
+        }
+        true
+    }
+
+    /// 取出捕获到的写入
+    pub fn take_hits(&mut self) -> Vec<WatchHit> {
+        core::mem::take(&mut self.hits)
+    }
+}
+
+impl Default for WatchpointTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 全局观察点表
+static WATCHPOINTS: IrqSpinLock<WatchpointTable> = IrqSpinLock::new(WatchpointTable::new());
+
+/// 在 root 页表的 [addr, addr + len) 上设置观察点
+///
+/// # Safety
+/// root 必须是有效的根页表
+pub unsafe fn set_watchpoint(root: PhysAddr, addr: usize, len: usize) -> bool {
+    WATCHPOINTS.lock().watch(root, addr, len)
+}
+
+/// 删除观察点
+///
+/// # Safety
+/// 同 set_watchpoint
+pub unsafe fn remove_watchpoint(root: PhysAddr, addr: usize) -> bool {
+    WATCHPOINTS.lock().unwatch(root, addr)
+}
+
+/// 取出捕获到的写入
+pub fn take_hits() -> Vec<WatchHit> {
+    WATCHPOINTS.lock().take_hits()
+}
+
+/// 写入触发的页错误：观察页上的写入在这里处理
+///
+/// # 返回
+/// 是否已处理（true 时直接返回，重新执行存储指令）
+pub(crate) fn handle_store_fault(stval: usize, frame: &TrapFrame) -> bool {
+    use riscv::register::satp;
+
+    let satp_value = satp::read();
+    if satp_value.mode() == satp::Mode::Bare {
+        return false;
+    }
+    let root = PhysAddr::new(satp_value.ppn() << 12);
+
+    let mut watchpoints = WATCHPOINTS.lock();
+    match unsafe { watchpoints.on_store_fault(root, stval, frame.sepc) } {
+        StoreFault::NotWatched => return false,
+        StoreFault::Hit(hit) => {
+            serial_println!(
+                "[WATCH] Write to {:#x} (watchpoint {:#x}) at PC {:#x}",
+                hit.addr,
+                hit.watch,
+                hit.pc
+            );
+        }
+        StoreFault::SamePage => {}
+    }
+
+    // 单步执行存储指令，完成后由 step_done 重新保护
+    if !unsafe { breakpoint::single_step(frame) } {
+        serial_println!("[WATCH] Another single-step is in progress; page stays writable until it ends");
+    }
+    true
+}
+
+/// 单步完成时由 breakpoint_handler 调用
+///
+/// # 返回
+/// 单步是否由观察点发起（是则直接继续执行，不交给调试器）
+pub(crate) fn step_done() -> bool {
+    unsafe { WATCHPOINTS.lock().step_done() }
+}
+
+// ============================================
+// 测试
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{lookup_pte, PageTable};
+    use alloc::boxed::Box;
+
+    const DATA_PAGE: usize = 0x4000_0000;
+
+    #[test_case]
+    fn test_write_to_watched_variable_is_reported() {
+        // 在堆上搭建一套三级页表，映射一个可读写的数据页
+        let mut root = Box::new(PageTable::new());
+        let mut table1 = Box::new(PageTable::new());
+        let mut table0 = Box::new(PageTable::new());
+        let valid = PageTableFlags::Valid as usize;
+        let data = VirtAddr::new(DATA_PAGE);
+        root.get_entry_mut(data.vpn2()).set(&*table1 as *const PageTable as usize >> 12, valid);
+        table1.get_entry_mut(data.vpn1()).set(&*table0 as *const PageTable as usize >> 12, valid);
+        table0.get_entry_mut(data.vpn0()).set(
+            0x80000,
+            valid | PageTableFlags::Read as usize | PageTableFlags::Write as usize,
+        );
+        // 下一页只读
+        let read_only = VirtAddr::new(DATA_PAGE + PAGE_SIZE);
+        table0.get_entry_mut(read_only.vpn0()).set(0x80001, valid | PageTableFlags::Read as usize);
+
+        let root_paddr = PhysAddr::new(&*root as *const PageTable as usize);
+        let flags_of = |page: VirtAddr| lookup_pte(root_paddr, page).unwrap().flags();
+        let writable = || flags_of(data) & WRITE != 0;
+        let original = flags_of(data);
+
+        // 页内偏移 0x10 处的 8 字节变量
+        let variable = DATA_PAGE + 0x10;
+        let mut table = WatchpointTable::new();
+        unsafe {
+            assert!(table.watch(root_paddr, variable, 8));
+            assert!(!table.watch(root_paddr, variable, 8));
+            assert!(!table.watch(root_paddr, 0x5000_0000, 8));
+            assert!(!writable());
+
+            // 写入变量：报告，并放行一次
+            let pc = 0x8020_0000;
+            let hit = WatchHit { watch: variable, addr: variable + 4, pc };
+            assert_eq!(table.on_store_fault(root_paddr, variable + 4, pc), StoreFault::Hit(hit));
+            assert!(writable());
+            assert!(table.step_done());
+            assert!(!writable());
+            assert!(!table.step_done());
+
+            // 同一页上的其他地址：放行但不报告；其他页不归观察点处理
+            assert_eq!(table.on_store_fault(root_paddr, DATA_PAGE + 0x100, pc), StoreFault::SamePage);
+            assert!(table.step_done());
+            assert_eq!(table.on_store_fault(root_paddr, 0x5000_0000, pc), StoreFault::NotWatched);
+
+            // 同一虚拟地址在其他页表里是另一块内存，不归观察点处理
+            let other_root = PhysAddr::new(root_paddr.as_usize() + PAGE_SIZE);
+            assert_eq!(table.on_store_fault(other_root, variable, pc), StoreFault::NotWatched);
+            assert!(!table.unwatch(other_root, variable));
+            assert!(!writable());
+
+            assert_eq!(table.take_hits(), [hit]);
+            assert!(table.take_hits().is_empty());
+
+            // 删除后恢复原来的标志
+            assert!(table.unwatch(root_paddr, variable));
+            assert_eq!(flags_of(data), original);
+
+            // 只读页：删除后仍然只读，写入按普通页错误处理
+            let ro_original = flags_of(read_only);
+            assert!(table.watch(root_paddr, read_only.as_usize(), 8));
+            assert_eq!(table.on_store_fault(root_paddr, read_only.as_usize(), pc), StoreFault::NotWatched);
+            assert!(table.unwatch(root_paddr, read_only.as_usize()));
+            assert_eq!(flags_of(read_only), ro_original);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+/*
+ * ============================================
+ * 内嵌的用户测试程序
+ * ============================================
+ * 功能：把预先编译好的小型用户程序编进内核，
+ *       启动时写入 RamFS 的 /bin，供 exec 和进程测试使用
+ *
+ * 程序源码在 os/user 目录（.S 文件），用 `make user` 重新生成 os/user/bin 下的 ELF：
+ * - exit7：立即调用 sys_exit(7)
+ * - getpid_loop：循环调用 sys_getpid，永不退出
+ *
+ * 另有以 Rust 函数实现的内置程序，登记到 process::builtin，按名字启动：
+ * - hello：打印一行问候后退出
+ * - uptime：打印开机时间和系统负载（commands::cmd_uptime）
+ * ============================================
+ */
+
+use crate::fs::{FileError, RAMFS};
+use crate::process::builtin::{self, BuiltinEntry};
+use alloc::string::String;
+
+/// 程序安装的目录
+pub const BIN_DIR: &str = "bin";
+
+/// 内嵌的程序（名字, ELF 内容）
+pub static PROGRAMS: &[(&str, &[u8])] = &[
+    ("exit7", include_bytes!("../user/bin/exit7")),
+    ("getpid_loop", include_bytes!("../user/bin/getpid_loop")),
+];
+
+/// 按名字查找内嵌程序
+pub fn find(name: &str) -> Option<&'static [u8]> {
+    PROGRAMS
+        .iter()
+        .find(|(program, _)| *program == name)
+        .map(|(_, data)| *data)
+}
+
+/// 把所有内嵌程序写入 /bin
+///
+/// # 返回
+/// 写入的程序数
+///
+/// # 说明
+/// /bin 已存在时（如来自 initrd）直接使用；已有同名文件时覆盖其内容
+pub fn install() -> Result<usize, FileError> {
+    let root = RAMFS.root();
+    let bin = match RAMFS.lookup(root.clone(), BIN_DIR) {
+        Ok(dir) => dir,
+        Err(_) => RAMFS.create_directory(root, String::from(BIN_DIR))?,
+    };
+
+    for (name, data) in PROGRAMS {
+        let file = match RAMFS.lookup(bin.clone(), name) {
+            Ok(file) => file,
+            Err(_) => RAMFS.create_file(bin.clone(), String::from(*name))?,
+        };
+        let mut inode = file.lock();
+        inode.truncate(0)?;
+        inode.write_at(0, data)?;
+        drop(inode);
+        crate::process::maybe_yield();
+    }
+    Ok(PROGRAMS.len())
+}
+
+/// 内置程序（名字, 入口函数）
+pub static BUILTINS: &[(&str, BuiltinEntry)] = &[("hello", hello_main), ("uptime", uptime_main)];
+
+/// 登记所有内置程序
+///
+/// # 返回
+/// 新登记的程序数（已登记的名字跳过）
+pub fn register_builtins() -> usize {
+    BUILTINS
+        .iter()
+        .filter(|&&(name, entry)| builtin::register(name, entry))
+        .count()
+}
+
+/// 内置程序 hello
+fn hello_main() -> ! {
+    crate::println!("Hello from a built-in program!");
+    crate::process::exit_current_process(0);
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+/// 内置程序 uptime
+fn uptime_main() -> ! {
+    crate::commands::cmd_uptime();
+    crate::process::exit_current_process(0);
+    loop {
+        core::hint::spin_loop();
+    }
+}
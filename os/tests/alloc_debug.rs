@@ -0,0 +1,85 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(crate::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+// 需要 alloc_debug feature：cargo test --test alloc_debug --features alloc_debug
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::alloc::Layout;
+use core::arch::global_asm;
+use core::panic::PanicInfo;
+use os::{QemuExitCode, exit_qemu, serial_print, serial_println};
+
+// RISC-V 汇编入口点
+global_asm!(
+    ".section .text.entry",
+    ".globl _start",
+    "_start:",
+    "   la sp, stack_end",
+    "   la t0, bss_start",
+    "   la t1, bss_end",
+    "1:",
+    "   bgeu t0, t1, 2f",
+    "   sd zero, (t0)",
+    "   addi t0, t0, 8",
+    "   j 1b",
+    "2:",
+    "   call test_main_entry",
+    "3:",
+    "   wfi",
+    "   j 3b",
+);
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    // 检查触发时 panic，视为成功
+    serial_println!("[ok] {}", info.message());
+    exit_qemu(QemuExitCode::Success);
+    loop {}
+}
+
+#[no_mangle]
+pub extern "C" fn test_main_entry() -> ! {
+    use os::allocator;
+    use os::memory;
+
+    extern "C" {
+        static kernel_end: u8;
+    }
+    let kernel_end_addr = unsafe { &kernel_end as *const u8 as usize };
+    let mut memory_manager = memory::init(kernel_end_addr);
+    allocator::init_heap(&mut memory_manager.frame_allocator)
+        .expect("heap initialization failed");
+
+    test_main();
+    loop {
+        os::hlt_loop();
+    }
+}
+
+// 测试运行器：如果测试未 panic，则视为失败
+pub fn test_runner(tests: &[&dyn Fn()]) {
+    serial_println!("Running {} tests", tests.len());
+    for test in tests {
+        test();
+        serial_println!("[test did not panic]");
+        exit_qemu(QemuExitCode::Failed);
+    }
+    exit_qemu(QemuExitCode::Success);
+}
+
+// 同一个块释放两次：第二次释放时块已经在空闲链表中，检查应当 panic
+#[test_case]
+fn double_free_panics() {
+    serial_print!("double_free_panics... ");
+    let layout = Layout::new::<u64>();
+    let ptr = Box::into_raw(Box::new(42u64)) as *mut u8;
+    unsafe {
+        alloc::alloc::dealloc(ptr, layout);
+        alloc::alloc::dealloc(ptr, layout);
+    }
+}
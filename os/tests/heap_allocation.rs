@@ -99,3 +99,27 @@ fn many_boxes_long_lived() {
     }
     assert_eq!(*long_lived, 1);
 }
+
+#[test_case]
+fn freed_boxes_leave_room_for_large_vec() {
+    const BOXES: usize = 1000;
+
+    let mut boxes: Vec<Option<Box<[u64; 4]>>> =
+        (0..BOXES).map(|i| Some(Box::new([i as u64; 4]))).collect();
+
+    // 先释放隔一个的块，再释放剩下的：释放的块与两侧的空闲邻居合并
+    for slot in boxes.iter_mut().step_by(2) {
+        *slot = None;
+    }
+    for slot in boxes.iter_mut().skip(1).step_by(2) {
+        *slot = None;
+    }
+    drop(boxes);
+    assert!(os::allocator::check_integrity().is_ok());
+
+    // 之后仍能分配半个堆大小的连续缓冲区
+    let mut large: Vec<u64> = Vec::new();
+    assert!(large.try_reserve_exact(HEAP_SIZE / 2 / 8).is_ok());
+    large.resize(HEAP_SIZE / 2 / 8, 1);
+    assert_eq!(large.iter().sum::<u64>(), (HEAP_SIZE / 2 / 8) as u64);
+}
@@ -0,0 +1,74 @@
+//! 调度器锁中断状态测试
+//!
+//! 开中断时直接持有调度器锁调用 tick：持锁期间中断应被关闭，
+//! 释放锁后恢复为加锁前的开启状态
+
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use core::arch::global_asm;
+use core::panic::PanicInfo;
+use os::process::scheduler::lock_scheduler;
+use riscv::register::sstatus;
+
+// RISC-V 汇编入口点：设置栈并清零 BSS，锁和调度器的静态变量从零开始
+global_asm!(
+    ".section .text.entry",
+    ".globl _start",
+    "_start:",
+    "   la sp, stack_end",
+    "   la t0, bss_start",
+    "   la t1, bss_end",
+    "1:",
+    "   bgeu t0, t1, 2f",
+    "   sd zero, (t0)",
+    "   addi t0, t0, 8",
+    "   j 1b",
+    "2:",
+    "   call test_main_entry",
+    "3:",
+    "   wfi",
+    "   j 3b",
+);
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    os::test_panic_handler(info)
+}
+
+#[no_mangle]
+pub extern "C" fn test_main_entry() -> ! {
+    use os::allocator;
+    use os::memory;
+
+    // lock_scheduler 第一次调用时创建进程表（Arc::new），需要先初始化堆
+    extern "C" {
+        static kernel_end: u8;
+    }
+    let kernel_end_addr = unsafe { &kernel_end as *const u8 as usize };
+    let mut memory_manager = memory::init(kernel_end_addr);
+    allocator::init_heap(&mut memory_manager.frame_allocator)
+        .expect("heap initialization failed");
+
+    test_main();
+    loop {
+        os::hlt_loop();
+    }
+}
+
+#[test_case]
+fn tick_with_interrupts_enabled() {
+    // 开中断后绕过全局接口直接加锁，锁本身负责关中断
+    os::interrupts::enable_interrupts();
+    {
+        let mut scheduler = lock_scheduler();
+        assert!(!sstatus::read().sie());
+        scheduler.tick();
+    }
+    assert!(sstatus::read().sie());
+
+    os::interrupts::disable_interrupts();
+}